@@ -35,9 +35,11 @@ fn main() -> anyhow::Result<()> {
     );
 
     // Create layout configuration
-    let mut config = LayoutConfig::default();
-    config.page_size = PageSize::US_LETTER;
-    config.margins = Margins::uniform(72.0); // 1 inch margins
+    let config = LayoutConfig {
+        page_size: PageSize::US_LETTER,
+        margins: Margins::uniform(72.0), // 1 inch margins
+        ..LayoutConfig::default()
+    };
 
     // Layout the book
     println!("Laying out book: '{}'", book.title);
@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+/// Tags from `@tags: Fantasy, Historical`
+pub(crate) fn parse_tags(value: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in value.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+pub(crate) fn format_tags(tags: &[String]) -> String {
+    tags.join(", ")
+}
+
+/// Words from `@hyphenation: man-u-script, Tej`, hyphens marking break points
+pub(crate) fn parse_hyphenation(value: &str) -> Vec<String> {
+    value
+        .split([',', ' ', '\t'])
+        .map(str::trim)
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Identifiers from `@identifiers: isbn:9780000000002, goodreads:123`
+///
+/// Schemes are lowercased. Returns the offending entry if one has no scheme
+/// or no value.
+pub(crate) fn parse_identifiers(value: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut identifiers = BTreeMap::new();
+    for entry in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        match entry.split_once(':') {
+            Some((scheme, id)) if !scheme.trim().is_empty() && !id.trim().is_empty() => {
+                identifiers.insert(scheme.trim().to_lowercase(), id.trim().to_string());
+            }
+            _ => return Err(entry.to_string()),
+        }
+    }
+    Ok(identifiers)
+}
+
+pub(crate) fn format_identifiers(identifiers: &BTreeMap<String, String>) -> String {
+    identifiers
+        .iter()
+        .map(|(scheme, id)| format!("{}:{}", scheme, id))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
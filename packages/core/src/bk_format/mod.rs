@@ -1,9 +1,19 @@
 mod error;
+mod fields;
 mod models;
 mod parser;
+mod screenplay;
+mod writer;
 
 #[cfg(test)]
 mod tests;
 
 pub use error::BkParseError;
+pub(crate) use fields::{
+    format_identifiers, format_tags, parse_hyphenation, parse_identifiers, parse_tags,
+};
+pub(crate) use models::{BkChapter, BkMetadata};
+pub(crate) use parser::file_timestamps;
 pub use parser::BkParser;
+pub use screenplay::{parse_screenplay, ScreenplayElement};
+pub use writer::BkWriter;
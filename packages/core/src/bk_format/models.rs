@@ -1,3 +1,8 @@
+use crate::bk_format::error::BkParseError;
+use crate::models::{generate_chapter_id, Book, BookFormat, Chapter};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 /// Parser state machine states
@@ -8,12 +13,123 @@ pub(crate) enum ParserState {
 }
 
 /// Intermediate structure for book metadata during parsing
-#[derive(Debug, Default)]
+///
+/// Also the shape of Markdown frontmatter, hence the serde derives.
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub(crate) struct BkMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub dedication: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_words: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author_sort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series_index: Option<f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub identifiers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "is_prose")]
+    pub format: BookFormat,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hyphenation: Vec<String>,
+}
+
+fn is_prose(format: &BookFormat) -> bool {
+    *format == BookFormat::Prose
+}
+
+impl BkMetadata {
+    /// Metadata of an existing book
+    pub fn from_book(book: &Book) -> Self {
+        Self {
+            title: Some(book.title.clone()),
+            author: Some(book.author.clone()),
+            id: Some(book.id),
+            dedication: book.dedication.clone(),
+            language: book.language.clone(),
+            target_words: book.target_words,
+            author_sort: book.author_sort.clone(),
+            series: book.series.clone(),
+            series_index: book.series_index,
+            tags: book.tags.clone(),
+            identifiers: book.identifiers.clone(),
+            format: book.format,
+            hyphenation: book.hyphenation.clone(),
+        }
+    }
+
+    /// Validate the metadata and build a book with deterministic chapter IDs
+    pub fn into_book(
+        self,
+        chapters: Vec<BkChapter>,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    ) -> Result<Book, BkParseError> {
+        // Validate required metadata
+        let title = self.title.ok_or(BkParseError::MissingMetadata {
+            field: "title".to_string(),
+        })?;
+
+        let author = self.author.ok_or(BkParseError::MissingMetadata {
+            field: "author".to_string(),
+        })?;
+
+        // Generate book ID if not provided
+        let book_id = self.id.unwrap_or_else(Uuid::new_v4);
+
+        // Validate we have chapters
+        if chapters.is_empty() {
+            return Err(BkParseError::NoChapters);
+        }
+
+        // Convert chapters to final format with deterministic IDs
+        let chapters: Vec<Chapter> = chapters
+            .into_iter()
+            .map(|bk_chapter| {
+                let chapter_id = generate_chapter_id(&book_id, bk_chapter.order, &bk_chapter.title);
+
+                Chapter {
+                    id: chapter_id,
+                    title: bk_chapter.title,
+                    content: bk_chapter.content,
+                    order: bk_chapter.order,
+                    language: bk_chapter.language,
+                    created_at,
+                    updated_at,
+                }
+            })
+            .collect();
+
+        Ok(Book {
+            id: book_id,
+            title,
+            author,
+            dedication: self.dedication,
+            target_words: self.target_words,
+            language: self.language,
+            author_sort: self.author_sort,
+            series: self.series,
+            series_index: self.series_index,
+            tags: self.tags,
+            identifiers: self.identifiers,
+            format: self.format,
+            hyphenation: self.hyphenation,
+            created_at,
+            updated_at,
+            chapters,
+        })
+    }
 }
 
 /// Intermediate structure for chapters during parsing
@@ -22,6 +138,8 @@ pub(crate) struct BkChapter {
     pub title: String,
     pub order: usize,
     pub content: String, // Raw content
+    /// From an `@language:` line inside the chapter
+    pub language: Option<String>,
 }
 
 impl BkChapter {
@@ -30,6 +148,7 @@ impl BkChapter {
             title,
             order,
             content: String::new(),
+            language: None,
         }
     }
 }
@@ -1,12 +1,30 @@
 use crate::bk_format::error::BkParseError;
+use crate::bk_format::fields::{parse_hyphenation, parse_identifiers, parse_tags};
 use crate::bk_format::models::{BkChapter, BkMetadata, ParserState};
-use crate::models::{generate_chapter_id, Book, Chapter};
+use crate::models::{Book, BookFormat};
 use chrono::{DateTime, Utc};
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 use uuid::Uuid;
 
+/// Metadata fields read wherever they appear, even inside a chapter
+const METADATA_FIELDS: [&str; 13] = [
+    "@title:",
+    "@author:",
+    "@id:",
+    "@dedication:",
+    "@target_words:",
+    "@language:",
+    "@author_sort:",
+    "@series:",
+    "@series_index:",
+    "@tags:",
+    "@identifiers:",
+    "@format:",
+    "@hyphenation:",
+];
+
 /// Parser for .bk files
 pub struct BkParser {
     state: ParserState,
@@ -14,6 +32,8 @@ pub struct BkParser {
     metadata: BkMetadata,
     chapters: Vec<BkChapter>,
     current_chapter: Option<BkChapter>,
+    /// Line of the `@format:` field, since prose is also the default
+    format_line: Option<usize>,
 }
 
 impl BkParser {
@@ -25,6 +45,7 @@ impl BkParser {
             metadata: BkMetadata::default(),
             chapters: Vec::new(),
             current_chapter: None,
+            format_line: None,
         }
     }
 
@@ -56,13 +77,30 @@ impl BkParser {
         parser.finalize_with_timestamps(created_at, updated_at)
     }
 
+    /// Read only the metadata fields a .bk file sets
+    pub(crate) fn read_metadata(content: &str) -> Result<BkMetadata, BkParseError> {
+        let mut parser = Self::new();
+        for line in content.lines() {
+            parser.parse_line(line.to_string())?;
+        }
+        Ok(parser.metadata)
+    }
+
     /// Parse a single line
     fn parse_line(&mut self, line: String) -> Result<(), BkParseError> {
         self.line_number += 1;
         let trimmed = line.trim();
 
-        // Skip empty lines
+        // Blank lines only matter inside screenplay chapters; prose drops them
+        // when the chapter is finished
         if trimmed.is_empty() {
+            self.accumulate_blank_line();
+            return Ok(());
+        }
+
+        // A leading backslash marks a content line that looks like a directive
+        if let Some(unescaped) = unescape_content_line(&line) {
+            self.accumulate_content(unescaped);
             return Ok(());
         }
 
@@ -74,12 +112,7 @@ impl BkParser {
 
         // Handle metadata (starts with @)
         if trimmed.starts_with('@') {
-            if self.state == ParserState::ReadingMetadata
-                || trimmed.starts_with("@title:")
-                || trimmed.starts_with("@author:")
-                || trimmed.starts_with("@id:")
-                || trimmed.starts_with("@dedication:")
-            {
+            if self.state == ParserState::ReadingMetadata || is_metadata_field(trimmed) {
                 self.parse_metadata(trimmed)?;
             } else {
                 // Unknown @ directive, treat as content
@@ -144,6 +177,113 @@ impl BkParser {
                 }
                 self.metadata.dedication = Some(value);
             }
+            "target_words" => {
+                if self.metadata.target_words.is_some() {
+                    return Err(BkParseError::DuplicateMetadata {
+                        field: "target_words".to_string(),
+                        line: self.line_number,
+                    });
+                }
+                let target = value.replace([',', '_'], "").parse::<u32>().map_err(|_| {
+                    BkParseError::MalformedMetadata {
+                        line: self.line_number,
+                        reason: format!("@target_words must be a whole number, got '{}'", value),
+                    }
+                })?;
+                self.metadata.target_words = Some(target);
+            }
+            "language" => {
+                // Inside a chapter, the language applies to that chapter only
+                let language = match &mut self.current_chapter {
+                    Some(chapter) => &mut chapter.language,
+                    None => &mut self.metadata.language,
+                };
+                if language.is_some() {
+                    return Err(BkParseError::DuplicateMetadata {
+                        field: "language".to_string(),
+                        line: self.line_number,
+                    });
+                }
+                *language = Some(value);
+            }
+            "author_sort" => {
+                if self.metadata.author_sort.is_some() {
+                    return Err(BkParseError::DuplicateMetadata {
+                        field: "author_sort".to_string(),
+                        line: self.line_number,
+                    });
+                }
+                self.metadata.author_sort = Some(value);
+            }
+            "series" => {
+                if self.metadata.series.is_some() {
+                    return Err(BkParseError::DuplicateMetadata {
+                        field: "series".to_string(),
+                        line: self.line_number,
+                    });
+                }
+                self.metadata.series = Some(value);
+            }
+            "series_index" => {
+                if self.metadata.series_index.is_some() {
+                    return Err(BkParseError::DuplicateMetadata {
+                        field: "series_index".to_string(),
+                        line: self.line_number,
+                    });
+                }
+                let index = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|index| index.is_finite() && *index >= 0.0)
+                    .ok_or_else(|| BkParseError::MalformedMetadata {
+                        line: self.line_number,
+                        reason: format!(
+                            "@series_index must be a number such as 2 or 1.5, got '{}'",
+                            value
+                        ),
+                    })?;
+                self.metadata.series_index = Some(index);
+            }
+            "tags" => {
+                if !self.metadata.tags.is_empty() {
+                    return Err(BkParseError::DuplicateMetadata {
+                        field: "tags".to_string(),
+                        line: self.line_number,
+                    });
+                }
+                self.metadata.tags = parse_tags(&value);
+            }
+            "identifiers" => {
+                if !self.metadata.identifiers.is_empty() {
+                    return Err(BkParseError::DuplicateMetadata {
+                        field: "identifiers".to_string(),
+                        line: self.line_number,
+                    });
+                }
+                self.metadata.identifiers =
+                    parse_identifiers(&value).map_err(|entry| BkParseError::MalformedMetadata {
+                        line: self.line_number,
+                        reason: format!("@identifiers entries are 'scheme:value', got '{}'", entry),
+                    })?;
+            }
+            "format" => {
+                if self.format_line.is_some() {
+                    return Err(BkParseError::DuplicateMetadata {
+                        field: "format".to_string(),
+                        line: self.line_number,
+                    });
+                }
+                self.metadata.format =
+                    BookFormat::parse(&value).ok_or_else(|| BkParseError::MalformedMetadata {
+                        line: self.line_number,
+                        reason: format!("@format must be 'prose' or 'screenplay', got '{}'", value),
+                    })?;
+                self.format_line = Some(self.line_number);
+            }
+            "hyphenation" => {
+                // A dictionary may run over several lines
+                self.metadata.hyphenation.extend(parse_hyphenation(&value));
+            }
             _ => {
                 // Unknown metadata field, ignore
             }
@@ -187,6 +327,15 @@ impl BkParser {
         }
     }
 
+    /// Keep a blank line between content lines of the current chapter
+    fn accumulate_blank_line(&mut self) {
+        if let Some(chapter) = &mut self.current_chapter {
+            if !chapter.content.is_empty() {
+                chapter.content.push('\n');
+            }
+        }
+    }
+
     /// Finish the current chapter and add it to chapters list
     fn finish_current_chapter(&mut self) {
         if let Some(mut chapter) = self.current_chapter.take() {
@@ -198,28 +347,7 @@ impl BkParser {
 
     /// Finalize parsing and construct Book (with filesystem metadata)
     fn finalize(self, file_metadata: std::fs::Metadata) -> Result<Book, BkParseError> {
-        let created_at = file_metadata
-            .created()
-            .ok()
-            .and_then(|t| {
-                DateTime::from_timestamp(
-                    t.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs() as i64,
-                    0,
-                )
-            })
-            .unwrap_or_else(Utc::now);
-
-        let updated_at = file_metadata
-            .modified()
-            .ok()
-            .and_then(|t| {
-                DateTime::from_timestamp(
-                    t.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs() as i64,
-                    0,
-                )
-            })
-            .unwrap_or_else(Utc::now);
-
+        let (created_at, updated_at) = file_timestamps(&file_metadata);
         self.finalize_with_timestamps(created_at, updated_at)
     }
 
@@ -232,50 +360,14 @@ impl BkParser {
         // Finish any pending chapter
         self.finish_current_chapter();
 
-        // Validate required metadata
-        let title = self.metadata.title.ok_or(BkParseError::MissingMetadata {
-            field: "title".to_string(),
-        })?;
-
-        let author = self.metadata.author.ok_or(BkParseError::MissingMetadata {
-            field: "author".to_string(),
-        })?;
-
-        // Generate book ID if not provided
-        let book_id = self.metadata.id.unwrap_or_else(Uuid::new_v4);
-
-        // Validate we have chapters
-        if self.chapters.is_empty() {
-            return Err(BkParseError::NoChapters);
+        if self.metadata.format == BookFormat::Prose {
+            for chapter in &mut self.chapters {
+                chapter.content = drop_blank_lines(&chapter.content);
+            }
         }
 
-        // Convert chapters to final format with deterministic IDs
-        let chapters: Vec<Chapter> = self
-            .chapters
-            .into_iter()
-            .map(|bk_chapter| {
-                let chapter_id = generate_chapter_id(&book_id, bk_chapter.order, &bk_chapter.title);
-
-                Chapter {
-                    id: chapter_id,
-                    title: bk_chapter.title,
-                    content: bk_chapter.content,
-                    order: bk_chapter.order,
-                    created_at,
-                    updated_at,
-                }
-            })
-            .collect();
-
-        Ok(Book {
-            id: book_id,
-            title,
-            author,
-            dedication: self.metadata.dedication,
-            created_at,
-            updated_at,
-            chapters,
-        })
+        self.metadata
+            .into_book(self.chapters, created_at, updated_at)
     }
 }
 
@@ -284,3 +376,64 @@ impl Default for BkParser {
         Self::new()
     }
 }
+
+fn is_metadata_field(trimmed: &str) -> bool {
+    METADATA_FIELDS
+        .iter()
+        .any(|field| trimmed.starts_with(field))
+}
+
+/// Whether a trimmed line starts with a backslash the parser removes
+fn is_escaped(trimmed: &str) -> bool {
+    trimmed.strip_prefix('\\').is_some_and(|rest| {
+        rest.starts_with('@') || rest.starts_with("#chapter:") || rest.starts_with('\\')
+    })
+}
+
+/// Escape a chapter content line the parser would otherwise read as a
+/// directive, by putting a backslash before its first character
+pub(crate) fn escape_content_line(line: &str) -> String {
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("#chapter:") || is_metadata_field(trimmed) || is_escaped(trimmed) {
+        let indent = line.len() - trimmed.len();
+        format!("{}\\{}", &line[..indent], trimmed)
+    } else {
+        line.to_string()
+    }
+}
+
+/// Undo [`escape_content_line`], or `None` if the line is not escaped
+fn unescape_content_line(line: &str) -> Option<String> {
+    let trimmed = line.trim_start();
+    if !is_escaped(trimmed) {
+        return None;
+    }
+    let indent = line.len() - trimmed.len();
+    Some(format!("{}{}", &line[..indent], &trimmed[1..]))
+}
+
+fn drop_blank_lines(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Creation and modification times of a file, falling back to now
+pub(crate) fn file_timestamps(file_metadata: &std::fs::Metadata) -> (DateTime<Utc>, DateTime<Utc>) {
+    let to_utc = |time: std::io::Result<std::time::SystemTime>| {
+        time.ok()
+            .and_then(|t| {
+                DateTime::from_timestamp(
+                    t.duration_since(std::time::UNIX_EPOCH).ok()?.as_secs() as i64,
+                    0,
+                )
+            })
+            .unwrap_or_else(Utc::now)
+    };
+    (
+        to_utc(file_metadata.created()),
+        to_utc(file_metadata.modified()),
+    )
+}
@@ -0,0 +1,222 @@
+/// A typed element of a screenplay chapter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScreenplayElement {
+    /// `INT. KITCHEN - NIGHT`, or any line forced with a leading `.`
+    SceneHeading(String),
+    /// Description; lines of one paragraph are joined with `\n`
+    Action(String),
+    /// Character cue, with any extension such as `(V.O.)`
+    Character(String),
+    /// `(quietly)` between a cue and its dialogue
+    Parenthetical(String),
+    Dialogue(String),
+    /// `CUT TO:`, or any line forced with a leading `>`
+    Transition(String),
+    /// `>THE END<`
+    Centered(String),
+    /// `===`
+    PageBreak,
+}
+
+impl ScreenplayElement {
+    /// Text of the element; empty for a page break
+    pub fn text(&self) -> &str {
+        match self {
+            ScreenplayElement::SceneHeading(text)
+            | ScreenplayElement::Action(text)
+            | ScreenplayElement::Character(text)
+            | ScreenplayElement::Parenthetical(text)
+            | ScreenplayElement::Dialogue(text)
+            | ScreenplayElement::Transition(text)
+            | ScreenplayElement::Centered(text) => text,
+            ScreenplayElement::PageBreak => "",
+        }
+    }
+
+    /// Name of the element in kebab case, such as `scene-heading`, for use
+    /// as a CSS class or style name
+    pub fn class(&self) -> &'static str {
+        match self {
+            ScreenplayElement::SceneHeading(_) => "scene-heading",
+            ScreenplayElement::Action(_) => "action",
+            ScreenplayElement::Character(_) => "character",
+            ScreenplayElement::Parenthetical(_) => "parenthetical",
+            ScreenplayElement::Dialogue(_) => "dialogue",
+            ScreenplayElement::Transition(_) => "transition",
+            ScreenplayElement::Centered(_) => "centered",
+            ScreenplayElement::PageBreak => "page-break",
+        }
+    }
+
+    /// Whether a blank line goes before the element; dialogue and
+    /// parentheticals sit right under their cue
+    pub fn spaced(&self) -> bool {
+        !matches!(
+            self,
+            ScreenplayElement::Parenthetical(_) | ScreenplayElement::Dialogue(_)
+        )
+    }
+}
+
+/// Scene heading prefixes, matched without regard to case
+const SCENE_PREFIXES: [&str; 6] = ["INT./EXT", "INT/EXT", "I/E", "INT", "EXT", "EST"];
+
+/// Split Fountain text into screenplay elements
+///
+/// Sections (`#`), synopses (`=`), notes (`[[...]]`) and boneyard
+/// (`/* ... */`) are not printed and are dropped.
+pub fn parse_screenplay(content: &str) -> Vec<ScreenplayElement> {
+    let text = strip_comments(content);
+    let mut elements = Vec::new();
+    for paragraph in paragraphs(&text) {
+        parse_paragraph(&paragraph, &mut elements);
+    }
+    elements
+}
+
+/// Remove notes and boneyard, which may span lines
+fn strip_comments(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut rest = content;
+    loop {
+        let next = [("[[", "]]"), ("/*", "*/")]
+            .iter()
+            .filter_map(|(open, close)| rest.find(open).map(|at| (at, *open, *close)))
+            .min_by_key(|(at, _, _)| *at);
+        let Some((at, open, close)) = next else {
+            out.push_str(rest);
+            return out;
+        };
+        out.push_str(&rest[..at]);
+        rest = match rest[at + open.len()..].find(close) {
+            Some(end) => &rest[at + open.len() + end + close.len()..],
+            None => "",
+        };
+    }
+}
+
+/// Non-blank lines grouped by the blank lines between them
+fn paragraphs(text: &str) -> Vec<Vec<&str>> {
+    let mut paragraphs = Vec::new();
+    let mut current = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            if !current.is_empty() {
+                paragraphs.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+    paragraphs
+}
+
+fn parse_paragraph(lines: &[&str], elements: &mut Vec<ScreenplayElement>) {
+    let mut action: Vec<&str> = Vec::new();
+    let flush = |action: &mut Vec<&str>, elements: &mut Vec<ScreenplayElement>| {
+        if !action.is_empty() {
+            elements.push(ScreenplayElement::Action(action.join("\n")));
+            action.clear();
+        }
+    };
+
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        let first = index == 0;
+        let alone = lines.len() == 1;
+        index += 1;
+
+        if is_page_break(line) {
+            flush(&mut action, elements);
+            elements.push(ScreenplayElement::PageBreak);
+        } else if line.starts_with('#') || line.starts_with('=') {
+            // Section or synopsis
+        } else if let Some(text) = line.strip_prefix('!') {
+            action.push(text);
+        } else if let Some(text) = centered(line) {
+            flush(&mut action, elements);
+            elements.push(ScreenplayElement::Centered(text.to_string()));
+        } else if let Some(text) = line.strip_prefix('>') {
+            flush(&mut action, elements);
+            elements.push(ScreenplayElement::Transition(text.trim().to_string()));
+        } else if let Some(heading) = scene_heading(line).filter(|_| first) {
+            elements.push(ScreenplayElement::SceneHeading(heading));
+        } else if first && alone && is_transition(line) {
+            elements.push(ScreenplayElement::Transition(line.to_string()));
+        } else if let Some(cue) = character_cue(line).filter(|_| first && !alone) {
+            elements.push(ScreenplayElement::Character(cue));
+            parse_dialogue(&lines[index..], elements);
+            return;
+        } else {
+            action.push(line);
+        }
+    }
+    flush(&mut action, elements);
+}
+
+/// Parentheticals and dialogue that follow a character cue
+fn parse_dialogue(lines: &[&str], elements: &mut Vec<ScreenplayElement>) {
+    let mut speech: Vec<&str> = Vec::new();
+    for line in lines {
+        if line.starts_with('(') && line.ends_with(')') {
+            if !speech.is_empty() {
+                elements.push(ScreenplayElement::Dialogue(speech.join("\n")));
+                speech.clear();
+            }
+            elements.push(ScreenplayElement::Parenthetical(line.to_string()));
+        } else {
+            speech.push(line);
+        }
+    }
+    if !speech.is_empty() {
+        elements.push(ScreenplayElement::Dialogue(speech.join("\n")));
+    }
+}
+
+fn is_page_break(line: &str) -> bool {
+    line.len() >= 3 && line.chars().all(|c| c == '=')
+}
+
+fn centered(line: &str) -> Option<&str> {
+    line.strip_prefix('>')?
+        .strip_suffix('<')
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
+
+fn scene_heading(line: &str) -> Option<String> {
+    if let Some(text) = line.strip_prefix('.') {
+        return (!text.starts_with('.')).then(|| text.trim().to_string());
+    }
+    let upper = line.to_uppercase();
+    SCENE_PREFIXES
+        .iter()
+        .any(|prefix| {
+            upper
+                .strip_prefix(prefix)
+                .is_some_and(|rest| rest.starts_with(['.', ' ']))
+        })
+        .then(|| line.to_string())
+}
+
+fn is_transition(line: &str) -> bool {
+    line.ends_with("TO:") && is_upper(line)
+}
+
+/// A cue is in capitals, apart from an extension in parentheses
+fn character_cue(line: &str) -> Option<String> {
+    if let Some(name) = line.strip_prefix('@') {
+        return Some(name.trim_end_matches('^').trim().to_string());
+    }
+    let name = line.split('(').next().unwrap_or(line);
+    (is_upper(name) && name.chars().any(char::is_alphabetic))
+        .then(|| line.trim_end_matches('^').trim().to_string())
+}
+
+fn is_upper(text: &str) -> bool {
+    !text.chars().any(char::is_lowercase)
+}
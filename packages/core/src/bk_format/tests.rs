@@ -1,5 +1,8 @@
 use crate::bk_format::error::BkParseError;
 use crate::bk_format::parser::BkParser;
+use crate::bk_format::writer::BkWriter;
+use crate::bk_format::{parse_screenplay, ScreenplayElement};
+use crate::models::BookFormat;
 use chrono::Utc;
 
 #[test]
@@ -300,3 +303,257 @@ fn test_help_messages() {
     let error = BkParseError::NoChapters;
     assert!(error.help_message().contains("#chapter:"));
 }
+
+#[test]
+fn test_writer_round_trip() {
+    let content = r#"
+@title: The Way of Iron
+@author: Tej
+@id: 550e8400-e29b-41d4-a009-426655440000
+@dedication: To my family...
+
+#chapter: Chapter One
+The morning sun cracked over the horizon...
+Another day began...
+
+#chapter: Chapter Two
+The road stretched endlessly.
+    "#;
+
+    let book = BkParser::parse_string(content, Utc::now(), Utc::now()).unwrap();
+    let written = BkWriter::write_string(&book);
+    let reparsed = BkParser::parse_string(&written, Utc::now(), Utc::now()).unwrap();
+
+    assert_eq!(reparsed.id, book.id);
+    assert_eq!(reparsed.title, book.title);
+    assert_eq!(reparsed.dedication, book.dedication);
+    assert_eq!(reparsed.chapters.len(), 2);
+    assert_eq!(reparsed.chapters[0].id, book.chapters[0].id);
+    assert_eq!(reparsed.chapters[0].content, book.chapters[0].content);
+    assert_eq!(reparsed.chapters[1].title, "Chapter Two");
+}
+
+#[test]
+fn test_writer_escapes_directive_lines() {
+    let mut book = BkParser::parse_string(
+        "@title: Notes\n@author: Tej\n\n#chapter: One\nPlaceholder.",
+        Utc::now(),
+        Utc::now(),
+    )
+    .unwrap();
+    let content = "@tags: x\n#chapter: Two\n  @language: fr\n\\@title: y\n\\section\n@page:\nEnd.";
+    book.chapters[0].content = content.to_string();
+
+    let written = BkWriter::write_string(&book);
+    assert!(written.contains("\n\\@tags: x\n\\#chapter: Two\n  \\@language: fr\n\\\\@title: y\n"));
+    assert!(written.contains("\n\\section\n@page:\n"));
+
+    let reparsed = BkParser::parse_string(&written, Utc::now(), Utc::now()).unwrap();
+    assert!(reparsed.tags.is_empty());
+    assert_eq!(reparsed.title, "Notes");
+    assert_eq!(reparsed.chapters.len(), 1);
+    assert_eq!(reparsed.chapters[0].language, None);
+    assert_eq!(reparsed.chapters[0].content, content);
+}
+
+#[test]
+fn test_parse_target_words() {
+    let content = r#"
+@title: Book
+@author: Author
+@target_words: 80,000
+
+#chapter: Chapter
+Content
+    "#;
+
+    let book = BkParser::parse_string(content, Utc::now(), Utc::now()).unwrap();
+    assert_eq!(book.target_words, Some(80_000));
+
+    let written = BkWriter::write_string(&book);
+    assert!(written.contains("@target_words: 80000"));
+
+    let invalid = content.replace("80,000", "lots");
+    let result = BkParser::parse_string(&invalid, Utc::now(), Utc::now());
+    assert!(matches!(
+        result,
+        Err(BkParseError::MalformedMetadata { line: 4, .. })
+    ));
+}
+
+#[test]
+fn test_parse_language() {
+    let content = r#"
+@title: Book
+@author: Author
+@language: fr-CA
+
+#chapter: Chapter
+Content
+    "#;
+
+    let book = BkParser::parse_string(content, Utc::now(), Utc::now()).unwrap();
+    assert_eq!(book.language.as_deref(), Some("fr-CA"));
+    assert!(BkWriter::write_string(&book).contains("@language: fr-CA"));
+
+    let duplicate = content.replace("@language: fr-CA", "@language: fr-CA\n@language: en");
+    let result = BkParser::parse_string(&duplicate, Utc::now(), Utc::now());
+    assert!(matches!(
+        result,
+        Err(BkParseError::DuplicateMetadata { .. })
+    ));
+}
+
+#[test]
+fn test_parse_hyphenation_and_chapter_language() {
+    let content = r#"
+@title: Book
+@author: Author
+@language: en
+@hyphenation: man-u-script, Tej
+@hyphenation: ta-ble
+
+#chapter: One
+Content
+
+#chapter: Deux
+@language: fr
+Contenu
+    "#;
+
+    let book = BkParser::parse_string(content, Utc::now(), Utc::now()).unwrap();
+    assert_eq!(book.language.as_deref(), Some("en"));
+    assert_eq!(book.hyphenation, ["man-u-script", "Tej", "ta-ble"]);
+    assert_eq!(book.chapters[0].language, None);
+    assert_eq!(book.chapters[1].language.as_deref(), Some("fr"));
+    assert_eq!(book.chapters[1].content, "Contenu");
+
+    let written = BkWriter::write_string(&book);
+    assert!(written.contains("@hyphenation: man-u-script, Tej, ta-ble\n"));
+    assert!(written.contains("#chapter: Deux\n@language: fr\nContenu"));
+    let reparsed = BkParser::parse_string(&written, Utc::now(), Utc::now()).unwrap();
+    assert_eq!(reparsed.hyphenation, book.hyphenation);
+    assert_eq!(reparsed.chapters[1].language.as_deref(), Some("fr"));
+
+    let duplicate = content.replace("@language: fr", "@language: fr\n@language: de");
+    assert!(matches!(
+        BkParser::parse_string(&duplicate, Utc::now(), Utc::now()),
+        Err(BkParseError::DuplicateMetadata { line: 13, .. })
+    ));
+}
+
+#[test]
+fn test_parse_catalog_fields() {
+    let content = r#"
+@title: Book
+@author: Ana Lima & Ben Ode
+@author_sort: Lima, Ana & Ode, Ben
+@series: Sea Trilogy
+@series_index: 1.5
+@tags: Fantasy, Sea,  , Fantasy
+@identifiers: ISBN:9780000000002, goodreads: 123
+
+#chapter: Chapter
+Content
+    "#;
+
+    let book = BkParser::parse_string(content, Utc::now(), Utc::now()).unwrap();
+    assert_eq!(book.author_sort.as_deref(), Some("Lima, Ana & Ode, Ben"));
+    assert_eq!(book.series.as_deref(), Some("Sea Trilogy"));
+    assert_eq!(book.series_index, Some(1.5));
+    assert_eq!(book.tags, ["Fantasy", "Sea"]);
+    assert_eq!(book.identifiers["isbn"], "9780000000002");
+    assert_eq!(book.identifiers["goodreads"], "123");
+
+    let written = BkWriter::write_string(&book);
+    assert!(written.contains("@series_index: 1.5\n@tags: Fantasy, Sea\n"));
+    assert!(written.contains("@identifiers: goodreads:123, isbn:9780000000002\n"));
+
+    let invalid = content.replace("goodreads: 123", "123");
+    assert!(matches!(
+        BkParser::parse_string(&invalid, Utc::now(), Utc::now()),
+        Err(BkParseError::MalformedMetadata { line: 8, .. })
+    ));
+    let invalid = content.replace("1.5", "second");
+    assert!(matches!(
+        BkParser::parse_string(&invalid, Utc::now(), Utc::now()),
+        Err(BkParseError::MalformedMetadata { line: 6, .. })
+    ));
+}
+
+#[test]
+fn test_parse_screenplay() {
+    let content = r#"
+@title: Salt
+@author: Tej
+@format: screenplay
+
+#chapter: Act One
+INT. FORGE - NIGHT
+
+Marcus hammers a blade.
+Sparks fly.
+
+MARCUS (V.O.)
+(quietly)
+It never cools.
+Never.
+
+[[check the timing]]
+CUT TO:
+
+>THE END<
+===
+    "#;
+
+    let book = BkParser::parse_string(content, Utc::now(), Utc::now()).unwrap();
+    assert_eq!(book.format, BookFormat::Screenplay);
+    assert!(book.chapters[0].content.contains("NIGHT\n\nMarcus"));
+    assert_eq!(
+        parse_screenplay(&book.chapters[0].content),
+        [
+            ScreenplayElement::SceneHeading("INT. FORGE - NIGHT".to_string()),
+            ScreenplayElement::Action("Marcus hammers a blade.\nSparks fly.".to_string()),
+            ScreenplayElement::Character("MARCUS (V.O.)".to_string()),
+            ScreenplayElement::Parenthetical("(quietly)".to_string()),
+            ScreenplayElement::Dialogue("It never cools.\nNever.".to_string()),
+            ScreenplayElement::Transition("CUT TO:".to_string()),
+            ScreenplayElement::Centered("THE END".to_string()),
+            ScreenplayElement::PageBreak,
+        ]
+    );
+
+    let written = BkWriter::write_string(&book);
+    assert!(written.contains("@format: screenplay\n"));
+    let reparsed = BkParser::parse_string(&written, Utc::now(), Utc::now()).unwrap();
+    assert_eq!(reparsed.chapters[0].content, book.chapters[0].content);
+
+    // Prose keeps one paragraph per line
+    let prose = content.replace("screenplay", "prose");
+    let book = BkParser::parse_string(&prose, Utc::now(), Utc::now()).unwrap();
+    assert!(book.chapters[0].content.contains("NIGHT\nMarcus"));
+    assert!(!BkWriter::write_string(&book).contains("@format:"));
+
+    let invalid = content.replace("screenplay", "teleplay");
+    assert!(matches!(
+        BkParser::parse_string(&invalid, Utc::now(), Utc::now()),
+        Err(BkParseError::MalformedMetadata { line: 4, .. })
+    ));
+}
+
+#[test]
+fn test_screenplay_forced_elements() {
+    let elements = parse_screenplay(
+        ".FLASHBACK\n\n!SHOUTS FROM BELOW.\n\n@McCLANE\nYippee.\n\n> FADE OUT.\n\n# Act Two\n\n= Marcus leaves",
+    );
+    assert_eq!(
+        elements,
+        [
+            ScreenplayElement::SceneHeading("FLASHBACK".to_string()),
+            ScreenplayElement::Action("SHOUTS FROM BELOW.".to_string()),
+            ScreenplayElement::Character("McCLANE".to_string()),
+            ScreenplayElement::Dialogue("Yippee.".to_string()),
+            ScreenplayElement::Transition("FADE OUT.".to_string()),
+        ]
+    );
+}
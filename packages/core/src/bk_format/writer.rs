@@ -0,0 +1,72 @@
+use crate::bk_format::fields::{format_identifiers, format_tags};
+use crate::bk_format::parser::escape_content_line;
+use crate::models::{Book, BookFormat, Chapter};
+use std::path::Path;
+
+/// Serializer that turns a `Book` back into .bk text
+pub struct BkWriter;
+
+impl BkWriter {
+    /// Serialize a book to a .bk string that `BkParser` reads back
+    pub fn write_string(book: &Book) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("@title: {}\n", book.title));
+        out.push_str(&format!("@author: {}\n", book.author));
+        out.push_str(&format!("@id: {}\n", book.id));
+        if let Some(dedication) = &book.dedication {
+            out.push_str(&format!("@dedication: {}\n", dedication));
+        }
+        if let Some(language) = &book.language {
+            out.push_str(&format!("@language: {}\n", language));
+        }
+        if let Some(target) = book.target_words {
+            out.push_str(&format!("@target_words: {}\n", target));
+        }
+        if let Some(author_sort) = &book.author_sort {
+            out.push_str(&format!("@author_sort: {}\n", author_sort));
+        }
+        if let Some(series) = &book.series {
+            out.push_str(&format!("@series: {}\n", series));
+        }
+        if let Some(index) = book.series_index {
+            out.push_str(&format!("@series_index: {}\n", index));
+        }
+        if !book.tags.is_empty() {
+            out.push_str(&format!("@tags: {}\n", format_tags(&book.tags)));
+        }
+        if !book.identifiers.is_empty() {
+            out.push_str(&format!(
+                "@identifiers: {}\n",
+                format_identifiers(&book.identifiers)
+            ));
+        }
+
+        if book.format != BookFormat::Prose {
+            out.push_str(&format!("@format: {}\n", book.format.as_str()));
+        }
+        if !book.hyphenation.is_empty() {
+            out.push_str(&format!("@hyphenation: {}\n", book.hyphenation.join(", ")));
+        }
+
+        let mut chapters: Vec<&Chapter> = book.chapters.iter().collect();
+        chapters.sort_by_key(|c| c.order);
+        for chapter in chapters {
+            out.push('\n');
+            out.push_str(&format!("#chapter: {}\n", chapter.title));
+            if let Some(language) = &chapter.language {
+                out.push_str(&format!("@language: {}\n", language));
+            }
+            for line in chapter.content.lines() {
+                out.push_str(&escape_content_line(line));
+                out.push('\n');
+            }
+        }
+
+        out
+    }
+
+    /// Serialize a book and write it to `path`
+    pub fn write_file(book: &Book, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, Self::write_string(book))
+    }
+}
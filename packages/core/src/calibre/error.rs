@@ -0,0 +1,17 @@
+use thiserror::Error;
+
+/// Errors that can occur while reading or writing Calibre metadata
+#[derive(Error, Debug)]
+pub enum CalibreError {
+    #[error("IO error in Calibre metadata: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Malformed OPF: {0}")]
+    Xml(#[from] quick_xml::Error),
+
+    #[error("Not an OPF package document: no <metadata> element")]
+    NotOpf,
+
+    #[error("Could not read the book's metadata: {0}")]
+    Book(#[from] crate::bk_format::BkParseError),
+}
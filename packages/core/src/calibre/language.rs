@@ -0,0 +1,82 @@
+/// ISO 639-1 codes used in .bk files and their ISO 639-2/T forms used by Calibre
+const CODES: &[(&str, &str)] = &[
+    ("ar", "ara"),
+    ("ca", "cat"),
+    ("cs", "ces"),
+    ("da", "dan"),
+    ("de", "deu"),
+    ("el", "ell"),
+    ("en", "eng"),
+    ("es", "spa"),
+    ("fi", "fin"),
+    ("fr", "fra"),
+    ("he", "heb"),
+    ("hi", "hin"),
+    ("hu", "hun"),
+    ("it", "ita"),
+    ("ja", "jpn"),
+    ("ko", "kor"),
+    ("la", "lat"),
+    ("nb", "nob"),
+    ("nl", "nld"),
+    ("nn", "nno"),
+    ("no", "nor"),
+    ("pl", "pol"),
+    ("pt", "por"),
+    ("ru", "rus"),
+    ("sv", "swe"),
+    ("tr", "tur"),
+    ("uk", "ukr"),
+    ("zh", "zho"),
+];
+
+/// Three-letter bibliographic codes Calibre also accepts
+const BIBLIOGRAPHIC: &[(&str, &str)] = &[
+    ("cze", "cs"),
+    ("chi", "zh"),
+    ("dut", "nl"),
+    ("fre", "fr"),
+    ("ger", "de"),
+    ("gre", "el"),
+];
+
+/// Language tag for a Calibre language code (`eng` -> `en`)
+///
+/// `current` is the book's own tag; it is kept when it names the same
+/// language, so a region such as `en-GB` survives a round trip.
+pub(crate) fn from_calibre(code: &str, current: Option<&str>) -> String {
+    let code = code.trim();
+    let lower = code.to_ascii_lowercase();
+    let tag = CODES
+        .iter()
+        .find(|(_, three)| *three == lower)
+        .map(|(two, _)| *two)
+        .or_else(|| {
+            BIBLIOGRAPHIC
+                .iter()
+                .find(|(three, _)| *three == lower)
+                .map(|(_, two)| *two)
+        })
+        .unwrap_or(code)
+        .to_string();
+    match current {
+        Some(current) if primary(current) == primary(&tag) => current.to_string(),
+        _ => tag,
+    }
+}
+
+/// Calibre language code for a language tag (`en-GB` -> `eng`)
+pub(crate) fn to_calibre(tag: &str) -> String {
+    let primary = primary(tag);
+    CODES
+        .iter()
+        .find(|(two, _)| *two == primary)
+        .map_or(primary, |(_, three)| three.to_string())
+}
+
+fn primary(tag: &str) -> String {
+    tag.split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
@@ -0,0 +1,677 @@
+use crate::bk_format::{BkMetadata, BkParser};
+use crate::calibre::error::CalibreError;
+use crate::calibre::language;
+use crate::export::xml::escape;
+use crate::models::{generate_chapter_id, Book};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Name of the metadata file in a Calibre book folder
+pub const METADATA_FILE: &str = "metadata.opf";
+
+/// Name Calibre gives the cover image in a book folder
+pub const COVER_FILE: &str = "cover.jpg";
+
+const DC_NAMESPACE: &str = "http://purl.org/dc/elements/1.1/";
+const OPF_NAMESPACE: &str = "http://www.idpf.org/2007/opf";
+
+/// Id Calibre gives the identifier holding the book's UUID
+const UUID_ID: &str = "uuid_id";
+
+/// Indentation of the children of `<metadata>`, as Calibre writes it
+const INDENT: &str = "        ";
+
+/// Metadata of a book in a Calibre library, as kept in its `metadata.opf`
+///
+/// Fields a `Book` has no place for, such as the publisher, comments and
+/// custom columns, are left as they were when the file is written back.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CalibreMetadata {
+    /// Calibre's UUID for the book, used as `Book.id`
+    pub uuid: Option<Uuid>,
+    /// Id of the book in its library
+    pub calibre_id: Option<String>,
+    pub title: Option<String>,
+    /// Title for sorting, such as "Iron Road, The"
+    pub title_sort: Option<String>,
+    pub authors: Vec<String>,
+    /// Sort form of all the authors, such as "Chhabra, Tej & Silva, Ana"
+    pub author_sort: Option<String>,
+    pub series: Option<String>,
+    pub series_index: Option<f64>,
+    pub tags: Vec<String>,
+    /// Identifiers other than the UUID and library id, by lowercase scheme
+    pub identifiers: BTreeMap<String, String>,
+    /// Calibre language code, such as `eng`
+    pub language: Option<String>,
+    /// Cover image named in the `<guide>`, relative to the book folder
+    pub cover: Option<String>,
+    source: Option<Source>,
+}
+
+/// The document metadata was read from, so it can be updated in place
+#[derive(Debug, Clone, PartialEq)]
+struct Source {
+    text: String,
+    /// The `<metadata ...>` start tag
+    start_tag: Range<usize>,
+    /// Start of `</metadata>`
+    end_tag: usize,
+    /// Children of `<metadata>` that are copied through unchanged
+    kept: Vec<Range<usize>>,
+    unique_identifier: Option<String>,
+}
+
+/// A child of `<metadata>`
+struct Element {
+    /// Local name, such as `creator` or `meta`
+    name: String,
+    /// Attributes by local name, so `opf:file-as` is `file-as`
+    attrs: HashMap<String, String>,
+    text: String,
+    span: Range<usize>,
+}
+
+impl Element {
+    fn new(e: &BytesStart, span: Range<usize>) -> Self {
+        let attrs = e
+            .attributes()
+            .flatten()
+            .filter_map(|a| {
+                let value = a.unescape_value().ok()?.into_owned();
+                Some((local(a.key.local_name().as_ref()), value))
+            })
+            .collect();
+        Self {
+            name: local(e.local_name().as_ref()),
+            attrs,
+            text: String::new(),
+            span,
+        }
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.get(name).map(String::as_str)
+    }
+}
+
+impl CalibreMetadata {
+    /// `metadata.opf` in the folder of a .bk file
+    pub fn sidecar_path(book_path: &Path) -> PathBuf {
+        book_path.with_file_name(METADATA_FILE)
+    }
+
+    /// Read a `metadata.opf` file
+    pub fn load(path: &Path) -> Result<Self, CalibreError> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Write the metadata to `path`
+    pub fn save(&self, path: &Path) -> Result<(), CalibreError> {
+        std::fs::write(path, self.to_opf())?;
+        Ok(())
+    }
+
+    /// Read the metadata of an OPF package document
+    ///
+    /// Both the OPF 2 files Calibre keeps in its library and OPF 3 package
+    /// documents, with their `refines` metadata, are understood.
+    pub fn parse(opf: &str) -> Result<Self, CalibreError> {
+        let mut reader = Reader::from_str(opf);
+        let mut path: Vec<String> = Vec::new();
+        let mut children: Vec<Element> = Vec::new();
+        let mut current: Option<Element> = None;
+        let mut start_tag = None;
+        let mut end_tag = None;
+        let mut unique_identifier = None;
+        let mut cover = None;
+
+        loop {
+            let start = reader.buffer_position();
+            let event = reader.read_event()?;
+            let end = reader.buffer_position();
+            let in_metadata = path.len() == 2 && path[1] == "metadata";
+            match event {
+                Event::Start(e) => {
+                    let element = Element::new(&e, start..end);
+                    match (path.len(), element.name.as_str()) {
+                        (0, "package") => {
+                            unique_identifier =
+                                element.attr("unique-identifier").map(str::to_string)
+                        }
+                        (1, "metadata") => start_tag = Some(start..end),
+                        _ => {}
+                    }
+                    path.push(element.name.clone());
+                    if in_metadata {
+                        current = Some(element);
+                    }
+                }
+                Event::Empty(e) => {
+                    let element = Element::new(&e, start..end);
+                    if in_metadata {
+                        children.push(element);
+                    } else if path.last().is_some_and(|p| p == "guide")
+                        && element.name == "reference"
+                        && element.attr("type") == Some("cover")
+                    {
+                        cover = element.attr("href").map(str::to_string);
+                    }
+                }
+                Event::Text(t) => {
+                    if let Some(element) = &mut current {
+                        element.text.push_str(&t.unescape()?);
+                    }
+                }
+                Event::CData(t) => {
+                    if let Some(element) = &mut current {
+                        element.text.push_str(&String::from_utf8_lossy(&t));
+                    }
+                }
+                Event::End(_) => {
+                    let closed = path.pop();
+                    if path.len() == 2 && path[1] == "metadata" {
+                        if let Some(mut element) = current.take() {
+                            element.span.end = end;
+                            children.push(element);
+                        }
+                    } else if path.len() == 1 && closed.as_deref() == Some("metadata") {
+                        end_tag = Some(start);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        let (Some(start_tag), Some(end_tag)) = (start_tag, end_tag) else {
+            return Err(CalibreError::NotOpf);
+        };
+        Ok(Self {
+            cover,
+            source: Some(Source {
+                text: opf.to_string(),
+                start_tag,
+                end_tag,
+                kept: kept_spans(&children),
+                unique_identifier,
+            }),
+            ..Self::from_elements(&children)
+        })
+    }
+
+    fn from_elements(children: &[Element]) -> Self {
+        let refinements = refinements(children);
+        let refined = |element: &Element, property: &str| refined(&refinements, element, property);
+
+        let mut metadata = Self::default();
+        for element in children {
+            let text = collapse(&element.text);
+            match element.name.as_str() {
+                "title" if metadata.title.is_none() && !text.is_empty() => {
+                    metadata.title = Some(text)
+                }
+                "creator" if is_author(element, refined(element, "role")) => {
+                    if metadata.author_sort.is_none() {
+                        metadata.author_sort = element
+                            .attr("file-as")
+                            .or_else(|| refined(element, "file-as"))
+                            .map(collapse)
+                            .filter(|sort| !sort.is_empty());
+                    }
+                    if !text.is_empty() {
+                        metadata.authors.push(text);
+                    }
+                }
+                "identifier" => {
+                    if let Some((scheme, value)) = identifier(element, &text) {
+                        metadata.add_identifier(scheme, value);
+                    }
+                }
+                "language" if metadata.language.is_none() && !text.is_empty() => {
+                    metadata.language = Some(text)
+                }
+                "subject" if !text.is_empty() && !metadata.tags.contains(&text) => {
+                    metadata.tags.push(text)
+                }
+                "meta" => {
+                    let content = element.attr("content").map(collapse);
+                    match element.attr("name") {
+                        Some("calibre:series") => {
+                            metadata.series = content.filter(|s| !s.is_empty())
+                        }
+                        Some("calibre:series_index") => {
+                            metadata.series_index = content.and_then(|c| c.parse().ok())
+                        }
+                        Some("calibre:title_sort") => {
+                            metadata.title_sort = content.filter(|s| !s.is_empty())
+                        }
+                        _ => {}
+                    }
+                    // An OPF 3 series, unless calibre:series names one
+                    if element.attr("property") == Some("belongs-to-collection")
+                        && refined(element, "collection-type").is_none_or(|t| t == "series")
+                        && metadata.series.is_none()
+                        && !text.is_empty()
+                    {
+                        metadata.series = Some(text);
+                        metadata.series_index =
+                            refined(element, "group-position").and_then(|p| p.parse().ok());
+                    }
+                }
+                _ => {}
+            }
+        }
+        metadata
+    }
+
+    fn add_identifier(&mut self, scheme: String, value: String) {
+        match scheme.as_str() {
+            "uuid" => {
+                if self.uuid.is_none() {
+                    self.uuid = Uuid::parse_str(&value).ok();
+                }
+            }
+            "calibre" => {
+                self.calibre_id.get_or_insert(value);
+            }
+            _ => {
+                self.identifiers.entry(scheme).or_insert(value);
+            }
+        }
+    }
+
+    /// Metadata for a book that is not yet in a Calibre library; the book's
+    /// id becomes its UUID
+    pub fn from_book(book: &Book) -> Self {
+        let mut metadata = Self {
+            uuid: Some(book.id),
+            ..Self::default()
+        };
+        metadata.update_from(book);
+        metadata
+    }
+
+    /// Copy the metadata the book sets into these fields
+    ///
+    /// Fields the book leaves empty keep their values, and the UUID, which
+    /// Calibre owns, is never changed.
+    pub fn update_from(&mut self, book: &Book) {
+        self.merge(&BkMetadata {
+            id: None,
+            ..BkMetadata::from_book(book)
+        });
+    }
+
+    /// Copy the fields a .bk file sets, leaving the others as they are
+    ///
+    /// A declared id only becomes the UUID when there is none yet. The title
+    /// sort is dropped if the title changed, since it would no longer match;
+    /// Calibre regenerates it.
+    fn merge(&mut self, bk: &BkMetadata) {
+        if let Some(title) = &bk.title {
+            if self.title.as_ref() != Some(title) {
+                self.title_sort = None;
+            }
+            self.title = Some(title.clone());
+        }
+        if self.uuid.is_none() {
+            self.uuid = bk.id;
+        }
+        let authors: Vec<String> = bk
+            .author
+            .iter()
+            .flat_map(|author| author.split(" & "))
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(str::to_string)
+            .collect();
+        if !authors.is_empty() {
+            self.authors = authors;
+        }
+        if bk.author_sort.is_some() {
+            self.author_sort = bk.author_sort.clone();
+        }
+        if bk.series.is_some() {
+            self.series = bk.series.clone();
+            self.series_index = bk.series_index.or(self.series_index);
+        } else if bk.series_index.is_some() {
+            self.series_index = bk.series_index;
+        }
+        if !bk.tags.is_empty() {
+            self.tags = bk.tags.clone();
+        }
+        if !bk.identifiers.is_empty() {
+            self.identifiers = bk.identifiers.clone();
+        }
+        if let Some(code) = &bk.language {
+            self.language = Some(language::to_calibre(code));
+        }
+    }
+
+    /// Set the book's metadata from these fields
+    ///
+    /// Several authors are joined with " & ", as Calibre displays them. When
+    /// the UUID gives the book a new id, chapter ids are derived from it as
+    /// the parser would.
+    pub fn apply_to(&self, book: &mut Book) {
+        if let Some(uuid) = self.uuid.filter(|uuid| *uuid != book.id) {
+            book.id = uuid;
+            for chapter in &mut book.chapters {
+                chapter.id = generate_chapter_id(&uuid, chapter.order, &chapter.title);
+            }
+        }
+        if let Some(title) = &self.title {
+            book.title = title.clone();
+        }
+        if !self.authors.is_empty() {
+            book.author = self.authors.join(" & ");
+        }
+        book.author_sort = self.author_sort.clone();
+        book.series = self.series.clone();
+        book.series_index = self.series_index;
+        book.tags = self.tags.clone();
+        book.identifiers = self.identifiers.clone();
+        if let Some(code) = &self.language {
+            book.language = Some(language::from_calibre(code, book.language.as_deref()));
+        }
+    }
+
+    /// Cover image in a book folder: the one the guide names, or `cover.jpg`
+    pub fn cover_path(&self, folder: &Path) -> Option<PathBuf> {
+        let path = folder.join(self.cover.as_deref().unwrap_or(COVER_FILE));
+        path.is_file().then_some(path)
+    }
+
+    /// Serialize to a `metadata.opf` document
+    ///
+    /// A document that was read is updated in place: only the elements for
+    /// these fields are replaced, and everything else is copied through.
+    pub fn to_opf(&self) -> String {
+        let Some(source) = &self.source else {
+            return self.new_opf();
+        };
+        let uuid_id = source.unique_identifier.as_deref().unwrap_or(UUID_ID);
+
+        let mut out = String::with_capacity(source.text.len());
+        out.push_str(&source.text[..source.start_tag.start]);
+        out.push_str(&with_namespaces(&source.text[source.start_tag.clone()]));
+        out.push('\n');
+        let kept = source.kept.iter().map(|span| &source.text[span.clone()]);
+        for line in self
+            .dc_elements(uuid_id)
+            .iter()
+            .map(String::as_str)
+            .chain(kept)
+            .chain(self.calibre_metas().iter().map(String::as_str))
+        {
+            out.push_str(INDENT);
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push_str("    ");
+        out.push_str(&source.text[source.end_tag..]);
+        out
+    }
+
+    fn new_opf(&self) -> String {
+        let mut out = format!(
+            "<?xml version='1.0' encoding='utf-8'?>\n\
+             <package xmlns=\"{opf}\" unique-identifier=\"{uuid_id}\" version=\"2.0\">\n    \
+             <metadata xmlns:dc=\"{dc}\" xmlns:opf=\"{opf}\">\n",
+            opf = OPF_NAMESPACE,
+            dc = DC_NAMESPACE,
+            uuid_id = UUID_ID
+        );
+        for line in self
+            .dc_elements(UUID_ID)
+            .into_iter()
+            .chain(self.calibre_metas())
+        {
+            out.push_str(INDENT);
+            out.push_str(&line);
+            out.push('\n');
+        }
+        out.push_str("    </metadata>\n");
+        if let Some(cover) = &self.cover {
+            out.push_str(&format!(
+                "    <guide>\n        <reference type=\"cover\" title=\"Cover\" href=\"{}\"/>\n    </guide>\n",
+                escape(cover)
+            ));
+        }
+        out.push_str("</package>\n");
+        out
+    }
+
+    /// Dublin Core elements for the fields, in the order Calibre writes them
+    fn dc_elements(&self, uuid_id: &str) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(id) = &self.calibre_id {
+            lines.push(format!(
+                "<dc:identifier opf:scheme=\"calibre\" id=\"calibre_id\">{}</dc:identifier>",
+                escape(id)
+            ));
+        }
+        if let Some(uuid) = self.uuid {
+            lines.push(format!(
+                "<dc:identifier opf:scheme=\"uuid\" id=\"{}\">{}</dc:identifier>",
+                escape(uuid_id),
+                uuid
+            ));
+        }
+        if let Some(title) = &self.title {
+            lines.push(format!("<dc:title>{}</dc:title>", escape(title)));
+        }
+        let file_as = self
+            .author_sort
+            .as_ref()
+            .map(|sort| format!(" opf:file-as=\"{}\"", escape(sort)))
+            .unwrap_or_default();
+        for author in &self.authors {
+            lines.push(format!(
+                "<dc:creator{} opf:role=\"aut\">{}</dc:creator>",
+                file_as,
+                escape(author)
+            ));
+        }
+        for (scheme, value) in &self.identifiers {
+            lines.push(format!(
+                "<dc:identifier opf:scheme=\"{}\">{}</dc:identifier>",
+                escape(&scheme.to_uppercase()),
+                escape(value)
+            ));
+        }
+        if let Some(language) = &self.language {
+            lines.push(format!("<dc:language>{}</dc:language>", escape(language)));
+        }
+        for tag in &self.tags {
+            lines.push(format!("<dc:subject>{}</dc:subject>", escape(tag)));
+        }
+        lines
+    }
+
+    fn calibre_metas(&self) -> Vec<String> {
+        let mut metas = Vec::new();
+        let mut meta = |name: &str, content: &str| {
+            metas.push(format!(
+                "<meta name=\"calibre:{}\" content=\"{}\"/>",
+                name,
+                escape(content)
+            ))
+        };
+        if let Some(series) = &self.series {
+            meta("series", series);
+            // Calibre writes whole indexes as "2.0"
+            let index = self.series_index.unwrap_or(1.0);
+            if index.fract() == 0.0 {
+                meta("series_index", &format!("{:.1}", index));
+            } else {
+                meta("series_index", &index.to_string());
+            }
+        }
+        if let Some(title_sort) = &self.title_sort {
+            meta("title_sort", title_sort);
+        }
+        metas
+    }
+}
+
+/// Update a book from the `metadata.opf` in its folder, if there is one
+///
+/// Returns whether a file was found.
+pub fn sync_from_calibre(book: &mut Book, book_path: &Path) -> Result<bool, CalibreError> {
+    let path = CalibreMetadata::sidecar_path(book_path);
+    if !path.is_file() {
+        return Ok(false);
+    }
+    CalibreMetadata::load(&path)?.apply_to(book);
+    Ok(true)
+}
+
+/// Write the metadata a .bk file sets to the `metadata.opf` in its folder
+///
+/// `content` is the text of the .bk file. An existing file is updated in
+/// place, and only for the fields the .bk file sets, so a sparse .bk file
+/// leaves Calibre's tags, identifiers and the like alone. Only an `@id:`
+/// written in the file can become the UUID. A new file points at
+/// `cover.jpg` if the folder has one.
+pub fn sync_to_calibre(content: &str, book_path: &Path) -> Result<(), CalibreError> {
+    let bk = BkParser::read_metadata(content)?;
+    let path = CalibreMetadata::sidecar_path(book_path);
+    let mut metadata = if path.is_file() {
+        CalibreMetadata::load(&path)?
+    } else {
+        let cover = path.with_file_name(COVER_FILE);
+        CalibreMetadata {
+            cover: cover.is_file().then(|| COVER_FILE.to_string()),
+            ..CalibreMetadata::default()
+        }
+    };
+    metadata.merge(&bk);
+    metadata.save(&path)
+}
+
+fn local(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).into_owned()
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Values of OPF 3 `<meta refines="#id" property="...">`, by id and property
+type Refinements = HashMap<(String, String), String>;
+
+fn refinements(children: &[Element]) -> Refinements {
+    children
+        .iter()
+        .filter(|element| element.name == "meta")
+        .filter_map(|element| {
+            let target = element.attr("refines")?.trim_start_matches('#');
+            let property = element.attr("property")?;
+            Some((
+                (target.to_string(), property.to_string()),
+                collapse(&element.text),
+            ))
+        })
+        .collect()
+}
+
+/// Value of a refinement of an element
+fn refined<'a>(refinements: &'a Refinements, element: &Element, property: &str) -> Option<&'a str> {
+    element
+        .attr("id")
+        .and_then(|id| refinements.get(&(id.to_string(), property.to_string())))
+        .map(String::as_str)
+}
+
+/// Children of `<metadata>` that are copied through rather than rewritten
+///
+/// Refinements of rewritten elements are dropped along with them.
+fn kept_spans(children: &[Element]) -> Vec<Range<usize>> {
+    let refinements = refinements(children);
+    let rewritten = |element: &Element| match element.name.as_str() {
+        "title" | "language" | "subject" => true,
+        "creator" => is_author(element, refined(&refinements, element, "role")),
+        "identifier" => identifier(element, &collapse(&element.text)).is_some(),
+        "meta" => {
+            matches!(
+                element.attr("name"),
+                Some("calibre:series" | "calibre:series_index" | "calibre:title_sort")
+            ) || element.attr("property") == Some("belongs-to-collection")
+        }
+        _ => false,
+    };
+    let rewritten_ids: HashSet<&str> = children
+        .iter()
+        .filter(|element| rewritten(element))
+        .filter_map(|element| element.attr("id"))
+        .collect();
+    children
+        .iter()
+        .filter(|element| {
+            !rewritten(element)
+                && !element
+                    .attr("refines")
+                    .is_some_and(|target| rewritten_ids.contains(target.trim_start_matches('#')))
+        })
+        .map(|element| element.span.clone())
+        .collect()
+}
+
+/// Creators without a role are authors, as in OPF 2
+fn is_author(element: &Element, refined_role: Option<&str>) -> bool {
+    element
+        .attr("role")
+        .or(refined_role)
+        .is_none_or(|role| role == "aut" || role == "marc:relators:aut")
+}
+
+/// Scheme and value of an identifier, if it has a recognisable scheme
+///
+/// The scheme comes from `opf:scheme`, from a prefix such as `urn:isbn:` or
+/// `isbn:`, or from the ids Calibre uses for its own identifiers.
+fn identifier(element: &Element, text: &str) -> Option<(String, String)> {
+    if text.is_empty() {
+        return None;
+    }
+    if let Some(scheme) = element.attr("scheme") {
+        let value = text
+            .strip_prefix("urn:uuid:")
+            .filter(|_| scheme.eq_ignore_ascii_case("uuid"))
+            .unwrap_or(text);
+        return Some((scheme.to_lowercase(), value.to_string()));
+    }
+    let rest = text.strip_prefix("urn:").unwrap_or(text);
+    if let Some((scheme, value)) = rest.split_once(':') {
+        if !scheme.is_empty()
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric())
+            && !value.starts_with("//")
+            && !value.is_empty()
+        {
+            return Some((scheme.to_lowercase(), value.to_string()));
+        }
+    }
+    match element.attr("id") {
+        Some("calibre_id") => Some(("calibre".to_string(), text.to_string())),
+        Some(UUID_ID) => Some(("uuid".to_string(), text.to_string())),
+        _ => None,
+    }
+}
+
+/// Add the `dc` and `opf` namespace declarations the rewritten elements use
+fn with_namespaces(start_tag: &str) -> String {
+    let mut tag = start_tag.trim_end_matches('>').to_string();
+    if !tag.contains("xmlns:dc=") {
+        tag.push_str(&format!(" xmlns:dc=\"{}\"", DC_NAMESPACE));
+    }
+    if !tag.contains("xmlns:opf=") {
+        tag.push_str(&format!(" xmlns:opf=\"{}\"", OPF_NAMESPACE));
+    }
+    tag.push('>');
+    tag
+}
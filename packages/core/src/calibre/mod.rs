@@ -0,0 +1,11 @@
+mod error;
+mod language;
+mod metadata;
+
+#[cfg(test)]
+mod tests;
+
+pub use error::CalibreError;
+pub use metadata::{
+    sync_from_calibre, sync_to_calibre, CalibreMetadata, COVER_FILE, METADATA_FILE,
+};
@@ -0,0 +1,311 @@
+use crate::bk_format::{BkParser, BkWriter};
+use crate::calibre::{sync_from_calibre, sync_to_calibre, CalibreError, CalibreMetadata};
+use crate::models::{generate_chapter_id, Book};
+use chrono::{TimeZone, Utc};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Temporary directory removed when dropped
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("bk-calibre-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A `metadata.opf` as Calibre writes it in a library book folder
+const LIBRARY_OPF: &str = r#"<?xml version='1.0' encoding='utf-8'?>
+<package xmlns="http://www.idpf.org/2007/opf" unique-identifier="uuid_id" version="2.0">
+    <metadata xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:opf="http://www.idpf.org/2007/opf">
+        <dc:identifier opf:scheme="calibre" id="calibre_id">42</dc:identifier>
+        <dc:identifier opf:scheme="uuid" id="uuid_id">6f1c2f9e-8f6b-4c47-9d3a-0e5b8f6f7a10</dc:identifier>
+        <dc:title>The Iron Road</dc:title>
+        <dc:creator opf:file-as="Chhabra, Tej &amp; Silva, Ana" opf:role="aut">Tej Chhabra</dc:creator>
+        <dc:creator opf:file-as="Chhabra, Tej &amp; Silva, Ana" opf:role="aut">Ana Silva</dc:creator>
+        <dc:contributor opf:file-as="calibre" opf:role="bkp">calibre (7.3.0) [https://calibre-ebook.com]</dc:contributor>
+        <dc:date>2024-03-01T00:00:00+00:00</dc:date>
+        <dc:description>&lt;p&gt;A smith's apprentice
+walks north.&lt;/p&gt;</dc:description>
+        <dc:publisher>Forge Press</dc:publisher>
+        <dc:identifier opf:scheme="ISBN">9780000000002</dc:identifier>
+        <dc:identifier opf:scheme="GOODREADS">12345</dc:identifier>
+        <dc:language>eng</dc:language>
+        <dc:subject>Fantasy</dc:subject>
+        <dc:subject>Historical</dc:subject>
+        <meta name="calibre:author_link_map" content="{&quot;Tej Chhabra&quot;: &quot;&quot;, &quot;Ana Silva&quot;: &quot;&quot;}"/>
+        <meta name="calibre:series" content="The Forge Cycle"/>
+        <meta name="calibre:series_index" content="2.0"/>
+        <meta name="calibre:timestamp" content="2024-03-05T10:00:00+00:00"/>
+        <meta name="calibre:title_sort" content="Iron Road, The"/>
+        <meta name="calibre:user_metadata:#draft" content="{&quot;#value#&quot;: 3}"/>
+    </metadata>
+    <guide>
+        <reference type="cover" title="Cover" href="cover.jpg"/>
+    </guide>
+</package>
+"#;
+
+fn sample_book() -> Book {
+    let content = "@title: Draft Title
+@author: Tej Chhabra
+@language: en-GB
+
+#chapter: The Forge
+The morning sun cracked over the horizon.
+
+#chapter: The Road
+The road stretched on.
+";
+    let time = Utc.with_ymd_and_hms(2025, 12, 1, 9, 30, 0).unwrap();
+    BkParser::parse_string(content, time, time).unwrap()
+}
+
+#[test]
+fn test_parse_library_opf() {
+    let metadata = CalibreMetadata::parse(LIBRARY_OPF).unwrap();
+
+    assert_eq!(
+        metadata.uuid,
+        Some(Uuid::parse_str("6f1c2f9e-8f6b-4c47-9d3a-0e5b8f6f7a10").unwrap())
+    );
+    assert_eq!(metadata.calibre_id.as_deref(), Some("42"));
+    assert_eq!(metadata.title.as_deref(), Some("The Iron Road"));
+    assert_eq!(metadata.title_sort.as_deref(), Some("Iron Road, The"));
+    assert_eq!(metadata.authors, ["Tej Chhabra", "Ana Silva"]);
+    assert_eq!(
+        metadata.author_sort.as_deref(),
+        Some("Chhabra, Tej & Silva, Ana")
+    );
+    assert_eq!(metadata.series.as_deref(), Some("The Forge Cycle"));
+    assert_eq!(metadata.series_index, Some(2.0));
+    assert_eq!(metadata.tags, ["Fantasy", "Historical"]);
+    assert_eq!(metadata.identifiers.len(), 2);
+    assert_eq!(metadata.identifiers["isbn"], "9780000000002");
+    assert_eq!(metadata.identifiers["goodreads"], "12345");
+    assert_eq!(metadata.language.as_deref(), Some("eng"));
+    assert_eq!(metadata.cover.as_deref(), Some("cover.jpg"));
+
+    // Writing back is stable, and keeps what the fields do not cover
+    let written = metadata.to_opf();
+    assert_eq!(CalibreMetadata::parse(&written).unwrap().to_opf(), written);
+    let reread = CalibreMetadata::parse(&written).unwrap();
+    assert_eq!(reread.title_sort, metadata.title_sort);
+    assert_eq!(reread.identifiers, metadata.identifiers);
+    assert_eq!(written.lines().count(), LIBRARY_OPF.lines().count());
+}
+
+#[test]
+fn test_apply_to_book_and_write_bk() {
+    let mut book = sample_book();
+    let metadata = CalibreMetadata::parse(LIBRARY_OPF).unwrap();
+    metadata.apply_to(&mut book);
+
+    assert_eq!(book.id, metadata.uuid.unwrap());
+    assert_eq!(book.title, "The Iron Road");
+    assert_eq!(book.author, "Tej Chhabra & Ana Silva");
+    // The file only says "eng", so the book's region is kept
+    assert_eq!(book.language.as_deref(), Some("en-GB"));
+    // Chapter ids follow the new book id, as after a reload
+    assert_eq!(
+        book.chapters[1].id,
+        generate_chapter_id(&book.id, 1, "The Road")
+    );
+
+    let written = BkWriter::write_string(&book);
+    assert!(written.contains("@author_sort: Chhabra, Tej & Silva, Ana\n"));
+    assert!(written.contains("@series: The Forge Cycle\n@series_index: 2\n"));
+    assert!(written.contains("@tags: Fantasy, Historical\n"));
+    assert!(written.contains("@identifiers: goodreads:12345, isbn:9780000000002\n"));
+
+    let time = Utc.with_ymd_and_hms(2025, 12, 1, 9, 30, 0).unwrap();
+    let reread = BkParser::parse_string(&written, time, time).unwrap();
+    assert_eq!(reread.id, book.id);
+    assert_eq!(reread.chapters[1].id, book.chapters[1].id);
+    assert_eq!(reread.series_index, Some(2.0));
+    assert_eq!(reread.tags, book.tags);
+    assert_eq!(reread.identifiers, book.identifiers);
+}
+
+#[test]
+fn test_update_keeps_fields_calibre_owns() {
+    let mut book = sample_book();
+    let mut metadata = CalibreMetadata::parse(LIBRARY_OPF).unwrap();
+    metadata.apply_to(&mut book);
+
+    book.title = "The Iron Road North".to_string();
+    book.series_index = Some(2.5);
+    book.tags.retain(|t| t != "Historical");
+    book.tags.push("Coming of age".to_string());
+    book.identifiers.remove("goodreads");
+    book.language = Some("fr".to_string());
+    metadata.update_from(&book);
+    let opf = metadata.to_opf();
+
+    assert!(opf.contains("<dc:title>The Iron Road North</dc:title>"));
+    assert!(opf.contains("<meta name=\"calibre:series_index\" content=\"2.5\"/>"));
+    assert!(opf.contains("<dc:subject>Coming of age</dc:subject>"));
+    assert!(!opf.contains("Historical"));
+    assert!(!opf.contains("GOODREADS"));
+    assert!(opf.contains("<dc:language>fra</dc:language>"));
+    // The old title sort no longer applies
+    assert!(!opf.contains("calibre:title_sort"));
+    // Everything without a place in the book is copied through
+    assert!(opf.contains("<dc:publisher>Forge Press</dc:publisher>"));
+    assert!(opf.contains("&lt;p&gt;A smith's apprentice\nwalks north.&lt;/p&gt;"));
+    assert!(opf.contains("calibre:user_metadata:#draft"));
+    assert!(
+        opf.contains("<dc:identifier opf:scheme=\"calibre\" id=\"calibre_id\">42</dc:identifier>")
+    );
+    assert!(opf.contains("<reference type=\"cover\" title=\"Cover\" href=\"cover.jpg\"/>"));
+    assert_eq!(opf.matches("<dc:creator").count(), 2);
+    assert_eq!(opf.matches("calibre:series\"").count(), 1);
+
+    let reread = CalibreMetadata::parse(&opf).unwrap();
+    assert_eq!(reread.title.as_deref(), Some("The Iron Road North"));
+    assert_eq!(reread.tags, ["Fantasy", "Coming of age"]);
+    assert_eq!(reread.uuid, Some(book.id));
+    assert_eq!(reread.author_sort, book.author_sort);
+}
+
+#[test]
+fn test_parse_opf3_package() {
+    let opf = r##"<?xml version="1.0" encoding="utf-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="id">urn:uuid:00000000-0000-4000-8000-000000000001</dc:identifier>
+    <dc:identifier>urn:isbn:9780000000019</dc:identifier>
+    <dc:identifier>https://example.com/books/1</dc:identifier>
+    <dc:title>Salt</dc:title>
+    <dc:creator id="author">Ana Lima</dc:creator>
+    <meta refines="#author" property="file-as">Lima, Ana</meta>
+    <dc:creator id="editor">Ben Ode</dc:creator>
+    <meta refines="#editor" property="role" scheme="marc:relators">edt</meta>
+    <dc:language>pt-BR</dc:language>
+    <meta property="belongs-to-collection" id="c1">Sea Trilogy</meta>
+    <meta refines="#c1" property="collection-type">series</meta>
+    <meta refines="#c1" property="group-position">3</meta>
+    <meta property="dcterms:modified">2024-01-01T00:00:00Z</meta>
+  </metadata>
+  <manifest/>
+</package>"##;
+    let mut metadata = CalibreMetadata::parse(opf).unwrap();
+
+    assert_eq!(metadata.authors, ["Ana Lima"]);
+    assert_eq!(metadata.author_sort.as_deref(), Some("Lima, Ana"));
+    assert_eq!(metadata.series.as_deref(), Some("Sea Trilogy"));
+    assert_eq!(metadata.series_index, Some(3.0));
+    assert_eq!(metadata.identifiers.len(), 1);
+    assert_eq!(metadata.identifiers["isbn"], "9780000000019");
+    assert!(metadata.uuid.is_some());
+
+    let mut book = sample_book();
+    metadata.apply_to(&mut book);
+    assert_eq!(book.language.as_deref(), Some("pt-BR"));
+
+    // Rewritten in OPF 2 form, with the namespace it needs and the unique
+    // identifier still pointing at the UUID
+    metadata.update_from(&book);
+    let written = metadata.to_opf();
+    assert!(written.contains("xmlns:opf=\"http://www.idpf.org/2007/opf\""));
+    assert!(written.contains(&format!(
+        "<dc:identifier opf:scheme=\"uuid\" id=\"id\">{}</dc:identifier>",
+        book.id
+    )));
+    assert!(written.contains("<dc:creator id=\"editor\">Ben Ode</dc:creator>"));
+    assert!(written.contains("property=\"role\""));
+    assert!(!written.contains("group-position"));
+    assert!(!written.contains("property=\"file-as\""));
+    assert!(written.contains("https://example.com/books/1"));
+    assert!(written.contains("<manifest/>"));
+    let reread = CalibreMetadata::parse(&written).unwrap();
+    assert_eq!(reread.series.as_deref(), Some("Sea Trilogy"));
+    assert_eq!(reread.author_sort.as_deref(), Some("Lima, Ana"));
+    assert_eq!(reread.language.as_deref(), Some("por"));
+
+    assert!(matches!(
+        CalibreMetadata::parse("<package><manifest/></package>"),
+        Err(CalibreError::NotOpf)
+    ));
+}
+
+#[test]
+fn test_sync_with_calibre_folder() {
+    let dir = TempDir::new();
+    let book_path = dir.path().join("The Iron Road.bk");
+    let mut book = sample_book();
+
+    // Without a metadata.opf there is nothing to read
+    assert!(!sync_from_calibre(&mut book, &book_path).unwrap());
+    assert_eq!(book.title, "Draft Title");
+
+    // A new file points at the cover if the folder has one
+    std::fs::write(dir.path().join("cover.jpg"), b"\xff\xd8\xff").unwrap();
+    book.series = Some("The Forge Cycle".to_string());
+    sync_to_calibre(&BkWriter::write_string(&book), &book_path).unwrap();
+    let opf_path = CalibreMetadata::sidecar_path(&book_path);
+    let created = CalibreMetadata::load(&opf_path).unwrap();
+    assert_eq!(created.uuid, Some(book.id));
+    assert_eq!(created.series_index, Some(1.0));
+    assert_eq!(
+        created.cover_path(dir.path()),
+        Some(dir.path().join("cover.jpg"))
+    );
+
+    // Calibre edits the file; the book picks the changes up
+    std::fs::write(&opf_path, LIBRARY_OPF).unwrap();
+    assert!(sync_from_calibre(&mut book, &book_path).unwrap());
+    assert_eq!(book.title, "The Iron Road");
+    assert_eq!(book.tags, ["Fantasy", "Historical"]);
+
+    // Editing the book updates the file in place
+    book.tags.push("Smithing".to_string());
+    sync_to_calibre(&BkWriter::write_string(&book), &book_path).unwrap();
+    let opf = std::fs::read_to_string(&opf_path).unwrap();
+    assert!(opf.contains("<dc:subject>Smithing</dc:subject>"));
+    assert!(opf.contains("<dc:publisher>Forge Press</dc:publisher>"));
+}
+
+#[test]
+fn test_sync_sparse_book_keeps_library_fields() {
+    let dir = TempDir::new();
+    let book_path = dir.path().join("The Iron Road.bk");
+    let opf_path = CalibreMetadata::sidecar_path(&book_path);
+    std::fs::write(&opf_path, LIBRARY_OPF).unwrap();
+
+    // No id, language, tags, series or identifiers: parsing would give the
+    // book a fresh id, which must not reach the file
+    let content = "@title: The Iron Road\n\n#chapter: One\n\nThe forge was cold.\n";
+    sync_to_calibre(content, &book_path).unwrap();
+
+    let synced = CalibreMetadata::load(&opf_path).unwrap();
+    let library = CalibreMetadata::parse(LIBRARY_OPF).unwrap();
+    assert_eq!(synced.uuid, library.uuid);
+    assert_eq!(synced.calibre_id, library.calibre_id);
+    assert_eq!(synced.title_sort.as_deref(), Some("Iron Road, The"));
+    assert_eq!(synced.authors, ["Tej Chhabra", "Ana Silva"]);
+    assert_eq!(synced.author_sort, library.author_sort);
+    assert_eq!(synced.series.as_deref(), Some("The Forge Cycle"));
+    assert_eq!(synced.series_index, Some(2.0));
+    assert_eq!(synced.tags, ["Fantasy", "Historical"]);
+    assert_eq!(synced.identifiers, library.identifiers);
+    assert_eq!(synced.identifiers["isbn"], "9780000000002");
+    assert_eq!(synced.language.as_deref(), Some("eng"));
+
+    // A declared id still never replaces Calibre's UUID
+    let declared = format!("@id: {}\n{}", Uuid::new_v4(), content);
+    sync_to_calibre(&declared, &book_path).unwrap();
+    assert_eq!(CalibreMetadata::load(&opf_path).unwrap().uuid, library.uuid);
+}
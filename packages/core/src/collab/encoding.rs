@@ -0,0 +1,405 @@
+use crate::collab::error::CollabError;
+use crate::collab::ops::{BookField, Op, OpId, OpKind, ReplicaId, VersionVector};
+use chrono::{DateTime, SecondsFormat, Utc};
+use uuid::Uuid;
+
+const MAGIC: &[u8; 4] = b"BKCR";
+const VERSION: u8 = 1;
+
+const TAG_SET_BOOK_FIELD: u8 = 1;
+const TAG_CREATE_CHAPTER: u8 = 2;
+const TAG_PLACE_CHAPTER: u8 = 3;
+const TAG_UNPLACE_CHAPTER: u8 = 4;
+const TAG_SET_CHAPTER_TITLE: u8 = 5;
+const TAG_INSERT_TEXT: u8 = 6;
+const TAG_DELETE_TEXT: u8 = 7;
+const TAG_SET_CHAPTER_LANGUAGE: u8 = 8;
+const TAG_SET_CHAPTER_TIMESTAMPS: u8 = 9;
+
+/// Fewest bytes an encoded op can take: id, prev and tag
+const MIN_OP_LEN: usize = 4;
+/// Fewest bytes a version vector entry can take
+const MIN_VERSION_ENTRY_LEN: usize = 2;
+/// Fewest bytes a run of deleted ids can take: start id and length
+const MIN_RUN_LEN: usize = 3;
+/// Most characters a single delete may remove
+const MAX_DELETE_TARGETS: u64 = 1 << 22;
+
+/// Encode a batch of ops for `book_id` into the binary update format
+///
+/// Layout: `BKCR`, version byte, 16-byte book id, varint op count, then the ops.
+/// Integers are LEB128 varints and strings are length-prefixed UTF-8.
+pub fn encode_update(book_id: Uuid, ops: &[Op]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(book_id.as_bytes());
+    write_varint(&mut out, ops.len() as u64);
+    for op in ops {
+        write_op(&mut out, op);
+    }
+    out
+}
+
+/// Decode an update produced by [`encode_update`], returning its book id and ops
+pub fn decode_update(bytes: &[u8]) -> Result<(Uuid, Vec<Op>), CollabError> {
+    let mut reader = Reader::new(bytes);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(CollabError::InvalidHeader);
+    }
+    let version = reader.byte()?;
+    if version != VERSION {
+        return Err(CollabError::UnsupportedVersion(version));
+    }
+    let book_id = reader.uuid()?;
+    let count = reader.count(MIN_OP_LEN)?;
+    let mut ops = Vec::with_capacity(count);
+    for _ in 0..count {
+        ops.push(reader.op()?);
+    }
+    Ok((book_id, ops))
+}
+
+/// Encode a version vector so a peer can ask for the ops it is missing
+pub fn encode_version(version: &VersionVector) -> Vec<u8> {
+    let entries: Vec<(ReplicaId, u64)> = version.iter().collect();
+    let mut out = Vec::new();
+    write_varint(&mut out, entries.len() as u64);
+    for (replica, counter) in entries {
+        write_varint(&mut out, replica.0);
+        write_varint(&mut out, counter);
+    }
+    out
+}
+
+/// Decode a version vector produced by [`encode_version`]
+pub fn decode_version(bytes: &[u8]) -> Result<VersionVector, CollabError> {
+    let mut reader = Reader::new(bytes);
+    let mut version = VersionVector::new();
+    for _ in 0..reader.count(MIN_VERSION_ENTRY_LEN)? {
+        let replica = ReplicaId(reader.varint()?);
+        version.observe(replica, reader.varint()?);
+    }
+    Ok(version)
+}
+
+fn write_op(out: &mut Vec<u8>, op: &Op) {
+    write_varint(out, op.id.replica.0);
+    write_varint(out, op.id.counter);
+    write_varint(out, op.prev);
+
+    match &op.kind {
+        OpKind::SetBookField { field, value } => {
+            out.push(TAG_SET_BOOK_FIELD);
+            out.push(match field {
+                BookField::Title => 0,
+                BookField::Author => 1,
+                BookField::Dedication => 2,
+                BookField::TargetWords => 3,
+                BookField::Language => 4,
+                BookField::AuthorSort => 5,
+                BookField::Series => 6,
+                BookField::SeriesIndex => 7,
+                BookField::Tags => 8,
+                BookField::Identifiers => 9,
+                BookField::Format => 10,
+                BookField::Hyphenation => 11,
+                BookField::CreatedAt => 12,
+                BookField::UpdatedAt => 13,
+            });
+            write_optional_str(out, value.as_deref());
+        }
+        OpKind::CreateChapter { chapter } => {
+            out.push(TAG_CREATE_CHAPTER);
+            out.extend_from_slice(chapter.as_bytes());
+        }
+        OpKind::PlaceChapter { chapter, origin } => {
+            out.push(TAG_PLACE_CHAPTER);
+            out.extend_from_slice(chapter.as_bytes());
+            write_origin(out, *origin);
+        }
+        OpKind::UnplaceChapter { slot } => {
+            out.push(TAG_UNPLACE_CHAPTER);
+            write_id(out, *slot);
+        }
+        OpKind::SetChapterTitle { chapter, title } => {
+            out.push(TAG_SET_CHAPTER_TITLE);
+            out.extend_from_slice(chapter.as_bytes());
+            write_str(out, title);
+        }
+        OpKind::SetChapterLanguage { chapter, language } => {
+            out.push(TAG_SET_CHAPTER_LANGUAGE);
+            out.extend_from_slice(chapter.as_bytes());
+            write_optional_str(out, language.as_deref());
+        }
+        OpKind::SetChapterTimestamps {
+            chapter,
+            created_at,
+            updated_at,
+        } => {
+            out.push(TAG_SET_CHAPTER_TIMESTAMPS);
+            out.extend_from_slice(chapter.as_bytes());
+            write_str(out, &format_timestamp(*created_at));
+            write_str(out, &format_timestamp(*updated_at));
+        }
+        OpKind::InsertText {
+            chapter,
+            origin,
+            text,
+        } => {
+            out.push(TAG_INSERT_TEXT);
+            out.extend_from_slice(chapter.as_bytes());
+            write_origin(out, *origin);
+            write_str(out, text);
+        }
+        OpKind::DeleteText { chapter, targets } => {
+            out.push(TAG_DELETE_TEXT);
+            out.extend_from_slice(chapter.as_bytes());
+            // Deletions of a selection are consecutive ids, so store them as runs
+            let runs = id_runs(targets);
+            write_varint(out, runs.len() as u64);
+            for (start, len) in runs {
+                write_id(out, start);
+                write_varint(out, len);
+            }
+        }
+    }
+}
+
+/// Collapse ids into `(start, length)` runs of consecutive counters
+fn id_runs(ids: &[OpId]) -> Vec<(OpId, u64)> {
+    let mut runs: Vec<(OpId, u64)> = Vec::new();
+    for id in ids {
+        if let Some((start, len)) = runs.last_mut() {
+            if start.replica == id.replica && start.counter.checked_add(*len) == Some(id.counter) {
+                *len += 1;
+                continue;
+            }
+        }
+        runs.push((*id, 1));
+    }
+    runs
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_str(out: &mut Vec<u8>, value: &str) {
+    write_varint(out, value.len() as u64);
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn write_optional_str(out: &mut Vec<u8>, value: Option<&str>) {
+    match value {
+        Some(value) => {
+            out.push(1);
+            write_str(out, value);
+        }
+        None => out.push(0),
+    }
+}
+
+fn write_id(out: &mut Vec<u8>, id: OpId) {
+    write_varint(out, id.replica.0);
+    write_varint(out, id.counter);
+}
+
+fn write_origin(out: &mut Vec<u8>, origin: Option<OpId>) {
+    match origin {
+        Some(id) => {
+            out.push(1);
+            write_id(out, id);
+        }
+        None => out.push(0),
+    }
+}
+
+/// Cursor over an encoded update
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CollabError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(CollabError::UnexpectedEof)?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    /// Read an item count, rejecting one the rest of the input cannot hold
+    /// when each item takes at least `min_len` bytes
+    fn count(&mut self, min_len: usize) -> Result<usize, CollabError> {
+        let count = self.varint()?;
+        let remaining = (self.bytes.len() - self.position) / min_len;
+        if count > remaining as u64 {
+            return Err(CollabError::UnexpectedEof);
+        }
+        Ok(count as usize)
+    }
+
+    fn byte(&mut self) -> Result<u8, CollabError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, CollabError> {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= 64 {
+                return Err(CollabError::UnexpectedEof);
+            }
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    fn string(&mut self) -> Result<String, CollabError> {
+        let len = self.varint()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| CollabError::InvalidUtf8)
+    }
+
+    fn optional_string(&mut self) -> Result<Option<String>, CollabError> {
+        match self.byte()? {
+            0 => Ok(None),
+            _ => Ok(Some(self.string()?)),
+        }
+    }
+
+    fn uuid(&mut self) -> Result<Uuid, CollabError> {
+        let bytes = self.take(16)?;
+        Ok(Uuid::from_slice(bytes).expect("slice is 16 bytes"))
+    }
+
+    fn timestamp(&mut self) -> Result<DateTime<Utc>, CollabError> {
+        parse_timestamp(&self.string()?).ok_or(CollabError::InvalidTimestamp)
+    }
+
+    fn id(&mut self) -> Result<OpId, CollabError> {
+        let replica = ReplicaId(self.varint()?);
+        Ok(OpId::new(replica, self.varint()?))
+    }
+
+    fn origin(&mut self) -> Result<Option<OpId>, CollabError> {
+        match self.byte()? {
+            0 => Ok(None),
+            _ => Ok(Some(self.id()?)),
+        }
+    }
+
+    fn op(&mut self) -> Result<Op, CollabError> {
+        let id = self.id()?;
+        let prev = self.varint()?;
+
+        let kind = match self.byte()? {
+            TAG_SET_BOOK_FIELD => {
+                let field = match self.byte()? {
+                    0 => BookField::Title,
+                    1 => BookField::Author,
+                    2 => BookField::Dedication,
+                    3 => BookField::TargetWords,
+                    4 => BookField::Language,
+                    5 => BookField::AuthorSort,
+                    6 => BookField::Series,
+                    7 => BookField::SeriesIndex,
+                    8 => BookField::Tags,
+                    9 => BookField::Identifiers,
+                    10 => BookField::Format,
+                    11 => BookField::Hyphenation,
+                    12 => BookField::CreatedAt,
+                    13 => BookField::UpdatedAt,
+                    other => return Err(CollabError::InvalidTag(other)),
+                };
+                let value = self.optional_string()?;
+                OpKind::SetBookField { field, value }
+            }
+            TAG_CREATE_CHAPTER => OpKind::CreateChapter {
+                chapter: self.uuid()?,
+            },
+            TAG_PLACE_CHAPTER => OpKind::PlaceChapter {
+                chapter: self.uuid()?,
+                origin: self.origin()?,
+            },
+            TAG_UNPLACE_CHAPTER => OpKind::UnplaceChapter { slot: self.id()? },
+            TAG_SET_CHAPTER_TITLE => OpKind::SetChapterTitle {
+                chapter: self.uuid()?,
+                title: self.string()?,
+            },
+            TAG_SET_CHAPTER_LANGUAGE => OpKind::SetChapterLanguage {
+                chapter: self.uuid()?,
+                language: self.optional_string()?,
+            },
+            TAG_SET_CHAPTER_TIMESTAMPS => OpKind::SetChapterTimestamps {
+                chapter: self.uuid()?,
+                created_at: self.timestamp()?,
+                updated_at: self.timestamp()?,
+            },
+            TAG_INSERT_TEXT => OpKind::InsertText {
+                chapter: self.uuid()?,
+                origin: self.origin()?,
+                text: self.string()?,
+            },
+            TAG_DELETE_TEXT => {
+                let chapter = self.uuid()?;
+                let mut targets = Vec::new();
+                let mut total = 0u64;
+                for _ in 0..self.count(MIN_RUN_LEN)? {
+                    let start = self.id()?;
+                    let len = self.varint()?;
+                    total = total.saturating_add(len);
+                    if total > MAX_DELETE_TARGETS {
+                        return Err(CollabError::TooLarge);
+                    }
+                    if len > 0 && start.counter.checked_add(len - 1).is_none() {
+                        return Err(CollabError::CounterOverflow);
+                    }
+                    targets.extend(
+                        (0..len).map(|offset| OpId::new(start.replica, start.counter + offset)),
+                    );
+                }
+                OpKind::DeleteText { chapter, targets }
+            }
+            other => return Err(CollabError::InvalidTag(other)),
+        };
+
+        let op = Op { id, prev, kind };
+        if op.last_counter().is_none() {
+            return Err(CollabError::CounterOverflow);
+        }
+        Ok(op)
+    }
+}
+
+/// RFC 3339 with as many fractional digits as needed, so times round-trip exactly
+pub(crate) fn format_timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+pub(crate) fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
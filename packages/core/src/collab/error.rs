@@ -0,0 +1,39 @@
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Errors that can occur while editing or syncing a collaborative book
+#[derive(Error, Debug)]
+pub enum CollabError {
+    #[error("Chapter {0} does not exist in this replica")]
+    UnknownChapter(Uuid),
+
+    #[error("Position {position} is out of bounds (length {len})")]
+    OutOfBounds { position: usize, len: usize },
+
+    #[error("Update is not a bookwriter collaboration update")]
+    InvalidHeader,
+
+    #[error("Unsupported update encoding version: {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Update ended unexpectedly")]
+    UnexpectedEof,
+
+    #[error("Unknown operation tag in update: {0}")]
+    InvalidTag(u8),
+
+    #[error("Update contains an operation counter that overflows")]
+    CounterOverflow,
+
+    #[error("Update deletes more text than a single operation may")]
+    TooLarge,
+
+    #[error("Update contains invalid UTF-8 text")]
+    InvalidUtf8,
+
+    #[error("Update contains an invalid timestamp")]
+    InvalidTimestamp,
+
+    #[error("Update was created for book {found}, expected {expected}")]
+    BookMismatch { expected: Uuid, found: Uuid },
+}
@@ -0,0 +1,14 @@
+mod encoding;
+mod error;
+mod ops;
+mod replica;
+mod sequence;
+
+#[cfg(test)]
+mod tests;
+
+pub use encoding::{decode_update, decode_version, encode_update, encode_version};
+pub use error::CollabError;
+pub use ops::{BookField, Op, OpId, OpKind, ReplicaId, VersionVector};
+pub use replica::BookReplica;
+pub use sequence::Sequence;
@@ -0,0 +1,156 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Identifier of a peer taking part in collaborative editing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ReplicaId(pub u64);
+
+/// Lamport timestamp that uniquely identifies an operation or sequence element
+///
+/// Ordering compares the counter first and breaks ties with the replica,
+/// which gives every replica the same total order over concurrent edits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OpId {
+    pub counter: u64,
+    pub replica: ReplicaId,
+}
+
+impl OpId {
+    pub fn new(replica: ReplicaId, counter: u64) -> Self {
+        Self { counter, replica }
+    }
+}
+
+/// Book-level fields edited as last-writer-wins registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookField {
+    Title,
+    Author,
+    Dedication,
+    TargetWords,
+    Language,
+    AuthorSort,
+    Series,
+    SeriesIndex,
+    /// Tags as a whole, in their `@tags:` form
+    Tags,
+    /// Identifiers as a whole, in their `@identifiers:` form
+    Identifiers,
+    Format,
+    /// Custom hyphenations as a whole, in their `@hyphenation:` form
+    Hyphenation,
+    /// Creation time, in RFC 3339 form
+    CreatedAt,
+    /// Last modification time, in RFC 3339 form
+    UpdatedAt,
+}
+
+/// A single replicated change
+#[derive(Debug, Clone, PartialEq)]
+pub struct Op {
+    pub id: OpId,
+    /// Last counter used by the same replica before this op (0 for its first op)
+    pub prev: u64,
+    pub kind: OpKind,
+}
+
+/// The kinds of change a replica can broadcast
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpKind {
+    /// Set a book-level field; `None` clears optional fields
+    SetBookField {
+        field: BookField,
+        value: Option<String>,
+    },
+    /// Register a chapter so that text and title ops can target it
+    CreateChapter {
+        chapter: Uuid,
+    },
+    /// Insert a slot for `chapter` into the chapter order after `origin`
+    PlaceChapter {
+        chapter: Uuid,
+        origin: Option<OpId>,
+    },
+    /// Remove a slot from the chapter order
+    UnplaceChapter {
+        slot: OpId,
+    },
+    SetChapterTitle {
+        chapter: Uuid,
+        title: String,
+    },
+    /// Set or clear a chapter's language override
+    SetChapterLanguage {
+        chapter: Uuid,
+        language: Option<String>,
+    },
+    /// Set when a chapter was created and last modified
+    SetChapterTimestamps {
+        chapter: Uuid,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    },
+    /// Insert a run of characters; the n-th character gets `id.counter + n`
+    InsertText {
+        chapter: Uuid,
+        origin: Option<OpId>,
+        text: String,
+    },
+    DeleteText {
+        chapter: Uuid,
+        targets: Vec<OpId>,
+    },
+}
+
+impl Op {
+    /// Highest counter consumed by this op, or `None` if it would overflow
+    pub fn last_counter(&self) -> Option<u64> {
+        match &self.kind {
+            OpKind::InsertText { text, .. } => self
+                .id
+                .counter
+                .checked_add((text.chars().count() as u64).saturating_sub(1)),
+            _ => Some(self.id.counter),
+        }
+    }
+}
+
+/// Per-replica record of the latest counter that has been applied
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VersionVector {
+    entries: HashMap<ReplicaId, u64>,
+}
+
+impl VersionVector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Latest counter seen from `replica` (0 if none)
+    pub fn get(&self, replica: ReplicaId) -> u64 {
+        self.entries.get(&replica).copied().unwrap_or(0)
+    }
+
+    /// Record that `replica` has advanced to `counter`
+    pub fn observe(&mut self, replica: ReplicaId, counter: u64) {
+        let entry = self.entries.entry(replica).or_insert(0);
+        if counter > *entry {
+            *entry = counter;
+        }
+    }
+
+    /// Whether the op has already been applied
+    pub fn contains(&self, op: &Op) -> bool {
+        op.last_counter()
+            .is_some_and(|last| self.get(op.id.replica) >= last)
+    }
+
+    /// Iterate over `(replica, counter)` pairs in replica order
+    pub fn iter(&self) -> impl Iterator<Item = (ReplicaId, u64)> {
+        let mut entries: Vec<(ReplicaId, u64)> =
+            self.entries.iter().map(|(r, c)| (*r, *c)).collect();
+        entries.sort();
+        entries.into_iter()
+    }
+}
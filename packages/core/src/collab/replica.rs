@@ -0,0 +1,770 @@
+use crate::bk_format::{
+    format_identifiers, format_tags, parse_hyphenation, parse_identifiers, parse_tags,
+};
+use crate::collab::encoding::{decode_update, encode_update, format_timestamp, parse_timestamp};
+use crate::collab::error::CollabError;
+use crate::collab::ops::{BookField, Op, OpId, OpKind, ReplicaId, VersionVector};
+use crate::collab::sequence::Sequence;
+use crate::models::{Book, BookFormat, Chapter};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+/// Last-writer-wins register ordered by `OpId`
+#[derive(Debug, Clone, Default)]
+struct Register<T> {
+    value: T,
+    stamp: Option<OpId>,
+}
+
+impl<T> Register<T> {
+    fn set(&mut self, stamp: OpId, value: T) {
+        if self.stamp.is_none_or(|current| stamp > current) {
+            self.value = value;
+            self.stamp = Some(stamp);
+        }
+    }
+}
+
+/// Replicated state of a single chapter
+#[derive(Debug, Clone, Default)]
+struct ChapterState {
+    title: Register<String>,
+    language: Register<Option<String>>,
+    /// Creation and last modification time
+    timestamps: Register<Option<(DateTime<Utc>, DateTime<Utc>)>>,
+    text: Sequence<char>,
+}
+
+/// One peer's copy of a collaboratively edited `Book`
+///
+/// Text is a sequence CRDT per chapter, chapter order is a list CRDT of slots,
+/// and book/chapter fields are last-writer-wins registers. Local edits are
+/// recorded as ops that peers receive through [`BookReplica::encode_update_since`]
+/// and [`BookReplica::apply_update`]; replicas that have seen the same ops
+/// produce the same `Book`, whatever order the updates arrived in.
+#[derive(Debug, Clone)]
+pub struct BookReplica {
+    replica: ReplicaId,
+    clock: u64,
+    book_id: Uuid,
+    created_at: Register<Option<DateTime<Utc>>>,
+    updated_at: Register<Option<DateTime<Utc>>>,
+    title: Register<String>,
+    author: Register<String>,
+    dedication: Register<Option<String>>,
+    target_words: Register<Option<u32>>,
+    language: Register<Option<String>>,
+    author_sort: Register<Option<String>>,
+    series: Register<Option<String>>,
+    series_index: Register<Option<f64>>,
+    tags: Register<Vec<String>>,
+    identifiers: Register<BTreeMap<String, String>>,
+    format: Register<BookFormat>,
+    hyphenation: Register<Vec<String>>,
+    order: Sequence<Uuid>,
+    chapters: HashMap<Uuid, ChapterState>,
+    log: Vec<Op>,
+    version: VersionVector,
+    pending: Vec<Op>,
+}
+
+impl BookReplica {
+    /// Create an empty replica that will be filled by applying a peer's updates
+    pub fn new(book_id: Uuid, replica: ReplicaId) -> Self {
+        Self {
+            replica,
+            clock: 0,
+            book_id,
+            created_at: Register::default(),
+            updated_at: Register::default(),
+            title: Register::default(),
+            author: Register::default(),
+            dedication: Register::default(),
+            target_words: Register::default(),
+            language: Register::default(),
+            author_sort: Register::default(),
+            series: Register::default(),
+            series_index: Register::default(),
+            tags: Register::default(),
+            identifiers: Register::default(),
+            format: Register::default(),
+            hyphenation: Register::default(),
+            order: Sequence::new(),
+            chapters: HashMap::new(),
+            log: Vec::new(),
+            version: VersionVector::new(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Seed a replica from an existing `Book`
+    ///
+    /// The book's contents are recorded as ops from `replica`, so other peers
+    /// start from [`BookReplica::new`] and apply [`BookReplica::encode_state`].
+    pub fn from_book(book: &Book, replica: ReplicaId) -> Self {
+        let mut doc = Self::new(book.id, replica);
+        doc.set_timestamps(book.created_at, book.updated_at);
+        doc.set_title(&book.title);
+        doc.set_author(&book.author);
+        if book.dedication.is_some() {
+            doc.set_dedication(book.dedication.as_deref());
+        }
+        if book.target_words.is_some() {
+            doc.set_target_words(book.target_words);
+        }
+        if book.language.is_some() {
+            doc.set_language(book.language.as_deref());
+        }
+        if book.author_sort.is_some() {
+            doc.set_author_sort(book.author_sort.as_deref());
+        }
+        if book.series.is_some() {
+            doc.set_series(book.series.as_deref());
+        }
+        if book.series_index.is_some() {
+            doc.set_series_index(book.series_index);
+        }
+        if !book.tags.is_empty() {
+            doc.set_tags(&book.tags);
+        }
+        if !book.identifiers.is_empty() {
+            doc.set_identifiers(&book.identifiers);
+        }
+        if book.format != BookFormat::Prose {
+            doc.set_format(book.format);
+        }
+        if !book.hyphenation.is_empty() {
+            doc.set_hyphenation(&book.hyphenation);
+        }
+
+        let mut chapters: Vec<&Chapter> = book.chapters.iter().collect();
+        chapters.sort_by_key(|c| c.order);
+        for chapter in chapters {
+            let index = doc.chapter_count();
+            doc.insert_chapter_with_id(index, chapter.id, &chapter.title);
+            doc.record(OpKind::SetChapterTimestamps {
+                chapter: chapter.id,
+                created_at: chapter.created_at,
+                updated_at: chapter.updated_at,
+            });
+            if chapter.language.is_some() {
+                doc.record(OpKind::SetChapterLanguage {
+                    chapter: chapter.id,
+                    language: chapter.language.clone(),
+                });
+            }
+            if !chapter.content.is_empty() {
+                doc.record(OpKind::InsertText {
+                    chapter: chapter.id,
+                    origin: None,
+                    text: chapter.content.clone(),
+                });
+            }
+        }
+
+        doc
+    }
+
+    /// Copy this replica's state into a new peer with a different id
+    pub fn fork(&self, replica: ReplicaId) -> Self {
+        let mut doc = self.clone();
+        doc.replica = replica;
+        doc
+    }
+
+    pub fn replica_id(&self) -> ReplicaId {
+        self.replica
+    }
+
+    pub fn book_id(&self) -> Uuid {
+        self.book_id
+    }
+
+    /// Ops applied so far, per replica
+    pub fn version(&self) -> &VersionVector {
+        &self.version
+    }
+
+    /// Number of received ops still waiting for the ops they depend on
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.record(OpKind::SetBookField {
+            field: BookField::Title,
+            value: Some(title.to_string()),
+        });
+    }
+
+    pub fn set_author(&mut self, author: &str) {
+        self.record(OpKind::SetBookField {
+            field: BookField::Author,
+            value: Some(author.to_string()),
+        });
+    }
+
+    pub fn set_dedication(&mut self, dedication: Option<&str>) {
+        self.record(OpKind::SetBookField {
+            field: BookField::Dedication,
+            value: dedication.map(str::to_string),
+        });
+    }
+
+    pub fn set_target_words(&mut self, target: Option<u32>) {
+        self.record(OpKind::SetBookField {
+            field: BookField::TargetWords,
+            value: target.map(|t| t.to_string()),
+        });
+    }
+
+    pub fn set_language(&mut self, language: Option<&str>) {
+        self.record(OpKind::SetBookField {
+            field: BookField::Language,
+            value: language.map(str::to_string),
+        });
+    }
+
+    pub fn set_author_sort(&mut self, author_sort: Option<&str>) {
+        self.record(OpKind::SetBookField {
+            field: BookField::AuthorSort,
+            value: author_sort.map(str::to_string),
+        });
+    }
+
+    pub fn set_series(&mut self, series: Option<&str>) {
+        self.record(OpKind::SetBookField {
+            field: BookField::Series,
+            value: series.map(str::to_string),
+        });
+    }
+
+    pub fn set_series_index(&mut self, index: Option<f64>) {
+        self.record(OpKind::SetBookField {
+            field: BookField::SeriesIndex,
+            value: index.map(|i| i.to_string()),
+        });
+    }
+
+    /// Replace the tags; an empty list clears them
+    pub fn set_tags(&mut self, tags: &[String]) {
+        self.record(OpKind::SetBookField {
+            field: BookField::Tags,
+            value: Some(format_tags(tags)).filter(|t| !t.is_empty()),
+        });
+    }
+
+    /// Replace the identifiers; an empty map clears them
+    pub fn set_identifiers(&mut self, identifiers: &BTreeMap<String, String>) {
+        self.record(OpKind::SetBookField {
+            field: BookField::Identifiers,
+            value: Some(format_identifiers(identifiers)).filter(|i| !i.is_empty()),
+        });
+    }
+
+    pub fn set_format(&mut self, format: BookFormat) {
+        self.record(OpKind::SetBookField {
+            field: BookField::Format,
+            value: Some(format.as_str().to_string()),
+        });
+    }
+
+    /// Replace the custom hyphenations; an empty list clears them
+    pub fn set_hyphenation(&mut self, words: &[String]) {
+        self.record(OpKind::SetBookField {
+            field: BookField::Hyphenation,
+            value: Some(words.join(", ")).filter(|w| !w.is_empty()),
+        });
+    }
+
+    /// Set when the book was created and last modified
+    pub fn set_timestamps(&mut self, created_at: DateTime<Utc>, updated_at: DateTime<Utc>) {
+        self.record(OpKind::SetBookField {
+            field: BookField::CreatedAt,
+            value: Some(format_timestamp(created_at)),
+        });
+        self.record(OpKind::SetBookField {
+            field: BookField::UpdatedAt,
+            value: Some(format_timestamp(updated_at)),
+        });
+    }
+
+    /// Number of chapters currently in the book
+    pub fn chapter_count(&self) -> usize {
+        self.chapter_slots().len()
+    }
+
+    /// Chapter ids in reading order
+    pub fn chapter_ids(&self) -> Vec<Uuid> {
+        self.chapter_slots()
+            .into_iter()
+            .map(|(_, chapter)| chapter)
+            .collect()
+    }
+
+    /// Insert a new empty chapter at `index` and return its id
+    ///
+    /// The chapter is stamped as created now.
+    pub fn insert_chapter(&mut self, index: usize, title: &str) -> Result<Uuid, CollabError> {
+        let len = self.chapter_count();
+        if index > len {
+            return Err(CollabError::OutOfBounds {
+                position: index,
+                len,
+            });
+        }
+
+        let name = format!("collab-{}-{}", self.replica.0, self.clock + 1);
+        let chapter = Uuid::new_v5(&self.book_id, name.as_bytes());
+        self.insert_chapter_with_id(index, chapter, title);
+        let now = Utc::now();
+        self.record(OpKind::SetChapterTimestamps {
+            chapter,
+            created_at: now,
+            updated_at: now,
+        });
+        Ok(chapter)
+    }
+
+    /// Append a new chapter with initial content and return its id
+    pub fn add_chapter(&mut self, title: &str, content: &str) -> Uuid {
+        let index = self.chapter_count();
+        let chapter = self
+            .insert_chapter(index, title)
+            .expect("appending is always in bounds");
+        self.insert_text(chapter, 0, content)
+            .expect("chapter was just created");
+        chapter
+    }
+
+    /// Move the chapter at `from` so that it ends up at index `to`
+    ///
+    /// Concurrent moves of the same chapter resolve to the move with the
+    /// highest op id.
+    pub fn move_chapter(&mut self, from: usize, to: usize) -> Result<(), CollabError> {
+        let slots = self.chapter_slots();
+        let len = slots.len();
+        if from >= len || to >= len {
+            return Err(CollabError::OutOfBounds {
+                position: from.max(to),
+                len,
+            });
+        }
+        if from == to {
+            return Ok(());
+        }
+
+        let (_, chapter) = slots[from];
+        let mut remaining = slots.clone();
+        remaining.remove(from);
+        let origin = if to == 0 {
+            None
+        } else {
+            Some(remaining[to - 1].0)
+        };
+
+        self.unplace_all(chapter);
+        self.record(OpKind::PlaceChapter { chapter, origin });
+        Ok(())
+    }
+
+    /// Remove the chapter at `index` from the book
+    pub fn remove_chapter(&mut self, index: usize) -> Result<Uuid, CollabError> {
+        let slots = self.chapter_slots();
+        let (_, chapter) = *slots.get(index).ok_or(CollabError::OutOfBounds {
+            position: index,
+            len: slots.len(),
+        })?;
+        self.unplace_all(chapter);
+        Ok(chapter)
+    }
+
+    pub fn set_chapter_title(&mut self, chapter: Uuid, title: &str) -> Result<(), CollabError> {
+        self.chapter_state(chapter)?;
+        self.record(OpKind::SetChapterTitle {
+            chapter,
+            title: title.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Set or clear the language of a chapter, overriding the book's
+    pub fn set_chapter_language(
+        &mut self,
+        chapter: Uuid,
+        language: Option<&str>,
+    ) -> Result<(), CollabError> {
+        self.chapter_state(chapter)?;
+        self.record(OpKind::SetChapterLanguage {
+            chapter,
+            language: language.map(str::to_string),
+        });
+        Ok(())
+    }
+
+    /// Set when a chapter was created and last modified
+    pub fn set_chapter_timestamps(
+        &mut self,
+        chapter: Uuid,
+        created_at: DateTime<Utc>,
+        updated_at: DateTime<Utc>,
+    ) -> Result<(), CollabError> {
+        self.chapter_state(chapter)?;
+        self.record(OpKind::SetChapterTimestamps {
+            chapter,
+            created_at,
+            updated_at,
+        });
+        Ok(())
+    }
+
+    /// Current text of a chapter
+    pub fn chapter_text(&self, chapter: Uuid) -> Result<String, CollabError> {
+        Ok(self.chapter_state(chapter)?.text.iter().collect())
+    }
+
+    /// Insert `text` at character `position` of a chapter
+    pub fn insert_text(
+        &mut self,
+        chapter: Uuid,
+        position: usize,
+        text: &str,
+    ) -> Result<(), CollabError> {
+        let state = self.chapter_state(chapter)?;
+        let len = state.text.len();
+        if position > len {
+            return Err(CollabError::OutOfBounds { position, len });
+        }
+        if text.is_empty() {
+            return Ok(());
+        }
+
+        let origin = state.text.origin_for(position);
+        self.record(OpKind::InsertText {
+            chapter,
+            origin,
+            text: text.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Delete `count` characters starting at character `position` of a chapter
+    pub fn delete_text(
+        &mut self,
+        chapter: Uuid,
+        position: usize,
+        count: usize,
+    ) -> Result<(), CollabError> {
+        let state = self.chapter_state(chapter)?;
+        let len = state.text.len();
+        if position
+            .checked_add(count)
+            .filter(|end| *end <= len)
+            .is_none()
+        {
+            return Err(CollabError::OutOfBounds {
+                position: position.saturating_add(count),
+                len,
+            });
+        }
+        if count == 0 {
+            return Ok(());
+        }
+
+        let targets = state
+            .text
+            .entries()
+            .skip(position)
+            .take(count)
+            .map(|(id, _)| id)
+            .collect();
+        self.record(OpKind::DeleteText { chapter, targets });
+        Ok(())
+    }
+
+    /// Encode every op this replica knows about, for bootstrapping a new peer
+    pub fn encode_state(&self) -> Vec<u8> {
+        self.encode_update_since(&VersionVector::new())
+    }
+
+    /// Encode the ops a peer with version `since` has not seen yet
+    pub fn encode_update_since(&self, since: &VersionVector) -> Vec<u8> {
+        let ops: Vec<Op> = self
+            .log
+            .iter()
+            .filter(|op| !since.contains(op))
+            .cloned()
+            .collect();
+        encode_update(self.book_id, &ops)
+    }
+
+    /// Apply an update from a peer
+    ///
+    /// Ops that were already applied are ignored, and ops whose dependencies
+    /// have not arrived yet are held back until they have.
+    pub fn apply_update(&mut self, update: &[u8]) -> Result<(), CollabError> {
+        let (book_id, ops) = decode_update(update)?;
+        if book_id != self.book_id {
+            return Err(CollabError::BookMismatch {
+                expected: self.book_id,
+                found: book_id,
+            });
+        }
+
+        self.pending.extend(ops);
+        self.drain_pending();
+        Ok(())
+    }
+
+    /// Materialize the current state as a `Book`
+    ///
+    /// Timestamps that were never set are the Unix epoch, and chapters without
+    /// their own take the book's.
+    pub fn to_book(&self) -> Book {
+        let created_at = self.created_at.value.unwrap_or(DateTime::UNIX_EPOCH);
+        let updated_at = self.updated_at.value.unwrap_or(DateTime::UNIX_EPOCH);
+        let chapters = self
+            .chapter_slots()
+            .into_iter()
+            .enumerate()
+            .map(|(order, (_, id))| {
+                let state = &self.chapters[&id];
+                let (chapter_created_at, chapter_updated_at) =
+                    state.timestamps.value.unwrap_or((created_at, updated_at));
+                Chapter {
+                    id,
+                    title: state.title.value.clone(),
+                    content: state.text.iter().collect(),
+                    order,
+                    language: state.language.value.clone(),
+                    created_at: chapter_created_at,
+                    updated_at: chapter_updated_at,
+                }
+            })
+            .collect();
+
+        Book {
+            id: self.book_id,
+            title: self.title.value.clone(),
+            author: self.author.value.clone(),
+            dedication: self.dedication.value.clone(),
+            target_words: self.target_words.value,
+            language: self.language.value.clone(),
+            author_sort: self.author_sort.value.clone(),
+            series: self.series.value.clone(),
+            series_index: self.series_index.value,
+            tags: self.tags.value.clone(),
+            identifiers: self.identifiers.value.clone(),
+            format: self.format.value,
+            hyphenation: self.hyphenation.value.clone(),
+            created_at,
+            updated_at,
+            chapters,
+        }
+    }
+
+    fn insert_chapter_with_id(&mut self, index: usize, chapter: Uuid, title: &str) {
+        let origin = match index {
+            0 => None,
+            _ => self.chapter_slots().get(index - 1).map(|(slot, _)| *slot),
+        };
+        self.record(OpKind::CreateChapter { chapter });
+        self.record(OpKind::SetChapterTitle {
+            chapter,
+            title: title.to_string(),
+        });
+        self.record(OpKind::PlaceChapter { chapter, origin });
+    }
+
+    fn unplace_all(&mut self, chapter: Uuid) {
+        let slots: Vec<OpId> = self
+            .order
+            .entries()
+            .filter(|(_, c)| **c == chapter)
+            .map(|(slot, _)| slot)
+            .collect();
+        for slot in slots {
+            self.record(OpKind::UnplaceChapter { slot });
+        }
+    }
+
+    /// Visible chapter slots in order, keeping only the winning slot per chapter
+    fn chapter_slots(&self) -> Vec<(OpId, Uuid)> {
+        let mut winners: HashMap<Uuid, OpId> = HashMap::new();
+        for (slot, chapter) in self.order.entries() {
+            let winner = winners.entry(*chapter).or_insert(slot);
+            if slot > *winner {
+                *winner = slot;
+            }
+        }
+
+        self.order
+            .entries()
+            .filter(|(slot, chapter)| winners[*chapter] == *slot)
+            .map(|(slot, chapter)| (slot, *chapter))
+            .collect()
+    }
+
+    fn chapter_state(&self, chapter: Uuid) -> Result<&ChapterState, CollabError> {
+        self.chapters
+            .get(&chapter)
+            .ok_or(CollabError::UnknownChapter(chapter))
+    }
+
+    /// Create a local op, apply it and append it to the log
+    fn record(&mut self, kind: OpKind) {
+        let op = Op {
+            id: OpId::new(self.replica, self.clock + 1),
+            prev: self.version.get(self.replica),
+            kind,
+        };
+        let applied = self.integrate(&op);
+        debug_assert!(applied, "local ops always have their dependencies");
+        self.finish(op);
+    }
+
+    /// Apply pending ops until none of the remaining ones are ready
+    fn drain_pending(&mut self) {
+        loop {
+            let mut progressed = false;
+            let mut index = 0;
+            while index < self.pending.len() {
+                let op = self.pending[index].clone();
+                if self.version.contains(&op) {
+                    self.pending.swap_remove(index);
+                    continue;
+                }
+                if self.version.get(op.id.replica) == op.prev && self.integrate(&op) {
+                    self.pending.swap_remove(index);
+                    self.finish(op);
+                    progressed = true;
+                    continue;
+                }
+                index += 1;
+            }
+            if !progressed {
+                return;
+            }
+        }
+    }
+
+    /// Record an integrated op in the clock, version vector and log
+    fn finish(&mut self, op: Op) {
+        // Decoding rejects ops whose counters overflow
+        let last = op.last_counter().unwrap_or(op.id.counter);
+        self.clock = self.clock.max(last);
+        self.version.observe(op.id.replica, last);
+        self.log.push(op);
+    }
+
+    /// Apply an op to the state; returns `false` if its dependencies are missing
+    fn integrate(&mut self, op: &Op) -> bool {
+        match &op.kind {
+            OpKind::SetBookField { field, value } => {
+                match field {
+                    BookField::Title => self.title.set(op.id, value.clone().unwrap_or_default()),
+                    BookField::Author => self.author.set(op.id, value.clone().unwrap_or_default()),
+                    BookField::Dedication => self.dedication.set(op.id, value.clone()),
+                    BookField::TargetWords => self
+                        .target_words
+                        .set(op.id, value.as_deref().and_then(|v| v.parse().ok())),
+                    BookField::Language => self.language.set(op.id, value.clone()),
+                    BookField::AuthorSort => self.author_sort.set(op.id, value.clone()),
+                    BookField::Series => self.series.set(op.id, value.clone()),
+                    BookField::SeriesIndex => self
+                        .series_index
+                        .set(op.id, value.as_deref().and_then(|v| v.parse().ok())),
+                    BookField::Tags => self
+                        .tags
+                        .set(op.id, value.as_deref().map(parse_tags).unwrap_or_default()),
+                    BookField::Identifiers => self.identifiers.set(
+                        op.id,
+                        value
+                            .as_deref()
+                            .and_then(|v| parse_identifiers(v).ok())
+                            .unwrap_or_default(),
+                    ),
+                    BookField::Format => self.format.set(
+                        op.id,
+                        value
+                            .as_deref()
+                            .and_then(BookFormat::parse)
+                            .unwrap_or_default(),
+                    ),
+                    BookField::Hyphenation => self.hyphenation.set(
+                        op.id,
+                        value.as_deref().map(parse_hyphenation).unwrap_or_default(),
+                    ),
+                    BookField::CreatedAt => self
+                        .created_at
+                        .set(op.id, value.as_deref().and_then(parse_timestamp)),
+                    BookField::UpdatedAt => self
+                        .updated_at
+                        .set(op.id, value.as_deref().and_then(parse_timestamp)),
+                }
+                true
+            }
+            OpKind::CreateChapter { chapter } => {
+                self.chapters.entry(*chapter).or_default();
+                true
+            }
+            OpKind::PlaceChapter { chapter, origin } => {
+                self.chapters.contains_key(chapter)
+                    && self.order.integrate_run(op.id, *origin, vec![*chapter])
+            }
+            OpKind::UnplaceChapter { slot } => self.order.integrate_delete(*slot),
+            OpKind::SetChapterTitle { chapter, title } => match self.chapters.get_mut(chapter) {
+                Some(state) => {
+                    state.title.set(op.id, title.clone());
+                    true
+                }
+                None => false,
+            },
+            OpKind::SetChapterLanguage { chapter, language } => {
+                match self.chapters.get_mut(chapter) {
+                    Some(state) => {
+                        state.language.set(op.id, language.clone());
+                        true
+                    }
+                    None => false,
+                }
+            }
+            OpKind::SetChapterTimestamps {
+                chapter,
+                created_at,
+                updated_at,
+            } => match self.chapters.get_mut(chapter) {
+                Some(state) => {
+                    state
+                        .timestamps
+                        .set(op.id, Some((*created_at, *updated_at)));
+                    true
+                }
+                None => false,
+            },
+            OpKind::InsertText {
+                chapter,
+                origin,
+                text,
+            } => match self.chapters.get_mut(chapter) {
+                Some(state) => state
+                    .text
+                    .integrate_run(op.id, *origin, text.chars().collect()),
+                None => false,
+            },
+            OpKind::DeleteText { chapter, targets } => match self.chapters.get_mut(chapter) {
+                Some(state) => {
+                    if !targets.iter().all(|id| state.text.contains(*id)) {
+                        return false;
+                    }
+                    for id in targets {
+                        state.text.integrate_delete(*id);
+                    }
+                    true
+                }
+                None => false,
+            },
+        }
+    }
+}
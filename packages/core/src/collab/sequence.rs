@@ -0,0 +1,139 @@
+use crate::collab::ops::OpId;
+
+/// A single element of a replicated sequence (kept as a tombstone once deleted)
+#[derive(Debug, Clone)]
+struct Element<T> {
+    id: OpId,
+    value: T,
+    deleted: bool,
+}
+
+/// Replicated Growable Array (RGA) sequence CRDT
+///
+/// Each element is inserted after an origin element. Concurrent inserts after
+/// the same origin are ordered by descending `OpId`, so every replica that has
+/// applied the same set of operations holds the same sequence.
+#[derive(Debug, Clone)]
+pub struct Sequence<T> {
+    elements: Vec<Element<T>>,
+}
+
+impl<T: Clone> Sequence<T> {
+    /// Create an empty sequence
+    pub fn new() -> Self {
+        Self {
+            elements: Vec::new(),
+        }
+    }
+
+    /// Number of visible (non-deleted) elements
+    pub fn len(&self) -> usize {
+        self.elements.iter().filter(|e| !e.deleted).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether an element with this id has been integrated (deleted or not)
+    pub fn contains(&self, id: OpId) -> bool {
+        self.position(id).is_some()
+    }
+
+    /// Iterate over visible values in sequence order
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.elements
+            .iter()
+            .filter(|e| !e.deleted)
+            .map(|e| &e.value)
+    }
+
+    /// Iterate over visible `(id, value)` pairs in sequence order
+    pub fn entries(&self) -> impl Iterator<Item = (OpId, &T)> {
+        self.elements
+            .iter()
+            .filter(|e| !e.deleted)
+            .map(|e| (e.id, &e.value))
+    }
+
+    /// Id of the visible element at `index`
+    pub fn id_at(&self, index: usize) -> Option<OpId> {
+        self.entries().nth(index).map(|(id, _)| id)
+    }
+
+    /// Origin to use when inserting at visible `index` (`None` means the start)
+    pub fn origin_for(&self, index: usize) -> Option<OpId> {
+        if index == 0 {
+            None
+        } else {
+            self.id_at(index - 1)
+        }
+    }
+
+    /// Integrate a run of values whose ids are consecutive counters starting at `first`
+    ///
+    /// Returns `false` without changing anything if the origin is unknown or
+    /// the run's counters would overflow.
+    pub(crate) fn integrate_run(
+        &mut self,
+        first: OpId,
+        origin: Option<OpId>,
+        values: Vec<T>,
+    ) -> bool {
+        if values.is_empty() || self.contains(first) {
+            return true;
+        }
+        if first.counter.checked_add(values.len() as u64 - 1).is_none() {
+            return false;
+        }
+
+        let mut index = match origin {
+            None => 0,
+            Some(origin) => match self.position(origin) {
+                Some(position) => position + 1,
+                None => return false,
+            },
+        };
+
+        // Skip concurrent siblings (and their descendants) that sort before us
+        while index < self.elements.len() && self.elements[index].id > first {
+            index += 1;
+        }
+
+        // Every later element of the run has a larger id than whatever now
+        // follows `index`, so the whole run lands contiguously.
+        let run = values
+            .into_iter()
+            .enumerate()
+            .map(|(offset, value)| Element {
+                id: OpId::new(first.replica, first.counter + offset as u64),
+                value,
+                deleted: false,
+            });
+        self.elements.splice(index..index, run);
+        true
+    }
+
+    /// Mark an element as deleted
+    ///
+    /// Returns `false` if the element is unknown.
+    pub(crate) fn integrate_delete(&mut self, id: OpId) -> bool {
+        match self.position(id) {
+            Some(position) => {
+                self.elements[position].deleted = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn position(&self, id: OpId) -> Option<usize> {
+        self.elements.iter().position(|e| e.id == id)
+    }
+}
+
+impl<T: Clone> Default for Sequence<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
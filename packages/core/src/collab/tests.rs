@@ -0,0 +1,462 @@
+use crate::collab::encoding::{decode_update, decode_version, encode_update, encode_version};
+use crate::collab::error::CollabError;
+use crate::collab::ops::{Op, OpId, OpKind, ReplicaId};
+use crate::collab::replica::BookReplica;
+use crate::collab::sequence::Sequence;
+use crate::models::{Book, BookFormat};
+use chrono::{Duration, TimeZone, Utc};
+
+fn sample_book() -> Book {
+    let mut book = Book::new("The Way of Iron".to_string(), "Tej".to_string());
+    book.add_chapter("One".to_string(), "The morning sun".to_string());
+    book.add_chapter("Two".to_string(), "The road".to_string());
+    book
+}
+
+/// Send every op `from` has that `to` is missing
+fn sync(from: &BookReplica, to: &mut BookReplica) {
+    let update = from.encode_update_since(to.version());
+    to.apply_update(&update).unwrap();
+}
+
+fn assert_converged(a: &BookReplica, b: &BookReplica) {
+    assert_eq!(a.to_book(), b.to_book());
+}
+
+#[test]
+fn test_sequence_concurrent_inserts_at_same_origin() {
+    let a = OpId::new(ReplicaId(1), 1);
+    let b = OpId::new(ReplicaId(2), 1);
+
+    let mut first = Sequence::new();
+    first.integrate_run(a, None, vec!['a']);
+    first.integrate_run(b, None, vec!['b']);
+
+    let mut second = Sequence::new();
+    second.integrate_run(b, None, vec!['b']);
+    second.integrate_run(a, None, vec!['a']);
+
+    let first: String = first.iter().collect();
+    let second: String = second.iter().collect();
+    assert_eq!(first, "ba");
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_sequence_rejects_unknown_origin() {
+    let mut seq = Sequence::new();
+    let missing = OpId::new(ReplicaId(9), 4);
+    assert!(!seq.integrate_run(OpId::new(ReplicaId(1), 1), Some(missing), vec!['x']));
+    assert!(seq.is_empty());
+}
+
+#[test]
+fn test_seeded_replica_round_trips_book() {
+    let book = sample_book();
+    let origin = BookReplica::from_book(&book, ReplicaId(1));
+
+    let mut peer = BookReplica::new(book.id, ReplicaId(2));
+    peer.apply_update(&origin.encode_state()).unwrap();
+
+    let copy = peer.to_book();
+    assert_eq!(copy.title, "The Way of Iron");
+    assert_eq!(copy.chapters.len(), 2);
+    assert_eq!(copy.chapters[0].id, book.chapters[0].id);
+    assert_eq!(copy.chapters[1].content, "The road");
+    assert_converged(&origin, &peer);
+}
+
+#[test]
+fn test_timestamps_replicate() {
+    let mut book = sample_book();
+    book.created_at = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+    book.updated_at =
+        Utc.with_ymd_and_hms(2025, 6, 7, 8, 9, 10).unwrap() + Duration::nanoseconds(123_456_789);
+    book.chapters[0].created_at = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+    book.chapters[0].updated_at = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+    let origin = BookReplica::from_book(&book, ReplicaId(1));
+
+    // A peer started empty ends up with the seeding book, timestamps included
+    let mut peer = BookReplica::new(book.id, ReplicaId(2));
+    peer.apply_update(&origin.encode_state()).unwrap();
+    assert_eq!(peer.to_book(), book);
+
+    // Later changes to them replicate as well
+    let chapter = book.chapters[1].id;
+    let edited = Utc.with_ymd_and_hms(2025, 7, 1, 12, 0, 0).unwrap();
+    peer.set_chapter_timestamps(chapter, book.chapters[1].created_at, edited)
+        .unwrap();
+    peer.set_timestamps(book.created_at, edited);
+    let added = peer.insert_chapter(2, "Three").unwrap();
+
+    let mut origin = origin;
+    sync(&peer, &mut origin);
+    assert_converged(&origin, &peer);
+    let copy = origin.to_book();
+    assert_eq!(copy.updated_at, edited);
+    assert_eq!(copy.chapters[0].updated_at, book.chapters[0].updated_at);
+    assert_eq!(copy.chapters[1].updated_at, edited);
+    assert_eq!(copy.chapters[2].id, added);
+    assert!(copy.chapters[2].created_at > edited);
+}
+
+#[test]
+fn test_concurrent_text_edits_converge() {
+    let book = sample_book();
+    let mut alice = BookReplica::from_book(&book, ReplicaId(1));
+    let mut bob = alice.fork(ReplicaId(2));
+    let chapter = book.chapters[0].id;
+
+    alice.insert_text(chapter, 4, "early ").unwrap();
+    bob.insert_text(chapter, 15, " rose").unwrap();
+    bob.delete_text(chapter, 0, 4).unwrap();
+
+    sync(&alice, &mut bob);
+    sync(&bob, &mut alice);
+
+    assert_converged(&alice, &bob);
+    assert_eq!(
+        alice.chapter_text(chapter).unwrap(),
+        "early morning sun rose"
+    );
+}
+
+#[test]
+fn test_concurrent_inserts_at_same_position_converge() {
+    let book = sample_book();
+    let mut alice = BookReplica::from_book(&book, ReplicaId(1));
+    let mut bob = alice.fork(ReplicaId(2));
+    let chapter = book.chapters[1].id;
+
+    alice.insert_text(chapter, 8, " north").unwrap();
+    bob.insert_text(chapter, 8, " west").unwrap();
+
+    sync(&alice, &mut bob);
+    sync(&bob, &mut alice);
+
+    assert_converged(&alice, &bob);
+    let text = alice.chapter_text(chapter).unwrap();
+    assert!(text.contains(" north") && text.contains(" west"));
+}
+
+#[test]
+fn test_chapter_order_converges() {
+    let book = sample_book();
+    let mut alice = BookReplica::from_book(&book, ReplicaId(1));
+    let mut bob = alice.fork(ReplicaId(2));
+
+    let epilogue = alice.add_chapter("Epilogue", "The end");
+    bob.move_chapter(1, 0).unwrap();
+    bob.insert_chapter(0, "Prologue").unwrap();
+
+    sync(&alice, &mut bob);
+    sync(&bob, &mut alice);
+
+    assert_converged(&alice, &bob);
+    let titles: Vec<String> = alice
+        .to_book()
+        .chapters
+        .into_iter()
+        .map(|c| c.title)
+        .collect();
+    assert_eq!(titles, vec!["Prologue", "Two", "One", "Epilogue"]);
+    assert_eq!(alice.chapter_ids()[3], epilogue);
+}
+
+#[test]
+fn test_concurrent_moves_keep_one_slot() {
+    let book = sample_book();
+    let mut alice = BookReplica::from_book(&book, ReplicaId(1));
+    alice.add_chapter("Three", "");
+    let mut bob = alice.fork(ReplicaId(2));
+
+    alice.move_chapter(0, 2).unwrap();
+    bob.move_chapter(0, 1).unwrap();
+
+    sync(&alice, &mut bob);
+    sync(&bob, &mut alice);
+
+    assert_converged(&alice, &bob);
+    assert_eq!(alice.chapter_count(), 3);
+}
+
+#[test]
+fn test_concurrent_metadata_edits_converge() {
+    let book = sample_book();
+    let mut alice = BookReplica::from_book(&book, ReplicaId(1));
+    let mut bob = alice.fork(ReplicaId(2));
+    let chapter = book.chapters[0].id;
+
+    alice.set_title("Iron");
+    bob.set_title("Steel");
+    bob.set_dedication(Some("For the smiths"));
+    alice.set_chapter_title(chapter, "Dawn").unwrap();
+
+    sync(&alice, &mut bob);
+    sync(&bob, &mut alice);
+
+    assert_converged(&alice, &bob);
+    assert_eq!(alice.to_book().title, "Steel");
+    assert_eq!(alice.to_book().chapters[0].title, "Dawn");
+}
+
+#[test]
+fn test_catalog_fields_replicate() {
+    let mut book = sample_book();
+    book.series = Some("Iron".to_string());
+    book.series_index = Some(1.5);
+    book.tags = vec!["Fantasy".to_string(), "Smithing".to_string()];
+    book.identifiers
+        .insert("isbn".to_string(), "9780000000002".to_string());
+    let mut alice = BookReplica::from_book(&book, ReplicaId(1));
+    let mut bob = BookReplica::new(book.id, ReplicaId(2));
+    bob.apply_update(&alice.encode_state()).unwrap();
+
+    let copy = bob.to_book();
+    assert_eq!(copy.series.as_deref(), Some("Iron"));
+    assert_eq!(copy.series_index, Some(1.5));
+    assert_eq!(copy.tags, book.tags);
+    assert_eq!(copy.identifiers, book.identifiers);
+
+    alice.set_tags(&[]);
+    alice.set_author_sort(Some("Tej"));
+    alice.set_format(BookFormat::Screenplay);
+    alice.set_hyphenation(&["ta-ble".to_string(), "Tej".to_string()]);
+    alice
+        .set_chapter_language(book.chapters[1].id, Some("fr"))
+        .unwrap();
+    sync(&alice, &mut bob);
+    assert!(bob.to_book().tags.is_empty());
+    assert_eq!(bob.to_book().format, BookFormat::Screenplay);
+    assert_eq!(bob.to_book().author_sort.as_deref(), Some("Tej"));
+    assert_eq!(bob.to_book().hyphenation, ["ta-ble", "Tej"]);
+    assert_eq!(bob.to_book().chapters[0].language, None);
+    assert_eq!(bob.to_book().chapters[1].language.as_deref(), Some("fr"));
+
+    let reseeded = BookReplica::from_book(&bob.to_book(), ReplicaId(3));
+    assert_eq!(
+        reseeded.to_book().chapters[1].language.as_deref(),
+        Some("fr")
+    );
+}
+
+#[test]
+fn test_out_of_order_delivery_is_buffered() {
+    let book = sample_book();
+    let mut alice = BookReplica::from_book(&book, ReplicaId(1));
+    let mut bob = alice.fork(ReplicaId(2));
+    let chapter = book.chapters[0].id;
+    let before = alice.version().clone();
+
+    alice.insert_text(chapter, 0, "At last, ").unwrap();
+    let first = alice.encode_update_since(&before);
+    let middle = alice.version().clone();
+    alice.delete_text(chapter, 0, 4).unwrap();
+    let second = alice.encode_update_since(&middle);
+
+    bob.apply_update(&second).unwrap();
+    assert_eq!(bob.pending_count(), 1);
+    bob.apply_update(&first).unwrap();
+    assert_eq!(bob.pending_count(), 0);
+
+    // Re-delivering an update is harmless
+    bob.apply_update(&first).unwrap();
+    assert_converged(&alice, &bob);
+}
+
+#[test]
+fn test_three_peers_converge_over_simulated_network() {
+    let book = sample_book();
+    let seed = BookReplica::from_book(&book, ReplicaId(1));
+    let mut peers = [
+        seed.fork(ReplicaId(1)),
+        seed.fork(ReplicaId(2)),
+        seed.fork(ReplicaId(3)),
+    ];
+    let chapter = book.chapters[0].id;
+
+    // Deterministic pseudo-random schedule of edits and partial syncs
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = move |bound: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % bound as u64) as usize
+    };
+
+    for round in 0..60 {
+        let peer = next(peers.len());
+        let len = peers[peer].chapter_text(chapter).unwrap().chars().count();
+        if len > 0 && next(3) == 0 {
+            let count = 1 + next(len.min(3));
+            let position = next(len - count + 1);
+            peers[peer].delete_text(chapter, position, count).unwrap();
+        } else {
+            let position = next(len + 1);
+            peers[peer]
+                .insert_text(chapter, position, &format!("<{}>", round))
+                .unwrap();
+        }
+
+        let from = next(peers.len());
+        let to = next(peers.len());
+        if from != to {
+            let update = peers[from].encode_update_since(peers[to].version());
+            peers[to].apply_update(&update).unwrap();
+        }
+    }
+
+    for from in 0..peers.len() {
+        for to in 0..peers.len() {
+            if from != to {
+                let update = peers[from].encode_update_since(peers[to].version());
+                peers[to].apply_update(&update).unwrap();
+            }
+        }
+    }
+
+    assert_converged(&peers[0], &peers[1]);
+    assert_converged(&peers[1], &peers[2]);
+}
+
+#[test]
+fn test_update_encoding_round_trip() {
+    let book = sample_book();
+    let mut doc = BookReplica::from_book(&book, ReplicaId(7));
+    doc.delete_text(book.chapters[0].id, 2, 5).unwrap();
+
+    let bytes = doc.encode_state();
+    let (book_id, ops) = decode_update(&bytes).unwrap();
+    assert_eq!(book_id, book.id);
+    assert_eq!(encode_update(book_id, &ops), bytes);
+
+    let version = decode_version(&encode_version(doc.version())).unwrap();
+    assert_eq!(&version, doc.version());
+}
+
+#[test]
+fn test_rejects_malformed_updates() {
+    let book = sample_book();
+    let mut doc = BookReplica::new(book.id, ReplicaId(1));
+
+    assert!(matches!(
+        doc.apply_update(b"nope"),
+        Err(CollabError::InvalidHeader)
+    ));
+
+    let bytes = BookReplica::from_book(&book, ReplicaId(2)).encode_state();
+    assert!(matches!(
+        doc.apply_update(&bytes[..bytes.len() - 3]),
+        Err(CollabError::UnexpectedEof)
+    ));
+
+    let other = BookReplica::new(uuid::Uuid::new_v4(), ReplicaId(3)).encode_state();
+    assert!(matches!(
+        doc.apply_update(&other),
+        Err(CollabError::BookMismatch { .. })
+    ));
+
+    // The update ends with a chapter's modification time; spoil its zone
+    let stamp = Op {
+        id: OpId::new(ReplicaId(2), 1),
+        prev: 0,
+        kind: OpKind::SetChapterTimestamps {
+            chapter: book.chapters[0].id,
+            created_at: book.created_at,
+            updated_at: book.updated_at,
+        },
+    };
+    let mut bytes = encode_update(book.id, &[stamp]);
+    assert_eq!(bytes.pop(), Some(b'Z'));
+    bytes.push(b'x');
+    assert!(matches!(
+        decode_update(&bytes),
+        Err(CollabError::InvalidTimestamp)
+    ));
+}
+
+#[test]
+fn test_rejects_updates_that_overflow_or_overallocate() {
+    let book_id = uuid::Uuid::new_v4();
+    let chapter = uuid::Uuid::new_v4();
+    let max_varint = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+
+    // Counts larger than the rest of the input could hold
+    let mut bytes = encode_update(book_id, &[]);
+    bytes.pop();
+    bytes.extend_from_slice(&max_varint);
+    assert!(matches!(
+        decode_update(&bytes),
+        Err(CollabError::UnexpectedEof)
+    ));
+    assert!(matches!(
+        decode_version(&max_varint),
+        Err(CollabError::UnexpectedEof)
+    ));
+
+    // An insert whose characters run past the last counter
+    let insert = Op {
+        id: OpId::new(ReplicaId(1), u64::MAX),
+        prev: 0,
+        kind: OpKind::InsertText {
+            chapter,
+            origin: None,
+            text: "ab".to_string(),
+        },
+    };
+    assert!(matches!(
+        decode_update(&encode_update(book_id, &[insert])),
+        Err(CollabError::CounterOverflow)
+    ));
+
+    // Delete runs end with their length; stretch it past the last counter,
+    // then past the size limit
+    let delete = |counter| Op {
+        id: OpId::new(ReplicaId(2), 1),
+        prev: 0,
+        kind: OpKind::DeleteText {
+            chapter,
+            targets: vec![OpId::new(ReplicaId(1), counter)],
+        },
+    };
+    let mut bytes = encode_update(book_id, &[delete(u64::MAX)]);
+    *bytes.last_mut().unwrap() = 2;
+    assert!(matches!(
+        decode_update(&bytes),
+        Err(CollabError::CounterOverflow)
+    ));
+    let mut bytes = encode_update(book_id, &[delete(0)]);
+    bytes.pop();
+    bytes.extend_from_slice(&[0x80, 0x80, 0x80, 0x04]);
+    assert!(matches!(decode_update(&bytes), Err(CollabError::TooLarge)));
+
+    let mut seq = Sequence::new();
+    assert!(!seq.integrate_run(OpId::new(ReplicaId(1), u64::MAX), None, vec!['a', 'b']));
+}
+
+#[test]
+fn test_local_edit_errors() {
+    let book = sample_book();
+    let mut doc = BookReplica::from_book(&book, ReplicaId(1));
+
+    assert!(matches!(
+        doc.insert_text(uuid::Uuid::nil(), 0, "x"),
+        Err(CollabError::UnknownChapter(_))
+    ));
+    assert!(matches!(
+        doc.delete_text(book.chapters[1].id, 5, 10),
+        Err(CollabError::OutOfBounds { .. })
+    ));
+    assert!(matches!(
+        doc.delete_text(book.chapters[1].id, 1, usize::MAX),
+        Err(CollabError::OutOfBounds { .. })
+    ));
+    assert!(matches!(
+        doc.insert_text(book.chapters[1].id, usize::MAX, "x"),
+        Err(CollabError::OutOfBounds { .. })
+    ));
+    assert!(matches!(
+        doc.move_chapter(0, 5),
+        Err(CollabError::OutOfBounds { .. })
+    ));
+}
@@ -0,0 +1,121 @@
+use crate::bk_format::{parse_screenplay, ScreenplayElement};
+use crate::models::{Book, BookFormat, Chapter};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[cfg(test)]
+mod tests;
+
+/// Language used when the book has no `@language:` field
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// Format-neutral view of a book that the exporters and the layout engine
+/// work from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub id: Uuid,
+    pub title: String,
+    pub author: String,
+    pub language: String,
+    pub dedication: Option<String>,
+    pub modified: DateTime<Utc>,
+    /// Chapters in reading order
+    pub chapters: Vec<DocChapter>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocChapter {
+    pub id: Uuid,
+    pub title: String,
+    pub blocks: Vec<Block>,
+}
+
+/// Block-level content of a chapter
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Paragraph(String),
+    /// A line such as `***`, `* * *` or `#` separating scenes
+    SceneBreak,
+    /// An element of a screenplay chapter
+    Screenplay(ScreenplayElement),
+}
+
+impl Document {
+    pub fn from_book(book: &Book) -> Self {
+        let mut chapters: Vec<&Chapter> = book.chapters.iter().collect();
+        chapters.sort_by_key(|c| c.order);
+
+        Self {
+            id: book.id,
+            title: book.title.clone(),
+            author: book.author.clone(),
+            language: book
+                .language
+                .clone()
+                .filter(|l| !l.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string()),
+            dedication: book.dedication.clone().filter(|d| !d.trim().is_empty()),
+            modified: book.updated_at,
+            chapters: chapters
+                .into_iter()
+                .map(|chapter| match book.format {
+                    BookFormat::Prose => DocChapter::from_chapter(chapter),
+                    BookFormat::Screenplay => DocChapter::from_screenplay(chapter),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl DocChapter {
+    pub fn from_chapter(chapter: &Chapter) -> Self {
+        Self {
+            id: chapter.id,
+            title: chapter.title.clone(),
+            blocks: parse_blocks(&chapter.content),
+        }
+    }
+
+    /// A chapter of a screenplay book, one block per element
+    pub fn from_screenplay(chapter: &Chapter) -> Self {
+        Self {
+            id: chapter.id,
+            title: chapter.title.clone(),
+            blocks: parse_screenplay(&chapter.content)
+                .into_iter()
+                .map(Block::Screenplay)
+                .collect(),
+        }
+    }
+}
+
+/// Split chapter content into blocks; each non-empty line is one paragraph
+///
+/// `@page:` and `@block:` markers only group content for the editor and are dropped.
+pub fn parse_blocks(content: &str) -> Vec<Block> {
+    content
+        .lines()
+        .map(|line| strip_block_marker(line.trim()))
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            if is_scene_break(line) {
+                Block::SceneBreak
+            } else {
+                Block::Paragraph(line.to_string())
+            }
+        })
+        .collect()
+}
+
+/// A line made only of `*`, `#` or `~` marks (spaces allowed between them)
+fn is_scene_break(line: &str) -> bool {
+    let mut marks = line.chars().filter(|c| !c.is_whitespace()).peekable();
+    marks.peek().is_some() && marks.all(|c| matches!(c, '*' | '#' | '~'))
+}
+
+fn strip_block_marker(line: &str) -> &str {
+    ["@page:", "@block:"]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+        .map_or(line, str::trim)
+}
@@ -0,0 +1,67 @@
+use crate::bk_format::ScreenplayElement;
+use crate::document::{parse_blocks, Block, Document, DEFAULT_LANGUAGE};
+use crate::export::tests::{sample_book, screenplay_book};
+
+#[test]
+fn test_parse_blocks_scene_breaks_and_markers() {
+    let blocks = parse_blocks("@page:\nFirst.\n***\n  # \nSecond.\n---\n@block: Third.");
+    assert_eq!(
+        blocks,
+        vec![
+            Block::Paragraph("First.".to_string()),
+            Block::SceneBreak,
+            Block::SceneBreak,
+            Block::Paragraph("Second.".to_string()),
+            Block::Paragraph("---".to_string()),
+            Block::Paragraph("Third.".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_document_from_book() {
+    let mut book = sample_book();
+    book.chapters.reverse();
+    let doc = Document::from_book(&book);
+
+    assert_eq!(doc.language, "en-GB");
+    assert_eq!(doc.dedication.as_deref(), Some("For \"the dreamers\""));
+    let titles: Vec<&str> = doc.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, ["The Beginning", "The Road"]);
+    assert_eq!(doc.chapters[0].blocks.len(), 3);
+
+    book.language = None;
+    assert_eq!(Document::from_book(&book).language, DEFAULT_LANGUAGE);
+}
+
+#[test]
+fn test_document_from_screenplay() {
+    let doc = Document::from_book(&screenplay_book());
+    let classes: Vec<&str> = doc.chapters[0]
+        .blocks
+        .iter()
+        .map(|block| match block {
+            Block::Screenplay(element) => element.class(),
+            other => panic!("expected a screenplay element, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(
+        classes,
+        [
+            "scene-heading",
+            "action",
+            "character",
+            "parenthetical",
+            "dialogue",
+            "transition",
+            "page-break",
+            "centered"
+        ]
+    );
+    assert_eq!(
+        doc.chapters[0].blocks[1],
+        Block::Screenplay(ScreenplayElement::Action(
+            "Waves break on the rocks.\nGulls circle.".to_string()
+        ))
+    );
+}
@@ -0,0 +1,437 @@
+use crate::bk_format::ScreenplayElement;
+use crate::document::{Block, Document};
+use crate::export::error::ExportError;
+use crate::export::xml::escape;
+use crate::models::Book;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Seek, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Letter paper and one-inch margins, in twentieths of a point
+const PAGE_WIDTH: u32 = 12240;
+const PAGE_HEIGHT: u32 = 15840;
+const MARGIN: u32 = 1440;
+const TEXT_WIDTH: u32 = PAGE_WIDTH - 2 * MARGIN;
+
+/// Space above a chapter title so it sits about a third of the way down the page
+const CHAPTER_DROP: u32 = 3600;
+/// Space above the title on the cover so it sits about halfway down
+const TITLE_DROP: u32 = 5040;
+
+/// Typeface for a standard manuscript
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ManuscriptFont {
+    #[default]
+    Courier,
+    Times,
+}
+
+impl ManuscriptFont {
+    fn name(self) -> &'static str {
+        match self {
+            ManuscriptFont::Courier => "Courier New",
+            ManuscriptFont::Times => "Times New Roman",
+        }
+    }
+}
+
+/// Settings for standard manuscript format export
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ManuscriptOptions {
+    pub font: ManuscriptFont,
+    /// Title used in the running header (defaults to the full title)
+    pub short_title: Option<String>,
+    /// Lines under the author's name on the cover, such as address and email
+    pub contact: Vec<String>,
+}
+
+/// Export a book as a .docx in standard manuscript format (Shunn)
+pub fn export_docx(book: &Book, options: &ManuscriptOptions) -> Result<Vec<u8>, ExportError> {
+    let mut cursor = Cursor::new(Vec::new());
+    write_docx(book, options, &mut cursor)?;
+    Ok(cursor.into_inner())
+}
+
+/// Write a book as a standard manuscript .docx to `writer`
+///
+/// The cover page carries the author, contact lines, approximate word count
+/// and title. Every chapter starts on a new page with the header
+/// "Surname / TITLE / page", and the cover is left unnumbered.
+pub fn write_docx<W: Write + Seek>(
+    book: &Book,
+    options: &ManuscriptOptions,
+    writer: W,
+) -> Result<(), ExportError> {
+    let doc = Document::from_book(book);
+    let words = book.stats().total.words;
+
+    let parts = [
+        ("[Content_Types].xml", CONTENT_TYPES.to_string()),
+        ("_rels/.rels", ROOT_RELS.to_string()),
+        ("docProps/core.xml", core_xml(&doc)),
+        ("word/_rels/document.xml.rels", DOCUMENT_RELS.to_string()),
+        ("word/document.xml", document_xml(&doc, options, words)),
+        ("word/styles.xml", styles_xml(options.font)),
+        ("word/settings.xml", SETTINGS.to_string()),
+        ("word/header1.xml", header_xml(&doc, options)),
+        ("word/header2.xml", empty_header_xml()),
+    ];
+
+    let mut zip = ZipWriter::new(writer);
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (path, content) in parts {
+        zip.start_file(path, deflated)?;
+        zip.write_all(content.as_bytes())?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// Word count rounded the way manuscripts state it
+///
+/// Nearest hundred below 10,000 words (never less than 100), nearest thousand above.
+pub fn approximate_word_count(words: usize) -> usize {
+    let step = if words < 10_000 { 100 } else { 1_000 };
+    (((words + step / 2) / step) * step).max(100)
+}
+
+/// Author's surname for the running header
+fn surname(author: &str) -> &str {
+    author.split_whitespace().last().unwrap_or(author)
+}
+
+fn format_thousands(value: usize) -> String {
+    let digits = value.to_string();
+    let mut out = String::new();
+    for (index, c) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+  <Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+  <Default Extension="xml" ContentType="application/xml"/>
+  <Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+  <Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+  <Override PartName="/word/settings.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.settings+xml"/>
+  <Override PartName="/word/header1.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.header+xml"/>
+  <Override PartName="/word/header2.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.header+xml"/>
+  <Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+</Types>
+"#;
+
+const ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+  <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+</Relationships>
+"#;
+
+const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+  <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/settings" Target="settings.xml"/>
+  <Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/header" Target="header1.xml"/>
+  <Relationship Id="rId4" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/header" Target="header2.xml"/>
+</Relationships>
+"#;
+
+const SETTINGS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:settings xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:defaultTabStop w:val="720"/>
+  <w:compat>
+    <w:compatSetting w:name="compatibilityMode" w:uri="http://schemas.microsoft.com/office/word" w:val="15"/>
+  </w:compat>
+</w:settings>
+"#;
+
+const WORD_NAMESPACES: &str = r#"xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships""#;
+
+fn core_xml(doc: &Document) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <dc:title>{}</dc:title>
+  <dc:creator>{}</dc:creator>
+  <dc:language>{}</dc:language>
+  <dcterms:modified xsi:type="dcterms:W3CDTF">{}</dcterms:modified>
+</cp:coreProperties>
+"#,
+        escape(&doc.title),
+        escape(&doc.author),
+        escape(&doc.language),
+        doc.modified.format("%Y-%m-%dT%H:%M:%SZ")
+    )
+}
+
+fn styles_xml(font: ManuscriptFont) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+  <w:docDefaults>
+    <w:rPrDefault>
+      <w:rPr>
+        <w:rFonts w:ascii="{font}" w:hAnsi="{font}" w:eastAsia="{font}" w:cs="{font}"/>
+        <w:sz w:val="24"/>
+        <w:szCs w:val="24"/>
+      </w:rPr>
+    </w:rPrDefault>
+    <w:pPrDefault>
+      <w:pPr>
+        <w:spacing w:before="0" w:after="0" w:line="480" w:lineRule="auto"/>
+      </w:pPr>
+    </w:pPrDefault>
+  </w:docDefaults>
+  <w:style w:type="paragraph" w:default="1" w:styleId="Normal">
+    <w:name w:val="Normal"/>
+    <w:qFormat/>
+  </w:style>
+  <w:style w:type="paragraph" w:styleId="BodyText">
+    <w:name w:val="Body Text"/>
+    <w:basedOn w:val="Normal"/>
+    <w:qFormat/>
+    <w:pPr>
+      <w:ind w:firstLine="720"/>
+    </w:pPr>
+  </w:style>
+  <w:style w:type="paragraph" w:styleId="Heading1">
+    <w:name w:val="heading 1"/>
+    <w:basedOn w:val="Normal"/>
+    <w:next w:val="BodyText"/>
+    <w:qFormat/>
+    <w:pPr>
+      <w:keepNext/>
+      <w:pageBreakBefore/>
+      <w:spacing w:before="{drop}" w:after="480"/>
+      <w:jc w:val="center"/>
+      <w:outlineLvl w:val="0"/>
+    </w:pPr>
+  </w:style>
+  <w:style w:type="paragraph" w:styleId="SceneBreak">
+    <w:name w:val="Scene Break"/>
+    <w:basedOn w:val="Normal"/>
+    <w:next w:val="BodyText"/>
+    <w:qFormat/>
+    <w:pPr>
+      <w:jc w:val="center"/>
+    </w:pPr>
+  </w:style>
+  <w:style w:type="paragraph" w:styleId="Screenplay">
+    <w:name w:val="Screenplay"/>
+    <w:basedOn w:val="Normal"/>
+    <w:pPr>
+      <w:spacing w:before="240" w:line="240" w:lineRule="auto"/>
+    </w:pPr>
+  </w:style>
+  <w:style w:type="paragraph" w:styleId="SceneHeading">
+    <w:name w:val="Scene Heading"/>
+    <w:basedOn w:val="Screenplay"/>
+    <w:qFormat/>
+    <w:pPr>
+      <w:keepNext/>
+    </w:pPr>
+  </w:style>
+  <w:style w:type="paragraph" w:styleId="Action">
+    <w:name w:val="Action"/>
+    <w:basedOn w:val="Screenplay"/>
+    <w:qFormat/>
+  </w:style>
+  <w:style w:type="paragraph" w:styleId="Character">
+    <w:name w:val="Character"/>
+    <w:basedOn w:val="Screenplay"/>
+    <w:qFormat/>
+    <w:pPr>
+      <w:keepNext/>
+      <w:ind w:left="3168"/>
+    </w:pPr>
+  </w:style>
+  <w:style w:type="paragraph" w:styleId="Parenthetical">
+    <w:name w:val="Parenthetical"/>
+    <w:basedOn w:val="Screenplay"/>
+    <w:qFormat/>
+    <w:pPr>
+      <w:keepNext/>
+      <w:spacing w:before="0"/>
+      <w:ind w:left="2304" w:right="3024"/>
+    </w:pPr>
+  </w:style>
+  <w:style w:type="paragraph" w:styleId="Dialogue">
+    <w:name w:val="Dialogue"/>
+    <w:basedOn w:val="Screenplay"/>
+    <w:qFormat/>
+    <w:pPr>
+      <w:spacing w:before="0"/>
+      <w:ind w:left="1440" w:right="2160"/>
+    </w:pPr>
+  </w:style>
+  <w:style w:type="paragraph" w:styleId="Transition">
+    <w:name w:val="Transition"/>
+    <w:basedOn w:val="Screenplay"/>
+    <w:qFormat/>
+    <w:pPr>
+      <w:jc w:val="right"/>
+    </w:pPr>
+  </w:style>
+  <w:style w:type="paragraph" w:styleId="Centered">
+    <w:name w:val="Centered"/>
+    <w:basedOn w:val="Screenplay"/>
+    <w:qFormat/>
+    <w:pPr>
+      <w:jc w:val="center"/>
+    </w:pPr>
+  </w:style>
+  <w:style w:type="paragraph" w:styleId="Header">
+    <w:name w:val="header"/>
+    <w:basedOn w:val="Normal"/>
+    <w:pPr>
+      <w:spacing w:line="240" w:lineRule="auto"/>
+      <w:jc w:val="right"/>
+    </w:pPr>
+  </w:style>
+</w:styles>
+"#,
+        font = font.name(),
+        drop = CHAPTER_DROP
+    )
+}
+
+/// Running header: "Surname / SHORT TITLE / page"
+fn header_xml(doc: &Document, options: &ManuscriptOptions) -> String {
+    let short_title = options
+        .short_title
+        .as_deref()
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or(&doc.title)
+        .to_uppercase();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:hdr {ns}>
+  <w:p>
+    <w:pPr><w:pStyle w:val="Header"/></w:pPr>
+    <w:r><w:t xml:space="preserve">{surname} / {title} / </w:t></w:r>
+    <w:r><w:fldChar w:fldCharType="begin"/></w:r>
+    <w:r><w:instrText xml:space="preserve"> PAGE </w:instrText></w:r>
+    <w:r><w:fldChar w:fldCharType="separate"/></w:r>
+    <w:r><w:t>1</w:t></w:r>
+    <w:r><w:fldChar w:fldCharType="end"/></w:r>
+  </w:p>
+</w:hdr>
+"#,
+        ns = WORD_NAMESPACES,
+        surname = escape(surname(&doc.author)),
+        title = escape(&short_title)
+    )
+}
+
+/// Header for the cover page, which carries no running head
+fn empty_header_xml() -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:hdr {}>\n  <w:p/>\n</w:hdr>\n",
+        WORD_NAMESPACES
+    )
+}
+
+/// A paragraph of one run per line, with breaks between them
+fn paragraph(style: &str, text: &str) -> String {
+    let runs: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            format!(
+                "<w:r><w:t xml:space=\"preserve\">{}</w:t></w:r>",
+                escape(line)
+            )
+        })
+        .collect();
+    format!(
+        "    <w:p><w:pPr><w:pStyle w:val=\"{}\"/></w:pPr>{}</w:p>\n",
+        style,
+        runs.join("<w:r><w:br/></w:r>")
+    )
+}
+
+/// Paragraph style of a screenplay element
+fn screenplay_style(element: &ScreenplayElement) -> &'static str {
+    match element {
+        ScreenplayElement::SceneHeading(_) => "SceneHeading",
+        ScreenplayElement::Character(_) => "Character",
+        ScreenplayElement::Parenthetical(_) => "Parenthetical",
+        ScreenplayElement::Dialogue(_) => "Dialogue",
+        ScreenplayElement::Transition(_) => "Transition",
+        ScreenplayElement::Centered(_) => "Centered",
+        ScreenplayElement::Action(_) | ScreenplayElement::PageBreak => "Action",
+    }
+}
+
+fn document_xml(doc: &Document, options: &ManuscriptOptions, words: usize) -> String {
+    let mut body = String::new();
+
+    // Cover: author and contact block top left, word count top right
+    body.push_str(&format!(
+        "    <w:p><w:pPr><w:tabs><w:tab w:val=\"right\" w:pos=\"{}\"/></w:tabs><w:spacing w:line=\"240\" w:lineRule=\"auto\"/></w:pPr><w:r><w:t xml:space=\"preserve\">{}</w:t></w:r><w:r><w:tab/><w:t xml:space=\"preserve\">about {} words</w:t></w:r></w:p>\n",
+        TEXT_WIDTH,
+        escape(&doc.author),
+        format_thousands(approximate_word_count(words))
+    ));
+    for line in &options.contact {
+        body.push_str(&format!(
+            "    <w:p><w:pPr><w:spacing w:line=\"240\" w:lineRule=\"auto\"/></w:pPr><w:r><w:t xml:space=\"preserve\">{}</w:t></w:r></w:p>\n",
+            escape(line)
+        ));
+    }
+    body.push_str(&format!(
+        "    <w:p><w:pPr><w:spacing w:before=\"{}\"/><w:jc w:val=\"center\"/></w:pPr><w:r><w:t xml:space=\"preserve\">{}</w:t></w:r></w:p>\n",
+        TITLE_DROP,
+        escape(&doc.title)
+    ));
+    body.push_str(&format!(
+        "    <w:p><w:pPr><w:jc w:val=\"center\"/></w:pPr><w:r><w:t xml:space=\"preserve\">by {}</w:t></w:r></w:p>\n",
+        escape(&doc.author)
+    ));
+
+    for chapter in &doc.chapters {
+        body.push_str(&paragraph("Heading1", &chapter.title));
+        for block in &chapter.blocks {
+            match block {
+                Block::Paragraph(text) => body.push_str(&paragraph("BodyText", text)),
+                Block::SceneBreak => body.push_str(&paragraph("SceneBreak", "#")),
+                Block::Screenplay(ScreenplayElement::PageBreak) => {
+                    body.push_str("    <w:p><w:r><w:br w:type=\"page\"/></w:r></w:p>\n")
+                }
+                Block::Screenplay(element) => {
+                    body.push_str(&paragraph(screenplay_style(element), element.text()))
+                }
+            }
+        }
+    }
+
+    // Page numbering starts at 0 so the first page after the cover is page 1;
+    // the cover uses the first-page header, which is empty.
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document {ns}>
+  <w:body>
+{body}    <w:sectPr>
+      <w:headerReference w:type="default" r:id="rId3"/>
+      <w:headerReference w:type="first" r:id="rId4"/>
+      <w:pgSz w:w="{width}" w:h="{height}"/>
+      <w:pgMar w:top="{margin}" w:right="{margin}" w:bottom="{margin}" w:left="{margin}" w:header="720" w:footer="720" w:gutter="0"/>
+      <w:pgNumType w:start="0"/>
+      <w:titlePg/>
+    </w:sectPr>
+  </w:body>
+</w:document>
+"#,
+        ns = WORD_NAMESPACES,
+        body = body,
+        width = PAGE_WIDTH,
+        height = PAGE_HEIGHT,
+        margin = MARGIN
+    )
+}
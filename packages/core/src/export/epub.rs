@@ -0,0 +1,309 @@
+use crate::bk_format::ScreenplayElement;
+use crate::document::{Block, DocChapter, Document};
+use crate::export::error::ExportError;
+use crate::export::xml::escape;
+use crate::models::Book;
+use std::io::{Cursor, Seek, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Media type stored in the `mimetype` entry
+pub const MIMETYPE: &str = "application/epub+zip";
+
+/// Path of the package document inside the archive
+const PACKAGE_PATH: &str = "OEBPS/content.opf";
+
+const STYLESHEET: &str = "\
+body { margin: 0 5%; font-family: serif; line-height: 1.5; }
+h1 { text-align: center; margin: 3em 0 2em; font-weight: normal; }
+p { margin: 0; text-indent: 1.5em; text-align: justify; }
+h1 + p, hr + p { text-indent: 0; }
+hr.scene-break { border: none; margin: 1.5em 0; text-align: center; }
+hr.scene-break::after { content: \"* * *\"; }
+hr.page-break { border: none; break-after: page; }
+p.scene-heading, p.action, p.character, p.parenthetical, p.dialogue, p.transition, p.centered { text-indent: 0; text-align: left; }
+p.scene-heading, p.action, p.character, p.transition, p.centered { margin-top: 1em; }
+p.character { margin-left: 37%; }
+p.parenthetical { margin-left: 27%; margin-right: 35%; }
+p.dialogue { margin-left: 17%; margin-right: 25%; }
+p.transition { text-align: right; }
+p.centered { text-align: center; }
+.title-page, .dedication { text-align: center; margin-top: 30%; }
+.title-page .author { font-size: 1.2em; margin-top: 2em; }
+.dedication p { font-style: italic; text-indent: 0; text-align: center; }
+";
+
+/// Export a book as an EPUB 3 archive
+pub fn export_epub(book: &Book) -> Result<Vec<u8>, ExportError> {
+    let mut cursor = Cursor::new(Vec::new());
+    write_epub(book, &mut cursor)?;
+    Ok(cursor.into_inner())
+}
+
+/// Write a book as an EPUB 3 archive to `writer`
+///
+/// Besides the EPUB 3 nav document the archive carries an NCX table of
+/// contents for EPUB 2 readers.
+pub fn write_epub<W: Write + Seek>(book: &Book, writer: W) -> Result<(), ExportError> {
+    let doc = Document::from_book(book);
+    let mut zip = ZipWriter::new(writer);
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // The mimetype must be the first entry and stored uncompressed
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(MIMETYPE.as_bytes())?;
+
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(container_xml().as_bytes())?;
+
+    let mut entries = vec![
+        ("OEBPS/style.css".to_string(), STYLESHEET.to_string()),
+        ("OEBPS/nav.xhtml".to_string(), nav_xhtml(&doc)),
+        ("OEBPS/toc.ncx".to_string(), toc_ncx(&doc)),
+        ("OEBPS/title.xhtml".to_string(), title_xhtml(&doc)),
+    ];
+    if let Some(dedication) = &doc.dedication {
+        entries.push((
+            "OEBPS/dedication.xhtml".to_string(),
+            dedication_xhtml(&doc, dedication),
+        ));
+    }
+    for chapter in &doc.chapters {
+        entries.push((
+            format!("OEBPS/{}", chapter_href(chapter)),
+            chapter_xhtml(&doc, chapter),
+        ));
+    }
+    entries.push((PACKAGE_PATH.to_string(), package_opf(&doc)));
+
+    for (path, content) in entries {
+        zip.start_file(path, deflated)?;
+        zip.write_all(content.as_bytes())?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+fn chapter_href(chapter: &DocChapter) -> String {
+    format!("chapter-{}.xhtml", chapter.id)
+}
+
+/// Manifest id of a chapter; XML ids may not start with a digit
+fn chapter_item_id(chapter: &DocChapter) -> String {
+    format!("ch-{}", chapter.id)
+}
+
+fn container_xml() -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="{}" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#,
+        PACKAGE_PATH
+    )
+}
+
+fn package_opf(doc: &Document) -> String {
+    let mut manifest = String::new();
+    let mut spine = String::new();
+    let mut item = |id: &str, href: &str, media_type: &str, properties: Option<&str>| {
+        let properties = properties
+            .map(|p| format!(r#" properties="{}""#, p))
+            .unwrap_or_default();
+        manifest.push_str(&format!(
+            "    <item id=\"{}\" href=\"{}\" media-type=\"{}\"{}/>\n",
+            id, href, media_type, properties
+        ));
+    };
+
+    item("css", "style.css", "text/css", None);
+    item("nav", "nav.xhtml", "application/xhtml+xml", Some("nav"));
+    item("ncx", "toc.ncx", "application/x-dtbncx+xml", None);
+    item("title", "title.xhtml", "application/xhtml+xml", None);
+    spine.push_str("    <itemref idref=\"title\"/>\n");
+    if doc.dedication.is_some() {
+        item(
+            "dedication",
+            "dedication.xhtml",
+            "application/xhtml+xml",
+            None,
+        );
+        spine.push_str("    <itemref idref=\"dedication\"/>\n");
+    }
+    for chapter in &doc.chapters {
+        let id = chapter_item_id(chapter);
+        item(&id, &chapter_href(chapter), "application/xhtml+xml", None);
+        spine.push_str(&format!("    <itemref idref=\"{}\"/>\n", id));
+    }
+
+    format!(
+        r##"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" xml:lang="{lang}">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="book-id">urn:uuid:{id}</dc:identifier>
+    <dc:title>{title}</dc:title>
+    <dc:creator id="author">{author}</dc:creator>
+    <meta refines="#author" property="role" scheme="marc:relators">aut</meta>
+    <dc:language>{lang}</dc:language>
+    <meta property="dcterms:modified">{modified}</meta>
+  </metadata>
+  <manifest>
+{manifest}  </manifest>
+  <spine toc="ncx">
+{spine}  </spine>
+</package>
+"##,
+        lang = escape(&doc.language),
+        id = doc.id,
+        title = escape(&doc.title),
+        author = escape(&doc.author),
+        modified = doc.modified.format("%Y-%m-%dT%H:%M:%SZ"),
+        manifest = manifest,
+        spine = spine,
+    )
+}
+
+fn nav_xhtml(doc: &Document) -> String {
+    let mut items = String::new();
+    for chapter in &doc.chapters {
+        items.push_str(&format!(
+            "        <li><a href=\"{}\">{}</a></li>\n",
+            chapter_href(chapter),
+            escape(&chapter.title)
+        ));
+    }
+
+    let mut landmarks = String::from(
+        "        <li><a epub:type=\"titlepage\" href=\"title.xhtml\">Title Page</a></li>\n",
+    );
+    if let Some(first) = doc.chapters.first() {
+        landmarks.push_str(&format!(
+            "        <li><a epub:type=\"bodymatter\" href=\"{}\">Start of Content</a></li>\n",
+            chapter_href(first)
+        ));
+    }
+
+    let body = format!(
+        r#"    <nav epub:type="toc" id="toc">
+      <h1>Contents</h1>
+      <ol>
+{}      </ol>
+    </nav>
+    <nav epub:type="landmarks" hidden="hidden">
+      <ol>
+{}      </ol>
+    </nav>
+"#,
+        items, landmarks
+    );
+    xhtml_page(doc, "Contents", &body)
+}
+
+fn toc_ncx(doc: &Document) -> String {
+    let mut points = String::new();
+    for (index, chapter) in doc.chapters.iter().enumerate() {
+        points.push_str(&format!(
+            r#"    <navPoint id="nav-{order}" playOrder="{order}">
+      <navLabel><text>{title}</text></navLabel>
+      <content src="{href}"/>
+    </navPoint>
+"#,
+            order = index + 1,
+            title = escape(&chapter.title),
+            href = chapter_href(chapter),
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/" version="2005-1" xml:lang="{lang}">
+  <head>
+    <meta name="dtb:uid" content="urn:uuid:{id}"/>
+    <meta name="dtb:depth" content="1"/>
+    <meta name="dtb:totalPageCount" content="0"/>
+    <meta name="dtb:maxPageNumber" content="0"/>
+  </head>
+  <docTitle><text>{title}</text></docTitle>
+  <docAuthor><text>{author}</text></docAuthor>
+  <navMap>
+{points}  </navMap>
+</ncx>
+"#,
+        lang = escape(&doc.language),
+        id = doc.id,
+        title = escape(&doc.title),
+        author = escape(&doc.author),
+        points = points,
+    )
+}
+
+fn title_xhtml(doc: &Document) -> String {
+    let body = format!(
+        r#"    <section class="title-page" epub:type="titlepage">
+      <h1>{}</h1>
+      <p class="author">{}</p>
+    </section>
+"#,
+        escape(&doc.title),
+        escape(&doc.author)
+    );
+    xhtml_page(doc, &doc.title, &body)
+}
+
+fn dedication_xhtml(doc: &Document, dedication: &str) -> String {
+    let mut body = String::from("    <section class=\"dedication\" epub:type=\"dedication\">\n");
+    for line in dedication.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        body.push_str(&format!("      <p>{}</p>\n", escape(line)));
+    }
+    body.push_str("    </section>\n");
+    xhtml_page(doc, "Dedication", &body)
+}
+
+fn chapter_xhtml(doc: &Document, chapter: &DocChapter) -> String {
+    let mut body = format!(
+        "    <section id=\"{}\" epub:type=\"chapter\">\n      <h1>{}</h1>\n",
+        chapter_item_id(chapter),
+        escape(&chapter.title)
+    );
+    for block in &chapter.blocks {
+        match block {
+            Block::Paragraph(text) => body.push_str(&format!("      <p>{}</p>\n", escape(text))),
+            Block::SceneBreak => body.push_str("      <hr class=\"scene-break\"/>\n"),
+            Block::Screenplay(ScreenplayElement::PageBreak) => {
+                body.push_str("      <hr class=\"page-break\"/>\n")
+            }
+            Block::Screenplay(element) => body.push_str(&format!(
+                "      <p class=\"{}\">{}</p>\n",
+                element.class(),
+                escape(element.text()).replace('\n', "<br/>")
+            )),
+        }
+    }
+    body.push_str("    </section>\n");
+    xhtml_page(doc, &chapter.title, &body)
+}
+
+fn xhtml_page(doc: &Document, title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" xml:lang="{lang}" lang="{lang}">
+  <head>
+    <meta charset="UTF-8"/>
+    <title>{title}</title>
+    <link rel="stylesheet" type="text/css" href="style.css"/>
+  </head>
+  <body>
+{body}  </body>
+</html>
+"#,
+        lang = escape(&doc.language),
+        title = escape(title),
+        body = body,
+    )
+}
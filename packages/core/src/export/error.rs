@@ -0,0 +1,20 @@
+use thiserror::Error;
+
+/// Errors that can occur while exporting a book
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("IO error during export: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to write archive: {0}")]
+    Archive(#[from] zip::result::ZipError),
+
+    #[error("Layout failed: {0}")]
+    Layout(#[from] crate::layout::LayoutError),
+
+    #[error("Unusable font: {0}")]
+    Font(String),
+
+    #[error("Unknown export format: {0}")]
+    UnknownFormat(String),
+}
@@ -0,0 +1,55 @@
+use crate::bk_format::{parse_screenplay, ScreenplayElement};
+use crate::document::{Block, Document};
+use crate::models::{Book, BookFormat, Chapter};
+
+/// Serialize a book to Fountain
+///
+/// A title page comes first and each chapter becomes a `#` section.
+/// Screenplay chapters are already Fountain and are copied as written. Prose
+/// paragraphs become action, forced with `!` where Fountain would read them
+/// as another element, and scene breaks are centered.
+pub fn export_fountain(book: &Book) -> String {
+    let mut out = format!(
+        "Title: {}\nCredit: Written by\nAuthor: {}\n",
+        book.title, book.author
+    );
+
+    match book.format {
+        BookFormat::Screenplay => {
+            let mut chapters: Vec<&Chapter> = book.chapters.iter().collect();
+            chapters.sort_by_key(|c| c.order);
+            for chapter in chapters {
+                out.push_str(&format!("\n# {}\n", chapter.title));
+                for line in chapter.content.lines() {
+                    out.push('\n');
+                    out.push_str(line.trim_end());
+                }
+                out.push('\n');
+            }
+        }
+        BookFormat::Prose => {
+            for chapter in Document::from_book(book).chapters {
+                out.push_str(&format!("\n# {}\n", chapter.title));
+                for block in &chapter.blocks {
+                    out.push('\n');
+                    match block {
+                        Block::Paragraph(text) => out.push_str(&action(text)),
+                        Block::Screenplay(element) => out.push_str(&action(element.text())),
+                        Block::SceneBreak => out.push_str(">* * *<"),
+                    }
+                    out.push('\n');
+                }
+            }
+        }
+    }
+    out
+}
+
+/// A paragraph as an action line, forced if it would read as something else
+fn action(text: &str) -> String {
+    if parse_screenplay(text) == [ScreenplayElement::Action(text.to_string())] {
+        text.to_string()
+    } else {
+        format!("!{}", text)
+    }
+}
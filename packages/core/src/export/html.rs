@@ -0,0 +1,327 @@
+use crate::bk_format::ScreenplayElement;
+use crate::document::{Block, DocChapter, Document};
+use crate::export::error::ExportError;
+use crate::export::xml::escape;
+use crate::models::Book;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use unicode_segmentation::UnicodeSegmentation;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Number of words in a search result excerpt
+const EXCERPT_WORDS: usize = 30;
+
+const STYLESHEET: &str = "\
+body { max-width: 38em; margin: 0 auto; padding: 2em 1em; font-family: Georgia, serif; line-height: 1.6; color: #1a1a1a; background: #f7f5f0; }
+h1, h2 { font-weight: normal; text-align: center; }
+p { margin: 0; text-indent: 1.5em; }
+h2 + p, hr + p { text-indent: 0; }
+hr.scene-break { border: none; margin: 1.5em 0; text-align: center; }
+hr.scene-break::after { content: \"* * *\"; }
+hr.page-break { border: none; break-after: page; }
+p.scene-heading, p.action, p.character, p.parenthetical, p.dialogue, p.transition, p.centered { text-indent: 0; text-align: left; }
+p.scene-heading, p.action, p.character, p.transition, p.centered { margin-top: 1em; }
+p.character { margin-left: 37%; }
+p.parenthetical { margin-left: 27%; margin-right: 35%; }
+p.dialogue { margin-left: 17%; margin-right: 25%; }
+p.transition { text-align: right; }
+p.centered { text-align: center; }
+.author { text-align: center; font-style: italic; }
+.dedication { text-align: center; font-style: italic; margin: 3em 0; }
+nav.toc ol { padding-left: 1.5em; }
+nav.pager { display: flex; justify-content: space-between; margin: 3em 0 1em; }
+#search { width: 100%; padding: 0.4em; font: inherit; }
+#results li { margin: 0.5em 0; }
+";
+
+const STYLESHEET_LINK: &str = "  <link rel=\"stylesheet\" href=\"style.css\">\n";
+
+/// JavaScript for the site's search box; reads `search-index.json`
+const SEARCH_SCRIPT: &str = r#"(function () {
+  var input = document.getElementById("search");
+  var list = document.getElementById("results");
+  var index = null;
+  fetch("search-index.json").then(function (r) { return r.json(); }).then(function (data) { index = data; });
+  function tokens(text) {
+    return text.toLowerCase().split(/[^\p{L}\p{N}']+/u).filter(function (t) { return t.length > 0; });
+  }
+  input.addEventListener("input", function () {
+    list.innerHTML = "";
+    if (!index) return;
+    var scores = null;
+    tokens(input.value).forEach(function (token) {
+      var found = {};
+      Object.keys(index.terms).forEach(function (term) {
+        if (term.indexOf(token) === 0) {
+          index.terms[term].forEach(function (p) { found[p[0]] = (found[p[0]] || 0) + p[1]; });
+        }
+      });
+      if (scores === null) { scores = found; return; }
+      Object.keys(scores).forEach(function (doc) {
+        if (found[doc] === undefined) delete scores[doc]; else scores[doc] += found[doc];
+      });
+    });
+    if (!scores) return;
+    Object.keys(scores).sort(function (a, b) { return scores[b] - scores[a]; }).forEach(function (doc) {
+      var entry = index.documents[doc];
+      var item = document.createElement("li");
+      var link = document.createElement("a");
+      link.href = entry.url;
+      link.textContent = entry.title;
+      item.appendChild(link);
+      item.appendChild(document.createTextNode(" — " + entry.excerpt));
+      list.appendChild(item);
+    });
+  });
+})();
+"#;
+
+/// A file of the static site, with a path relative to the site root
+#[derive(Debug, Clone, PartialEq)]
+pub struct SiteFile {
+    pub path: String,
+    pub contents: String,
+}
+
+/// Prebuilt index for the site's search box
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub documents: Vec<SearchDocument>,
+    /// Lowercased word -> `(document index, occurrences)` pairs
+    pub terms: BTreeMap<String, Vec<(usize, usize)>>,
+}
+
+/// One searchable chapter
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchDocument {
+    pub title: String,
+    pub url: String,
+    pub excerpt: String,
+}
+
+/// Build a multi-page static site: an index page, one page per chapter,
+/// a stylesheet, and a search script with its prebuilt index
+pub fn html_site(book: &Book) -> Vec<SiteFile> {
+    let doc = Document::from_book(book);
+    let mut files = vec![
+        SiteFile {
+            path: "index.html".to_string(),
+            contents: site_index(&doc),
+        },
+        SiteFile {
+            path: "style.css".to_string(),
+            contents: STYLESHEET.to_string(),
+        },
+        SiteFile {
+            path: "search.js".to_string(),
+            contents: SEARCH_SCRIPT.to_string(),
+        },
+        SiteFile {
+            path: "search-index.json".to_string(),
+            contents: serde_json::to_string(&search_index(&doc))
+                .expect("search index has string keys and serializes"),
+        },
+    ];
+
+    for (index, chapter) in doc.chapters.iter().enumerate() {
+        let prev = index.checked_sub(1).map(|i| &doc.chapters[i]);
+        let next = doc.chapters.get(index + 1);
+        files.push(SiteFile {
+            path: chapter_page(chapter),
+            contents: site_chapter(&doc, chapter, prev, next),
+        });
+    }
+    files
+}
+
+/// Static site packed into a zip archive
+pub fn export_html_site(book: &Book) -> Result<Vec<u8>, ExportError> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for file in html_site(book) {
+        zip.start_file(file.path, deflated)?;
+        zip.write_all(file.contents.as_bytes())?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Write the static site into `dir`, creating it if needed
+pub fn write_html_site(book: &Book, dir: &Path) -> Result<(), ExportError> {
+    std::fs::create_dir_all(dir)?;
+    for file in html_site(book) {
+        std::fs::write(dir.join(&file.path), file.contents)?;
+    }
+    Ok(())
+}
+
+/// Single self-contained HTML file with inline CSS and no external resources
+pub fn export_html(book: &Book) -> String {
+    let doc = Document::from_book(book);
+    let mut body = title_block(&doc);
+    body.push_str(&toc(&doc, |chapter| format!("#{}", chapter.id)));
+    for chapter in &doc.chapters {
+        body.push_str(&chapter_section(chapter));
+    }
+    page(
+        &doc,
+        &doc.title,
+        &format!("  <style>\n{}  </style>\n", STYLESHEET),
+        &body,
+    )
+}
+
+fn chapter_page(chapter: &DocChapter) -> String {
+    format!("{}.html", chapter.id)
+}
+
+fn site_index(doc: &Document) -> String {
+    let mut body = title_block(doc);
+    body.push_str(
+        "  <input id=\"search\" type=\"search\" placeholder=\"Search\" aria-label=\"Search the book\">\n  <ul id=\"results\"></ul>\n",
+    );
+    body.push_str(&toc(doc, chapter_page));
+    body.push_str("  <script src=\"search.js\"></script>\n");
+    page(doc, &doc.title, STYLESHEET_LINK, &body)
+}
+
+fn site_chapter(
+    doc: &Document,
+    chapter: &DocChapter,
+    prev: Option<&DocChapter>,
+    next: Option<&DocChapter>,
+) -> String {
+    let link = |target: Option<&DocChapter>, rel: &str, label: &str| match target {
+        Some(target) => format!(
+            "<a rel=\"{}\" href=\"{}\">{} {}</a>",
+            rel,
+            chapter_page(target),
+            label,
+            escape(&target.title)
+        ),
+        None => "<span></span>".to_string(),
+    };
+    let pager = format!(
+        "  <nav class=\"pager\">\n    {}\n    <a href=\"index.html\">Contents</a>\n    {}\n  </nav>\n",
+        link(prev, "prev", "&larr;"),
+        link(next, "next", "&rarr;")
+    );
+
+    let body = format!("{}{}", chapter_section(chapter), pager);
+    page(
+        doc,
+        &format!("{} \u{2013} {}", chapter.title, doc.title),
+        STYLESHEET_LINK,
+        &body,
+    )
+}
+
+fn title_block(doc: &Document) -> String {
+    let mut out = format!(
+        "  <header>\n    <h1>{}</h1>\n    <p class=\"author\">{}</p>\n  </header>\n",
+        escape(&doc.title),
+        escape(&doc.author)
+    );
+    if let Some(dedication) = &doc.dedication {
+        out.push_str(&format!(
+            "  <p class=\"dedication\">{}</p>\n",
+            escape(dedication)
+        ));
+    }
+    out
+}
+
+fn toc(doc: &Document, href: impl Fn(&DocChapter) -> String) -> String {
+    let mut out = String::from("  <nav class=\"toc\">\n    <h2>Contents</h2>\n    <ol>\n");
+    for chapter in &doc.chapters {
+        out.push_str(&format!(
+            "      <li><a href=\"{}\">{}</a></li>\n",
+            href(chapter),
+            escape(&chapter.title)
+        ));
+    }
+    out.push_str("    </ol>\n  </nav>\n");
+    out
+}
+
+/// A chapter as a `<section>` whose id is the chapter id
+fn chapter_section(chapter: &DocChapter) -> String {
+    let mut out = format!(
+        "  <section id=\"{}\" class=\"chapter\">\n    <h2>{}</h2>\n",
+        chapter.id,
+        escape(&chapter.title)
+    );
+    for block in &chapter.blocks {
+        match block {
+            Block::Paragraph(text) => out.push_str(&format!("    <p>{}</p>\n", escape(text))),
+            Block::SceneBreak => out.push_str("    <hr class=\"scene-break\">\n"),
+            Block::Screenplay(ScreenplayElement::PageBreak) => {
+                out.push_str("    <hr class=\"page-break\">\n")
+            }
+            Block::Screenplay(element) => out.push_str(&format!(
+                "    <p class=\"{}\">{}</p>\n",
+                element.class(),
+                escape(element.text()).replace('\n', "<br>")
+            )),
+        }
+    }
+    out.push_str("  </section>\n");
+    out
+}
+
+fn page(doc: &Document, title: &str, head: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n  <meta charset=\"utf-8\">\n  <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n  <meta name=\"author\" content=\"{}\">\n  <title>{}</title>\n{}</head>\n<body>\n{}</body>\n</html>\n",
+        escape(&doc.language),
+        escape(&doc.author),
+        escape(title),
+        head,
+        body
+    )
+}
+
+fn search_index(doc: &Document) -> SearchIndex {
+    let mut terms: BTreeMap<String, Vec<(usize, usize)>> = BTreeMap::new();
+    let mut documents = Vec::new();
+
+    for (index, chapter) in doc.chapters.iter().enumerate() {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        let mut excerpt = Vec::new();
+        let mut truncated = false;
+        for block in &chapter.blocks {
+            let text = match block {
+                Block::Paragraph(text) => text.as_str(),
+                Block::Screenplay(element) => element.text(),
+                Block::SceneBreak => continue,
+            };
+            for word in text.unicode_words() {
+                if excerpt.len() < EXCERPT_WORDS {
+                    excerpt.push(word);
+                } else {
+                    truncated = true;
+                }
+                *counts.entry(word.to_lowercase()).or_insert(0) += 1;
+            }
+        }
+        for word in chapter.title.unicode_words() {
+            *counts.entry(word.to_lowercase()).or_insert(0) += 1;
+        }
+        for (term, count) in counts {
+            terms.entry(term).or_default().push((index, count));
+        }
+
+        let mut excerpt = excerpt.join(" ");
+        if truncated {
+            excerpt.push('\u{2026}');
+        }
+        documents.push(SearchDocument {
+            title: chapter.title.clone(),
+            url: chapter_page(chapter),
+            excerpt,
+        });
+    }
+
+    SearchIndex { documents, terms }
+}
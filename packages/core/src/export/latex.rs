@@ -0,0 +1,218 @@
+use crate::bk_format::ScreenplayElement;
+use crate::document::{Block, Document};
+use crate::models::Book;
+use serde::{Deserialize, Serialize};
+
+/// Preamble used when the options supply none
+///
+/// Works with pdfLaTeX, XeLaTeX and LuaLaTeX; text outside the Latin, Greek
+/// and Cyrillic scripts needs one of the latter two.
+const DEFAULT_PREAMBLE: &str = r"\usepackage{iftex}
+\ifPDFTeX
+  \usepackage[T1]{fontenc}
+  \usepackage[utf8]{inputenc}
+  \usepackage{lmodern}
+\else
+  \usepackage{fontspec}
+\fi
+\usepackage{textcomp}
+\usepackage[$language$]{babel}
+\usepackage{microtype}
+";
+
+/// Commands the body relies on, defined only if the class or preamble has not
+const SUPPORT: &str = r"\providecommand{\frontmatter}{}
+\providecommand{\mainmatter}{}
+\providecommand{\scenebreak}{\par\bigskip\begin{center}*\quad*\quad*\end{center}\bigskip\par}
+";
+
+/// Commands for screenplay elements, added for screenplay books, indented
+/// like a screenplay page
+const SCREENPLAY_SUPPORT: &str = r"\providecommand{\sceneheading}[1]{\par\bigskip\noindent #1\par\nopagebreak}
+\providecommand{\action}[1]{\par\bigskip\noindent #1\par}
+\providecommand{\cue}[1]{\par\bigskip\noindent\hspace*{2.2in}#1\par\nopagebreak}
+\providecommand{\parenthetical}[1]{\par\noindent\hspace*{1.6in}\parbox{2.3in}{\raggedright #1}\par\nopagebreak}
+\providecommand{\dialogue}[1]{\par\noindent\hspace*{1in}\parbox{3.5in}{\raggedright #1}\par}
+\providecommand{\transition}[1]{\par\bigskip\noindent\hfill #1\par}
+\providecommand{\centeredline}[1]{\par\bigskip{\centering #1\par}}
+";
+
+/// babel names for BCP 47 language tags; anything else uses `english`
+const BABEL_LANGUAGES: &[(&str, &str)] = &[
+    ("en-gb", "british"),
+    ("en-us", "american"),
+    ("en-ca", "canadian"),
+    ("en-au", "australian"),
+    ("en-nz", "newzealand"),
+    ("en", "english"),
+    ("fr", "french"),
+    ("de", "ngerman"),
+    ("es", "spanish"),
+    ("it", "italian"),
+    ("pt-br", "brazilian"),
+    ("pt", "portuguese"),
+    ("nl", "dutch"),
+    ("sv", "swedish"),
+    ("fi", "finnish"),
+    ("da", "danish"),
+    ("nb", "norsk"),
+    ("no", "norsk"),
+    ("pl", "polish"),
+    ("ca", "catalan"),
+    ("la", "latin"),
+];
+
+/// Settings for LaTeX export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatexOptions {
+    /// A class that defines `\chapter`, such as `book`, `memoir` or `scrbook`
+    pub document_class: String,
+    /// Class options such as `12pt` or `a5paper`
+    pub class_options: Vec<String>,
+    /// Preamble to use instead of the default, between `\documentclass` and
+    /// `\begin{document}`. `$title$`, `$author$` and `$language$` (a babel
+    /// language name) are replaced with the book's values.
+    pub preamble: Option<String>,
+}
+
+impl Default for LatexOptions {
+    fn default() -> Self {
+        Self {
+            document_class: "book".to_string(),
+            class_options: Vec::new(),
+            preamble: None,
+        }
+    }
+}
+
+/// Serialize a book to a LaTeX source file
+///
+/// The title page, dedication and table of contents form the front matter
+/// and each chapter is a `\chapter`. Every LaTeX special character in the
+/// text is escaped; the .bk format has no inline markup, so nothing in the
+/// text becomes a command.
+pub fn export_latex(book: &Book, options: &LatexOptions) -> String {
+    let doc = Document::from_book(book);
+    let language = babel_language(&doc.language);
+
+    let mut out = String::new();
+    out.push_str("\\documentclass");
+    if !options.class_options.is_empty() {
+        out.push_str(&format!("[{}]", options.class_options.join(",")));
+    }
+    out.push_str(&format!("{{{}}}\n\n", options.document_class));
+    let preamble = options
+        .preamble
+        .as_deref()
+        .unwrap_or(DEFAULT_PREAMBLE)
+        .replace("$title$", &escape(&doc.title))
+        .replace("$author$", &escape(&doc.author))
+        .replace("$language$", language);
+    out.push_str(preamble.trim_end());
+    out.push_str("\n\n");
+    out.push_str(SUPPORT);
+    let screenplay = doc
+        .chapters
+        .iter()
+        .flat_map(|chapter| &chapter.blocks)
+        .any(|block| matches!(block, Block::Screenplay(_)));
+    if screenplay {
+        out.push_str(SCREENPLAY_SUPPORT);
+    }
+    out.push_str(&format!(
+        "\n\\title{{{}}}\n\\author{{{}}}\n\\date{{}}\n",
+        escape(&doc.title),
+        escape(&doc.author)
+    ));
+
+    out.push_str("\n\\begin{document}\n\n\\frontmatter\n\\maketitle\n");
+    if let Some(dedication) = &doc.dedication {
+        out.push_str(&format!(
+            "\n\\cleardoublepage\n\\thispagestyle{{empty}}\n\\vspace*{{0.3\\textheight}}\n\
+             \\begin{{center}}\n\\itshape {}\n\\end{{center}}\n",
+            escape(dedication)
+        ));
+    }
+    out.push_str("\n\\cleardoublepage\n\\tableofcontents\n\n\\mainmatter\n");
+
+    for chapter in &doc.chapters {
+        out.push_str(&format!("\n\\chapter{{{}}}\n", escape(&chapter.title)));
+        let mut after_break = false;
+        for block in &chapter.blocks {
+            match block {
+                Block::Paragraph(text) => {
+                    out.push('\n');
+                    if after_break {
+                        out.push_str("\\noindent ");
+                    }
+                    out.push_str(&escape(text));
+                    out.push('\n');
+                    after_break = false;
+                }
+                Block::SceneBreak => {
+                    out.push_str("\n\\scenebreak\n");
+                    after_break = true;
+                }
+                Block::Screenplay(ScreenplayElement::PageBreak) => out.push_str("\n\\newpage\n"),
+                Block::Screenplay(element) => {
+                    let command = match element {
+                        ScreenplayElement::SceneHeading(_) => "sceneheading",
+                        ScreenplayElement::Character(_) => "cue",
+                        ScreenplayElement::Parenthetical(_) => "parenthetical",
+                        ScreenplayElement::Dialogue(_) => "dialogue",
+                        ScreenplayElement::Transition(_) => "transition",
+                        ScreenplayElement::Centered(_) => "centeredline",
+                        _ => "action",
+                    };
+                    let lines: Vec<String> = element.text().split('\n').map(escape).collect();
+                    out.push_str(&format!("\n\\{}{{{}}}\n", command, lines.join("\\\\ ")));
+                }
+            }
+        }
+    }
+    out.push_str("\n\\end{document}\n");
+    out
+}
+
+fn babel_language(tag: &str) -> &'static str {
+    let tag = tag.trim().to_ascii_lowercase();
+    BABEL_LANGUAGES
+        .iter()
+        .find(|(code, _)| *code == tag)
+        .or_else(|| {
+            let primary = tag.split(['-', '_']).next().unwrap_or_default();
+            BABEL_LANGUAGES.iter().find(|(code, _)| *code == primary)
+        })
+        .map_or("english", |(_, name)| name)
+}
+
+/// Escape text so LaTeX typesets every character as written
+///
+/// Besides the ten special characters, `<`, `>`, `|` and `"` are spelled as
+/// commands, since some font encodings and babel languages treat them
+/// specially. A no-break space becomes `~`.
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '{' => out.push_str("\\{"),
+            '}' => out.push_str("\\}"),
+            '#' => out.push_str("\\#"),
+            '$' => out.push_str("\\$"),
+            '%' => out.push_str("\\%"),
+            '&' => out.push_str("\\&"),
+            '_' => out.push_str("\\_"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '<' => out.push_str("\\textless{}"),
+            '>' => out.push_str("\\textgreater{}"),
+            '|' => out.push_str("\\textbar{}"),
+            '"' => out.push_str("\\textquotedbl{}"),
+            '\u{a0}' => out.push('~'),
+            c => out.push(c),
+        }
+    }
+    out
+}
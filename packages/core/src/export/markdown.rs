@@ -0,0 +1,82 @@
+use crate::bk_format::BkMetadata;
+use crate::models::{Book, BookFormat, Chapter};
+
+/// Scene break written as a CommonMark thematic break; read back verbatim
+pub(crate) const THEMATIC_BREAK: &str = "* * *";
+
+/// Serialize a book to CommonMark with YAML frontmatter
+///
+/// Each chapter becomes a `#` heading and each content line a paragraph.
+/// Characters Markdown would interpret are backslash-escaped, so importing the
+/// result gives back the same text. Screenplay chapters are Fountain, whose
+/// blank lines matter, so each is kept verbatim in a fenced code block.
+pub fn export_markdown(book: &Book) -> String {
+    let frontmatter = serde_yaml::to_string(&BkMetadata::from_book(book))
+        .expect("metadata has string keys and serializes");
+    let mut out = format!("---\n{}---\n", frontmatter);
+
+    let mut chapters: Vec<&Chapter> = book.chapters.iter().collect();
+    chapters.sort_by_key(|c| c.order);
+    for chapter in chapters {
+        out.push_str(&format!("\n# {}\n", escape_line(&chapter.title, true)));
+        if book.format == BookFormat::Screenplay {
+            out.push('\n');
+            out.push_str(&fenced(&chapter.content));
+            continue;
+        }
+        for line in chapter.content.lines().map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+            out.push('\n');
+            if line == THEMATIC_BREAK {
+                out.push_str(line);
+            } else {
+                out.push_str(&escape_line(line, false));
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Text in a fenced code block, with a fence longer than any run of
+/// backticks inside it
+fn fenced(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}fountain\n{}\n{}\n", fence, text, fence)
+}
+
+/// Backslash-escape a line so CommonMark reads it as plain paragraph text
+///
+/// In headings every `#` is escaped, since trailing ones would close the heading.
+fn escape_line(line: &str, heading: bool) -> String {
+    let chars: Vec<char> = line.trim().chars().collect();
+    let mut out = String::with_capacity(line.len());
+
+    // Ordered list marker: up to nine digits followed by `.` or `)` and a space
+    let digits = chars.iter().take_while(|c| c.is_ascii_digit()).count();
+    let list_marker = (1..=9).contains(&digits)
+        && matches!(chars.get(digits), Some('.' | ')'))
+        && chars.get(digits + 1).is_none_or(|c| *c == ' ');
+
+    for (index, &c) in chars.iter().enumerate() {
+        let escape = match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' => true,
+            // Only an ampersand that could start an entity reference
+            '&' => chars
+                .get(index + 1)
+                .is_some_and(|next| next.is_ascii_alphanumeric() || *next == '#'),
+            '#' => heading || index == 0,
+            '>' | '-' | '+' | '~' => index == 0,
+            '.' | ')' => list_marker && index == digits,
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,21 @@
+pub mod docx;
+pub mod epub;
+mod error;
+pub mod fountain;
+pub mod html;
+pub mod latex;
+pub mod markdown;
+pub mod odt;
+pub mod pandoc;
+pub mod pdf;
+mod registry;
+pub mod rtf;
+pub(crate) mod xml;
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+pub use error::ExportError;
+pub use registry::{
+    export_book, exporter, exporters, formats, ExportOptions, Exporter, FormatInfo,
+};
@@ -0,0 +1,246 @@
+use crate::bk_format::ScreenplayElement;
+use crate::document::{Block, Document};
+use crate::export::error::ExportError;
+use crate::export::xml::escape;
+use crate::models::Book;
+use std::io::{Cursor, Seek, Write};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Media type stored in the `mimetype` entry
+pub const MIMETYPE: &str = "application/vnd.oasis.opendocument.text";
+
+const OFFICE_NAMESPACES: &str = r#"xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:meta="urn:oasis:names:tc:opendocument:xmlns:meta:1.0""#;
+
+/// Export a book as an OpenDocument text file
+pub fn export_odt(book: &Book) -> Result<Vec<u8>, ExportError> {
+    let mut cursor = Cursor::new(Vec::new());
+    write_odt(book, &mut cursor)?;
+    Ok(cursor.into_inner())
+}
+
+/// Write a book as an .odt to `writer`
+///
+/// A title page comes first, then the dedication on a page of its own. Each
+/// chapter opens on a new page with a Heading 1, so it appears in the
+/// navigator and in a generated table of contents.
+pub fn write_odt<W: Write + Seek>(book: &Book, writer: W) -> Result<(), ExportError> {
+    let doc = Document::from_book(book);
+    let mut zip = ZipWriter::new(writer);
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    // The mimetype must be the first entry and stored uncompressed
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(MIMETYPE.as_bytes())?;
+
+    let parts = [
+        ("META-INF/manifest.xml", MANIFEST.to_string()),
+        ("meta.xml", meta_xml(&doc)),
+        ("styles.xml", STYLES.to_string()),
+        ("content.xml", content_xml(&doc)),
+    ];
+    for (path, content) in parts {
+        zip.start_file(path, deflated)?;
+        zip.write_all(content.as_bytes())?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+const MANIFEST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.3">
+  <manifest:file-entry manifest:full-path="/" manifest:version="1.3" manifest:media-type="application/vnd.oasis.opendocument.text"/>
+  <manifest:file-entry manifest:full-path="meta.xml" manifest:media-type="text/xml"/>
+  <manifest:file-entry manifest:full-path="styles.xml" manifest:media-type="text/xml"/>
+  <manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>
+"#;
+
+/// Paragraph styles; chapter headings and the dedication start new pages
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-styles xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" xmlns:svg="urn:oasis:names:tc:opendocument:xmlns:svg-compatible:1.0" office:version="1.3">
+  <office:font-face-decls>
+    <style:font-face style:name="Liberation Serif" svg:font-family="'Liberation Serif'" style:font-family-generic="roman"/>
+  </office:font-face-decls>
+  <office:styles>
+    <style:default-style style:family="paragraph">
+      <style:paragraph-properties fo:line-height="150%"/>
+      <style:text-properties style:font-name="Liberation Serif" fo:font-size="12pt"/>
+    </style:default-style>
+    <style:style style:name="Standard" style:family="paragraph" style:class="text"/>
+    <style:style style:name="Text_20_body" style:display-name="Text body" style:family="paragraph" style:parent-style-name="Standard" style:class="text">
+      <style:paragraph-properties fo:text-indent="0.5in" fo:text-align="justify"/>
+    </style:style>
+    <style:style style:name="First_20_paragraph" style:display-name="First paragraph" style:family="paragraph" style:parent-style-name="Text_20_body" style:class="text">
+      <style:paragraph-properties fo:text-indent="0in"/>
+    </style:style>
+    <style:style style:name="Title" style:family="paragraph" style:parent-style-name="Standard" style:next-style-name="Subtitle" style:class="chapter">
+      <style:paragraph-properties fo:text-align="center" fo:margin-top="2.5in" fo:margin-bottom="0.5in"/>
+      <style:text-properties fo:font-size="28pt"/>
+    </style:style>
+    <style:style style:name="Subtitle" style:family="paragraph" style:parent-style-name="Standard" style:class="chapter">
+      <style:paragraph-properties fo:text-align="center"/>
+      <style:text-properties fo:font-size="16pt"/>
+    </style:style>
+    <style:style style:name="Dedication" style:family="paragraph" style:parent-style-name="Standard" style:class="text">
+      <style:paragraph-properties fo:text-align="center" fo:margin-top="2in" fo:break-before="page"/>
+      <style:text-properties fo:font-style="italic"/>
+    </style:style>
+    <style:style style:name="Heading_20_1" style:display-name="Heading 1" style:family="paragraph" style:parent-style-name="Standard" style:next-style-name="First_20_paragraph" style:default-outline-level="1" style:class="text">
+      <style:paragraph-properties fo:text-align="center" fo:margin-top="1.5in" fo:margin-bottom="0.5in" fo:break-before="page" fo:keep-with-next="always"/>
+      <style:text-properties fo:font-size="20pt"/>
+    </style:style>
+    <style:style style:name="Scene_20_break" style:display-name="Scene break" style:family="paragraph" style:parent-style-name="Standard" style:next-style-name="First_20_paragraph" style:class="text">
+      <style:paragraph-properties fo:text-align="center" fo:margin-top="0.15in" fo:margin-bottom="0.15in"/>
+    </style:style>
+    <style:style style:name="Screenplay" style:family="paragraph" style:parent-style-name="Standard" style:class="text">
+      <style:paragraph-properties fo:line-height="100%" fo:margin-top="0.1665in"/>
+    </style:style>
+    <style:style style:name="Scene_20_heading" style:display-name="Scene heading" style:family="paragraph" style:parent-style-name="Screenplay" style:class="text">
+      <style:paragraph-properties fo:keep-with-next="always"/>
+    </style:style>
+    <style:style style:name="Action" style:family="paragraph" style:parent-style-name="Screenplay" style:class="text"/>
+    <style:style style:name="Character" style:family="paragraph" style:parent-style-name="Screenplay" style:class="text">
+      <style:paragraph-properties fo:margin-left="2.2in" fo:keep-with-next="always"/>
+    </style:style>
+    <style:style style:name="Parenthetical" style:family="paragraph" style:parent-style-name="Screenplay" style:class="text">
+      <style:paragraph-properties fo:margin-left="1.6in" fo:margin-right="2.1in" fo:margin-top="0in" fo:keep-with-next="always"/>
+    </style:style>
+    <style:style style:name="Dialogue" style:family="paragraph" style:parent-style-name="Screenplay" style:class="text">
+      <style:paragraph-properties fo:margin-left="1in" fo:margin-right="1.5in" fo:margin-top="0in"/>
+    </style:style>
+    <style:style style:name="Transition" style:family="paragraph" style:parent-style-name="Screenplay" style:class="text">
+      <style:paragraph-properties fo:text-align="end"/>
+    </style:style>
+    <style:style style:name="Centered" style:family="paragraph" style:parent-style-name="Screenplay" style:class="text">
+      <style:paragraph-properties fo:text-align="center"/>
+    </style:style>
+    <style:style style:name="Page_20_break" style:display-name="Page break" style:family="paragraph" style:parent-style-name="Standard" style:class="text">
+      <style:paragraph-properties fo:break-before="page"/>
+    </style:style>
+  </office:styles>
+  <office:automatic-styles>
+    <style:page-layout style:name="Book">
+      <style:page-layout-properties fo:page-width="8.5in" fo:page-height="11in" fo:margin-top="1in" fo:margin-bottom="1in" fo:margin-left="1in" fo:margin-right="1in"/>
+    </style:page-layout>
+  </office:automatic-styles>
+  <office:master-styles>
+    <style:master-page style:name="Standard" style:page-layout-name="Book"/>
+  </office:master-styles>
+</office:document-styles>
+"#;
+
+fn meta_xml(doc: &Document) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-meta {} office:version="1.3">
+  <office:meta>
+    <dc:title>{}</dc:title>
+    <meta:initial-creator>{}</meta:initial-creator>
+    <dc:creator>{}</dc:creator>
+    <dc:language>{}</dc:language>
+    <dc:date>{}</dc:date>
+  </office:meta>
+</office:document-meta>
+"#,
+        OFFICE_NAMESPACES,
+        escape(&doc.title),
+        escape(&doc.author),
+        escape(&doc.author),
+        escape(&doc.language),
+        doc.modified.format("%Y-%m-%dT%H:%M:%S")
+    )
+}
+
+fn content_xml(doc: &Document) -> String {
+    let mut body = String::new();
+    body.push_str(&paragraph("Title", &doc.title));
+    body.push_str(&paragraph("Subtitle", &doc.author));
+    if let Some(dedication) = &doc.dedication {
+        body.push_str(&paragraph("Dedication", dedication));
+    }
+    for chapter in &doc.chapters {
+        body.push_str(&format!(
+            "      <text:h text:style-name=\"Heading_20_1\" text:outline-level=\"1\">{}</text:h>\n",
+            text(&chapter.title)
+        ));
+        let mut style = "First_20_paragraph";
+        for block in &chapter.blocks {
+            match block {
+                Block::Paragraph(content) => {
+                    body.push_str(&paragraph(style, content));
+                    style = "Text_20_body";
+                }
+                Block::SceneBreak => {
+                    body.push_str(&paragraph("Scene_20_break", "* * *"));
+                    style = "First_20_paragraph";
+                }
+                Block::Screenplay(element) => {
+                    body.push_str(&paragraph(screenplay_style(element), element.text()))
+                }
+            }
+        }
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content {} office:version="1.3">
+  <office:body>
+    <office:text>
+{}    </office:text>
+  </office:body>
+</office:document-content>
+"#,
+        OFFICE_NAMESPACES, body
+    )
+}
+
+fn paragraph(style: &str, content: &str) -> String {
+    format!(
+        "      <text:p text:style-name=\"{}\">{}</text:p>\n",
+        style,
+        text(content)
+    )
+}
+
+/// Paragraph style of a screenplay element
+fn screenplay_style(element: &ScreenplayElement) -> &'static str {
+    match element {
+        ScreenplayElement::SceneHeading(_) => "Scene_20_heading",
+        ScreenplayElement::Action(_) => "Action",
+        ScreenplayElement::Character(_) => "Character",
+        ScreenplayElement::Parenthetical(_) => "Parenthetical",
+        ScreenplayElement::Dialogue(_) => "Dialogue",
+        ScreenplayElement::Transition(_) => "Transition",
+        ScreenplayElement::Centered(_) => "Centered",
+        ScreenplayElement::PageBreak => "Page_20_break",
+    }
+}
+
+/// Escape text for a paragraph, keeping tabs, line breaks and runs of
+/// spaces, which ODF would otherwise collapse
+fn text(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\t' => out.push_str("<text:tab/>"),
+            '\n' => out.push_str("<text:line-break/>"),
+            ' ' => {
+                out.push(' ');
+                let mut extra = 0;
+                while chars.next_if_eq(&' ').is_some() {
+                    extra += 1;
+                }
+                match extra {
+                    0 => {}
+                    1 => out.push_str("<text:s/>"),
+                    n => out.push_str(&format!("<text:s text:c=\"{}\"/>", n)),
+                }
+            }
+            c => out.push_str(&escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    out
+}
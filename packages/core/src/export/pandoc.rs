@@ -0,0 +1,88 @@
+use crate::document::{Block, Document};
+use crate::models::Book;
+use serde_json::{json, Map, Value};
+
+/// Version of the pandoc-types API the JSON follows (Pandoc 3.1 and later)
+pub const PANDOC_API_VERSION: [u32; 3] = [1, 23, 1];
+
+/// Serialize a book to Pandoc's JSON AST, as read by `pandoc -f json`
+///
+/// Each chapter is a level 1 `Header` followed by its paragraphs, and scene
+/// breaks are `HorizontalRule`s. Screenplay elements are paragraphs in a
+/// `Div` whose class names the element, such as `character`. Title, author, language, dedication and the
+/// `urn:uuid:` identifier go in `meta`, where Pandoc's templates and EPUB
+/// writer find them.
+pub fn export_pandoc_json(book: &Book) -> String {
+    let doc = Document::from_book(book);
+
+    let mut meta = Map::new();
+    meta.insert("title".to_string(), meta_inlines(&doc.title));
+    meta.insert(
+        "author".to_string(),
+        json!({ "t": "MetaList", "c": [meta_inlines(&doc.author)] }),
+    );
+    meta.insert("lang".to_string(), meta_inlines(&doc.language));
+    meta.insert(
+        "identifier".to_string(),
+        meta_inlines(&format!("urn:uuid:{}", doc.id)),
+    );
+    if let Some(dedication) = &doc.dedication {
+        meta.insert("dedication".to_string(), meta_inlines(dedication));
+    }
+
+    let mut blocks = Vec::new();
+    for chapter in &doc.chapters {
+        blocks.push(json!({
+            "t": "Header",
+            "c": [1, ["", [], []], inlines(&chapter.title)],
+        }));
+        for block in &chapter.blocks {
+            blocks.push(match block {
+                Block::Paragraph(text) => json!({ "t": "Para", "c": inlines(text) }),
+                Block::SceneBreak => json!({ "t": "HorizontalRule" }),
+                Block::Screenplay(element) => {
+                    let mut content = Vec::new();
+                    for line in element.text().lines() {
+                        if !content.is_empty() {
+                            content.push(json!({ "t": "LineBreak" }));
+                        }
+                        content.extend(inlines(line));
+                    }
+                    let paragraphs = if content.is_empty() {
+                        json!([])
+                    } else {
+                        json!([{ "t": "Para", "c": content }])
+                    };
+                    json!({
+                        "t": "Div",
+                        "c": [["", [element.class()], []], paragraphs],
+                    })
+                }
+            });
+        }
+    }
+
+    json!({
+        "pandoc-api-version": PANDOC_API_VERSION,
+        "meta": meta,
+        "blocks": blocks,
+    })
+    .to_string()
+}
+
+fn meta_inlines(text: &str) -> Value {
+    json!({ "t": "MetaInlines", "c": inlines(text) })
+}
+
+/// Words as `Str` inlines separated by `Space`; the .bk format has no inline
+/// markup, so nothing else is produced
+fn inlines(text: &str) -> Vec<Value> {
+    let mut out = Vec::new();
+    for word in text.split_ascii_whitespace() {
+        if !out.is_empty() {
+            out.push(json!({ "t": "Space" }));
+        }
+        out.push(json!({ "t": "Str", "c": word }));
+    }
+    out
+}
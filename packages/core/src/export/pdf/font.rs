@@ -0,0 +1,287 @@
+use crate::export::error::ExportError;
+use crate::layout::{FontMetrics, LayoutError, PositionedGlyph, TextFragment};
+use crate::models::{Book, BookFormat};
+use pdf_writer::types::{CidFontType, FontFlags, SystemInfo, UnicodeCmap};
+use pdf_writer::{Filter, Finish, Name, Pdf, Rect, Ref, Str};
+use std::collections::BTreeMap;
+use ttf_parser::{name_id, Face, GlyphId};
+
+/// Font embedded when no font file is supplied
+pub(crate) const DEFAULT_FONT: &[u8] = include_bytes!("../../../assets/fonts/DejaVuSerif.ttf");
+
+/// Monospaced font embedded in screenplays when no font file is supplied
+pub(crate) const SCREENPLAY_FONT: &[u8] =
+    include_bytes!("../../../assets/fonts/DejaVuSansMono.ttf");
+
+/// Bundled font for a book: DejaVu Sans Mono for screenplays, DejaVu Serif
+/// for everything else
+pub(crate) fn bundled_font(book: &Book) -> &'static [u8] {
+    match book.format {
+        BookFormat::Screenplay => SCREENPLAY_FONT,
+        _ => DEFAULT_FONT,
+    }
+}
+
+/// TrueType/OpenType font that fragments are drawn with, embedded as a
+/// subset and addressed by glyph id
+pub(crate) struct PdfFont<'m, 'a> {
+    /// The layout's metrics, so text is drawn as it was measured
+    metrics: &'m FontMetrics<'a>,
+    /// Glyphs drawn so far, with the text each one stands for
+    used: BTreeMap<u16, String>,
+}
+
+/// Encoded text drawn from one position with a `TJ` operator
+pub(crate) struct GlyphRun {
+    /// Offset from the fragment's origin in points; `y` is up
+    pub x: f32,
+    pub y: f32,
+    /// Encoded glyphs, each followed by the adjustment to apply after them
+    /// in thousandths of an em
+    pub items: Vec<(Vec<u8>, f32)>,
+}
+
+impl<'m, 'a> PdfFont<'m, 'a> {
+    pub(crate) fn new(metrics: &'m FontMetrics<'a>) -> Self {
+        PdfFont {
+            metrics,
+            used: BTreeMap::new(),
+        }
+    }
+
+    fn face(&self) -> &'m Face<'a> {
+        self.metrics.face()
+    }
+
+    /// Encode a fragment for drawing, recording the glyphs it uses
+    ///
+    /// Shaped glyphs are placed where the layout put them. Fragments without
+    /// them are encoded character by character.
+    pub(crate) fn encode(&mut self, fragment: &TextFragment) -> Vec<GlyphRun> {
+        if fragment.glyphs.is_empty() {
+            vec![GlyphRun {
+                x: 0.0,
+                y: 0.0,
+                items: self.encode_text(&fragment.text),
+            }]
+        } else {
+            self.encode_glyphs(&fragment.text, &fragment.glyphs, fragment.style.font_size)
+        }
+    }
+
+    fn encode_text(&mut self, text: &str) -> Vec<(Vec<u8>, f32)> {
+        let scale = 1000.0 / f32::from(self.face().units_per_em());
+        let mut runs = Vec::new();
+        let mut bytes = Vec::with_capacity(text.len() * 2);
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let glyph = self.face().glyph_index(c).unwrap_or(GlyphId(0));
+            self.used.entry(glyph.0).or_insert_with(|| c.to_string());
+            bytes.extend_from_slice(&glyph.0.to_be_bytes());
+            // TJ moves the next glyph left by positive amounts
+            let kerning = chars
+                .peek()
+                .map_or(0, |next| self.metrics.kerning(c, *next));
+            if kerning != 0 {
+                runs.push((std::mem::take(&mut bytes), -f32::from(kerning) * scale));
+            }
+        }
+        if !bytes.is_empty() || runs.is_empty() {
+            runs.push((bytes, 0.0));
+        }
+        runs
+    }
+
+    /// Runs of shaped glyphs that put each glyph at its layout position
+    ///
+    /// The PDF pen moves by each glyph's advance in the font; differences from
+    /// the shaped advance become `TJ` adjustments, and a glyph moved off the
+    /// baseline starts a run of its own.
+    fn encode_glyphs(
+        &mut self,
+        text: &str,
+        glyphs: &[PositionedGlyph],
+        font_size: f32,
+    ) -> Vec<GlyphRun> {
+        let units = f32::from(self.face().units_per_em());
+        let mut runs: Vec<GlyphRun> = Vec::new();
+        // Where the PDF pen stands after the last glyph drawn
+        let mut pen = 0.0;
+        for glyph in glyphs {
+            let end = glyphs
+                .iter()
+                .map(|other| other.cluster)
+                .filter(|cluster| *cluster > glyph.cluster)
+                .min()
+                .unwrap_or(text.len());
+            let source = text.get(glyph.cluster..end).unwrap_or_default();
+            self.used
+                .entry(glyph.id)
+                .or_insert_with(|| source.to_string());
+
+            let gap = glyph.x - pen;
+            match runs.last_mut() {
+                Some(run) if run.y == glyph.y => {
+                    let (bytes, adjust) = run.items.last_mut().expect("runs start with an item");
+                    if gap.abs() > 0.001 {
+                        // TJ moves the next glyph left by positive amounts
+                        *adjust = -gap * 1000.0 / font_size;
+                        run.items.push((glyph.id.to_be_bytes().to_vec(), 0.0));
+                    } else {
+                        bytes.extend_from_slice(&glyph.id.to_be_bytes());
+                    }
+                }
+                _ => runs.push(GlyphRun {
+                    x: glyph.x,
+                    y: glyph.y,
+                    items: vec![(glyph.id.to_be_bytes().to_vec(), 0.0)],
+                }),
+            }
+            let advance = self
+                .face()
+                .glyph_hor_advance(GlyphId(glyph.id))
+                .unwrap_or(0);
+            pen = glyph.x + f32::from(advance) * font_size / units;
+        }
+        runs
+    }
+
+    /// Write a Type0 font with a CIDFontType2 descendant; `id` is the font
+    /// resource referenced by pages
+    ///
+    /// CIDs are glyph ids (Identity-H with an identity CIDToGIDMap), which stay
+    /// valid because the subsetter keeps glyph ids and only drops outlines.
+    pub(crate) fn write(self, pdf: &mut Pdf, id: Ref, alloc: &mut Ref) -> Result<(), ExportError> {
+        let cid_id = alloc.bump();
+        let descriptor_id = alloc.bump();
+        let file_id = alloc.bump();
+        let cmap_id = alloc.bump();
+
+        let glyphs: Vec<u16> = self.used.keys().copied().collect();
+        let base_font = format!("{}+{}", subset_tag(&glyphs), self.postscript_name());
+        let base_font = Name(base_font.as_bytes());
+        let scale = 1000.0 / f32::from(self.face().units_per_em());
+        let to_pdf = |units: i16| f32::from(units) * scale;
+
+        let system_info = SystemInfo {
+            registry: Str(b"Adobe"),
+            ordering: Str(b"Identity"),
+            supplement: 0,
+        };
+
+        pdf.type0_font(id)
+            .base_font(base_font)
+            .encoding_predefined(Name(b"Identity-H"))
+            .descendant_font(cid_id)
+            .to_unicode(cmap_id);
+
+        let mut cid = pdf.cid_font(cid_id);
+        cid.subtype(CidFontType::Type2)
+            .base_font(base_font)
+            .system_info(system_info)
+            .font_descriptor(descriptor_id)
+            .default_width(0.0)
+            .cid_to_gid_map_predefined(Name(b"Identity"));
+        let mut widths = cid.widths();
+        for glyph in &glyphs {
+            let advance = self.face().glyph_hor_advance(GlyphId(*glyph)).unwrap_or(0);
+            widths.consecutive(*glyph, [f32::from(advance) * scale]);
+        }
+        widths.finish();
+        cid.finish();
+
+        let bbox = self.face().global_bounding_box();
+        let mut flags = FontFlags::NON_SYMBOLIC;
+        if self.face().is_italic() {
+            flags |= FontFlags::ITALIC;
+        }
+        if self.face().is_monospaced() {
+            flags |= FontFlags::FIXED_PITCH;
+        }
+        pdf.font_descriptor(descriptor_id)
+            .name(base_font)
+            .flags(flags)
+            .bbox(Rect::new(
+                to_pdf(bbox.x_min),
+                to_pdf(bbox.y_min),
+                to_pdf(bbox.x_max),
+                to_pdf(bbox.y_max),
+            ))
+            .italic_angle(self.face().italic_angle().unwrap_or(0.0))
+            .ascent(to_pdf(self.face().ascender()))
+            .descent(to_pdf(self.face().descender()))
+            .cap_height(to_pdf(
+                self.face()
+                    .capital_height()
+                    .unwrap_or(self.face().ascender()),
+            ))
+            .stem_v(80.0)
+            .font_file2(file_id);
+
+        let subset = subsetter::subset(self.metrics.data(), 0, subsetter::Profile::pdf(&glyphs))
+            .map_err(|e| ExportError::Font(e.to_string()))?;
+        pdf.stream(file_id, &super::compress(&subset))
+            .filter(Filter::FlateDecode)
+            .pair(Name(b"Length1"), subset.len() as i32);
+
+        let mut cmap = UnicodeCmap::new(Name(b"Custom"), system_info);
+        for (glyph, text) in &self.used {
+            if !text.is_empty() {
+                cmap.pair_with_multiple(*glyph, text.chars());
+            }
+        }
+        pdf.cmap(cmap_id, &cmap.finish());
+        Ok(())
+    }
+
+    /// PostScript name from the font's name table, without characters PDF names disallow
+    fn postscript_name(&self) -> String {
+        let name: String = self
+            .face()
+            .names()
+            .into_iter()
+            .filter(|n| n.name_id == name_id::POST_SCRIPT_NAME)
+            .find_map(|n| n.to_string())
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| c.is_ascii_graphic() && !"()<>[]{}/%#".contains(*c))
+            .collect();
+        if name.is_empty() {
+            "Font".to_string()
+        } else {
+            name
+        }
+    }
+}
+
+/// Metrics of a font file, reporting an unreadable font as a font error
+pub(crate) fn font_metrics(data: &[u8]) -> Result<FontMetrics<'_>, ExportError> {
+    FontMetrics::new(data).map_err(font_error)
+}
+
+/// Report a font the layout engine could not read as a font error
+pub(crate) fn font_error(error: LayoutError) -> ExportError {
+    match error {
+        LayoutError::Font(reason) => ExportError::Font(reason),
+        other => other.into(),
+    }
+}
+
+/// Six uppercase letters that identify a subset, derived from its glyphs
+fn subset_tag(glyphs: &[u16]) -> String {
+    // FNV-1a keeps the tag stable between runs for the same text
+    let mut hash: u32 = 0x811c_9dc5;
+    for glyph in glyphs {
+        for byte in glyph.to_be_bytes() {
+            hash ^= u32::from(byte);
+            hash = hash.wrapping_mul(0x0100_0193);
+        }
+    }
+    (0..6)
+        .map(|_| {
+            let letter = (b'A' + (hash % 26) as u8) as char;
+            hash /= 26;
+            letter
+        })
+        .collect()
+}
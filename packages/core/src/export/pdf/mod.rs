@@ -0,0 +1,242 @@
+mod font;
+
+use crate::export::error::ExportError;
+use crate::layout::{
+    layout_book_with_patterns, FontCache, FontMetrics, HyphenationPatterns, Hyphenator,
+    LayoutConfig, NumberStyle, RenderTree,
+};
+use crate::models::{Book, BookFormat};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use font::{bundled_font, font_error, font_metrics, PdfFont};
+use pdf_writer::types::NumberingStyle;
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, TextStr};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Resource name of the body font in page content streams
+const FONT_NAME: &[u8] = b"F1";
+
+/// Settings for PDF export
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfOptions {
+    /// Page layout; without one, screenplays use [`LayoutConfig::screenplay`]
+    /// and other books the default
+    pub layout: Option<LayoutConfig>,
+    /// TrueType/OpenType font file to embed; without one, the bundled DejaVu
+    /// Sans Mono is used for screenplays and DejaVu Serif otherwise
+    pub font: Option<Vec<u8>>,
+    /// Path of a font file to embed, read through the font cache; `font`
+    /// takes precedence
+    pub font_path: Option<PathBuf>,
+    /// TeX hyphenation pattern files by language tag, on top of the built-in
+    /// English; used when `layout.hyphenation` is enabled
+    pub hyphenation_patterns: BTreeMap<String, String>,
+}
+
+/// Lay out a book and render it to PDF
+///
+/// Text is measured with the font's real widths and kerning, so lines break
+/// where the printed text ends.
+pub fn export_pdf(book: &Book, options: &PdfOptions) -> Result<Vec<u8>, ExportError> {
+    export_pdf_with_fonts(book, options, &mut FontCache::new())
+}
+
+/// Export to PDF, reading `PdfOptions::font_path` through a cache kept
+/// between exports
+pub fn export_pdf_with_fonts(
+    book: &Book,
+    options: &PdfOptions,
+    fonts: &mut FontCache,
+) -> Result<Vec<u8>, ExportError> {
+    let mut patterns = HyphenationPatterns::builtin();
+    for (language, source) in &options.hyphenation_patterns {
+        patterns.insert(language, Hyphenator::parse(source)?);
+    }
+    let layout = options.layout.clone().unwrap_or_else(|| match book.format {
+        BookFormat::Screenplay => LayoutConfig::screenplay(),
+        _ => LayoutConfig::default(),
+    });
+    let file = match (&options.font, &options.font_path) {
+        (None, Some(path)) => Some(fonts.load(path).map_err(font_error)?),
+        _ => None,
+    };
+    let font = options
+        .font
+        .as_deref()
+        .or(file.as_deref())
+        .unwrap_or(bundled_font(book));
+    let metrics = font_metrics(font)?;
+    let tree = layout_book_with_patterns(book, &layout, &metrics, &patterns)?;
+    render(&tree, book, &metrics)
+}
+
+/// Draw every page, frame, line and fragment of a render tree
+///
+/// Each chapter gets a bookmark pointing at its opening page, and page labels
+/// follow the labels in the tree. The font, or the bundled font for the
+/// book's format without one, is embedded as a subset of the glyphs that are
+/// drawn.
+pub fn render_pdf(
+    tree: &RenderTree,
+    book: &Book,
+    font: Option<&[u8]>,
+) -> Result<Vec<u8>, ExportError> {
+    render(
+        tree,
+        book,
+        &font_metrics(font.unwrap_or(bundled_font(book)))?,
+    )
+}
+
+/// Draw a render tree with the metrics it was laid out with
+fn render(tree: &RenderTree, book: &Book, metrics: &FontMetrics) -> Result<Vec<u8>, ExportError> {
+    let mut font = PdfFont::new(metrics);
+    let mut alloc = Ref::new(1);
+    let catalog_id = alloc.bump();
+    let page_tree_id = alloc.bump();
+    let font_id = alloc.bump();
+    let outline_id = alloc.bump();
+    let info_id = alloc.bump();
+    let page_ids: Vec<Ref> = tree.pages.iter().map(|_| alloc.bump()).collect();
+
+    let mut pdf = Pdf::new();
+    let page_size = tree.metadata.page_size;
+
+    for (page, page_id) in tree.pages.iter().zip(&page_ids) {
+        let mut content = Content::new();
+        for frame in &page.frames {
+            for line in &frame.lines {
+                // The tree measures from the top of the page, PDF from the bottom
+                let baseline = page_size.height - (frame.bounds.y + line.y_offset);
+                for fragment in &line.fragments {
+                    let x = frame.bounds.x + fragment.x_offset;
+                    for run in font.encode(fragment) {
+                        content
+                            .begin_text()
+                            .set_font(Name(FONT_NAME), fragment.style.font_size)
+                            .set_text_matrix([1.0, 0.0, 0.0, 1.0, x + run.x, baseline + run.y]);
+                        show(&mut content, &run.items);
+                        content.end_text();
+                    }
+                }
+            }
+        }
+
+        let content_id = alloc.bump();
+        pdf.stream(content_id, &compress(&content.finish()))
+            .filter(Filter::FlateDecode);
+
+        let mut page_writer = pdf.page(*page_id);
+        page_writer
+            .media_box(Rect::new(0.0, 0.0, page_size.width, page_size.height))
+            .parent(page_tree_id)
+            .contents(content_id);
+        page_writer
+            .resources()
+            .fonts()
+            .pair(Name(FONT_NAME), font_id);
+        page_writer.finish();
+    }
+
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().copied())
+        .count(page_ids.len() as i32);
+
+    // One bookmark per chapter
+    let chapters = &tree.metadata.chapters;
+    let item_ids: Vec<Ref> = chapters.iter().map(|_| alloc.bump()).collect();
+    for (index, chapter) in chapters.iter().enumerate() {
+        let mut item = pdf.outline_item(item_ids[index]);
+        item.title(TextStr(&chapter.title)).parent(outline_id);
+        if index > 0 {
+            item.prev(item_ids[index - 1]);
+        }
+        if let Some(next) = item_ids.get(index + 1) {
+            item.next(*next);
+        }
+        if let Some(page_id) = page_ids.get(chapter.page_number.saturating_sub(1)) {
+            item.dest().page(*page_id).xyz(0.0, page_size.height, None);
+        }
+    }
+    let mut outline = pdf.outline(outline_id);
+    if let (Some(first), Some(last)) = (item_ids.first(), item_ids.last()) {
+        outline.first(*first).last(*last);
+    }
+    outline.count(item_ids.len() as i32);
+    outline.finish();
+
+    // Page labels: a new range wherever the style changes or the numbering jumps
+    let mut label_ranges = Vec::new();
+    let mut expected = None;
+    for (index, page) in tree.pages.iter().enumerate() {
+        let label = page.label;
+        if expected != Some((label.style, label.number)) {
+            label_ranges.push((index as i32, label.style, label.number as i32, alloc.bump()));
+        }
+        expected = Some((label.style, label.number + 1));
+    }
+    for (_, style, start, label_id) in &label_ranges {
+        let style = match style {
+            NumberStyle::Arabic => NumberingStyle::Arabic,
+            NumberStyle::LowerRoman => NumberingStyle::LowerRoman,
+        };
+        pdf.indirect(*label_id)
+            .start::<pdf_writer::writers::PageLabel>()
+            .style(style)
+            .offset(*start);
+    }
+
+    let mut catalog = pdf.catalog(catalog_id);
+    catalog
+        .pages(page_tree_id)
+        .outlines(outline_id)
+        .page_mode(pdf_writer::types::PageMode::UseOutlines);
+    let mut labels = catalog.page_labels();
+    let mut nums = labels.nums();
+    for (index, _, _, label_id) in &label_ranges {
+        nums.insert(*index, *label_id);
+    }
+    nums.finish();
+    labels.finish();
+    catalog.finish();
+
+    pdf.document_info(info_id)
+        .title(TextStr(&book.title))
+        .author(TextStr(&book.author))
+        .creator(TextStr("BookWriter"));
+
+    font.write(&mut pdf, font_id, &mut alloc)?;
+    Ok(pdf.finish())
+}
+
+/// Show encoded text, with `TJ` only when there are adjustments
+fn show(content: &mut Content, items: &[(Vec<u8>, f32)]) {
+    match items {
+        [(glyphs, _)] => {
+            content.show(pdf_writer::Str(glyphs));
+        }
+        items => {
+            let mut shown = content.show_positioned();
+            let mut positioned = shown.items();
+            for (glyphs, adjust) in items {
+                positioned.show(pdf_writer::Str(glyphs));
+                if *adjust != 0.0 {
+                    positioned.adjust(*adjust);
+                }
+            }
+        }
+    }
+}
+
+/// Zlib-compress a stream for the FlateDecode filter
+fn compress(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .expect("writing to a Vec cannot fail");
+    encoder.finish().expect("writing to a Vec cannot fail")
+}
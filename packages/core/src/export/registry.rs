@@ -0,0 +1,221 @@
+use crate::bk_format::BkWriter;
+use crate::export::docx::{export_docx, ManuscriptOptions};
+use crate::export::error::ExportError;
+use crate::export::latex::{export_latex, LatexOptions};
+use crate::export::pdf::{export_pdf_with_fonts, PdfOptions};
+use crate::export::{epub, fountain, html, markdown, odt, pandoc, rtf};
+use crate::layout::FontCache;
+use crate::models::Book;
+use serde::{Deserialize, Serialize};
+
+/// Settings for every format; each exporter reads only its own
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub pdf: PdfOptions,
+    pub manuscript: ManuscriptOptions,
+    pub latex: LatexOptions,
+}
+
+/// An output format
+///
+/// Front ends list the formats through [`exporters`] and run one by its id,
+/// so a format added to the registry is offered everywhere.
+pub trait Exporter: Sync {
+    /// Stable identifier, such as `"epub"` or `"docx"`
+    fn id(&self) -> &'static str;
+
+    /// Name to show in menus and file dialogs
+    fn name(&self) -> &'static str;
+
+    /// File extension, without the dot
+    fn extension(&self) -> &'static str;
+
+    /// MIME type of the output
+    fn media_type(&self) -> &'static str;
+
+    /// Export a book as the bytes of a file
+    fn export(&self, book: &Book, options: &ExportOptions) -> Result<Vec<u8>, ExportError> {
+        self.export_with_fonts(book, options, &mut FontCache::new())
+    }
+
+    /// Export a book, reading font files through a cache kept between exports
+    fn export_with_fonts(
+        &self,
+        book: &Book,
+        options: &ExportOptions,
+        fonts: &mut FontCache,
+    ) -> Result<Vec<u8>, ExportError>;
+}
+
+/// Description of a format for a front end
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FormatInfo {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub extension: &'static str,
+    pub media_type: &'static str,
+}
+
+impl FormatInfo {
+    fn of(exporter: &dyn Exporter) -> Self {
+        Self {
+            id: exporter.id(),
+            name: exporter.name(),
+            extension: exporter.extension(),
+            media_type: exporter.media_type(),
+        }
+    }
+}
+
+type ExportFn = fn(&Book, &ExportOptions, &mut FontCache) -> Result<Vec<u8>, ExportError>;
+
+/// A built-in format: its description and the function that writes it
+struct Format {
+    id: &'static str,
+    name: &'static str,
+    extension: &'static str,
+    media_type: &'static str,
+    export: ExportFn,
+}
+
+impl Exporter for Format {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn extension(&self) -> &'static str {
+        self.extension
+    }
+
+    fn media_type(&self) -> &'static str {
+        self.media_type
+    }
+
+    fn export_with_fonts(
+        &self,
+        book: &Book,
+        options: &ExportOptions,
+        fonts: &mut FontCache,
+    ) -> Result<Vec<u8>, ExportError> {
+        (self.export)(book, options, fonts)
+    }
+}
+
+/// Built-in formats, in the order front ends list them
+static FORMATS: [Format; 12] = [
+    Format {
+        id: "bk",
+        name: "Book file",
+        extension: "bk",
+        media_type: "text/plain",
+        export: |book, _, _| Ok(BkWriter::write_string(book).into_bytes()),
+    },
+    Format {
+        id: "epub",
+        name: "EPUB 3",
+        extension: "epub",
+        media_type: epub::MIMETYPE,
+        export: |book, _, _| epub::export_epub(book),
+    },
+    Format {
+        id: "pdf",
+        name: "PDF",
+        extension: "pdf",
+        media_type: "application/pdf",
+        export: |book, options, fonts| export_pdf_with_fonts(book, &options.pdf, fonts),
+    },
+    Format {
+        id: "docx",
+        name: "Manuscript (Word)",
+        extension: "docx",
+        media_type: "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        export: |book, options, _| export_docx(book, &options.manuscript),
+    },
+    Format {
+        id: "odt",
+        name: "OpenDocument text",
+        extension: "odt",
+        media_type: odt::MIMETYPE,
+        export: |book, _, _| odt::export_odt(book),
+    },
+    Format {
+        id: "rtf",
+        name: "Rich Text Format",
+        extension: "rtf",
+        media_type: "application/rtf",
+        export: |book, _, _| Ok(rtf::export_rtf(book).into_bytes()),
+    },
+    Format {
+        id: "html",
+        name: "HTML",
+        extension: "html",
+        media_type: "text/html",
+        export: |book, _, _| Ok(html::export_html(book).into_bytes()),
+    },
+    Format {
+        id: "html-site",
+        name: "HTML site (zip)",
+        extension: "zip",
+        media_type: "application/zip",
+        export: |book, _, _| html::export_html_site(book),
+    },
+    Format {
+        id: "markdown",
+        name: "Markdown",
+        extension: "md",
+        media_type: "text/markdown",
+        export: |book, _, _| Ok(markdown::export_markdown(book).into_bytes()),
+    },
+    Format {
+        id: "latex",
+        name: "LaTeX",
+        extension: "tex",
+        media_type: "application/x-latex",
+        export: |book, options, _| Ok(export_latex(book, &options.latex).into_bytes()),
+    },
+    Format {
+        id: "pandoc",
+        name: "Pandoc JSON",
+        extension: "json",
+        media_type: "application/json",
+        export: |book, _, _| Ok(pandoc::export_pandoc_json(book).into_bytes()),
+    },
+    Format {
+        id: "fountain",
+        name: "Fountain screenplay",
+        extension: "fountain",
+        media_type: "text/plain",
+        export: |book, _, _| Ok(fountain::export_fountain(book).into_bytes()),
+    },
+];
+
+/// Every registered exporter
+pub fn exporters() -> impl Iterator<Item = &'static dyn Exporter> {
+    FORMATS.iter().map(|format| format as &dyn Exporter)
+}
+
+/// The exporter with the given id
+pub fn exporter(id: &str) -> Option<&'static dyn Exporter> {
+    exporters().find(|exporter| exporter.id() == id)
+}
+
+/// Descriptions of every registered format
+pub fn formats() -> Vec<FormatInfo> {
+    exporters().map(FormatInfo::of).collect()
+}
+
+/// Export a book in the format with the given id
+pub fn export_book(
+    book: &Book,
+    format: &str,
+    options: &ExportOptions,
+) -> Result<Vec<u8>, ExportError> {
+    exporter(format)
+        .ok_or_else(|| ExportError::UnknownFormat(format.to_string()))?
+        .export(book, options)
+}
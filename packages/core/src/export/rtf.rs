@@ -0,0 +1,118 @@
+use crate::bk_format::ScreenplayElement;
+use crate::document::{Block, Document};
+use crate::models::Book;
+
+/// Letter paper and one-inch margins, in twentieths of a point
+const PAGE_SETUP: &str = r"\paperw12240\paperh15840\margl1440\margr1440\margt1440\margb1440";
+
+/// Serialize a book to RTF
+///
+/// The output is plain ASCII: other characters are written as `\uN` escapes,
+/// with `?` for readers that do not understand them. A title page comes first
+/// and the dedication follows on its own page. Each chapter title uses the
+/// "heading 1" style and starts a new page.
+pub fn export_rtf(book: &Book) -> String {
+    let doc = Document::from_book(book);
+    let mut out = String::new();
+    out.push_str("{\\rtf1\\ansi\\ansicpg1252\\deff0\\uc1\n");
+    out.push_str("{\\fonttbl{\\f0\\froman\\fcharset0 Times New Roman;}}\n");
+    out.push_str(
+        "{\\stylesheet{\\s0\\f0\\fs24 Normal;}\
+         {\\s1\\sbasedon0\\snext0\\outlinelevel0\\qc\\fs36 heading 1;}}\n",
+    );
+    out.push_str(&format!(
+        "{{\\info{{\\title {}}}{{\\author {}}}}}\n",
+        escape(&doc.title),
+        escape(&doc.author)
+    ));
+    out.push_str(PAGE_SETUP);
+    out.push_str("\\widowctrl\\f0\\fs24\n");
+
+    out.push_str(&format!(
+        "\\pard\\qc\\sb4320\\sa720\\fs56 {}\\par\n\\pard\\qc\\fs32 {}\\par\n",
+        escape(&doc.title),
+        escape(&doc.author)
+    ));
+    if let Some(dedication) = &doc.dedication {
+        out.push_str(&format!(
+            "\\pard\\pagebb\\qc\\sb2880\\fs24\\i {}\\i0\\par\n",
+            escape(dedication)
+        ));
+    }
+
+    for chapter in &doc.chapters {
+        out.push_str(&format!(
+            "\\pard\\s1\\pagebb\\outlinelevel0\\qc\\sb2160\\sa720\\keepn\\fs36 {}\\par\n",
+            escape(&chapter.title)
+        ));
+        let mut indent = false;
+        for block in &chapter.blocks {
+            match block {
+                Block::Paragraph(text) => {
+                    let first_line = if indent { 360 } else { 0 };
+                    out.push_str(&format!(
+                        "\\pard\\s0\\fi{}\\sl360\\slmult1\\qj\\fs24 {}\\par\n",
+                        first_line,
+                        escape(text)
+                    ));
+                    indent = true;
+                }
+                Block::SceneBreak => {
+                    out.push_str("\\pard\\s0\\qc\\sb240\\sa240\\fs24 * * *\\par\n");
+                    indent = false;
+                }
+                Block::Screenplay(ScreenplayElement::PageBreak) => out.push_str("\\page\n"),
+                Block::Screenplay(element) => out.push_str(&screenplay_paragraph(element)),
+            }
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// A screenplay element in single spacing, indented like a screenplay page
+/// and with a blank line above unless it follows a cue
+fn screenplay_paragraph(element: &ScreenplayElement) -> String {
+    let (indents, align) = match element {
+        ScreenplayElement::Character(_) => ("\\li3168\\keepn", "\\ql"),
+        ScreenplayElement::Parenthetical(_) => ("\\li2304\\ri3024\\keepn", "\\ql"),
+        ScreenplayElement::Dialogue(_) => ("\\li1440\\ri2160", "\\ql"),
+        ScreenplayElement::SceneHeading(_) => ("\\keepn", "\\ql"),
+        ScreenplayElement::Transition(_) => ("", "\\qr"),
+        ScreenplayElement::Centered(_) => ("", "\\qc"),
+        _ => ("", "\\ql"),
+    };
+    let space_before = if element.spaced() { 240 } else { 0 };
+    let lines: Vec<String> = element.text().split('\n').map(escape).collect();
+    format!(
+        "\\pard\\s0{}{}\\sb{}\\fs24 {}\\par\n",
+        indents,
+        align,
+        space_before,
+        lines.join("\\line ")
+    )
+}
+
+/// Escape text for an RTF group: `\`, `{` and `}` are escaped, and everything
+/// outside printable ASCII becomes `\uN?` (UTF-16, signed, as RTF requires)
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\t' => out.push_str("\\tab "),
+            '\u{a0}' => out.push_str("\\~"),
+            ' '..='~' => out.push(c),
+            c if c.is_control() => {}
+            c => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    out.push_str(&format!("\\u{}?", *unit as i16));
+                }
+            }
+        }
+    }
+    out
+}
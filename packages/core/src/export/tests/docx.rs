@@ -0,0 +1,139 @@
+use super::{paragraphs, read_zip_entry, sample_book, screenplay_book};
+use crate::export::docx::{approximate_word_count, export_docx, ManuscriptFont, ManuscriptOptions};
+use std::io::Cursor;
+use zip::ZipArchive;
+
+fn parts(options: &ManuscriptOptions) -> ZipArchive<Cursor<Vec<u8>>> {
+    ZipArchive::new(Cursor::new(export_docx(&sample_book(), options).unwrap())).unwrap()
+}
+
+#[test]
+fn test_docx_package_parts() {
+    let mut archive = parts(&ManuscriptOptions::default());
+    let types = read_zip_entry(&mut archive, "[Content_Types].xml");
+    let rels = read_zip_entry(&mut archive, "word/_rels/document.xml.rels");
+
+    for part in [
+        "word/document.xml",
+        "word/styles.xml",
+        "word/settings.xml",
+        "word/header1.xml",
+        "word/header2.xml",
+        "docProps/core.xml",
+    ] {
+        let xml = read_zip_entry(&mut archive, part);
+        paragraphs(&xml);
+        assert!(types.contains(&format!("PartName=\"/{}\"", part)));
+        if let Some(name) = part.strip_prefix("word/") {
+            assert!(
+                name == "document.xml" || rels.contains(&format!("Target=\"{}\"", name)),
+                "{} is not related from the document",
+                part
+            );
+        }
+    }
+    assert!(read_zip_entry(&mut archive, "_rels/.rels").contains("Target=\"word/document.xml\""));
+}
+
+#[test]
+fn test_docx_manuscript_layout() {
+    let options = ManuscriptOptions {
+        contact: vec!["1 Forge Lane".to_string(), "tej@example.com".to_string()],
+        ..ManuscriptOptions::default()
+    };
+    let mut archive = parts(&options);
+    let document = read_zip_entry(&mut archive, "word/document.xml");
+    let paragraphs = paragraphs(&document);
+
+    // Cover page
+    assert_eq!(paragraphs[0].1, "Tej <Chhabra>\tabout 100 words");
+    assert_eq!(paragraphs[1].1, "1 Forge Lane");
+    assert_eq!(paragraphs[2].1, "tej@example.com");
+    assert_eq!(paragraphs[3].1, "Salt & Iron");
+    assert_eq!(paragraphs[4].1, "by Tej <Chhabra>");
+
+    let body: Vec<(&str, &str)> = paragraphs[5..]
+        .iter()
+        .map(|(style, text)| (style.as_str(), text.as_str()))
+        .collect();
+    assert_eq!(
+        body,
+        [
+            ("Heading1", "The Beginning"),
+            ("BodyText", "The morning sun cracked over the horizon."),
+            ("SceneBreak", "#"),
+            ("BodyText", "Marcus stood at the forge & waited."),
+            ("Heading1", "The Road"),
+            ("BodyText", "The road stretched <endlessly> before him."),
+        ]
+    );
+
+    // The cover is unnumbered and has its own empty header
+    assert!(document.contains("<w:titlePg/>"));
+    assert!(document.contains("<w:pgNumType w:start=\"0\"/>"));
+
+    let styles = read_zip_entry(&mut archive, "word/styles.xml");
+    assert!(styles.contains("w:ascii=\"Courier New\""));
+    assert!(styles.contains("<w:sz w:val=\"24\"/>"));
+    assert!(styles.contains("w:line=\"480\""));
+    assert!(styles.contains("<w:pageBreakBefore/>"));
+}
+
+#[test]
+fn test_docx_running_header() {
+    let mut archive = parts(&ManuscriptOptions::default());
+    let header = read_zip_entry(&mut archive, "word/header1.xml");
+    assert_eq!(paragraphs(&header)[0].1, "<Chhabra> / SALT & IRON / 1");
+    assert!(header.contains(" PAGE "));
+
+    let options = ManuscriptOptions {
+        font: ManuscriptFont::Times,
+        short_title: Some("Salt".to_string()),
+        ..ManuscriptOptions::default()
+    };
+    let mut archive = parts(&options);
+    let header = read_zip_entry(&mut archive, "word/header1.xml");
+    assert!(paragraphs(&header)[0].1.starts_with("<Chhabra> / SALT / "));
+    let styles = read_zip_entry(&mut archive, "word/styles.xml");
+    assert!(styles.contains("w:ascii=\"Times New Roman\""));
+}
+
+#[test]
+fn test_approximate_word_count() {
+    assert_eq!(approximate_word_count(0), 100);
+    assert_eq!(approximate_word_count(1_249), 1_200);
+    assert_eq!(approximate_word_count(1_250), 1_300);
+    assert_eq!(approximate_word_count(9_949), 9_900);
+    assert_eq!(approximate_word_count(84_612), 85_000);
+}
+
+#[test]
+fn test_docx_screenplay_styles() {
+    let bytes = export_docx(&screenplay_book(), &ManuscriptOptions::default()).unwrap();
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+    let styles = read_zip_entry(&mut archive, "word/styles.xml");
+    let body = paragraphs(&read_zip_entry(&mut archive, "word/document.xml"));
+
+    let script: Vec<(&str, &str)> = body
+        .iter()
+        .skip_while(|(style, _)| style != "Heading1")
+        .skip(1)
+        .map(|(style, text)| (style.as_str(), text.as_str()))
+        .collect();
+    assert_eq!(
+        script,
+        [
+            ("SceneHeading", "EXT. SHORE - DAWN"),
+            ("Action", "Waves break on the rocks.\nGulls circle."),
+            ("Character", "MARCUS"),
+            ("Parenthetical", "(quietly)"),
+            ("Dialogue", "Still here."),
+            ("Transition", "CUT TO:"),
+            ("", "\n"),
+            ("Centered", "THE END"),
+        ]
+    );
+    for (style, _) in &script[..6] {
+        assert!(styles.contains(&format!("w:styleId=\"{}\"", style)));
+    }
+}
@@ -0,0 +1,160 @@
+use super::{read_zip_entry, sample_book};
+use crate::export::epub::{export_epub, MIMETYPE};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::{HashMap, HashSet};
+use std::io::Cursor;
+use zip::{CompressionMethod, ZipArchive};
+
+/// Parse an XML document, returning each start/empty tag with its attributes
+fn xml_elements(xml: &str) -> Vec<(String, HashMap<String, String>)> {
+    let mut reader = Reader::from_str(xml);
+    reader.check_end_names(true);
+    let mut elements = Vec::new();
+    loop {
+        match reader.read_event().expect("well-formed XML") {
+            Event::Start(e) | Event::Empty(e) => {
+                let attrs = e
+                    .attributes()
+                    .map(|a| {
+                        let a = a.unwrap();
+                        (
+                            String::from_utf8(a.key.as_ref().to_vec()).unwrap(),
+                            a.unescape_value().unwrap().into_owned(),
+                        )
+                    })
+                    .collect();
+                elements.push((
+                    String::from_utf8(e.name().as_ref().to_vec()).unwrap(),
+                    attrs,
+                ));
+            }
+            Event::Eof => return elements,
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_epub_zip_layout() {
+    let bytes = export_epub(&sample_book()).unwrap();
+    let mut archive = ZipArchive::new(Cursor::new(bytes.clone())).unwrap();
+
+    // OCF: "mimetype" is first, stored, and readable at a fixed offset
+    let first = archive.by_index(0).unwrap();
+    assert_eq!(first.name(), "mimetype");
+    assert_eq!(first.compression(), CompressionMethod::Stored);
+    drop(first);
+    assert_eq!(&bytes[30..38], b"mimetype");
+    assert_eq!(&bytes[38..38 + MIMETYPE.len()], MIMETYPE.as_bytes());
+
+    let container = read_zip_entry(&mut archive, "META-INF/container.xml");
+    let rootfile = xml_elements(&container)
+        .into_iter()
+        .find(|(name, _)| name == "rootfile")
+        .unwrap();
+    let opf_path = &rootfile.1["full-path"];
+    assert!(archive.by_name(opf_path).is_ok());
+}
+
+#[test]
+fn test_epub_manifest_and_spine_consistent() {
+    let book = sample_book();
+    let mut archive = ZipArchive::new(Cursor::new(export_epub(&book).unwrap())).unwrap();
+    let opf = read_zip_entry(&mut archive, "OEBPS/content.opf");
+    let elements = xml_elements(&opf);
+
+    let items: HashMap<String, HashMap<String, String>> = elements
+        .iter()
+        .filter(|(name, _)| name == "item")
+        .map(|(_, attrs)| (attrs["id"].clone(), attrs.clone()))
+        .collect();
+    let spine: Vec<String> = elements
+        .iter()
+        .filter(|(name, _)| name == "itemref")
+        .map(|(_, attrs)| attrs["idref"].clone())
+        .collect();
+
+    // Every manifest item exists in the archive and every XHTML file is well-formed
+    let mut hrefs = HashSet::new();
+    for attrs in items.values() {
+        let path = format!("OEBPS/{}", attrs["href"]);
+        let content = read_zip_entry(&mut archive, &path);
+        if attrs["media-type"] == "application/xhtml+xml" {
+            xml_elements(&content);
+        }
+        hrefs.insert(path);
+    }
+    // ...and nothing in the archive is missing from the manifest
+    for name in archive.file_names() {
+        assert!(
+            hrefs.contains(name)
+                || ["mimetype", "META-INF/container.xml", "OEBPS/content.opf"].contains(&name),
+            "{} is not in the manifest",
+            name
+        );
+    }
+
+    assert!(spine.iter().all(|idref| items.contains_key(idref)));
+    assert_eq!(spine.len(), 2 + book.chapters.len());
+    assert_eq!(spine[1], "dedication");
+    assert_eq!(
+        items
+            .values()
+            .filter(|a| a.get("properties").map(String::as_str) == Some("nav"))
+            .count(),
+        1
+    );
+    let spine_tag = elements.iter().find(|(name, _)| name == "spine").unwrap();
+    assert_eq!(
+        items[&spine_tag.1["toc"]]["media-type"],
+        "application/x-dtbncx+xml"
+    );
+}
+
+#[test]
+fn test_epub_metadata_and_navigation() {
+    let book = sample_book();
+    let mut archive = ZipArchive::new(Cursor::new(export_epub(&book).unwrap())).unwrap();
+
+    let opf = read_zip_entry(&mut archive, "OEBPS/content.opf");
+    assert!(opf.contains(&format!(
+        "<dc:identifier id=\"book-id\">urn:uuid:{}</dc:identifier>",
+        book.id
+    )));
+    assert!(opf.contains("<dc:title>Salt &amp; Iron</dc:title>"));
+    assert!(opf.contains("Tej &lt;Chhabra&gt;</dc:creator>"));
+    assert!(opf.contains("<dc:language>en-GB</dc:language>"));
+    assert!(opf.contains("<meta property=\"dcterms:modified\">2025-12-01T09:30:00Z</meta>"));
+
+    let nav = read_zip_entry(&mut archive, "OEBPS/nav.xhtml");
+    let ncx = read_zip_entry(&mut archive, "OEBPS/toc.ncx");
+    for chapter in &book.chapters {
+        let href = format!("chapter-{}.xhtml", chapter.id);
+        assert!(nav.contains(&format!("href=\"{}\">{}</a>", href, chapter.title)));
+        assert!(ncx.contains(&format!("<content src=\"{}\"/>", href)));
+    }
+    assert!(ncx.contains(&format!("content=\"urn:uuid:{}\"", book.id)));
+
+    let dedication = read_zip_entry(&mut archive, "OEBPS/dedication.xhtml");
+    assert!(dedication.contains("For &quot;the dreamers&quot;"));
+
+    let first = read_zip_entry(
+        &mut archive,
+        &format!("OEBPS/chapter-{}.xhtml", book.chapters[0].id),
+    );
+    assert!(first.contains("<hr class=\"scene-break\"/>"));
+    assert!(first.contains("<p>Marcus stood at the forge &amp; waited.</p>"));
+    assert!(!first.contains("@page:"));
+}
+
+#[test]
+fn test_epub_without_dedication() {
+    let mut book = sample_book();
+    book.dedication = None;
+    let mut archive = ZipArchive::new(Cursor::new(export_epub(&book).unwrap())).unwrap();
+
+    assert!(archive.by_name("OEBPS/dedication.xhtml").is_err());
+    let opf = read_zip_entry(&mut archive, "OEBPS/content.opf");
+    assert!(!opf.contains("dedication"));
+}
@@ -0,0 +1,35 @@
+use super::sample_book;
+use crate::bk_format::{parse_screenplay, BkParser, ScreenplayElement};
+use crate::export::fountain::export_fountain;
+use chrono::Utc;
+
+#[test]
+fn test_export_fountain_from_prose() {
+    let mut book = sample_book();
+    book.chapters[1].content = "CUT TO:\nINT. ROAD - DAY".to_string();
+    let fountain = export_fountain(&book);
+
+    assert!(fountain.starts_with("Title: Salt & Iron\nCredit: Written by\nAuthor: Tej <Chhabra>\n"));
+    assert!(fountain
+        .contains("\n# The Beginning\n\nThe morning sun cracked over the horizon.\n\n>* * *<\n"));
+    // Lines Fountain would read as other elements are forced to action
+    assert!(fountain.contains("\n!CUT TO:\n\n!INT. ROAD - DAY\n"));
+}
+
+#[test]
+fn test_export_fountain_from_screenplay() {
+    let content = "@title: Salt\n@author: Tej\n@format: screenplay\n\n#chapter: Act One\nEXT. SHORE - DAWN\n\nMARCUS\nStill here.\n";
+    let book = BkParser::parse_string(content, Utc::now(), Utc::now()).unwrap();
+    let fountain = export_fountain(&book);
+
+    assert!(fountain.ends_with("\n# Act One\n\nEXT. SHORE - DAWN\n\nMARCUS\nStill here.\n"));
+    let body = fountain.split_once("\n\n").unwrap().1;
+    assert_eq!(
+        parse_screenplay(body),
+        [
+            ScreenplayElement::SceneHeading("EXT. SHORE - DAWN".to_string()),
+            ScreenplayElement::Character("MARCUS".to_string()),
+            ScreenplayElement::Dialogue("Still here.".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,116 @@
+use super::{sample_book, screenplay_book};
+use crate::export::html::{export_html, export_html_site, html_site, SearchIndex, SiteFile};
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+fn file<'a>(files: &'a [SiteFile], path: &str) -> &'a str {
+    &files
+        .iter()
+        .find(|f| f.path == path)
+        .unwrap_or_else(|| panic!("missing {}", path))
+        .contents
+}
+
+#[test]
+fn test_site_pages_and_navigation() {
+    let book = sample_book();
+    let files = html_site(&book);
+    let first = book.chapters[0].id;
+    let second = book.chapters[1].id;
+
+    let index = file(&files, "index.html");
+    assert!(index.contains(&format!("<a href=\"{}.html\">The Beginning</a>", first)));
+    assert!(index.contains(&format!("<a href=\"{}.html\">The Road</a>", second)));
+    assert!(index.contains("<script src=\"search.js\"></script>"));
+    assert!(index.contains("<html lang=\"en-GB\">"));
+
+    let opening = file(&files, &format!("{}.html", first));
+    assert!(opening.contains(&format!("<section id=\"{}\"", first)));
+    assert!(opening.contains(&format!("<a rel=\"next\" href=\"{}.html\">", second)));
+    assert!(!opening.contains("rel=\"prev\""));
+    assert!(opening.contains("<hr class=\"scene-break\">"));
+
+    let closing = file(&files, &format!("{}.html", second));
+    assert!(closing.contains(&format!("<a rel=\"prev\" href=\"{}.html\">", first)));
+    assert!(!closing.contains("rel=\"next\""));
+}
+
+#[test]
+fn test_site_search_index() {
+    let files = html_site(&sample_book());
+    let index: SearchIndex = serde_json::from_str(file(&files, "search-index.json")).unwrap();
+
+    assert_eq!(index.documents.len(), 2);
+    assert_eq!(index.documents[1].title, "The Road");
+    assert_eq!(
+        index.documents[1].excerpt,
+        "The road stretched endlessly before him"
+    );
+    assert_eq!(index.terms["forge"], vec![(0, 1)]);
+    // Title words are indexed alongside the text
+    assert_eq!(index.terms["road"], vec![(1, 2)]);
+    assert_eq!(index.terms["the"], vec![(0, 4), (1, 2)]);
+    assert!(!index.terms.contains_key("page"));
+}
+
+#[test]
+fn test_content_is_escaped() {
+    let book = sample_book();
+    let single = export_html(&book);
+    let site = html_site(&book);
+    let chapter = file(&site, &format!("{}.html", book.chapters[1].id));
+
+    for html in [single.as_str(), chapter] {
+        assert!(html.contains("The road stretched &lt;endlessly&gt; before him."));
+        assert!(!html.contains("<endlessly>"));
+        assert!(html.contains("<title>"));
+    }
+    assert!(single.contains("<h1>Salt &amp; Iron</h1>"));
+    assert!(single.contains("Tej &lt;Chhabra&gt;"));
+    assert!(single.contains("For &quot;the dreamers&quot;"));
+    assert!(!single.contains("@page:"));
+}
+
+#[test]
+fn test_single_file_is_self_contained() {
+    let book = sample_book();
+    let html = export_html(&book);
+
+    assert!(html.contains("<style>"));
+    assert!(!html.contains("<link"));
+    assert!(!html.contains("<script"));
+    for chapter in &book.chapters {
+        assert!(html.contains(&format!("<a href=\"#{}\">", chapter.id)));
+        assert!(html.contains(&format!("<section id=\"{}\"", chapter.id)));
+    }
+}
+
+#[test]
+fn test_site_zip_holds_every_file() {
+    let book = sample_book();
+    let bytes = export_html_site(&book).unwrap();
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+    for expected in html_site(&book) {
+        let mut content = String::new();
+        archive
+            .by_name(&expected.path)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, expected.contents);
+    }
+    assert_eq!(archive.len(), 6);
+}
+
+#[test]
+fn test_screenplay_elements_have_classes() {
+    let html = export_html(&screenplay_book());
+    assert!(html.contains("<p class=\"scene-heading\">EXT. SHORE - DAWN</p>"));
+    assert!(html.contains("<p class=\"action\">Waves break on the rocks.<br>Gulls circle.</p>"));
+    assert!(html.contains("<p class=\"character\">MARCUS</p>"));
+    assert!(html.contains("<p class=\"parenthetical\">(quietly)</p>"));
+    assert!(html.contains("<p class=\"dialogue\">Still here.</p>"));
+    assert!(html.contains("<hr class=\"page-break\">"));
+    assert!(html.contains("p.character { margin-left: 37%; }"));
+}
@@ -0,0 +1,112 @@
+use super::sample_book;
+use crate::export::latex::{escape, export_latex, LatexOptions};
+
+/// Braces outside escapes balance and every `\begin` has its `\end`
+fn assert_balanced(latex: &str) {
+    let mut depth = 0i32;
+    let mut chars = latex.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                assert!(depth >= 0, "unbalanced closing brace");
+            }
+            // An unescaped comment character would swallow the rest of a line
+            '%' => panic!("unescaped %"),
+            _ => {}
+        }
+    }
+    assert_eq!(depth, 0);
+    assert_eq!(
+        latex.matches("\\begin{").count(),
+        latex.matches("\\end{").count()
+    );
+}
+
+#[test]
+fn test_latex_escapes_every_special_character() {
+    assert_eq!(
+        escape(r#"# $ % & ~ _ ^ \ { } < > | ""#),
+        r"\# \$ \% \& \textasciitilde{} \_ \textasciicircum{} \textbackslash{} \{ \} \textless{} \textgreater{} \textbar{} \textquotedbl{}"
+    );
+    assert_eq!(escape("a\u{a0}b"), "a~b");
+    assert_eq!(
+        escape("\\\\section{x}"),
+        r"\textbackslash{}\textbackslash{}section\{x\}"
+    );
+    // Nothing special is left for TeX to interpret
+    let escaped = escape(r"100% of $5 & ~half_a^2 {#1} \par");
+    assert_balanced(&escaped);
+    for special in ['$', '&', '#', '_', '^', '~'] {
+        for (i, _) in escaped.match_indices(special) {
+            assert!(
+                escaped[..i].ends_with('\\'),
+                "unescaped {} in {}",
+                special,
+                escaped
+            );
+        }
+    }
+}
+
+#[test]
+fn test_export_latex_document() {
+    let latex = export_latex(&sample_book(), &LatexOptions::default());
+
+    assert!(latex.starts_with("\\documentclass{book}\n"));
+    assert!(latex.contains("\\usepackage[british]{babel}"));
+    assert!(
+        latex.contains("\\title{Salt \\& Iron}\n\\author{Tej \\textless{}Chhabra\\textgreater{}}")
+    );
+    assert!(
+        latex.contains("\\itshape For \\textquotedbl{}the dreamers\\textquotedbl{}\n\\end{center}")
+    );
+
+    let front = latex.find("\n\\frontmatter\n").unwrap();
+    let contents = latex.find("\\tableofcontents").unwrap();
+    let main = latex.find("\n\\mainmatter\n").unwrap();
+    assert!(front < latex.find("\\maketitle").unwrap());
+    assert!(contents < main);
+
+    let body = &latex[main..];
+    assert!(body.contains(
+        "\\chapter{The Beginning}\n\n\
+         The morning sun cracked over the horizon.\n\n\
+         \\scenebreak\n\n\
+         \\noindent Marcus stood at the forge \\& waited.\n"
+    ));
+    assert!(body.contains(
+        "\\chapter{The Road}\n\nThe road stretched \\textless{}endlessly\\textgreater{} before him.\n"
+    ));
+    assert!(latex.ends_with("\\end{document}\n"));
+    assert_balanced(&latex);
+}
+
+#[test]
+fn test_export_latex_with_custom_preamble() {
+    let mut book = sample_book();
+    book.dedication = None;
+    book.language = Some("de-AT".to_string());
+    let options = LatexOptions {
+        document_class: "memoir".to_string(),
+        class_options: vec!["12pt".to_string(), "a5paper".to_string()],
+        preamble: Some(
+            "\\usepackage{fontspec}\n\\usepackage[$language$]{babel}\n\\newcommand{\\booktitle}{$title$}\n"
+                .to_string(),
+        ),
+    };
+    let latex = export_latex(&book, &options);
+
+    assert!(latex.starts_with("\\documentclass[12pt,a5paper]{memoir}\n"));
+    assert!(latex.contains("\\usepackage[ngerman]{babel}"));
+    assert!(latex.contains("\\newcommand{\\booktitle}{Salt \\& Iron}"));
+    assert!(!latex.contains("lmodern"));
+    // Fallbacks for commands the class might lack are still defined
+    assert!(latex.contains("\\providecommand{\\scenebreak}"));
+    assert!(!latex.contains("\\itshape"));
+    assert_balanced(&latex);
+}
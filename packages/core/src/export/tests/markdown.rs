@@ -0,0 +1,24 @@
+use super::sample_book;
+use crate::export::markdown::export_markdown;
+
+#[test]
+fn test_markdown_frontmatter_and_chapters() {
+    let markdown = export_markdown(&sample_book());
+
+    assert!(markdown.starts_with("---\ntitle: Salt & Iron\n"));
+    assert!(markdown.contains("\nid: 550e8400-e29b-41d4-a009-426655440000\n"));
+    assert!(markdown.contains("\nlanguage: en-GB\n---\n"));
+    assert!(!markdown.contains("target_words"));
+
+    let body = markdown.split("\n---\n").nth(1).unwrap();
+    assert_eq!(
+        body,
+        "\n# The Beginning\n\n\
+         @page:\n\n\
+         The morning sun cracked over the horizon.\n\n\
+         * * *\n\n\
+         Marcus stood at the forge & waited.\n\n\
+         # The Road\n\n\
+         The road stretched \\<endlessly> before him.\n"
+    );
+}
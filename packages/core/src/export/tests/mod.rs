@@ -0,0 +1,137 @@
+mod docx;
+mod epub;
+mod fountain;
+mod html;
+mod latex;
+mod markdown;
+mod odt;
+mod pandoc;
+mod pdf;
+mod registry;
+mod rtf;
+
+use crate::bk_format::BkParser;
+use crate::models::Book;
+use chrono::{TimeZone, Utc};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+/// Parsed book with a dedication, a page break, a scene break and
+/// markup-sensitive text
+///
+/// The import tests share it to round-trip books through the exporters.
+pub(crate) fn sample_book() -> Book {
+    let content = r#"
+@title: Salt & Iron
+@author: Tej <Chhabra>
+@id: 550e8400-e29b-41d4-a009-426655440000
+@dedication: For "the dreamers"
+@language: en-GB
+
+#chapter: The Beginning
+@page:
+The morning sun cracked over the horizon.
+* * *
+Marcus stood at the forge & waited.
+
+#chapter: The Road
+The road stretched <endlessly> before him.
+"#;
+    let time = Utc.with_ymd_and_hms(2025, 12, 1, 9, 30, 0).unwrap();
+    BkParser::parse_string(content, time, time).unwrap()
+}
+
+/// Parsed screenplay with every kind of element
+pub(crate) fn screenplay_book() -> Book {
+    let content = r#"
+@title: Salt
+@author: Tej
+@format: screenplay
+
+#chapter: Act One
+EXT. SHORE - DAWN
+
+Waves break on the rocks.
+Gulls circle.
+
+MARCUS
+(quietly)
+Still here.
+
+CUT TO:
+
+===
+
+>THE END<
+"#;
+    let time = Utc.with_ymd_and_hms(2025, 12, 1, 9, 30, 0).unwrap();
+    BkParser::parse_string(content, time, time).unwrap()
+}
+
+/// Text of a zip entry, panicking if it is missing
+fn read_zip_entry(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+    let mut content = String::new();
+    archive
+        .by_name(name)
+        .unwrap_or_else(|_| panic!("missing {}", name))
+        .read_to_string(&mut content)
+        .unwrap();
+    content
+}
+
+/// Style and text of each paragraph in a WordprocessingML or ODF part
+///
+/// Tabs, runs of spaces and line breaks become their characters.
+fn paragraphs(xml: &str) -> Vec<(String, String)> {
+    let mut reader = Reader::from_str(xml);
+    reader.check_end_names(true);
+    let mut paragraphs = Vec::new();
+    let mut in_run = false;
+    let mut in_text = false;
+    loop {
+        match reader.read_event().expect("well-formed XML") {
+            Event::Start(e) if e.name().as_ref() == b"w:p" => {
+                paragraphs.push((String::new(), String::new()))
+            }
+            Event::Empty(e) if e.name().as_ref() == b"w:pStyle" => {
+                let style = e.try_get_attribute("w:val").unwrap().unwrap();
+                paragraphs.last_mut().unwrap().0 = style.unescape_value().unwrap().into_owned();
+            }
+            Event::Start(e) if matches!(e.name().as_ref(), b"text:p" | b"text:h") => {
+                let style = e.try_get_attribute("text:style-name").unwrap().unwrap();
+                let style = style.unescape_value().unwrap().into_owned();
+                paragraphs.push((style, String::new()));
+                in_text = true;
+            }
+            Event::End(e) if matches!(e.name().as_ref(), b"text:p" | b"text:h") => in_text = false,
+            Event::Start(e) if e.name().as_ref() == b"w:r" => in_run = true,
+            Event::End(e) if e.name().as_ref() == b"w:r" => in_run = false,
+            Event::Start(e) if e.name().as_ref() == b"w:t" => in_text = true,
+            Event::End(e) if e.name().as_ref() == b"w:t" => in_text = false,
+            Event::Empty(e) if !paragraphs.is_empty() => {
+                let text = &mut paragraphs.last_mut().unwrap().1;
+                match e.name().as_ref() {
+                    b"w:tab" if in_run => text.push('\t'),
+                    b"w:br" if in_run => text.push('\n'),
+                    b"text:tab" => text.push('\t'),
+                    b"text:line-break" => text.push('\n'),
+                    b"text:s" => {
+                        let count = e
+                            .try_get_attribute("text:c")
+                            .unwrap()
+                            .map_or(1, |c| c.unescape_value().unwrap().parse().unwrap());
+                        *text += &" ".repeat(count);
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(t) if in_text => {
+                paragraphs.last_mut().unwrap().1 += &t.unescape().unwrap();
+            }
+            Event::Eof => return paragraphs,
+            _ => {}
+        }
+    }
+}
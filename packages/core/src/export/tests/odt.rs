@@ -0,0 +1,75 @@
+use super::{paragraphs, read_zip_entry, sample_book};
+use crate::export::odt::{export_odt, MIMETYPE};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::Cursor;
+use zip::{CompressionMethod, ZipArchive};
+
+#[test]
+fn test_odt_package() {
+    let bytes = export_odt(&sample_book()).unwrap();
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+
+    let mimetype = archive.by_index(0).unwrap();
+    assert_eq!(mimetype.name(), "mimetype");
+    assert_eq!(mimetype.compression(), CompressionMethod::Stored);
+    drop(mimetype);
+    assert_eq!(read_zip_entry(&mut archive, "mimetype"), MIMETYPE);
+
+    let meta = read_zip_entry(&mut archive, "meta.xml");
+    assert!(meta.contains("<dc:title>Salt &amp; Iron</dc:title>"));
+    assert!(meta.contains("<dc:creator>Tej &lt;Chhabra&gt;</dc:creator>"));
+    assert!(meta.contains("<dc:language>en-GB</dc:language>"));
+
+    let manifest = read_zip_entry(&mut archive, "META-INF/manifest.xml");
+    for part in ["meta.xml", "styles.xml", "content.xml"] {
+        assert!(manifest.contains(&format!("manifest:full-path=\"{}\"", part)));
+    }
+    // Every part is well-formed
+    for part in ["META-INF/manifest.xml", "meta.xml", "styles.xml"] {
+        let xml = read_zip_entry(&mut archive, part);
+        let mut reader = Reader::from_str(&xml);
+        reader.check_end_names(true);
+        while reader.read_event().expect("well-formed XML") != Event::Eof {}
+    }
+}
+
+#[test]
+fn test_odt_content() {
+    let mut book = sample_book();
+    book.chapters[1].content = "Two  spaces,   three, and a\ttab.".to_string();
+    let bytes = export_odt(&book).unwrap();
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).unwrap();
+    let content = read_zip_entry(&mut archive, "content.xml");
+
+    let paragraphs = paragraphs(&content);
+    let expected = [
+        ("Title", "Salt & Iron"),
+        ("Subtitle", "Tej <Chhabra>"),
+        ("Dedication", "For \"the dreamers\""),
+        ("Heading_20_1", "The Beginning"),
+        (
+            "First_20_paragraph",
+            "The morning sun cracked over the horizon.",
+        ),
+        ("Scene_20_break", "* * *"),
+        ("First_20_paragraph", "Marcus stood at the forge & waited."),
+        ("Heading_20_1", "The Road"),
+        ("First_20_paragraph", "Two  spaces,   three, and a\ttab."),
+    ];
+    let actual: Vec<(&str, &str)> = paragraphs
+        .iter()
+        .map(|(style, text)| (style.as_str(), text.as_str()))
+        .collect();
+    assert_eq!(actual, expected);
+
+    // Chapter headings start new pages and are outline entries
+    let styles = read_zip_entry(&mut archive, "styles.xml");
+    let heading = styles
+        .split("<style:style style:name=\"Heading_20_1\"")
+        .nth(1)
+        .unwrap();
+    let heading = &heading[..heading.find("</style:style>").unwrap()];
+    assert!(heading.contains("fo:break-before=\"page\""));
+    assert!(heading.contains("style:default-outline-level=\"1\""));
+}
@@ -0,0 +1,43 @@
+use super::sample_book;
+use crate::export::pandoc::export_pandoc_json;
+use serde_json::{json, Value};
+
+#[test]
+fn test_export_pandoc_json() {
+    let ast: Value = serde_json::from_str(&export_pandoc_json(&sample_book())).unwrap();
+
+    assert_eq!(ast["pandoc-api-version"], json!([1, 23, 1]));
+    let meta = &ast["meta"];
+    assert_eq!(
+        meta["title"],
+        json!({ "t": "MetaInlines", "c": [
+            { "t": "Str", "c": "Salt" },
+            { "t": "Space" },
+            { "t": "Str", "c": "&" },
+            { "t": "Space" },
+            { "t": "Str", "c": "Iron" },
+        ]})
+    );
+    assert_eq!(meta["author"]["t"], "MetaList");
+    assert_eq!(meta["lang"]["c"][0]["c"], "en-GB");
+    assert_eq!(
+        meta["identifier"]["c"][0]["c"],
+        "urn:uuid:550e8400-e29b-41d4-a009-426655440000"
+    );
+
+    let tags: Vec<&str> = ast["blocks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|block| block["t"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        tags,
+        ["Header", "Para", "HorizontalRule", "Para", "Header", "Para"]
+    );
+    assert_eq!(ast["blocks"][0]["c"][0], 1);
+    assert_eq!(
+        ast["blocks"][4]["c"][2][2],
+        json!({ "t": "Str", "c": "Road" })
+    );
+}
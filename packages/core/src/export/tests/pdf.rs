@@ -0,0 +1,360 @@
+use super::{sample_book, screenplay_book};
+use crate::export::pdf::{export_pdf, render_pdf, PdfOptions};
+use crate::export::{export_book, exporter, ExportOptions};
+use crate::layout::{layout_book, FontCache, LayoutConfig};
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object};
+
+const FONT: &[u8] = include_bytes!("../../../assets/fonts/DejaVuSerif.ttf");
+
+fn catalog(doc: &Document) -> &Dictionary {
+    let root = doc.trailer.get(b"Root").unwrap().as_reference().unwrap();
+    doc.get_dictionary(root).unwrap()
+}
+
+/// Follow a reference (or return the object itself) and expect a dictionary
+fn resolve<'a>(doc: &'a Document, object: &'a Object) -> &'a Dictionary {
+    match object {
+        Object::Reference(id) => doc.get_dictionary(*id).unwrap(),
+        Object::Dictionary(dict) => dict,
+        other => panic!("expected a dictionary, got {:?}", other),
+    }
+}
+
+/// `(x, y)` of each text object drawn on a page, in PDF coordinates
+fn text_origins(doc: &Document, page: u32) -> Vec<(f32, f32)> {
+    let page = doc.get_pages()[&page];
+    let content = Content::decode(&doc.get_page_content(page).unwrap()).unwrap();
+    content
+        .operations
+        .iter()
+        .filter(|op| op.operator == "Tm")
+        .map(|op| {
+            (
+                op.operands[4].as_float().unwrap(),
+                op.operands[5].as_float().unwrap(),
+            )
+        })
+        .collect()
+}
+
+fn text(object: &Object) -> String {
+    String::from_utf8(object.as_str().unwrap().to_vec()).unwrap()
+}
+
+/// Titles of the top-level bookmarks, in order
+fn outline_titles(doc: &Document) -> Vec<String> {
+    let outlines = resolve(doc, catalog(doc).get(b"Outlines").unwrap());
+    let mut titles = Vec::new();
+    let mut next = outlines.get(b"First").ok();
+    while let Some(object) = next {
+        let item = resolve(doc, object);
+        titles.push(text(item.get(b"Title").unwrap()));
+        next = item.get(b"Next").ok();
+    }
+    titles
+}
+
+/// `(first page index, numbering style, starting number)` of each page label range
+fn page_labels(doc: &Document) -> Vec<(i64, String, i64)> {
+    let labels = resolve(doc, catalog(doc).get(b"PageLabels").unwrap());
+    let nums = labels.get(b"Nums").unwrap().as_array().unwrap();
+    nums.chunks(2)
+        .map(|pair| {
+            let label = resolve(doc, &pair[1]);
+            let style = label.get(b"S").unwrap().as_name().unwrap();
+            (
+                pair[0].as_i64().unwrap(),
+                String::from_utf8(style.to_vec()).unwrap(),
+                label.get(b"St").unwrap().as_i64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_pdf_pages_outline_and_labels() {
+    let book = sample_book();
+    let options = PdfOptions::default();
+    let tree = layout_book(&book, &LayoutConfig::default()).unwrap();
+    let doc = Document::load_mem(&export_pdf(&book, &options).unwrap()).unwrap();
+
+    assert_eq!(doc.get_pages().len(), tree.pages.len());
+    assert_eq!(outline_titles(&doc), ["The Beginning", "The Road"]);
+    assert_eq!(page_labels(&doc), [(0, "D".to_string(), 1)]);
+
+    // The second chapter's bookmark points at its opening page
+    let pages = doc.get_pages();
+    let outlines = resolve(&doc, catalog(&doc).get(b"Outlines").unwrap());
+    let last = resolve(&doc, outlines.get(b"Last").unwrap());
+    let dest = last.get(b"Dest").unwrap().as_array().unwrap();
+    let opening = tree.metadata.chapters[1].page_number as u32;
+    assert_eq!(dest[0].as_reference().unwrap(), pages[&opening]);
+
+    let info = resolve(&doc, doc.trailer.get(b"Info").unwrap());
+    assert_eq!(text(info.get(b"Title").unwrap()), "Salt & Iron");
+}
+
+#[test]
+fn test_pdf_embeds_default_font() {
+    let mut book = sample_book();
+    book.chapters[1].content = "Łódź, Жизнь and Ἀθῆναι".to_string();
+    let doc = Document::load_mem(&export_pdf(&book, &PdfOptions::default()).unwrap()).unwrap();
+
+    let page = *doc.get_pages().values().last().unwrap();
+    let font = *doc.get_page_fonts(page).values().next().unwrap();
+    assert!(font
+        .get(b"BaseFont")
+        .unwrap()
+        .as_name_str()
+        .unwrap()
+        .ends_with("+DejaVuSerif"));
+    let descendants = font.get(b"DescendantFonts").unwrap().as_array().unwrap();
+    let cid_font = resolve(&doc, &descendants[0]);
+    let descriptor = resolve(&doc, cid_font.get(b"FontDescriptor").unwrap());
+    assert!(descriptor.get(b"FontFile2").is_ok());
+
+    // Text outside Latin-1 is drawn with real glyphs and maps back to Unicode
+    let face = ttf_parser::Face::parse(FONT, 0).unwrap();
+    let cmap_id = font.get(b"ToUnicode").unwrap().as_reference().unwrap();
+    let mut cmap = doc
+        .get_object(cmap_id)
+        .unwrap()
+        .as_stream()
+        .unwrap()
+        .clone();
+    cmap.decompress();
+    let cmap = String::from_utf8_lossy(&cmap.content).to_lowercase();
+    for c in "ŁЖἈῆ".chars() {
+        let glyph = face.glyph_index(c).unwrap();
+        assert!(cmap.contains(&format!("<{:04x}> <{:04x}>", glyph.0, c as u32)));
+    }
+}
+
+#[test]
+fn test_pdf_embeds_subsetted_font() {
+    let options = PdfOptions {
+        font: Some(FONT.to_vec()),
+        ..PdfOptions::default()
+    };
+    let bytes = export_pdf(&sample_book(), &options).unwrap();
+    let doc = Document::load_mem(&bytes).unwrap();
+    // Much smaller than the font file once unused outlines are dropped
+    assert!(bytes.len() < FONT.len() / 4);
+
+    let page = doc.get_pages()[&1];
+    let font = *doc.get_page_fonts(page).values().next().unwrap();
+    assert_eq!(font.get(b"Subtype").unwrap().as_name().unwrap(), b"Type0");
+    assert_eq!(
+        font.get(b"Encoding").unwrap().as_name().unwrap(),
+        b"Identity-H"
+    );
+    let base_font = font.get(b"BaseFont").unwrap().as_name_str().unwrap();
+    assert_eq!(base_font.len(), "ABCDEF+DejaVuSerif".len());
+    assert!(base_font.ends_with("+DejaVuSerif"));
+    assert!(font.get(b"ToUnicode").is_ok());
+
+    let descendants = font.get(b"DescendantFonts").unwrap().as_array().unwrap();
+    let cid_font = resolve(&doc, &descendants[0]);
+    assert_eq!(
+        cid_font.get(b"Subtype").unwrap().as_name().unwrap(),
+        b"CIDFontType2"
+    );
+    let descriptor = resolve(&doc, cid_font.get(b"FontDescriptor").unwrap());
+    let file_id = descriptor
+        .get(b"FontFile2")
+        .unwrap()
+        .as_reference()
+        .unwrap();
+    let mut file = doc
+        .get_object(file_id)
+        .unwrap()
+        .as_stream()
+        .unwrap()
+        .clone();
+    file.decompress();
+    let subset = file.content;
+    assert_eq!(
+        file.dict.get(b"Length1").unwrap().as_i64().unwrap(),
+        subset.len() as i64
+    );
+    assert!(ttf_parser::Face::parse(&subset, 0).is_ok());
+}
+
+#[test]
+fn test_pdf_labels_follow_page_labels() {
+    let book = sample_book();
+    let mut tree = layout_book(&book, &LayoutConfig::default()).unwrap();
+    // Pretend the last chapter restarts the numbering
+    let restart = tree.metadata.chapters[1].page_number - 1;
+    for (offset, page) in tree.pages[restart..].iter_mut().enumerate() {
+        page.label.number = offset + 1;
+    }
+
+    let doc = Document::load_mem(&render_pdf(&tree, &book, None).unwrap()).unwrap();
+    assert_eq!(
+        page_labels(&doc),
+        [
+            (0, "D".to_string(), 1),
+            (restart as i64, "D".to_string(), 1)
+        ]
+    );
+
+    // Front matter is numbered in roman numerals, then chapter 1 starts at 1
+    let options = PdfOptions {
+        layout: Some(LayoutConfig {
+            front_matter: true,
+            ..LayoutConfig::default()
+        }),
+        ..PdfOptions::default()
+    };
+    let doc = Document::load_mem(&export_pdf(&book, &options).unwrap()).unwrap();
+    // Title page, blank verso, dedication and another blank verso
+    assert_eq!(
+        page_labels(&doc),
+        [(0, "r".to_string(), 1), (4, "D".to_string(), 1)]
+    );
+}
+
+#[test]
+fn test_pdf_rejects_invalid_font() {
+    let options = PdfOptions {
+        font: Some(b"not a font".to_vec()),
+        ..PdfOptions::default()
+    };
+    assert!(matches!(
+        export_pdf(&sample_book(), &options),
+        Err(crate::export::ExportError::Font(_))
+    ));
+}
+
+#[test]
+fn test_pdf_reads_font_path_through_cache() {
+    let path =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/fonts/DejaVuSansMono.ttf");
+    let options = ExportOptions {
+        pdf: PdfOptions {
+            font_path: Some(path.clone()),
+            ..PdfOptions::default()
+        },
+        ..ExportOptions::default()
+    };
+    let pdf = exporter("pdf").unwrap();
+    let mut fonts = FontCache::new();
+    let first = pdf
+        .export_with_fonts(&sample_book(), &options, &mut fonts)
+        .unwrap();
+    let cached = fonts.load(&path).unwrap();
+    let second = pdf
+        .export_with_fonts(&sample_book(), &options, &mut fonts)
+        .unwrap();
+    assert_eq!(first, second);
+    assert!(std::sync::Arc::ptr_eq(&cached, &fonts.load(&path).unwrap()));
+
+    let doc = Document::load_mem(&first).unwrap();
+    let page = *doc.get_pages().values().next().unwrap();
+    let font = *doc.get_page_fonts(page).values().next().unwrap();
+    assert!(font
+        .get(b"BaseFont")
+        .unwrap()
+        .as_name_str()
+        .unwrap()
+        .ends_with("+DejaVuSansMono"));
+
+    let missing = PdfOptions {
+        font_path: Some(path.with_file_name("Missing.ttf")),
+        ..PdfOptions::default()
+    };
+    assert!(matches!(
+        export_pdf(&sample_book(), &missing),
+        Err(crate::export::ExportError::Font(_))
+    ));
+}
+
+#[test]
+fn test_pdf_font_kerning() {
+    let mut book = sample_book();
+    book.chapters[1].content = "AVANT WAVE".to_string();
+    let options = PdfOptions {
+        font: Some(FONT.to_vec()),
+        ..PdfOptions::default()
+    };
+    let doc = Document::load_mem(&export_pdf(&book, &options).unwrap()).unwrap();
+    let page = *doc.get_pages().values().last().unwrap();
+    let content = String::from_utf8_lossy(&doc.get_page_content(page).unwrap()).into_owned();
+    // Kerned pairs are drawn with TJ adjustments
+    assert!(content.contains("] TJ"));
+}
+
+#[test]
+fn test_pdf_draws_shaped_glyphs() {
+    let mut book = sample_book();
+    book.chapters[1].content = "The office staff".to_string();
+    let options = PdfOptions {
+        font: Some(FONT.to_vec()),
+        ..PdfOptions::default()
+    };
+    let doc = Document::load_mem(&export_pdf(&book, &options).unwrap()).unwrap();
+
+    let page = *doc.get_pages().values().last().unwrap();
+    let font = *doc.get_page_fonts(page).values().next().unwrap();
+    let cmap_id = font.get(b"ToUnicode").unwrap().as_reference().unwrap();
+    let mut cmap = doc
+        .get_object(cmap_id)
+        .unwrap()
+        .as_stream()
+        .unwrap()
+        .clone();
+    cmap.decompress();
+    let cmap = String::from_utf8_lossy(&cmap.content).to_lowercase();
+    // The "ff" ligature maps back to both letters for copy and search
+    assert!(cmap.contains("<00660066>"));
+}
+
+#[test]
+fn test_pdf_screenplay_preset() {
+    let mut book = screenplay_book();
+    book.chapters[0].content = (1..=120)
+        .map(|n| format!("Sparks fly, line {}.", n))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let pdf = export_book(&book, "pdf", &ExportOptions::default()).unwrap();
+    let doc = Document::load_mem(&pdf).unwrap();
+
+    // US Letter with a monospaced face
+    let first = doc.get_pages()[&1];
+    let media_box = doc.get_dictionary(first).unwrap().get(b"MediaBox").unwrap();
+    let media_box: Vec<f32> = media_box
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n.as_float().unwrap())
+        .collect();
+    assert_eq!(media_box, [0.0, 0.0, 612.0, 792.0]);
+    let font = *doc.get_page_fonts(first).values().next().unwrap();
+    assert!(font
+        .get(b"BaseFont")
+        .unwrap()
+        .as_name_str()
+        .unwrap()
+        .ends_with("+DejaVuSansMono"));
+
+    // The first page opens with the chapter title and a blank line
+    let origins = text_origins(&doc, 1);
+    assert_eq!(origins.len(), 53);
+    assert!(origins[1..].iter().all(|(x, _)| *x == 108.0));
+
+    // 54 lines a page, six to the inch, inside a 1.5" left and 1" top and
+    // bottom margin; the page number sits in the top margin
+    let mut origins = text_origins(&doc, 2);
+    let (folio_x, folio_y) = origins.pop().unwrap();
+    assert!(folio_x > 504.0 && folio_y > 720.0);
+    assert_eq!(origins.len(), 54);
+    assert!(origins.iter().all(|(x, _)| *x == 108.0));
+    for pair in origins.windows(2) {
+        assert!((pair[0].1 - pair[1].1 - 12.0).abs() < 0.01);
+    }
+    assert!(origins[0].1 < 720.0 && origins[0].1 > 708.0);
+    assert!(origins[53].1 >= 72.0);
+    assert_eq!(doc.get_pages().len(), 3);
+}
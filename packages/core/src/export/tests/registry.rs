@@ -0,0 +1,57 @@
+use super::sample_book;
+use crate::export::docx::ManuscriptFont;
+use crate::export::{export_book, exporter, exporters, formats, ExportError, ExportOptions};
+use std::collections::HashSet;
+
+#[test]
+fn test_every_registered_format_exports() {
+    let book = sample_book();
+    let options = ExportOptions::default();
+
+    let mut ids = HashSet::new();
+    let mut extensions = HashSet::new();
+    for exporter in exporters() {
+        assert!(ids.insert(exporter.id()), "duplicate id {}", exporter.id());
+        assert!(extensions.insert(exporter.extension()));
+        assert!(!exporter.extension().starts_with('.'));
+        let bytes = exporter.export(&book, &options).unwrap();
+        assert!(!bytes.is_empty(), "{} wrote nothing", exporter.id());
+    }
+    assert_eq!(formats().len(), ids.len());
+    assert!(ids.is_superset(&HashSet::from(["bk", "epub", "pdf", "docx", "pandoc"])));
+
+    let pdf = export_book(&book, "pdf", &options).unwrap();
+    assert!(pdf.starts_with(b"%PDF-"));
+    let epub = exporter("epub").unwrap();
+    assert_eq!(epub.media_type(), "application/epub+zip");
+}
+
+#[test]
+fn test_export_book_by_id() {
+    let book = sample_book();
+    let bk = export_book(&book, "bk", &ExportOptions::default()).unwrap();
+    assert!(String::from_utf8(bk)
+        .unwrap()
+        .contains("@title: Salt & Iron"));
+
+    assert!(matches!(
+        export_book(&book, "docbook", &ExportOptions::default()),
+        Err(ExportError::UnknownFormat(id)) if id == "docbook"
+    ));
+}
+
+#[test]
+fn test_export_options_from_partial_json() {
+    let options: ExportOptions = serde_json::from_str(
+        r#"{ "manuscript": { "font": "Times" }, "latex": { "class_options": ["12pt"] } }"#,
+    )
+    .unwrap();
+    assert_eq!(options.manuscript.font, ManuscriptFont::Times);
+    assert!(options.manuscript.contact.is_empty());
+    assert_eq!(options.latex.document_class, "book");
+    assert_eq!(options.latex.class_options, ["12pt"]);
+    assert!(options.pdf.font.is_none());
+
+    let latex = export_book(&sample_book(), "latex", &options).unwrap();
+    assert!(latex.starts_with(b"\\documentclass[12pt]{book}"));
+}
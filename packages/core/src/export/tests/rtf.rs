@@ -0,0 +1,47 @@
+use super::sample_book;
+use crate::export::rtf::{escape, export_rtf};
+use crate::import::rtf::rtf_to_lines;
+use crate::import::ImportReport;
+
+#[test]
+fn test_rtf_escapes_non_ascii() {
+    assert_eq!(escape(r"a\b{c}"), r"a\\b\{c\}");
+    assert_eq!(escape("café"), r"caf\u233?");
+    // Signed 16-bit values above U+7FFF
+    assert_eq!(escape("\u{201c}"), r"\u8220?");
+    assert_eq!(escape("\u{ff01}"), r"\u-255?");
+    // Characters outside the BMP are written as a surrogate pair
+    assert_eq!(escape("\u{1f600}"), r"\u-10179?\u-8704?");
+    assert_eq!(escape("a\u{a0}b\tc"), "a\\~b\\tab c");
+}
+
+#[test]
+fn test_export_rtf() {
+    let mut book = sample_book();
+    book.chapters[1].content = "Crème brûlée, \u{201c}naïve\u{201d} \u{1f600}".to_string();
+    let rtf = export_rtf(&book);
+
+    assert!(rtf.is_ascii());
+    assert!(rtf.starts_with("{\\rtf1\\ansi"));
+    assert!(rtf.contains("{\\info{\\title Salt & Iron}{\\author Tej <Chhabra>}}"));
+    assert_eq!(rtf.matches("{").count(), rtf.matches("}").count());
+    // Every chapter title starts a new page
+    assert_eq!(rtf.matches("\\pard\\s1\\pagebb").count(), 2);
+
+    let mut report = ImportReport::default();
+    let lines = rtf_to_lines(rtf.as_bytes(), &mut report);
+    assert_eq!(
+        lines,
+        [
+            "Salt & Iron",
+            "Tej <Chhabra>",
+            "For \"the dreamers\"",
+            "The Beginning",
+            "The morning sun cracked over the horizon.",
+            "* * *",
+            "Marcus stood at the forge & waited.",
+            "The Road",
+            "Crème brûlée, \u{201c}naïve\u{201d} \u{1f600}",
+        ]
+    );
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+
+/// Edit scripts longer than this fall back to replacing the changed region wholesale
+const MAX_EDIT_DISTANCE: usize = 1000;
+
+/// One line of a line-level diff between two revisions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "text", rename_all = "snake_case")]
+pub enum DiffLine {
+    Unchanged(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Instruction for rebuilding a revision from the one before it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub(crate) enum DeltaOp {
+    /// Copy `len` lines of the base starting at line `start`
+    Copy { start: usize, len: usize },
+    /// Insert new lines
+    Insert { lines: Vec<String> },
+}
+
+/// Edit between two line arrays, by index
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Equal { old: usize },
+    Delete { old: usize },
+    Insert { new: usize },
+}
+
+/// Compute a line-level diff from `old` to `new`
+pub(crate) fn diff_text(old: &str, new: &str) -> Vec<DiffLine> {
+    let old_lines: Vec<&str> = old.split('\n').collect();
+    let new_lines: Vec<&str> = new.split('\n').collect();
+
+    diff_lines(&old_lines, &new_lines)
+        .into_iter()
+        .map(|edit| match edit {
+            Edit::Equal { old } => DiffLine::Unchanged(old_lines[old].to_string()),
+            Edit::Delete { old } => DiffLine::Removed(old_lines[old].to_string()),
+            Edit::Insert { new } => DiffLine::Added(new_lines[new].to_string()),
+        })
+        .collect()
+}
+
+/// Compute the delta that turns `base` into `target`
+pub(crate) fn make_delta(base: &str, target: &str) -> Vec<DeltaOp> {
+    let base_lines: Vec<&str> = base.split('\n').collect();
+    let target_lines: Vec<&str> = target.split('\n').collect();
+
+    let mut ops: Vec<DeltaOp> = Vec::new();
+    for edit in diff_lines(&base_lines, &target_lines) {
+        match edit {
+            Edit::Equal { old } => match ops.last_mut() {
+                Some(DeltaOp::Copy { start, len }) if *start + *len == old => *len += 1,
+                _ => ops.push(DeltaOp::Copy { start: old, len: 1 }),
+            },
+            Edit::Insert { new } => {
+                let line = target_lines[new].to_string();
+                match ops.last_mut() {
+                    Some(DeltaOp::Insert { lines }) => lines.push(line),
+                    _ => ops.push(DeltaOp::Insert { lines: vec![line] }),
+                }
+            }
+            Edit::Delete { .. } => {}
+        }
+    }
+    ops
+}
+
+/// Rebuild a revision from its base and delta; `None` if the delta does not fit the base
+pub(crate) fn apply_delta(base: &str, delta: &[DeltaOp]) -> Option<String> {
+    let base_lines: Vec<&str> = base.split('\n').collect();
+    let mut lines: Vec<&str> = Vec::new();
+    for op in delta {
+        match op {
+            DeltaOp::Copy { start, len } => {
+                lines.extend_from_slice(base_lines.get(*start..start.checked_add(*len)?)?)
+            }
+            DeltaOp::Insert { lines: inserted } => {
+                lines.extend(inserted.iter().map(String::as_str))
+            }
+        }
+    }
+    Some(lines.join("\n"))
+}
+
+/// Diff two line arrays, trimming the common prefix and suffix first
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<Edit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut edits: Vec<Edit> = (0..prefix).map(|old| Edit::Equal { old }).collect();
+    match myers(old_middle, new_middle) {
+        Some(middle) => edits.extend(middle.into_iter().map(|edit| match edit {
+            Edit::Equal { old } => Edit::Equal { old: old + prefix },
+            Edit::Delete { old } => Edit::Delete { old: old + prefix },
+            Edit::Insert { new } => Edit::Insert { new: new + prefix },
+        })),
+        None => {
+            edits.extend((0..old_middle.len()).map(|old| Edit::Delete { old: old + prefix }));
+            edits.extend((0..new_middle.len()).map(|new| Edit::Insert { new: new + prefix }));
+        }
+    }
+    edits.extend((old.len() - suffix..old.len()).map(|old| Edit::Equal { old }));
+    edits
+}
+
+/// Myers' O(ND) shortest edit script; `None` if it exceeds `MAX_EDIT_DISTANCE`
+fn myers(old: &[&str], new: &[&str]) -> Option<Vec<Edit>> {
+    let n = old.len() as isize;
+    let m = new.len() as isize;
+    let limit = (old.len() + new.len()).min(MAX_EDIT_DISTANCE) as isize;
+    let offset = limit + 1;
+    let mut v = vec![0isize; 2 * limit as usize + 3];
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    for d in 0..=limit {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let index = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+                v[index + 1]
+            } else {
+                v[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index] = x;
+            if x >= n && y >= m {
+                return Some(backtrack(&trace, offset, n, m));
+            }
+        }
+    }
+
+    None
+}
+
+/// Walk the saved frontiers back from the end to recover the edit script
+fn backtrack(trace: &[Vec<isize>], offset: isize, n: isize, m: isize) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let index = (k + offset) as usize;
+        let prev_k = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[(prev_k + offset) as usize];
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            edits.push(Edit::Equal { old: x as usize });
+        }
+        if d > 0 {
+            if x == prev_x {
+                edits.push(Edit::Insert {
+                    new: (y - 1) as usize,
+                });
+            } else {
+                edits.push(Edit::Delete {
+                    old: (x - 1) as usize,
+                });
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+
+    edits.reverse();
+    edits
+}
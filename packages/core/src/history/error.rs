@@ -0,0 +1,21 @@
+use crate::bk_format::BkParseError;
+use thiserror::Error;
+
+/// Errors that can occur while reading or writing revision history
+#[derive(Error, Debug)]
+pub enum RevisionError {
+    #[error("IO error in revision store: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Revision data is not valid JSON: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Revision {0} not found")]
+    NotFound(u64),
+
+    #[error("Revision {id} is corrupt: {reason}")]
+    Corrupt { id: u64, reason: String },
+
+    #[error("Stored revision could not be parsed: {0}")]
+    Parse(#[from] BkParseError),
+}
@@ -0,0 +1,10 @@
+mod diff;
+mod error;
+mod store;
+
+#[cfg(test)]
+mod tests;
+
+pub use diff::DiffLine;
+pub use error::RevisionError;
+pub use store::{RevisionInfo, RevisionStore};
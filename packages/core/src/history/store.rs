@@ -0,0 +1,218 @@
+use crate::bk_format::{BkParser, BkWriter};
+use crate::history::diff::{apply_delta, diff_text, make_delta, DeltaOp, DiffLine};
+use crate::history::error::RevisionError;
+use crate::models::Book;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Every this many revisions a full copy is stored instead of a delta
+const KEYFRAME_INTERVAL: u64 = 20;
+
+const INDEX_FILE: &str = "index.json";
+
+/// Summary of a stored revision
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RevisionInfo {
+    pub id: u64,
+    pub label: String,
+    pub timestamp: DateTime<Utc>,
+    pub book_id: Uuid,
+    pub title: String,
+    pub chapter_count: usize,
+    /// `created_at` of the book when it was snapshotted
+    pub book_created_at: DateTime<Utc>,
+}
+
+/// On-disk payload of a single revision
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum StoredRevision {
+    /// Complete .bk text
+    Full { text: String },
+    /// Changes relative to revision `base`
+    Delta { base: u64, ops: Vec<DeltaOp> },
+}
+
+/// Snapshot history of a book kept in a local directory
+///
+/// Each snapshot is stored as the .bk text of the book. Most revisions are
+/// line deltas against the previous one, with a full copy every
+/// `KEYFRAME_INTERVAL` revisions so restoring never replays a long chain.
+pub struct RevisionStore {
+    dir: PathBuf,
+    index: Vec<RevisionInfo>,
+}
+
+impl RevisionStore {
+    /// Open (or create) a revision store in `dir`
+    pub fn open(dir: &Path) -> Result<Self, RevisionError> {
+        fs::create_dir_all(dir)?;
+        let index_path = dir.join(INDEX_FILE);
+        let index = if index_path.exists() {
+            serde_json::from_str(&fs::read_to_string(&index_path)?)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            index,
+        })
+    }
+
+    /// Open the sidecar store that sits next to a .bk file
+    pub fn open_for_book_file(book_path: &Path) -> Result<Self, RevisionError> {
+        Self::open(&Self::sidecar_dir(book_path))
+    }
+
+    /// Sidecar directory for a .bk file (`my-book.bk` -> `my-book.bk.history`)
+    pub fn sidecar_dir(book_path: &Path) -> PathBuf {
+        let mut name = book_path
+            .file_name()
+            .map(|n| n.to_os_string())
+            .unwrap_or_default();
+        name.push(".history");
+        book_path.with_file_name(name)
+    }
+
+    /// All revisions, oldest first
+    pub fn list(&self) -> &[RevisionInfo] {
+        &self.index
+    }
+
+    /// The most recent revision
+    pub fn latest(&self) -> Option<&RevisionInfo> {
+        self.index.last()
+    }
+
+    /// The latest revision taken at or before `time`
+    pub fn revision_at(&self, time: DateTime<Utc>) -> Option<&RevisionInfo> {
+        self.index.iter().rev().find(|r| r.timestamp <= time)
+    }
+
+    /// Snapshot a book now
+    pub fn snapshot(&mut self, book: &Book, label: &str) -> Result<RevisionInfo, RevisionError> {
+        self.snapshot_at(book, label, Utc::now())
+    }
+
+    /// Snapshot a book with an explicit timestamp
+    ///
+    /// If the book and label are unchanged since the latest revision, no new
+    /// revision is written and the latest one is returned. A new label on an
+    /// unchanged book is recorded as a revision of its own.
+    pub fn snapshot_at(
+        &mut self,
+        book: &Book,
+        label: &str,
+        timestamp: DateTime<Utc>,
+    ) -> Result<RevisionInfo, RevisionError> {
+        let text = BkWriter::write_string(book);
+
+        let stored = match self.latest() {
+            Some(latest) => {
+                let base_text = self.text_of(latest.id)?;
+                if base_text == text && latest.label == label {
+                    return Ok(latest.clone());
+                }
+                let id = latest.id + 1;
+                if id % KEYFRAME_INTERVAL == 1 {
+                    StoredRevision::Full { text }
+                } else {
+                    StoredRevision::Delta {
+                        base: latest.id,
+                        ops: make_delta(&base_text, &text),
+                    }
+                }
+            }
+            None => StoredRevision::Full { text },
+        };
+
+        let info = RevisionInfo {
+            id: self.latest().map_or(1, |r| r.id + 1),
+            label: label.to_string(),
+            timestamp,
+            book_id: book.id,
+            title: book.title.clone(),
+            chapter_count: book.chapters.len(),
+            book_created_at: book.created_at,
+        };
+
+        fs::write(self.revision_path(info.id), serde_json::to_string(&stored)?)?;
+        self.index.push(info.clone());
+        fs::write(
+            self.dir.join(INDEX_FILE),
+            serde_json::to_string_pretty(&self.index)?,
+        )?;
+
+        Ok(info)
+    }
+
+    /// Rebuild the book as it was at revision `id`
+    pub fn restore(&self, id: u64) -> Result<Book, RevisionError> {
+        let info = self.info(id)?;
+        let text = self.text_of(id)?;
+        Ok(BkParser::parse_string(
+            &text,
+            info.book_created_at,
+            info.timestamp,
+        )?)
+    }
+
+    /// Line-level diff of the .bk text between two revisions
+    pub fn diff(&self, from: u64, to: u64) -> Result<Vec<DiffLine>, RevisionError> {
+        Ok(diff_text(&self.text_of(from)?, &self.text_of(to)?))
+    }
+
+    fn info(&self, id: u64) -> Result<&RevisionInfo, RevisionError> {
+        self.index
+            .iter()
+            .find(|r| r.id == id)
+            .ok_or(RevisionError::NotFound(id))
+    }
+
+    /// Reconstruct the .bk text of a revision from the nearest full copy
+    fn text_of(&self, id: u64) -> Result<String, RevisionError> {
+        self.info(id)?;
+
+        let mut chain = Vec::new();
+        let mut current = id;
+        let mut text = loop {
+            match self.load(current)? {
+                StoredRevision::Full { text } => break text,
+                StoredRevision::Delta { base, ops } => {
+                    if base >= current {
+                        return Err(RevisionError::Corrupt {
+                            id: current,
+                            reason: format!("delta base {} is not older", base),
+                        });
+                    }
+                    chain.push((current, ops));
+                    current = base;
+                }
+            }
+        };
+
+        for (revision, ops) in chain.into_iter().rev() {
+            text = apply_delta(&text, &ops).ok_or_else(|| RevisionError::Corrupt {
+                id: revision,
+                reason: "delta does not match its base revision".to_string(),
+            })?;
+        }
+        Ok(text)
+    }
+
+    fn load(&self, id: u64) -> Result<StoredRevision, RevisionError> {
+        let path = self.revision_path(id);
+        if !path.exists() {
+            return Err(RevisionError::NotFound(id));
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn revision_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{:06}.json", id))
+    }
+}
@@ -0,0 +1,185 @@
+use crate::history::diff::{apply_delta, make_delta, DiffLine};
+use crate::history::error::RevisionError;
+use crate::history::store::RevisionStore;
+use crate::models::Book;
+use chrono::{Duration, TimeZone, Utc};
+use std::path::{Path, PathBuf};
+
+/// Temporary directory removed when dropped
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("bk-history-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn sample_book() -> Book {
+    let mut book = Book::new("The Way of Iron".to_string(), "Tej".to_string());
+    book.add_chapter(
+        "One".to_string(),
+        "The morning sun cracked over the horizon.\nAnother day began.".to_string(),
+    );
+    book.add_chapter("Two".to_string(), "The road stretched on.".to_string());
+    book
+}
+
+#[test]
+fn test_delta_round_trip() {
+    let base = "a\nb\nc\nd\ne";
+    let target = "a\nc\nd\nX\ne\nf";
+    let delta = make_delta(base, target);
+    assert_eq!(apply_delta(base, &delta).unwrap(), target);
+
+    // Unrelated texts still round-trip
+    let delta = make_delta("one\ntwo", "three\nfour\nfive");
+    assert_eq!(
+        apply_delta("one\ntwo", &delta).unwrap(),
+        "three\nfour\nfive"
+    );
+}
+
+#[test]
+fn test_snapshot_and_restore() {
+    let dir = TempDir::new();
+    let mut store = RevisionStore::open(dir.path()).unwrap();
+    let mut book = sample_book();
+
+    let first = store.snapshot(&book, "First draft").unwrap();
+    book.chapters[1].content.push_str("\nHe kept walking.");
+    let second = store.snapshot(&book, "Second draft").unwrap();
+
+    assert_eq!(first.id, 1);
+    assert_eq!(second.id, 2);
+
+    let restored = store.restore(first.id).unwrap();
+    assert_eq!(restored.id, book.id);
+    assert_eq!(restored.chapters[1].content, "The road stretched on.");
+
+    let restored = store.restore(second.id).unwrap();
+    assert_eq!(
+        restored.chapters[1].content,
+        "The road stretched on.\nHe kept walking."
+    );
+}
+
+#[test]
+fn test_unchanged_book_is_not_snapshotted_twice() {
+    let dir = TempDir::new();
+    let mut store = RevisionStore::open(dir.path()).unwrap();
+    let book = sample_book();
+
+    let first = store.snapshot(&book, "Auto-save").unwrap();
+    let again = store.snapshot(&book, "Auto-save").unwrap();
+    assert_eq!(first, again);
+    assert_eq!(store.list().len(), 1);
+}
+
+#[test]
+fn test_new_label_on_unchanged_book_is_recorded() {
+    let dir = TempDir::new();
+    let mut store = RevisionStore::open(dir.path()).unwrap();
+    let book = sample_book();
+
+    let first = store.snapshot(&book, "Auto-save").unwrap();
+    let labelled = store.snapshot(&book, "Sent to editor").unwrap();
+    assert_eq!(labelled.id, first.id + 1);
+    assert_eq!(labelled.label, "Sent to editor");
+    assert_eq!(store.list().len(), 2);
+
+    let restored = store.restore(labelled.id).unwrap();
+    assert_eq!(restored.chapters[0].content, book.chapters[0].content);
+
+    let reopened = RevisionStore::open(dir.path()).unwrap();
+    assert_eq!(reopened.list()[1].label, "Sent to editor");
+}
+
+#[test]
+fn test_history_persists_and_uses_deltas() {
+    let dir = TempDir::new();
+    let mut book = sample_book();
+    {
+        let mut store = RevisionStore::open(dir.path()).unwrap();
+        for n in 0..25 {
+            book.chapters[0].content.push_str(&format!("\nLine {}", n));
+            store.snapshot(&book, &format!("Revision {}", n)).unwrap();
+        }
+    }
+
+    let store = RevisionStore::open(dir.path()).unwrap();
+    assert_eq!(store.list().len(), 25);
+    assert_eq!(store.list()[4].label, "Revision 4");
+
+    let restored = store.restore(25).unwrap();
+    assert_eq!(restored.chapters[0].content, book.chapters[0].content);
+    let restored = store.restore(19).unwrap();
+    assert!(restored.chapters[0].content.ends_with("Line 18"));
+
+    // Revision 2 is stored as a delta, so it is much smaller than a full copy
+    let full = std::fs::metadata(dir.path().join("000001.json"))
+        .unwrap()
+        .len();
+    let delta = std::fs::metadata(dir.path().join("000002.json"))
+        .unwrap()
+        .len();
+    assert!(delta < full);
+}
+
+#[test]
+fn test_diff_between_revisions() {
+    let dir = TempDir::new();
+    let mut store = RevisionStore::open(dir.path()).unwrap();
+    let mut book = sample_book();
+
+    store.snapshot(&book, "Before").unwrap();
+    book.chapters[0].content = "The morning sun cracked over the horizon.\nNight fell.".to_string();
+    store.snapshot(&book, "After").unwrap();
+
+    let diff = store.diff(1, 2).unwrap();
+    assert!(diff.contains(&DiffLine::Removed("Another day began.".to_string())));
+    assert!(diff.contains(&DiffLine::Added("Night fell.".to_string())));
+    assert!(diff.contains(&DiffLine::Unchanged(
+        "The morning sun cracked over the horizon.".to_string()
+    )));
+}
+
+#[test]
+fn test_revision_at_finds_earlier_draft() {
+    let dir = TempDir::new();
+    let mut store = RevisionStore::open(dir.path()).unwrap();
+    let mut book = sample_book();
+    let tuesday = Utc.with_ymd_and_hms(2025, 12, 9, 18, 0, 0).unwrap();
+
+    store.snapshot_at(&book, "Tuesday", tuesday).unwrap();
+    book.title = "Iron".to_string();
+    store
+        .snapshot_at(&book, "Friday", tuesday + Duration::days(3))
+        .unwrap();
+
+    let found = store.revision_at(tuesday + Duration::days(1)).unwrap();
+    assert_eq!(found.label, "Tuesday");
+    assert_eq!(store.restore(found.id).unwrap().title, "The Way of Iron");
+    assert!(store.revision_at(tuesday - Duration::days(1)).is_none());
+}
+
+#[test]
+fn test_sidecar_dir_and_missing_revision() {
+    let sidecar = RevisionStore::sidecar_dir(Path::new("/books/my-book.bk"));
+    assert_eq!(sidecar, Path::new("/books/my-book.bk.history"));
+
+    let dir = TempDir::new();
+    let store = RevisionStore::open(dir.path()).unwrap();
+    assert!(matches!(store.restore(3), Err(RevisionError::NotFound(3))));
+}
@@ -0,0 +1,29 @@
+use crate::import::error::ImportError;
+use quick_xml::events::BytesStart;
+use std::io::{Read, Seek};
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// A file in the archive as text, or `None` if the archive lacks it
+pub(crate) fn read_part<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<Option<String>, ImportError> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut content = String::new();
+    file.read_to_string(&mut content)?;
+    Ok(Some(content))
+}
+
+/// Unescaped value of the attribute with the given local name
+pub(crate) fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
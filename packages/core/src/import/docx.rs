@@ -0,0 +1,439 @@
+use crate::bk_format::{BkChapter, BkMetadata};
+use crate::import::archive::{attr, read_part};
+use crate::import::error::ImportError;
+use crate::import::heading::chapter_heading;
+use crate::import::report::{Dropped, ImportReport};
+use crate::import::{UNKNOWN_AUTHOR, UNTITLED};
+use crate::models::Book;
+use chrono::{DateTime, Utc};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+/// Read a .docx file; its name is the title if the document has none
+pub fn import_docx_file(path: &Path) -> Result<(Book, ImportReport), ImportError> {
+    let bytes = std::fs::read(path)?;
+    let stem = path.file_stem().and_then(|s| s.to_str());
+    read_docx(Cursor::new(bytes), stem)
+}
+
+/// Build a book from a Word document
+///
+/// Chapters start at Heading 1 paragraphs (any style with outline level 1), or
+/// when the document has none, at paragraphs like "Chapter 12" or "CHAPTER
+/// ONE: The Forge". Paragraph text is kept; tables, comments, images, notes and
+/// equations are dropped, tracked changes are accepted, and all of it is
+/// counted in the report.
+pub fn import_docx(bytes: &[u8]) -> Result<(Book, ImportReport), ImportError> {
+    read_docx(Cursor::new(bytes), None)
+}
+
+fn read_docx<R: Read + Seek>(
+    reader: R,
+    fallback_title: Option<&str>,
+) -> Result<(Book, ImportReport), ImportError> {
+    let mut archive = ZipArchive::new(reader)?;
+    let mut report = ImportReport::default();
+
+    let document_path = read_part(&mut archive, "_rels/.rels")?
+        .and_then(|rels| relationship_target(&rels, "", "/officeDocument"))
+        .unwrap_or_else(|| "word/document.xml".to_string());
+    let document = read_part(&mut archive, &document_path)?
+        .ok_or_else(|| ImportError::MissingPart(document_path.clone()))?;
+
+    let (dir, file) = document_path
+        .rsplit_once('/')
+        .map_or(("", document_path.as_str()), |(d, f)| (d, f));
+    let styles_path = read_part(&mut archive, &format!("{}/_rels/{}.rels", dir, file))?
+        .and_then(|rels| relationship_target(&rels, dir, "/styles"));
+    let styles = match styles_path {
+        Some(path) => match read_part(&mut archive, &path)? {
+            Some(xml) => Styles::parse(&xml)?,
+            None => Styles::default(),
+        },
+        None => Styles::default(),
+    };
+    let core = match read_part(&mut archive, "docProps/core.xml")? {
+        Some(xml) => CoreProperties::parse(&xml)?,
+        None => CoreProperties::default(),
+    };
+
+    let paragraphs = read_paragraphs(&document, &mut report)?;
+    let mut title_paragraph = None;
+    let mut front_matter: Vec<String> = Vec::new();
+    let mut chapters: Vec<BkChapter> = Vec::new();
+    let use_headings = paragraphs.iter().any(|p| styles.is_heading(p));
+
+    for paragraph in &paragraphs {
+        let lines: Vec<String> = paragraph
+            .text
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|line| !line.is_empty())
+            .collect();
+        if lines.is_empty() {
+            continue;
+        }
+        let text = lines.join(" ");
+
+        if styles.is_toc(paragraph) {
+            report.record(Dropped::TableOfContents);
+            continue;
+        }
+        let starts_chapter = if use_headings {
+            styles.is_heading(paragraph)
+        } else {
+            chapter_heading(&text).is_some()
+        };
+        if starts_chapter {
+            chapters.push(BkChapter::new(text, chapters.len()));
+            continue;
+        }
+
+        match chapters.last_mut() {
+            Some(chapter) => {
+                for line in lines {
+                    if !chapter.content.is_empty() {
+                        chapter.content.push('\n');
+                    }
+                    chapter.content.push_str(&line);
+                }
+            }
+            None => {
+                if title_paragraph.is_none() && styles.is_title(paragraph) {
+                    title_paragraph = Some(text);
+                }
+                front_matter.extend(lines);
+            }
+        }
+    }
+
+    if chapters.is_empty() {
+        if !front_matter.is_empty() {
+            report.warn("No chapter headings found; the whole document is one chapter");
+            let mut chapter = BkChapter::new(UNTITLED.to_string(), 0);
+            chapter.content = front_matter.join("\n");
+            chapters.push(chapter);
+        }
+    } else {
+        for _ in &front_matter {
+            report.record(Dropped::FrontMatter);
+        }
+    }
+
+    let title = core
+        .title
+        .or(title_paragraph)
+        .or_else(|| fallback_title.map(str::to_string))
+        .unwrap_or_else(|| {
+            report.warn("The document has no title");
+            UNTITLED.to_string()
+        });
+    let author = core.creator.unwrap_or_else(|| {
+        report.warn("The document names no author");
+        UNKNOWN_AUTHOR.to_string()
+    });
+
+    let metadata = BkMetadata {
+        title: Some(title),
+        author: Some(author),
+        language: core.language.or(styles.language),
+        ..BkMetadata::default()
+    };
+    let now = Utc::now();
+    let created_at = core.created.or(core.modified).unwrap_or(now);
+    let updated_at = core.modified.unwrap_or(created_at);
+    let book = metadata.into_book(chapters, created_at, updated_at)?;
+    Ok((book, report))
+}
+
+/// Package path of the first relationship whose type ends with `type_suffix`
+///
+/// Targets are relative to `base`, the folder of the part the relationships
+/// belong to, unless they start with `/`.
+fn relationship_target(rels: &str, base: &str, type_suffix: &str) -> Option<String> {
+    let mut reader = Reader::from_str(rels);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e) | Event::Empty(e))
+                if e.local_name().as_ref() == b"Relationship"
+                    && attr(&e, b"Type").is_some_and(|t| t.ends_with(type_suffix)) =>
+            {
+                let target = attr(&e, b"Target")?;
+                return Some(match target.strip_prefix('/') {
+                    Some(absolute) => absolute.to_string(),
+                    None if base.is_empty() => target,
+                    None => format!("{}/{}", base, target),
+                });
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+/// A body paragraph with the properties chapter detection needs
+#[derive(Debug, Default)]
+struct Paragraph {
+    style: Option<String>,
+    outline_level: Option<u8>,
+    /// Text with `\n` at line breaks
+    text: String,
+}
+
+/// Collect the text of every top-level body paragraph
+fn read_paragraphs(xml: &str, report: &mut ImportReport) -> Result<Vec<Paragraph>, ImportError> {
+    let mut reader = Reader::from_str(xml);
+    let mut paragraphs = Vec::new();
+    let mut current: Option<Paragraph> = None;
+    // Depth inside an element whose content is dropped
+    let mut skip = 0usize;
+    let mut in_run = false;
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                if skip > 0 {
+                    skip += 1;
+                    continue;
+                }
+                match e.local_name().as_ref() {
+                    b"p" => current = Some(Paragraph::default()),
+                    b"r" => in_run = true,
+                    b"t" => in_text = true,
+                    b"tbl" => {
+                        report.record(Dropped::Table);
+                        skip = 1;
+                    }
+                    b"drawing" | b"pict" | b"object" => {
+                        report.record(Dropped::Image);
+                        skip = 1;
+                    }
+                    b"oMathPara" | b"oMath" => {
+                        report.record(Dropped::Equation);
+                        skip = 1;
+                    }
+                    // Deleted text, and the old properties of a formatting change
+                    b"del" | b"moveFrom" | b"pPrChange" | b"rPrChange" => {
+                        report.record(Dropped::TrackedChange);
+                        skip = 1;
+                    }
+                    b"ins" | b"moveTo" => report.record(Dropped::TrackedChange),
+                    // Legacy rendering of content that `mc:Choice` already holds
+                    b"Fallback" => skip = 1,
+                    _ => {}
+                }
+            }
+            Event::Empty(e) if skip == 0 => {
+                let Some(paragraph) = current.as_mut() else {
+                    continue;
+                };
+                match e.local_name().as_ref() {
+                    b"pStyle" => paragraph.style = attr(&e, b"val"),
+                    b"outlineLvl" => {
+                        paragraph.outline_level = attr(&e, b"val").and_then(|v| v.parse().ok())
+                    }
+                    // Tab stops in paragraph properties share the name, so only runs count
+                    b"tab" if in_run => paragraph.text.push('\t'),
+                    b"br" | b"cr" if in_run => paragraph.text.push('\n'),
+                    b"noBreakHyphen" if in_run => paragraph.text.push('-'),
+                    b"commentReference" => report.record(Dropped::Comment),
+                    b"footnoteReference" => report.record(Dropped::Footnote),
+                    b"endnoteReference" => report.record(Dropped::Endnote),
+                    b"drawing" | b"pict" => report.record(Dropped::Image),
+                    _ => {}
+                }
+            }
+            Event::Text(t) if skip == 0 && in_text => {
+                if let Some(paragraph) = current.as_mut() {
+                    paragraph.text.push_str(&t.unescape()?);
+                }
+            }
+            Event::End(e) => {
+                if skip > 0 {
+                    skip -= 1;
+                    continue;
+                }
+                match e.local_name().as_ref() {
+                    b"p" => paragraphs.extend(current.take()),
+                    b"r" => in_run = false,
+                    b"t" => in_text = false,
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(paragraphs)
+}
+
+#[derive(Debug, Default)]
+struct Style {
+    name: String,
+    based_on: Option<String>,
+    outline_level: Option<u8>,
+}
+
+/// Paragraph styles by id, from `word/styles.xml`
+#[derive(Debug, Default)]
+struct Styles {
+    styles: HashMap<String, Style>,
+    /// Document language from the default run properties
+    language: Option<String>,
+}
+
+impl Styles {
+    fn parse(xml: &str) -> Result<Self, ImportError> {
+        let mut reader = Reader::from_str(xml);
+        let mut styles = Styles::default();
+        let mut current: Option<(String, Style)> = None;
+        let mut in_defaults = false;
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) => match e.local_name().as_ref() {
+                    b"style" => {
+                        current = attr(&e, b"styleId").map(|id| (id, Style::default()));
+                    }
+                    b"docDefaults" => in_defaults = true,
+                    _ => {}
+                },
+                Event::Empty(e) => match e.local_name().as_ref() {
+                    b"lang" if in_defaults => styles.language = attr(&e, b"val"),
+                    b"name" => {
+                        if let Some((_, style)) = current.as_mut() {
+                            style.name = attr(&e, b"val").unwrap_or_default();
+                        }
+                    }
+                    b"basedOn" => {
+                        if let Some((_, style)) = current.as_mut() {
+                            style.based_on = attr(&e, b"val");
+                        }
+                    }
+                    b"outlineLvl" => {
+                        if let Some((_, style)) = current.as_mut() {
+                            style.outline_level = attr(&e, b"val").and_then(|v| v.parse().ok());
+                        }
+                    }
+                    _ => {}
+                },
+                Event::End(e) => match e.local_name().as_ref() {
+                    b"style" => {
+                        if let Some((id, style)) = current.take() {
+                            styles.styles.insert(id, style);
+                        }
+                    }
+                    b"docDefaults" => in_defaults = false,
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(styles)
+    }
+
+    /// Styles from the paragraph's own up through its `basedOn` chain
+    fn chain<'a>(&'a self, paragraph: &Paragraph) -> impl Iterator<Item = (&'a str, &'a Style)> {
+        let mut next = paragraph.style.clone();
+        // The bound guards against cycles in malformed files
+        std::iter::from_fn(move || {
+            let id = next.take()?;
+            let (id, style) = self.styles.get_key_value(&id)?;
+            next = style.based_on.clone();
+            Some((id.as_str(), style))
+        })
+        .take(16)
+    }
+
+    /// Heading 1, by name or by outline level (`0` is level 1)
+    fn is_heading(&self, paragraph: &Paragraph) -> bool {
+        if let Some(level) = paragraph.outline_level {
+            return level == 0;
+        }
+        // Documents without styles.xml still use the built-in id
+        if paragraph.style.as_deref() == Some("Heading1") {
+            return true;
+        }
+        self.chain(paragraph)
+            .find_map(|(_, style)| {
+                if style.name.eq_ignore_ascii_case("heading 1") {
+                    Some(true)
+                } else {
+                    style.outline_level.map(|level| level == 0)
+                }
+            })
+            .unwrap_or(false)
+    }
+
+    fn is_title(&self, paragraph: &Paragraph) -> bool {
+        paragraph.style.as_deref() == Some("Title")
+            || self
+                .chain(paragraph)
+                .any(|(_, style)| style.name.eq_ignore_ascii_case("title"))
+    }
+
+    /// Entry of a generated table of contents ("toc 1", "toc 2", ...)
+    fn is_toc(&self, paragraph: &Paragraph) -> bool {
+        let is_toc_name = |name: &str| name.to_ascii_lowercase().starts_with("toc ");
+        paragraph
+            .style
+            .as_deref()
+            .is_some_and(|id| id.to_ascii_uppercase().starts_with("TOC"))
+            || self
+                .chain(paragraph)
+                .any(|(_, style)| is_toc_name(&style.name))
+    }
+}
+
+/// Document properties from `docProps/core.xml`
+#[derive(Debug, Default)]
+struct CoreProperties {
+    title: Option<String>,
+    creator: Option<String>,
+    language: Option<String>,
+    created: Option<DateTime<Utc>>,
+    modified: Option<DateTime<Utc>>,
+}
+
+impl CoreProperties {
+    fn parse(xml: &str) -> Result<Self, ImportError> {
+        let mut reader = Reader::from_str(xml);
+        let mut core = CoreProperties::default();
+        let mut element = Vec::new();
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) => element = e.local_name().as_ref().to_vec(),
+                Event::End(_) => element.clear(),
+                Event::Text(t) => {
+                    let text = t.unescape()?.trim().to_string();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    let date = || {
+                        DateTime::parse_from_rfc3339(&text)
+                            .ok()
+                            .map(|d| d.with_timezone(&Utc))
+                    };
+                    match element.as_slice() {
+                        b"title" => core.title = Some(text),
+                        b"creator" => core.creator = Some(text),
+                        b"language" => core.language = Some(text),
+                        b"created" => core.created = date(),
+                        b"modified" => core.modified = date(),
+                        _ => {}
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(core)
+    }
+}
@@ -0,0 +1,606 @@
+use crate::bk_format::{BkChapter, BkMetadata};
+use crate::import::archive::{attr, read_part};
+use crate::import::error::ImportError;
+use crate::import::report::{Dropped, ImportReport};
+use crate::import::{book_id, UNKNOWN_AUTHOR, UNTITLED};
+use crate::models::Book;
+use chrono::{DateTime, NaiveDate, Utc};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+const CONTAINER: &str = "META-INF/container.xml";
+
+/// Scene break line for `<hr/>`, as written in .bk files
+const SCENE_BREAK: &str = "* * *";
+
+/// `epub:type` values of documents that come before the text proper
+const FRONT_MATTER_TYPES: &[&str] = &[
+    "cover",
+    "titlepage",
+    "halftitlepage",
+    "frontmatter",
+    "copyright-page",
+    "toc",
+];
+
+/// Read an .epub file from disk
+pub fn import_epub_file(path: &Path) -> Result<(Book, ImportReport), ImportError> {
+    read_epub(Cursor::new(std::fs::read(path)?))
+}
+
+/// Build a book from an EPUB 2 or 3 publication
+///
+/// Title, author, identifier and language come from the package metadata.
+/// Every spine document listed in the table of contents (the EPUB 3 nav, or
+/// the NCX) starts a chapter with that title, and unlisted documents continue
+/// the chapter before them. Without a table of contents, documents that open
+/// with a heading start a chapter named after it. Only text is kept; images, tables,
+/// notes and the like are counted in the report. Nothing outside the archive
+/// is read.
+pub fn import_epub(bytes: &[u8]) -> Result<(Book, ImportReport), ImportError> {
+    read_epub(Cursor::new(bytes))
+}
+
+fn read_epub<R: Read + Seek>(reader: R) -> Result<(Book, ImportReport), ImportError> {
+    let mut archive = ZipArchive::new(reader)?;
+    let mut report = ImportReport::default();
+
+    let container = read_part(&mut archive, CONTAINER)?
+        .ok_or_else(|| ImportError::MissingPart(CONTAINER.to_string()))?;
+    let opf_path = rootfile(&container)?
+        .ok_or_else(|| ImportError::MissingPart("package document".to_string()))?;
+    let opf = read_part(&mut archive, &opf_path)?
+        .ok_or_else(|| ImportError::MissingPart(opf_path.clone()))?;
+    let package = Package::parse(&opf, parent(&opf_path))?;
+
+    // Table of contents: document path -> title of its first entry
+    let mut toc: HashMap<String, String> = HashMap::new();
+    let nav = package.manifest.values().find(|item| item.is_nav());
+    let ncx = package
+        .toc_id
+        .as_ref()
+        .and_then(|id| package.manifest.get(id))
+        .or_else(|| package.manifest.values().find(|item| item.is_ncx()));
+    if let Some(item) = nav {
+        if let Some(xml) = read_part(&mut archive, &item.path)? {
+            toc = read_nav(&xml, parent(&item.path))?;
+        }
+    }
+    if toc.is_empty() {
+        if let Some(item) = ncx {
+            if let Some(xml) = read_part(&mut archive, &item.path)? {
+                toc = read_ncx(&xml, parent(&item.path))?;
+            }
+        }
+    }
+
+    let mut chapters: Vec<BkChapter> = Vec::new();
+    let mut dedication = None;
+    for idref in &package.spine {
+        let Some(item) = package.manifest.get(idref) else {
+            report.warn(format!("Spine entry '{}' is not in the manifest", idref));
+            continue;
+        };
+        if item.is_nav() {
+            continue;
+        }
+        if !item.is_xhtml() {
+            report.warn(format!("Skipped {} ({})", item.path, item.media_type));
+            continue;
+        }
+        let Some(xml) = read_part(&mut archive, &item.path)? else {
+            report.warn(format!("Missing content document {}", item.path));
+            continue;
+        };
+        let page = match read_xhtml(&xml, &mut report) {
+            Ok(page) => page,
+            Err(e) => {
+                report.warn(format!("Could not read {}: {}", item.path, e));
+                continue;
+            }
+        };
+
+        let types: Vec<&str> = page
+            .epub_type
+            .as_deref()
+            .map_or(Vec::new(), |t| t.split_whitespace().collect());
+        if types.contains(&"dedication") && dedication.is_none() {
+            dedication = Some(page.all_lines().join(" "));
+            continue;
+        }
+        let title = if toc.is_empty() {
+            page.heading.clone()
+        } else {
+            toc.get(&item.path).cloned()
+        };
+        let front_matter = types.iter().any(|t| FRONT_MATTER_TYPES.contains(t));
+
+        match (title, chapters.last_mut()) {
+            (Some(title), _) if !front_matter => {
+                let lines = page.lines_after(&title);
+                let mut chapter = BkChapter::new(title, chapters.len());
+                append(&mut chapter, &lines);
+                chapters.push(chapter);
+            }
+            (None, Some(chapter)) if !front_matter => append(chapter, &page.all_lines()),
+            // Documents without a chapter title: untitled chapters if there is no
+            // table of contents to name them, otherwise front matter
+            (None, None) if !front_matter && toc.is_empty() => {
+                let mut chapter = BkChapter::new(UNTITLED.to_string(), 0);
+                append(&mut chapter, &page.all_lines());
+                chapters.push(chapter);
+            }
+            _ => {
+                for _ in page.all_lines() {
+                    report.record(Dropped::FrontMatter);
+                }
+            }
+        }
+    }
+
+    let title = package.title.unwrap_or_else(|| {
+        report.warn("The publication has no title");
+        UNTITLED.to_string()
+    });
+    let author = if package.creators.is_empty() {
+        report.warn("The publication names no author");
+        UNKNOWN_AUTHOR.to_string()
+    } else {
+        package.creators.join(" & ")
+    };
+    let metadata = BkMetadata {
+        title: Some(title),
+        author: Some(author),
+        id: package.identifier.as_deref().map(book_id),
+        dedication,
+        language: package.language,
+        ..BkMetadata::default()
+    };
+    let now = Utc::now();
+    let updated_at = package.modified.or(package.date).unwrap_or(now);
+    let created_at = package.date.unwrap_or(updated_at);
+    let book = metadata.into_book(chapters, created_at, updated_at)?;
+    Ok((book, report))
+}
+
+fn append(chapter: &mut BkChapter, lines: &[String]) {
+    for line in lines {
+        if !chapter.content.is_empty() {
+            chapter.content.push('\n');
+        }
+        chapter.content.push_str(line);
+    }
+}
+
+/// Path of the package document named in `META-INF/container.xml`
+fn rootfile(container: &str) -> Result<Option<String>, ImportError> {
+    let mut reader = Reader::from_str(container);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"rootfile" => {
+                return Ok(attr(&e, b"full-path"));
+            }
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// Folder of an archive path, without a trailing slash
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+/// Archive path of an href found in a document in `base`, without its fragment
+fn resolve(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+    let href = percent_decode(href);
+    let mut segments: Vec<&str> = if href.starts_with('/') {
+        Vec::new()
+    } else {
+        base.split('/').filter(|s| !s.is_empty()).collect()
+    };
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Value of `epub:type`, which shares its local name with HTML's `type`
+fn epub_type(e: &BytesStart) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == b"epub:type")
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.into_owned())
+}
+
+/// Collapse runs of whitespace as HTML does, keeping no-break spaces
+fn collapse_whitespace(text: &str) -> String {
+    text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Debug)]
+struct ManifestItem {
+    path: String,
+    media_type: String,
+    properties: String,
+}
+
+impl ManifestItem {
+    fn is_nav(&self) -> bool {
+        self.properties.split_whitespace().any(|p| p == "nav")
+    }
+
+    fn is_ncx(&self) -> bool {
+        self.media_type == "application/x-dtbncx+xml"
+    }
+
+    fn is_xhtml(&self) -> bool {
+        matches!(
+            self.media_type.as_str(),
+            "application/xhtml+xml" | "text/html"
+        )
+    }
+}
+
+/// What the importer needs from the package document (OPF)
+#[derive(Debug, Default)]
+struct Package {
+    title: Option<String>,
+    creators: Vec<String>,
+    identifier: Option<String>,
+    language: Option<String>,
+    date: Option<DateTime<Utc>>,
+    modified: Option<DateTime<Utc>>,
+    /// Manifest items by id, with paths resolved against the archive root
+    manifest: HashMap<String, ManifestItem>,
+    /// Manifest ids in reading order
+    spine: Vec<String>,
+    /// Manifest id of the NCX, from `<spine toc="...">`
+    toc_id: Option<String>,
+}
+
+impl Package {
+    fn parse(xml: &str, base: &str) -> Result<Self, ImportError> {
+        let mut reader = Reader::from_str(xml);
+        let mut package = Package::default();
+        let mut unique_identifier = None;
+        let mut identifiers: Vec<(Option<String>, String)> = Vec::new();
+        // Element whose text is being read, with its id and `property`
+        let mut element: Option<(Vec<u8>, Option<String>, Option<String>)> = None;
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) => {
+                    let name = e.local_name().as_ref().to_vec();
+                    match name.as_slice() {
+                        b"package" => unique_identifier = attr(&e, b"unique-identifier"),
+                        b"spine" => package.toc_id = attr(&e, b"toc"),
+                        _ => {}
+                    }
+                    element = Some((name, attr(&e, b"id"), attr(&e, b"property")));
+                }
+                Event::Empty(e) => match e.local_name().as_ref() {
+                    b"item" => {
+                        if let (Some(id), Some(href)) = (attr(&e, b"id"), attr(&e, b"href")) {
+                            package.manifest.insert(
+                                id,
+                                ManifestItem {
+                                    path: resolve(base, &href),
+                                    media_type: attr(&e, b"media-type").unwrap_or_default(),
+                                    properties: attr(&e, b"properties").unwrap_or_default(),
+                                },
+                            );
+                        }
+                    }
+                    b"itemref" => {
+                        if let Some(idref) = attr(&e, b"idref") {
+                            package.spine.push(idref);
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Text(t) => {
+                    let Some((name, id, property)) = &element else {
+                        continue;
+                    };
+                    let text = collapse_whitespace(&t.unescape()?);
+                    if text.is_empty() {
+                        continue;
+                    }
+                    match name.as_slice() {
+                        b"title" if package.title.is_none() => package.title = Some(text),
+                        b"creator" => package.creators.push(text),
+                        b"identifier" => identifiers.push((id.clone(), text)),
+                        b"language" if package.language.is_none() => package.language = Some(text),
+                        b"date" => package.date = parse_date(&text),
+                        b"meta" if property.as_deref() == Some("dcterms:modified") => {
+                            package.modified = parse_date(&text)
+                        }
+                        _ => {}
+                    }
+                }
+                Event::End(_) => element = None,
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        package.identifier = identifiers
+            .iter()
+            .find(|(id, _)| id.is_some() && *id == unique_identifier)
+            .or(identifiers.first())
+            .map(|(_, value)| value.clone());
+        Ok(package)
+    }
+}
+
+/// A full timestamp, or a bare date taken as midnight UTC
+fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|d| d.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|d| d.and_utc())
+        })
+}
+
+/// Entries of the `epub:type="toc"` nav in an EPUB 3 navigation document
+fn read_nav(xml: &str, base: &str) -> Result<HashMap<String, String>, ImportError> {
+    let mut reader = Reader::from_str(xml);
+    let mut toc = HashMap::new();
+    let mut nav_depth = 0usize;
+    let mut link: Option<(String, String)> = None;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"nav" if nav_depth > 0 => nav_depth += 1,
+                b"nav"
+                    if epub_type(&e).is_some_and(|t| t.split_whitespace().any(|t| t == "toc")) =>
+                {
+                    nav_depth = 1
+                }
+                b"a" if nav_depth > 0 => {
+                    link = attr(&e, b"href").map(|href| (resolve(base, &href), String::new()))
+                }
+                _ => {}
+            },
+            Event::Text(t) => {
+                if let Some((_, label)) = link.as_mut() {
+                    label.push_str(&t.unescape()?);
+                }
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"nav" if nav_depth > 0 => nav_depth -= 1,
+                b"a" => {
+                    if let Some((path, label)) = link.take() {
+                        toc.entry(path)
+                            .or_insert_with(|| collapse_whitespace(&label));
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    toc.retain(|_, label| !label.is_empty());
+    Ok(toc)
+}
+
+/// Entries of an EPUB 2 NCX: each `navPoint` label with its content source
+fn read_ncx(xml: &str, base: &str) -> Result<HashMap<String, String>, ImportError> {
+    let mut reader = Reader::from_str(xml);
+    let mut toc = HashMap::new();
+    let mut label = String::new();
+    let mut in_label = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"navPoint" => label.clear(),
+                b"text" => in_label = true,
+                _ => {}
+            },
+            Event::Text(t) if in_label => label.push_str(&t.unescape()?),
+            Event::End(e) if e.local_name().as_ref() == b"text" => in_label = false,
+            Event::Empty(e) if e.local_name().as_ref() == b"content" => {
+                let label = collapse_whitespace(&label);
+                if let (Some(src), false) = (attr(&e, b"src"), label.is_empty()) {
+                    toc.entry(resolve(base, &src)).or_insert(label);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(toc)
+}
+
+/// Text of one content document
+#[derive(Debug, Default)]
+struct Page {
+    /// `epub:type` of the body or its first section
+    epub_type: Option<String>,
+    /// First `h1`/`h2`, when it comes before any other text
+    heading: Option<String>,
+    lines: Vec<String>,
+}
+
+impl Page {
+    /// Every line, including the leading heading
+    fn all_lines(&self) -> Vec<String> {
+        self.heading.iter().chain(&self.lines).cloned().collect()
+    }
+
+    /// Content under a chapter title, leaving out a heading that repeats it
+    fn lines_after(&self, title: &str) -> Vec<String> {
+        match &self.heading {
+            Some(heading) if heading.eq_ignore_ascii_case(title) => self.lines.clone(),
+            _ => self.all_lines(),
+        }
+    }
+}
+
+/// Reduce XHTML to lines of text, one per paragraph or other block
+fn read_xhtml(xml: &str, report: &mut ImportReport) -> Result<Page, ImportError> {
+    let mut reader = Reader::from_str(xml);
+    let mut page = Page::default();
+    let mut text = String::new();
+    let mut in_heading = false;
+    let mut in_body = false;
+    // Depth inside an element whose content is dropped
+    let mut skip = 0usize;
+
+    let flush = |text: &mut String, page: &mut Page, heading: bool| {
+        let line = collapse_whitespace(text);
+        text.clear();
+        if line.is_empty() {
+            return;
+        }
+        if heading && page.heading.is_none() && page.lines.is_empty() {
+            page.heading = Some(line);
+        } else {
+            page.lines.push(line);
+        }
+    };
+
+    loop {
+        let event = reader.read_event()?;
+        if skip > 0 {
+            match event {
+                Event::Start(_) => skip += 1,
+                Event::End(_) => skip -= 1,
+                Event::Eof => break,
+                _ => {}
+            }
+            continue;
+        }
+        match event {
+            Event::Start(e) => {
+                let name = e.local_name();
+                let dropped = match name.as_ref() {
+                    b"head" | b"script" | b"style" | b"nav" => Some(None),
+                    b"table" => Some(Some(Dropped::Table)),
+                    b"math" => Some(Some(Dropped::Equation)),
+                    b"svg" | b"picture" => Some(Some(Dropped::Image)),
+                    b"audio" | b"video" | b"object" | b"iframe" => Some(Some(Dropped::Media)),
+                    b"aside" | b"a" if epub_type(&e).is_some_and(|t| t.contains("note")) => {
+                        Some(Some(Dropped::Footnote))
+                    }
+                    _ => None,
+                };
+                if let Some(kind) = dropped {
+                    if let Some(kind) = kind {
+                        report.record(kind);
+                    }
+                    skip = 1;
+                    continue;
+                }
+                match name.as_ref() {
+                    b"body" | b"section" if page.epub_type.is_none() => {
+                        page.epub_type = epub_type(&e);
+                    }
+                    _ => {}
+                }
+                if name.as_ref() == b"body" {
+                    in_body = true;
+                }
+                if is_block(name.as_ref()) {
+                    flush(&mut text, &mut page, in_heading);
+                    in_heading = matches!(name.as_ref(), b"h1" | b"h2");
+                }
+            }
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"br" => flush(&mut text, &mut page, in_heading),
+                b"hr" => {
+                    flush(&mut text, &mut page, in_heading);
+                    page.lines.push(SCENE_BREAK.to_string());
+                }
+                b"img" | b"image" => report.record(Dropped::Image),
+                b"embed" => report.record(Dropped::Media),
+                _ => {}
+            },
+            Event::Text(t) if in_body => text.push_str(&t.unescape()?),
+            Event::CData(t) if in_body => text.push_str(&String::from_utf8_lossy(&t)),
+            Event::End(e) => {
+                let name = e.local_name();
+                if is_block(name.as_ref()) {
+                    flush(&mut text, &mut page, in_heading);
+                    in_heading = false;
+                }
+                if name.as_ref() == b"body" {
+                    in_body = false;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    flush(&mut text, &mut page, false);
+    Ok(page)
+}
+
+/// Elements that start a new line of text
+fn is_block(name: &[u8]) -> bool {
+    matches!(
+        name,
+        b"p" | b"div"
+            | b"h1"
+            | b"h2"
+            | b"h3"
+            | b"h4"
+            | b"h5"
+            | b"h6"
+            | b"li"
+            | b"blockquote"
+            | b"pre"
+            | b"dt"
+            | b"dd"
+            | b"figcaption"
+            | b"section"
+            | b"article"
+            | b"header"
+            | b"footer"
+            | b"address"
+            | b"body"
+    )
+}
@@ -0,0 +1,30 @@
+use crate::bk_format::BkParseError;
+use thiserror::Error;
+
+/// Errors that can occur while importing a book from another format
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("IO error during import: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to read archive: {0}")]
+    Archive(#[from] zip::result::ZipError),
+
+    #[error("Malformed XML: {0}")]
+    Xml(#[from] quick_xml::Error),
+
+    #[error("Missing document part: {0}")]
+    MissingPart(String),
+
+    #[error("Invalid binder item id: {0:?}")]
+    InvalidItemId(String),
+
+    #[error("Malformed JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid frontmatter: {0}")]
+    Frontmatter(#[from] serde_yaml::Error),
+
+    #[error("{0}")]
+    Book(#[from] BkParseError),
+}
@@ -0,0 +1,118 @@
+/// Longest paragraph that can be a "Chapter N" title
+pub(crate) const MAX_CHAPTER_TITLE_CHARS: usize = 80;
+
+const ONES: [&str; 19] = [
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+];
+
+const TENS: [&str; 8] = [
+    "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// A "Chapter N" heading, split into its number and any title after it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ChapterHeading<'a> {
+    pub number: u32,
+    pub title: Option<&'a str>,
+}
+
+/// Parse "Chapter" followed by a number in digits, Roman numerals or words,
+/// as in "CHAPTER IV." or "Chapter Twelve: The Forge"
+pub(crate) fn chapter_heading(text: &str) -> Option<ChapterHeading<'_>> {
+    let text = text.trim();
+    if text.chars().count() > MAX_CHAPTER_TITLE_CHARS {
+        return None;
+    }
+    let (first, rest) = text.split_once(char::is_whitespace)?;
+    if !first.eq_ignore_ascii_case("chapter") {
+        return None;
+    }
+    let rest = rest.trim_start();
+    let end = rest
+        .find(|c: char| c.is_whitespace() || matches!(c, '.' | ':' | ',' | '\u{2014}' | '\u{2013}'))
+        .unwrap_or(rest.len());
+    let number = parse_number(rest[..end].trim_end_matches('-'))?;
+    let title = rest[end..]
+        .trim_start_matches(|c: char| {
+            c.is_whitespace() || matches!(c, '.' | ':' | ',' | '-' | '\u{2014}' | '\u{2013}')
+        })
+        .trim_end();
+    Some(ChapterHeading {
+        number,
+        title: (!title.is_empty()).then_some(title),
+    })
+}
+
+/// Value of a number written in digits, Roman numerals or English words
+pub(crate) fn parse_number(text: &str) -> Option<u32> {
+    let text = text.to_lowercase();
+    if text.is_empty() {
+        return None;
+    }
+    if text.chars().all(|c| c.is_ascii_digit()) {
+        return text.parse().ok();
+    }
+    parse_roman(&text).or_else(|| parse_words(&text))
+}
+
+/// Value of a lowercase Roman numeral up to a few hundred
+pub(crate) fn parse_roman(text: &str) -> Option<u32> {
+    if text.is_empty() || text.len() > 8 {
+        return None;
+    }
+    let digits = text
+        .chars()
+        .map(|c| match c {
+            'i' => Some(1),
+            'v' => Some(5),
+            'x' => Some(10),
+            'l' => Some(50),
+            'c' => Some(100),
+            _ => None,
+        })
+        .collect::<Option<Vec<i32>>>()?;
+    let total: i32 = digits
+        .iter()
+        .enumerate()
+        .map(|(i, &d)| match digits.get(i + 1) {
+            Some(&next) if next > d => -d,
+            _ => d,
+        })
+        .sum();
+    u32::try_from(total).ok().filter(|&n| n > 0)
+}
+
+/// Value of a hyphenated English number such as "twenty-one"
+fn parse_words(text: &str) -> Option<u32> {
+    let mut total = 0;
+    for part in text.split('-') {
+        if let Some(i) = ONES.iter().position(|&w| w == part) {
+            total += i as u32 + 1;
+        } else if let Some(i) = TENS.iter().position(|&w| w == part) {
+            total += (i as u32 + 2) * 10;
+        } else if part == "hundred" {
+            total = total.max(1) * 100;
+        } else {
+            return None;
+        }
+    }
+    Some(total)
+}
@@ -0,0 +1,183 @@
+use crate::bk_format::{file_timestamps, BkChapter, BkMetadata};
+use crate::export::markdown::THEMATIC_BREAK;
+use crate::import::error::ImportError;
+use crate::import::report::ImportReport;
+use crate::import::{UNKNOWN_AUTHOR, UNTITLED};
+use crate::models::{Book, BookFormat};
+use chrono::{DateTime, Utc};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use std::path::Path;
+
+/// Read a Markdown file, taking timestamps from the filesystem
+///
+/// Without a title in the frontmatter or a heading, the file name is the title.
+pub fn import_markdown_file(path: &Path) -> Result<(Book, ImportReport), ImportError> {
+    let content = std::fs::read_to_string(path)?;
+    let (created_at, updated_at) = file_timestamps(&std::fs::metadata(path)?);
+    let stem = path.file_stem().and_then(|s| s.to_str());
+    read_markdown(&content, stem, created_at, updated_at)
+}
+
+/// Build a book from CommonMark with optional YAML frontmatter
+///
+/// Frontmatter keys are the .bk metadata fields: `title`, `author`, `id`,
+/// `dedication`, `language`, `target_words`, `author_sort`, `series`,
+/// `series_index`, `tags`, `identifiers`, `format` and `hyphenation`; other
+/// keys are ignored. Without a title the first heading is used, and without
+/// an author "Unknown", each noted in the report. Every H1 or H2 heading
+/// starts a chapter, and each paragraph becomes a line of content. Inline
+/// formatting is reduced to its text. In a screenplay (`format: screenplay`),
+/// fenced code blocks are Fountain and are kept verbatim, blank lines
+/// included.
+pub fn import_markdown(
+    content: &str,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+) -> Result<(Book, ImportReport), ImportError> {
+    read_markdown(content, None, created_at, updated_at)
+}
+
+/// [`import_markdown`], with a file name to fall back on for the title
+fn read_markdown(
+    content: &str,
+    file_stem: Option<&str>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+) -> Result<(Book, ImportReport), ImportError> {
+    let mut report = ImportReport::default();
+    let mut metadata = BkMetadata::default();
+    let mut first_heading: Option<String> = None;
+    let mut chapters: Vec<BkChapter> = Vec::new();
+    let mut text = String::new();
+    let mut in_frontmatter = false;
+    let mut in_title = false;
+    let mut in_fountain = false;
+
+    let options = Options::ENABLE_YAML_STYLE_METADATA_BLOCKS;
+    for event in Parser::new_ext(content, options) {
+        match event {
+            Event::Start(Tag::MetadataBlock(_)) => in_frontmatter = true,
+            Event::End(TagEnd::MetadataBlock(_)) => in_frontmatter = false,
+            // An empty block would parse as null rather than as a mapping
+            Event::Text(yaml) if in_frontmatter && !yaml.trim().is_empty() => {
+                metadata = serde_yaml::from_str(&yaml)?;
+            }
+
+            Event::Start(Tag::CodeBlock(_)) if metadata.format == BookFormat::Screenplay => {
+                flush(&mut text, &mut chapters);
+                in_fountain = true;
+            }
+            Event::End(TagEnd::CodeBlock) if in_fountain => {
+                push_line(&mut chapters, text.trim_end_matches('\n'));
+                text.clear();
+                in_fountain = false;
+            }
+
+            Event::Start(Tag::Heading { level, .. }) if is_chapter_heading(level) => {
+                flush(&mut text, &mut chapters);
+                in_title = true;
+            }
+            Event::End(TagEnd::Heading(level)) if is_chapter_heading(level) => {
+                let title = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                text.clear();
+                in_title = false;
+                let title = if title.is_empty() {
+                    UNTITLED.to_string()
+                } else {
+                    first_heading.get_or_insert_with(|| title.clone());
+                    title
+                };
+                chapters.push(BkChapter::new(title, chapters.len()));
+            }
+
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak if in_title => text.push(' '),
+            Event::HardBreak => text.push('\n'),
+            Event::Rule => {
+                flush(&mut text, &mut chapters);
+                push_line(&mut chapters, THEMATIC_BREAK);
+            }
+            Event::Start(tag) if is_block(&tag) => flush(&mut text, &mut chapters),
+            Event::End(tag) if is_block_end(&tag) => flush(&mut text, &mut chapters),
+            // Raw HTML, footnote references and task markers carry no prose
+            _ => {}
+        }
+    }
+    flush(&mut text, &mut chapters);
+
+    for chapter in &mut chapters {
+        chapter.content = chapter.content.trim().to_string();
+    }
+
+    if metadata.title.is_none() {
+        metadata.title = Some(match (first_heading, file_stem) {
+            (Some(heading), _) => {
+                report.warn("The frontmatter has no title; using the first heading");
+                heading
+            }
+            (None, Some(stem)) => {
+                report.warn("The frontmatter has no title; using the file name");
+                stem.to_string()
+            }
+            (None, None) => {
+                report.warn("The document has no title");
+                UNTITLED.to_string()
+            }
+        });
+    }
+    if metadata.author.is_none() {
+        report.warn("The document names no author");
+        metadata.author = Some(UNKNOWN_AUTHOR.to_string());
+    }
+    let book = metadata.into_book(chapters, created_at, updated_at)?;
+    Ok((book, report))
+}
+
+fn is_chapter_heading(level: HeadingLevel) -> bool {
+    matches!(level, HeadingLevel::H1 | HeadingLevel::H2)
+}
+
+/// Tags that start a new block, ending any text collected so far
+fn is_block(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::Paragraph
+            | Tag::Heading { .. }
+            | Tag::BlockQuote(_)
+            | Tag::CodeBlock(_)
+            | Tag::List(_)
+            | Tag::Item
+    )
+}
+
+fn is_block_end(tag: &TagEnd) -> bool {
+    matches!(
+        tag,
+        TagEnd::Paragraph
+            | TagEnd::Heading(_)
+            | TagEnd::BlockQuote(_)
+            | TagEnd::CodeBlock
+            | TagEnd::List(_)
+            | TagEnd::Item
+    )
+}
+
+/// Move collected text into the current chapter, one line per non-empty line
+fn flush(text: &mut String, chapters: &mut Vec<BkChapter>) {
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        push_line(chapters, line);
+    }
+    text.clear();
+}
+
+fn push_line(chapters: &mut Vec<BkChapter>, line: &str) {
+    if chapters.is_empty() {
+        chapters.push(BkChapter::new(UNTITLED.to_string(), 0));
+    }
+    let chapter = chapters.last_mut().expect("a chapter was just ensured");
+    if !chapter.content.is_empty() {
+        chapter.content.push('\n');
+    }
+    chapter.content.push_str(line);
+}
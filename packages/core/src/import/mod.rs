@@ -0,0 +1,35 @@
+mod archive;
+pub mod docx;
+pub mod epub;
+mod error;
+mod heading;
+pub mod markdown;
+pub mod pandoc;
+mod report;
+pub(crate) mod rtf;
+pub mod scrivener;
+pub mod text;
+
+#[cfg(test)]
+mod tests;
+
+use uuid::Uuid;
+
+pub use error::ImportError;
+pub use report::{Dropped, ImportReport};
+
+/// Title for prose before the first heading, or a heading with no text
+pub(crate) const UNTITLED: &str = "Untitled";
+
+/// Author for sources that name nobody
+pub(crate) const UNKNOWN_AUTHOR: &str = "Unknown";
+
+/// Book id from the identifier of a publication
+///
+/// A `urn:uuid:` identifier is used as is; any other (an ISBN, a URL) is
+/// hashed, so importing the same publication twice gives the same id.
+pub(crate) fn book_id(identifier: &str) -> Uuid {
+    let trimmed = identifier.trim();
+    let bare = trimmed.strip_prefix("urn:uuid:").unwrap_or(trimmed);
+    Uuid::parse_str(bare).unwrap_or_else(|_| Uuid::new_v5(&Uuid::NAMESPACE_URL, trimmed.as_bytes()))
+}
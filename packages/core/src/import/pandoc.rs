@@ -0,0 +1,246 @@
+use crate::bk_format::{file_timestamps, BkChapter, BkMetadata};
+use crate::export::markdown::THEMATIC_BREAK;
+use crate::import::error::ImportError;
+use crate::import::report::{Dropped, ImportReport};
+use crate::import::{book_id, UNKNOWN_AUTHOR, UNTITLED};
+use crate::models::Book;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::path::Path;
+
+/// Read a file written by `pandoc -t json`, taking timestamps from the filesystem
+pub fn import_pandoc_json_file(path: &Path) -> Result<(Book, ImportReport), ImportError> {
+    let json = std::fs::read_to_string(path)?;
+    let (created_at, updated_at) = file_timestamps(&std::fs::metadata(path)?);
+    import_pandoc_json(&json, created_at, updated_at)
+}
+
+/// Build a book from Pandoc's JSON AST
+///
+/// Every level 1 `Header` starts a chapter; lower headers, paragraphs, list
+/// items and quotations become lines of content and `HorizontalRule` a scene
+/// break. Inline markup is reduced to its text, with `Quoted` spans given
+/// curly quotes. Tables, figures, images, notes and math are dropped and
+/// counted in the report. `title`, `author`, `lang`, `dedication` and
+/// `identifier` are read from `meta`.
+pub fn import_pandoc_json(
+    json: &str,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+) -> Result<(Book, ImportReport), ImportError> {
+    let value: Value = serde_json::from_str(json)?;
+    let blocks = value
+        .get("blocks")
+        .and_then(Value::as_array)
+        .ok_or_else(|| ImportError::MissingPart("blocks".to_string()))?;
+
+    let mut reader = AstReader::default();
+    reader.blocks(blocks);
+    let AstReader {
+        mut report,
+        mut chapters,
+    } = reader;
+    for chapter in &mut chapters {
+        chapter.content = chapter.content.trim().to_string();
+    }
+
+    let meta = value.get("meta");
+    let field = |names: &[&str], report: &mut ImportReport| {
+        names
+            .iter()
+            .find_map(|name| meta.and_then(|m| m.get(*name)))
+            .map(|value| meta_text(value, report))
+            .filter(|text| !text.is_empty())
+    };
+    let title = field(&["title"], &mut report).unwrap_or_else(|| {
+        report.warn("The document has no title");
+        UNTITLED.to_string()
+    });
+    let author = field(&["author", "authors"], &mut report).unwrap_or_else(|| {
+        report.warn("The document names no author");
+        UNKNOWN_AUTHOR.to_string()
+    });
+    let metadata = BkMetadata {
+        title: Some(title),
+        author: Some(author),
+        id: field(&["identifier"], &mut report).map(|id| book_id(&id)),
+        dedication: field(&["dedication"], &mut report),
+        language: field(&["lang", "language"], &mut report),
+        ..BkMetadata::default()
+    };
+    let book = metadata.into_book(chapters, created_at, updated_at)?;
+    Ok((book, report))
+}
+
+/// Type tag and contents of an AST node
+fn node(value: &Value) -> (&str, &Value) {
+    (
+        value.get("t").and_then(Value::as_str).unwrap_or_default(),
+        value.get("c").unwrap_or(&Value::Null),
+    )
+}
+
+fn items(value: &Value) -> &[Value] {
+    value.as_array().map(Vec::as_slice).unwrap_or_default()
+}
+
+#[derive(Default)]
+struct AstReader {
+    report: ImportReport,
+    chapters: Vec<BkChapter>,
+}
+
+impl AstReader {
+    fn blocks(&mut self, blocks: &[Value]) {
+        for block in blocks {
+            self.block(block);
+        }
+    }
+
+    fn block(&mut self, block: &Value) {
+        let (tag, content) = node(block);
+        match tag {
+            "Header" if content.get(0).and_then(Value::as_u64) == Some(1) => {
+                let title = collapse(&inline_text(&content[2], &mut self.report));
+                let title = if title.is_empty() {
+                    UNTITLED.to_string()
+                } else {
+                    title
+                };
+                self.chapters
+                    .push(BkChapter::new(title, self.chapters.len()));
+            }
+            "Header" => self.inlines(&content[2]),
+            "Para" | "Plain" => self.inlines(content),
+            "LineBlock" => {
+                for line in items(content) {
+                    self.inlines(line);
+                }
+            }
+            "CodeBlock" => self.text(content[1].as_str().unwrap_or_default()),
+            "HorizontalRule" => self.line(THEMATIC_BREAK),
+            "BlockQuote" => self.blocks(items(content)),
+            "Div" => self.blocks(items(&content[1])),
+            "BulletList" => {
+                for item in items(content) {
+                    self.blocks(items(item));
+                }
+            }
+            "OrderedList" => {
+                for item in items(&content[1]) {
+                    self.blocks(items(item));
+                }
+            }
+            "DefinitionList" => {
+                for entry in items(content) {
+                    self.inlines(&entry[0]);
+                    for definition in items(&entry[1]) {
+                        self.blocks(items(definition));
+                    }
+                }
+            }
+            "Table" => self.report.record(Dropped::Table),
+            "Figure" => self.report.record(Dropped::Image),
+            // RawBlock and Null carry no prose
+            _ => {}
+        }
+    }
+
+    fn inlines(&mut self, inlines: &Value) {
+        let text = inline_text(inlines, &mut self.report);
+        self.text(&text);
+    }
+
+    /// Add text as lines, one per line break
+    fn text(&mut self, text: &str) {
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            self.line(line);
+        }
+    }
+
+    fn line(&mut self, line: &str) {
+        if self.chapters.is_empty() {
+            self.chapters.push(BkChapter::new(UNTITLED.to_string(), 0));
+        }
+        let chapter = self
+            .chapters
+            .last_mut()
+            .expect("a chapter was just ensured");
+        if !chapter.content.is_empty() {
+            chapter.content.push('\n');
+        }
+        chapter.content.push_str(line);
+    }
+}
+
+/// Plain text of a list of inlines; `LineBreak` becomes a newline
+fn inline_text(inlines: &Value, report: &mut ImportReport) -> String {
+    let mut out = String::new();
+    push_inlines(inlines, report, &mut out);
+    out
+}
+
+fn push_inlines(inlines: &Value, report: &mut ImportReport, out: &mut String) {
+    for inline in items(inlines) {
+        let (tag, content) = node(inline);
+        match tag {
+            "Str" => out.push_str(content.as_str().unwrap_or_default()),
+            "Space" | "SoftBreak" => out.push(' '),
+            "LineBreak" => out.push('\n'),
+            "Emph" | "Underline" | "Strong" | "Strikeout" | "Superscript" | "Subscript"
+            | "SmallCaps" => push_inlines(content, report, out),
+            "Quoted" => {
+                let (open, close) = match node(&content[0]).0 {
+                    "SingleQuote" => ('\u{2018}', '\u{2019}'),
+                    _ => ('\u{201c}', '\u{201d}'),
+                };
+                out.push(open);
+                push_inlines(&content[1], report, out);
+                out.push(close);
+            }
+            "Cite" | "Span" | "Link" => push_inlines(&content[1], report, out),
+            "Code" => out.push_str(content[1].as_str().unwrap_or_default()),
+            "Image" => report.record(Dropped::Image),
+            "Note" => report.record(Dropped::Footnote),
+            "Math" => report.record(Dropped::Equation),
+            // RawInline is format-specific markup
+            _ => {}
+        }
+    }
+}
+
+/// Text of a metadata value; the entries of a list are joined with " & "
+fn meta_text(value: &Value, report: &mut ImportReport) -> String {
+    let (tag, content) = node(value);
+    match tag {
+        "MetaInlines" => collapse(&inline_text(content, report)),
+        "MetaString" => content.as_str().unwrap_or_default().trim().to_string(),
+        "MetaBlocks" => {
+            let mut text = String::new();
+            for block in items(content) {
+                if let ("Para" | "Plain", inlines) = node(block) {
+                    push_inlines(inlines, report, &mut text);
+                    text.push(' ');
+                }
+            }
+            collapse(&text)
+        }
+        "MetaList" => items(content)
+            .iter()
+            .map(|item| meta_text(item, report))
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<_>>()
+            .join(" & "),
+        // An author given as `{name: ...}` or an identifier as `{text: ...}`
+        "MetaMap" => ["name", "text"]
+            .iter()
+            .find_map(|key| content.get(*key))
+            .map(|value| meta_text(value, report))
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
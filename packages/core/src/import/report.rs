@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Kind of source content an importer left out of the book
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dropped {
+    /// Comments and document notes
+    Comment,
+    Table,
+    /// Revision marks; insertions are kept and deletions discarded
+    TrackedChange,
+    Image,
+    Footnote,
+    Endnote,
+    Equation,
+    /// Audio, video and other embedded objects
+    Media,
+    /// Entries of a generated table of contents
+    TableOfContents,
+    /// Paragraphs before the first chapter, such as a title page
+    FrontMatter,
+    /// Scrivener document synopses
+    Synopsis,
+    /// Scrivener document labels
+    Label,
+    /// Licence headers and footers, such as Project Gutenberg's
+    Boilerplate,
+}
+
+/// What an import left out or had to guess
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// Number of items dropped, by kind
+    pub dropped: BTreeMap<Dropped, usize>,
+    pub warnings: Vec<String>,
+}
+
+impl ImportReport {
+    pub fn record(&mut self, kind: Dropped) {
+        *self.dropped.entry(kind).or_insert(0) += 1;
+    }
+
+    /// Number of dropped items of one kind
+    pub fn count(&self, kind: Dropped) -> usize {
+        self.dropped.get(&kind).copied().unwrap_or(0)
+    }
+
+    pub fn warn(&mut self, message: impl Into<String>) {
+        self.warnings.push(message.into());
+    }
+
+    /// True when nothing was dropped and nothing guessed
+    pub fn is_clean(&self) -> bool {
+        self.dropped.is_empty() && self.warnings.is_empty()
+    }
+}
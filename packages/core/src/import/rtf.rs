@@ -0,0 +1,232 @@
+use crate::import::report::{Dropped, ImportReport};
+
+/// Destinations whose content is never text
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "info",
+    "listtable",
+    "listoverridetable",
+    "revtbl",
+    "rsidtbl",
+    "generator",
+    "header",
+    "headerl",
+    "headerr",
+    "headerf",
+    "footer",
+    "footerl",
+    "footerr",
+    "footerf",
+    "fldinst",
+    "themedata",
+    "colorschememapping",
+    "latentstyles",
+    "datastore",
+    "filetbl",
+    "xmlnstbl",
+];
+
+/// Per-group parser state, restored at `}`
+#[derive(Debug, Clone, Copy)]
+struct Group {
+    /// Inside a destination whose text is dropped
+    skip: bool,
+    /// Fallback characters that follow each `\u` (the `\uc` value)
+    unicode_skip: usize,
+}
+
+/// Plain text of an RTF document, one line per paragraph
+///
+/// Handles escapes, `\'hh` bytes (as Windows-1252) and `\u` characters.
+/// Pictures, embedded objects, footnotes and annotations are left out and
+/// counted in the report.
+pub(crate) fn rtf_to_lines(rtf: &[u8], report: &mut ImportReport) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut stack: Vec<Group> = Vec::new();
+    let mut group = Group {
+        skip: false,
+        unicode_skip: 1,
+    };
+    // Fallback characters still to drop after a `\u`
+    let mut pending_skip = 0usize;
+    let mut high_surrogate: Option<u16> = None;
+    let mut i = 0;
+
+    while i < rtf.len() {
+        let byte = rtf[i];
+        i += 1;
+        match byte {
+            b'{' => {
+                stack.push(group);
+                pending_skip = 0;
+            }
+            b'}' => {
+                group = stack.pop().unwrap_or(group);
+                pending_skip = 0;
+            }
+            b'\r' | b'\n' => {}
+            b'\\' => {
+                let Some(&next) = rtf.get(i) else { break };
+                if next.is_ascii_alphabetic() {
+                    let start = i;
+                    while i < rtf.len() && rtf[i].is_ascii_alphabetic() {
+                        i += 1;
+                    }
+                    let word = std::str::from_utf8(&rtf[start..i]).unwrap_or_default();
+                    let param_start = i;
+                    if rtf.get(i) == Some(&b'-') {
+                        i += 1;
+                    }
+                    while i < rtf.len() && rtf[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                    let param: Option<i32> = std::str::from_utf8(&rtf[param_start..i])
+                        .ok()
+                        .and_then(|p| p.parse().ok());
+                    // A single space ends the control word and is part of it
+                    if rtf.get(i) == Some(&b' ') {
+                        i += 1;
+                    }
+
+                    match word {
+                        "par" | "sect" | "page" | "line" | "row" if !group.skip => {
+                            lines.push(std::mem::take(&mut line));
+                        }
+                        "tab" | "cell" => push(' ', &group, &mut pending_skip, &mut line),
+                        "emdash" => push('\u{2014}', &group, &mut pending_skip, &mut line),
+                        "endash" => push('\u{2013}', &group, &mut pending_skip, &mut line),
+                        "lquote" => push('\u{2018}', &group, &mut pending_skip, &mut line),
+                        "rquote" => push('\u{2019}', &group, &mut pending_skip, &mut line),
+                        "ldblquote" => push('\u{201c}', &group, &mut pending_skip, &mut line),
+                        "rdblquote" => push('\u{201d}', &group, &mut pending_skip, &mut line),
+                        "bullet" => push('\u{2022}', &group, &mut pending_skip, &mut line),
+                        "emspace" | "enspace" | "qmspace" => {
+                            push(' ', &group, &mut pending_skip, &mut line)
+                        }
+                        "uc" => group.unicode_skip = param.unwrap_or(1).max(0) as usize,
+                        "u" => {
+                            let code = param.unwrap_or(0);
+                            let unit = if code < 0 { code + 65_536 } else { code } as u16;
+                            let c = match (high_surrogate.take(), unit) {
+                                (_, 0xd800..=0xdbff) => {
+                                    high_surrogate = Some(unit);
+                                    None
+                                }
+                                (Some(high), 0xdc00..=0xdfff) => {
+                                    char::decode_utf16([high, unit]).next().and_then(Result::ok)
+                                }
+                                (_, unit) => char::from_u32(u32::from(unit)),
+                            };
+                            if let (Some(c), false) = (c, group.skip) {
+                                line.push(c);
+                            }
+                            pending_skip = group.unicode_skip;
+                        }
+                        "bin" => i += param.unwrap_or(0).max(0) as usize,
+                        "pict" | "NeXTGraphic" | "shp" | "nonshppict" => {
+                            skip_destination(&mut group, stack.last(), report, Some(Dropped::Image))
+                        }
+                        "object" => {
+                            skip_destination(&mut group, stack.last(), report, Some(Dropped::Media))
+                        }
+                        "footnote" => skip_destination(
+                            &mut group,
+                            stack.last(),
+                            report,
+                            Some(Dropped::Footnote),
+                        ),
+                        "annotation" => skip_destination(
+                            &mut group,
+                            stack.last(),
+                            report,
+                            Some(Dropped::Comment),
+                        ),
+                        word if SKIPPED_DESTINATIONS.contains(&word) => {
+                            skip_destination(&mut group, stack.last(), report, None)
+                        }
+                        _ => {}
+                    }
+                } else {
+                    i += 1;
+                    match next {
+                        b'\'' => {
+                            let hex = rtf
+                                .get(i..i + 2)
+                                .and_then(|h| std::str::from_utf8(h).ok())
+                                .and_then(|h| u8::from_str_radix(h, 16).ok());
+                            if let Some(byte) = hex {
+                                i += 2;
+                                push(windows_1252(byte), &group, &mut pending_skip, &mut line);
+                            }
+                        }
+                        b'*' => group.skip = true,
+                        b'~' => push('\u{a0}', &group, &mut pending_skip, &mut line),
+                        b'_' => push('\u{2011}', &group, &mut pending_skip, &mut line),
+                        // Optional hyphen: only shown at a line break
+                        b'-' => {}
+                        b'\r' | b'\n' => {
+                            if !group.skip {
+                                lines.push(std::mem::take(&mut line));
+                            }
+                        }
+                        other => push(other as char, &group, &mut pending_skip, &mut line),
+                    }
+                }
+            }
+            byte => push(windows_1252(byte), &group, &mut pending_skip, &mut line),
+        }
+    }
+    lines.push(line);
+
+    lines
+        .into_iter()
+        .map(|line| line.split_ascii_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Add a character unless it is `\u` fallback or inside a skipped group
+fn push(c: char, group: &Group, pending_skip: &mut usize, line: &mut String) {
+    if *pending_skip > 0 {
+        *pending_skip -= 1;
+    } else if !group.skip {
+        line.push(c);
+    }
+}
+
+/// Drop the rest of the current group, counting what it held
+///
+/// Nothing is counted inside a group that was already being dropped; `\*`
+/// alone does not count, since it only marks the group as ignorable.
+fn skip_destination(
+    group: &mut Group,
+    parent: Option<&Group>,
+    report: &mut ImportReport,
+    kind: Option<Dropped>,
+) {
+    group.skip = true;
+    if parent.is_some_and(|p| p.skip) {
+        return;
+    }
+    if let Some(kind) = kind {
+        report.record(kind);
+    }
+}
+
+/// Decode a Windows-1252 byte; the C1 range differs from Latin-1
+pub(crate) fn windows_1252(byte: u8) -> char {
+    const C1: [char; 32] = [
+        '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}',
+        '\u{2021}', '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}',
+        '\u{8f}', '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}',
+        '\u{2014}', '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}',
+        '\u{178}',
+    ];
+    match byte {
+        0x80..=0x9f => C1[usize::from(byte - 0x80)],
+        byte => char::from(byte),
+    }
+}
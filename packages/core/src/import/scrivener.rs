@@ -0,0 +1,387 @@
+use crate::bk_format::{BkChapter, BkMetadata};
+use crate::import::archive::attr;
+use crate::import::error::ImportError;
+use crate::import::report::{Dropped, ImportReport};
+use crate::import::rtf::rtf_to_lines;
+use crate::import::{UNKNOWN_AUTHOR, UNTITLED};
+use crate::models::Book;
+use chrono::{DateTime, Utc};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use uuid::Uuid;
+
+/// Scene break placed between scenes of a chapter
+const SCENE_BREAK: &str = "* * *";
+
+/// Label id Scrivener uses for "No Label"
+const NO_LABEL: &str = "-1";
+
+/// Read a Scrivener project, given its `.scriv` folder or `.scrivx` file
+///
+/// Only the Draft folder is imported, in binder order. A folder becomes a
+/// chapter whose documents are its scenes, separated by scene breaks, and a
+/// document at the top of the Draft is a chapter of its own. Folders that hold
+/// other folders are parts: their folders become the chapters. Documents
+/// excluded from compile are skipped. Chapters take their dates from the
+/// binder; synopses, labels and notes have no place in a chapter and are
+/// counted in the report.
+pub fn import_scrivener(path: &Path) -> Result<(Book, ImportReport), ImportError> {
+    let scrivx = if path.is_dir() {
+        find_scrivx(path)?
+    } else {
+        path.to_path_buf()
+    };
+    let root = scrivx.parent().unwrap_or(Path::new("")).to_path_buf();
+    let project = Project::parse(&std::fs::read_to_string(&scrivx)?)?;
+    let draft = project
+        .binder
+        .iter()
+        .find(|item| item.kind == "DraftFolder")
+        .ok_or_else(|| ImportError::MissingPart("Draft folder".to_string()))?;
+
+    let mut importer = Importer {
+        root,
+        labels: &project.labels,
+        report: ImportReport::default(),
+        chapters: Vec::new(),
+    };
+    for item in &draft.children {
+        importer.add_top_level(item)?;
+    }
+    let Importer {
+        mut report,
+        chapters,
+        ..
+    } = importer;
+
+    let title = project
+        .title
+        .or_else(|| {
+            scrivx
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| UNTITLED.to_string());
+    let author = project.author.unwrap_or_else(|| {
+        report.warn("The project names no author");
+        UNKNOWN_AUTHOR.to_string()
+    });
+
+    let now = Utc::now();
+    let created_at = chapters.iter().map(|c| c.created_at).min().unwrap_or(now);
+    let updated_at = chapters.iter().map(|c| c.updated_at).max().unwrap_or(now);
+    let dates: Vec<(DateTime<Utc>, DateTime<Utc>)> = chapters
+        .iter()
+        .map(|c| (c.created_at, c.updated_at))
+        .collect();
+    let metadata = BkMetadata {
+        title: Some(title),
+        author: Some(author),
+        id: project.identifier,
+        ..BkMetadata::default()
+    };
+    let chapters = chapters
+        .into_iter()
+        .enumerate()
+        .map(|(order, c)| {
+            let mut chapter = BkChapter::new(c.title, order);
+            chapter.content = c.lines.join("\n");
+            chapter
+        })
+        .collect();
+
+    let mut book = metadata.into_book(chapters, created_at, updated_at)?;
+    for (chapter, (created, updated)) in book.chapters.iter_mut().zip(dates) {
+        chapter.created_at = created;
+        chapter.updated_at = updated;
+    }
+    Ok((book, report))
+}
+
+fn find_scrivx(dir: &Path) -> Result<PathBuf, ImportError> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "scrivx"))
+        .collect();
+    entries.sort();
+    entries
+        .into_iter()
+        .next()
+        .ok_or_else(|| ImportError::MissingPart("*.scrivx".to_string()))
+}
+
+/// Scrivener dates look like `2024-03-01 14:05:09 +0100`
+fn parse_date(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_str(text.trim(), "%Y-%m-%d %H:%M:%S %z")
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+/// A chapter being assembled from binder items
+struct Draft {
+    title: String,
+    lines: Vec<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+struct Importer<'a> {
+    root: PathBuf,
+    labels: &'a HashMap<String, String>,
+    report: ImportReport,
+    chapters: Vec<Draft>,
+}
+
+impl Importer<'_> {
+    fn add_top_level(&mut self, item: &BinderItem) -> Result<(), ImportError> {
+        if !self.included(item) {
+            return Ok(());
+        }
+        if item.is_folder() && item.children.iter().any(BinderItem::is_folder) {
+            // A part: its own text opens it as a chapter, its children follow
+            let lines = self.read_item(item)?;
+            if !lines.is_empty() {
+                self.chapters.push(Draft {
+                    title: item.title(),
+                    lines,
+                    created_at: item.created.unwrap_or_else(Utc::now),
+                    updated_at: item.modified.or(item.created).unwrap_or_else(Utc::now),
+                });
+            }
+            for child in &item.children {
+                self.add_top_level(child)?;
+            }
+            return Ok(());
+        }
+
+        let mut chapter = Draft {
+            title: item.title(),
+            lines: Vec::new(),
+            created_at: item.created.unwrap_or_else(Utc::now),
+            updated_at: item.modified.or(item.created).unwrap_or_else(Utc::now),
+        };
+        let mut scenes = vec![self.read_item(item)?];
+        for child in &item.children {
+            self.collect_scenes(child, &mut chapter, &mut scenes)?;
+        }
+        for scene in scenes.into_iter().filter(|s| !s.is_empty()) {
+            if !chapter.lines.is_empty() {
+                chapter.lines.push(SCENE_BREAK.to_string());
+            }
+            chapter.lines.extend(scene);
+        }
+        self.chapters.push(chapter);
+        Ok(())
+    }
+
+    /// Text of an item and its descendants, one scene per document
+    fn collect_scenes(
+        &mut self,
+        item: &BinderItem,
+        chapter: &mut Draft,
+        scenes: &mut Vec<Vec<String>>,
+    ) -> Result<(), ImportError> {
+        if !self.included(item) {
+            return Ok(());
+        }
+        if let Some(modified) = item.modified {
+            chapter.updated_at = chapter.updated_at.max(modified);
+        }
+        scenes.push(self.read_item(item)?);
+        for child in &item.children {
+            self.collect_scenes(child, chapter, scenes)?;
+        }
+        Ok(())
+    }
+
+    fn included(&mut self, item: &BinderItem) -> bool {
+        if !item.include_in_compile {
+            self.report.warn(format!(
+                "Left out '{}', which is excluded from compile",
+                item.title()
+            ));
+        }
+        item.include_in_compile
+    }
+
+    /// Paragraphs of an item's text, noting metadata that is not carried over
+    fn read_item(&mut self, item: &BinderItem) -> Result<Vec<String>, ImportError> {
+        let files = ItemFiles::locate(&self.root, &item.id)?;
+        if let Some(synopsis) = &files.synopsis {
+            if !std::fs::read_to_string(synopsis)?.trim().is_empty() {
+                self.report.record(Dropped::Synopsis);
+            }
+        }
+        if let Some(notes) = &files.notes {
+            let mut scratch = ImportReport::default();
+            if !rtf_to_lines(&std::fs::read(notes)?, &mut scratch).is_empty() {
+                self.report.record(Dropped::Comment);
+            }
+        }
+        if item
+            .label
+            .as_ref()
+            .is_some_and(|id| id != NO_LABEL && self.labels.contains_key(id))
+        {
+            self.report.record(Dropped::Label);
+        }
+        match &files.content {
+            Some(content) => Ok(rtf_to_lines(&std::fs::read(content)?, &mut self.report)),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Files that hold one binder item's text and metadata
+struct ItemFiles {
+    content: Option<PathBuf>,
+    synopsis: Option<PathBuf>,
+    notes: Option<PathBuf>,
+}
+
+impl ItemFiles {
+    /// Scrivener 3 keeps `Files/Data/<UUID>/content.rtf`; Scrivener 2 keeps
+    /// `Files/Docs/<ID>.rtf` with `<ID>_synopsis.txt` and `<ID>_notes.rtf`
+    ///
+    /// An id that is not a plain file name could reach outside the project,
+    /// so it is rejected.
+    fn locate(root: &Path, id: &str) -> Result<Self, ImportError> {
+        let mut components = Path::new(id).components();
+        let plain = matches!(components.next(), Some(Component::Normal(name)) if name == id)
+            && components.next().is_none()
+            && !id.contains(['/', '\\']);
+        if !plain {
+            return Err(ImportError::InvalidItemId(id.to_string()));
+        }
+        let existing = |path: PathBuf| path.is_file().then_some(path);
+        let data = root.join("Files").join("Data").join(id);
+        if data.is_dir() {
+            return Ok(Self {
+                content: existing(data.join("content.rtf")),
+                synopsis: existing(data.join("synopsis.txt")),
+                notes: existing(data.join("notes.rtf")),
+            });
+        }
+        let docs = root.join("Files").join("Docs");
+        Ok(Self {
+            content: existing(docs.join(format!("{}.rtf", id))),
+            synopsis: existing(docs.join(format!("{}_synopsis.txt", id))),
+            notes: existing(docs.join(format!("{}_notes.rtf", id))),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct BinderItem {
+    /// `UUID` in Scrivener 3, numeric `ID` in Scrivener 2
+    id: String,
+    kind: String,
+    title: String,
+    created: Option<DateTime<Utc>>,
+    modified: Option<DateTime<Utc>>,
+    label: Option<String>,
+    include_in_compile: bool,
+    children: Vec<BinderItem>,
+}
+
+impl BinderItem {
+    fn is_folder(&self) -> bool {
+        self.kind.ends_with("Folder")
+    }
+
+    fn title(&self) -> String {
+        if self.title.is_empty() {
+            UNTITLED.to_string()
+        } else {
+            self.title.clone()
+        }
+    }
+}
+
+/// What the importer needs from the `.scrivx` project file
+#[derive(Debug, Default)]
+struct Project {
+    identifier: Option<Uuid>,
+    title: Option<String>,
+    author: Option<String>,
+    binder: Vec<BinderItem>,
+    /// Label names by id
+    labels: HashMap<String, String>,
+}
+
+impl Project {
+    fn parse(xml: &str) -> Result<Self, ImportError> {
+        let mut reader = Reader::from_str(xml);
+        let mut project = Project::default();
+        // Binder items being read, innermost last
+        let mut open: Vec<BinderItem> = Vec::new();
+        let mut element = Vec::new();
+        let mut label_id = None;
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(e) => {
+                    element = e.local_name().as_ref().to_vec();
+                    match element.as_slice() {
+                        b"ScrivenerProject" => {
+                            project.identifier =
+                                attr(&e, b"Identifier").and_then(|id| Uuid::parse_str(&id).ok());
+                        }
+                        b"BinderItem" => open.push(BinderItem {
+                            id: attr(&e, b"UUID")
+                                .or_else(|| attr(&e, b"ID"))
+                                .unwrap_or_default(),
+                            kind: attr(&e, b"Type").unwrap_or_default(),
+                            title: String::new(),
+                            created: attr(&e, b"Created").as_deref().and_then(parse_date),
+                            modified: attr(&e, b"Modified").as_deref().and_then(parse_date),
+                            label: None,
+                            include_in_compile: true,
+                            children: Vec::new(),
+                        }),
+                        b"Label" => label_id = attr(&e, b"ID"),
+                        _ => {}
+                    }
+                }
+                Event::Text(t) => {
+                    let text = t.unescape()?.trim().to_string();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    match (element.as_slice(), open.last_mut()) {
+                        (b"Title", Some(item)) if item.title.is_empty() => item.title = text,
+                        (b"LabelID", Some(item)) => item.label = Some(text),
+                        (b"IncludeInCompile", Some(item)) => {
+                            item.include_in_compile = !text.eq_ignore_ascii_case("no")
+                        }
+                        (b"Label", _) => {
+                            if let Some(id) = label_id.take() {
+                                project.labels.insert(id, text);
+                            }
+                        }
+                        (b"ProjectTitle", _) => project.title = Some(text),
+                        (b"FullName", _) => project.author = Some(text),
+                        _ => {}
+                    }
+                }
+                Event::End(e) => {
+                    element.clear();
+                    if e.local_name().as_ref() == b"BinderItem" {
+                        if let Some(item) = open.pop() {
+                            match open.last_mut() {
+                                Some(parent) => parent.children.push(item),
+                                None => project.binder.push(item),
+                            }
+                        }
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(project)
+    }
+}
@@ -0,0 +1,180 @@
+use super::{sample_book, time};
+use crate::export::docx::{export_docx, ManuscriptOptions};
+use crate::import::docx::import_docx;
+use crate::import::{Dropped, ImportError};
+use std::io::{Cursor, Write};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+const W: &str = r#"xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main""#;
+
+/// Minimal package holding a document body, optional styles and core properties
+fn docx(body: &str, styles: Option<&str>, core: Option<&str>) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let mut part = |name: &str, content: String| {
+        zip.start_file(name, FileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    };
+    part(
+        "_rels/.rels",
+        r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#.to_string(),
+    );
+    part(
+        "word/document.xml",
+        format!("<w:document {}><w:body>{}</w:body></w:document>", W, body),
+    );
+    if let Some(styles) = styles {
+        part(
+            "word/_rels/document.xml.rels",
+            r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#.to_string(),
+        );
+        part(
+            "word/styles.xml",
+            format!("<w:styles {}>{}</w:styles>", W, styles),
+        );
+    }
+    if let Some(core) = core {
+        part(
+            "docProps/core.xml",
+            format!(
+                r#"<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/">{}</cp:coreProperties>"#,
+                core
+            ),
+        );
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+fn p(style: Option<&str>, text: &str) -> String {
+    let style = style
+        .map(|s| format!(r#"<w:pPr><w:pStyle w:val="{}"/></w:pPr>"#, s))
+        .unwrap_or_default();
+    format!(
+        r#"<w:p>{}<w:r><w:t xml:space="preserve">{}</w:t></w:r></w:p>"#,
+        style, text
+    )
+}
+
+#[test]
+fn test_import_exported_manuscript() {
+    let book = sample_book();
+    let bytes = export_docx(&book, &ManuscriptOptions::default()).unwrap();
+    let (imported, report) = import_docx(&bytes).unwrap();
+
+    assert_eq!(imported.title, "Salt & Iron");
+    assert_eq!(imported.author, "Tej <Chhabra>");
+    assert_eq!(imported.language.as_deref(), Some("en-GB"));
+    assert_eq!(imported.updated_at, time());
+    let titles: Vec<&str> = imported.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, ["The Beginning", "The Road"]);
+    assert_eq!(
+        imported.chapters[0].content,
+        "The morning sun cracked over the horizon.\n#\nMarcus stood at the forge & waited."
+    );
+    assert_eq!(
+        imported.chapters[1].content,
+        "The road stretched <endlessly> before him."
+    );
+    // The cover page comes before the first chapter
+    assert!(report.count(Dropped::FrontMatter) > 0);
+    assert!(report.warnings.is_empty());
+}
+
+#[test]
+fn test_headings_by_style_and_dropped_content() {
+    let styles = r#"
+<w:docDefaults><w:rPrDefault><w:rPr><w:lang w:val="nl-NL"/></w:rPr></w:rPrDefault></w:docDefaults>
+<w:style w:type="paragraph" w:styleId="Kop1"><w:name w:val="heading 1"/></w:style>
+<w:style w:type="paragraph" w:styleId="Hoofdstuk"><w:name w:val="Hoofdstuk"/><w:basedOn w:val="Kop1"/></w:style>
+<w:style w:type="paragraph" w:styleId="Inhopg1"><w:name w:val="toc 1"/></w:style>"#;
+    let body = [
+        p(Some("Inhopg1"), "Een\t3"),
+        p(Some("Kop1"), "Een"),
+        r#"<w:p><w:pPr><w:tabs><w:tab w:val="left" w:pos="720"/></w:tabs></w:pPr><w:r><w:t>Kept </w:t></w:r><w:ins w:id="1"><w:r><w:t>inserted</w:t></w:r></w:ins><w:del w:id="2"><w:r><w:delText> deleted</w:delText></w:r></w:del><w:r><w:commentReference w:id="0"/><w:footnoteReference w:id="1"/></w:r><w:r><w:t xml:space="preserve"> text.</w:t><w:br/><w:t>Second line.</w:t></w:r></w:p>"#.to_string(),
+        r#"<w:tbl><w:tr><w:tc><w:p><w:r><w:t>Cell</w:t></w:r></w:p></w:tc></w:tr></w:tbl>"#.to_string(),
+        r#"<w:p><w:r><w:drawing><w:p><w:r><w:t>Caption</w:t></w:r></w:p></w:drawing></w:r></w:p>"#.to_string(),
+        p(Some("Hoofdstuk"), "Twee"),
+        p(None, "Chapter 3 is just a sentence here."),
+    ]
+    .concat();
+    let core = "<dc:title>Boek</dc:title><dc:creator>Anna</dc:creator>";
+    let (book, report) = import_docx(&docx(&body, Some(styles), Some(core))).unwrap();
+
+    assert_eq!(book.language.as_deref(), Some("nl-NL"));
+    let titles: Vec<&str> = book.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, ["Een", "Twee"]);
+    assert_eq!(
+        book.chapters[0].content,
+        "Kept inserted text.\nSecond line."
+    );
+    assert_eq!(
+        book.chapters[1].content,
+        "Chapter 3 is just a sentence here."
+    );
+
+    assert_eq!(report.count(Dropped::TableOfContents), 1);
+    assert_eq!(report.count(Dropped::TrackedChange), 2);
+    assert_eq!(report.count(Dropped::Comment), 1);
+    assert_eq!(report.count(Dropped::Footnote), 1);
+    assert_eq!(report.count(Dropped::Table), 1);
+    assert_eq!(report.count(Dropped::Image), 1);
+    assert_eq!(report.count(Dropped::FrontMatter), 0);
+}
+
+#[test]
+fn test_chapter_patterns_without_headings() {
+    let body = [
+        p(Some("Title"), "The Long Way"),
+        p(None, "A novel"),
+        p(None, "Chapter One"),
+        p(None, "It began."),
+        p(None, "CHAPTER 2: The Middle"),
+        p(None, "Chapter and verse, she said."),
+        p(None, "Chapter XII."),
+        p(None, "Chapter twenty-one"),
+        p(None, "The end."),
+    ]
+    .concat();
+    let (book, report) = import_docx(&docx(&body, None, None)).unwrap();
+
+    assert_eq!(book.title, "The Long Way");
+    assert_eq!(book.author, "Unknown");
+    let titles: Vec<&str> = book.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(
+        titles,
+        [
+            "Chapter One",
+            "CHAPTER 2: The Middle",
+            "Chapter XII.",
+            "Chapter twenty-one"
+        ]
+    );
+    assert_eq!(book.chapters[1].content, "Chapter and verse, she said.");
+    assert_eq!(book.chapters[2].content, "");
+    assert_eq!(report.count(Dropped::FrontMatter), 2);
+    assert_eq!(report.warnings, ["The document names no author"]);
+}
+
+#[test]
+fn test_document_without_chapters_or_parts() {
+    let body = [p(None, "Just one"), p(None, "and two.")].concat();
+    let (book, report) = import_docx(&docx(&body, None, None)).unwrap();
+    assert_eq!(book.title, "Untitled");
+    assert_eq!(book.chapters.len(), 1);
+    assert_eq!(book.chapters[0].content, "Just one\nand two.");
+    assert_eq!(report.warnings.len(), 3);
+
+    assert!(matches!(
+        import_docx(b"not a zip"),
+        Err(ImportError::Archive(_))
+    ));
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("readme.txt", FileOptions::default())
+        .unwrap();
+    let bytes = zip.finish().unwrap().into_inner();
+    assert!(matches!(
+        import_docx(&bytes),
+        Err(ImportError::MissingPart(part)) if part == "word/document.xml"
+    ));
+}
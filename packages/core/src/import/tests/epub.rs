@@ -0,0 +1,195 @@
+use super::{sample_book, time};
+use crate::export::epub::export_epub;
+use crate::import::epub::import_epub;
+use crate::import::{Dropped, ImportError};
+use chrono::{TimeZone, Utc};
+use std::io::{Cursor, Write};
+use uuid::Uuid;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+fn epub(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in files {
+        zip.start_file(*name, FileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+const CONTAINER: &str = r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#;
+
+fn xhtml(body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head><title>Ignored</title><style>p {{ margin: 0 }}</style></head>
+<body>{}</body>
+</html>"#,
+        body
+    )
+}
+
+#[test]
+fn test_import_exported_epub() {
+    let book = sample_book();
+    let (imported, report) = import_epub(&export_epub(&book).unwrap()).unwrap();
+
+    assert_eq!(imported.id, book.id);
+    assert_eq!(imported.title, "Salt & Iron");
+    assert_eq!(imported.author, "Tej <Chhabra>");
+    assert_eq!(imported.language.as_deref(), Some("en-GB"));
+    assert_eq!(imported.dedication.as_deref(), Some("For \"the dreamers\""));
+    assert_eq!(imported.updated_at, time());
+    for (a, b) in imported.chapters.iter().zip(&book.chapters) {
+        assert_eq!(a.id, b.id);
+        assert_eq!(a.title, b.title);
+        // A chapter starts on a new page anyway, so its opening break is not kept
+        assert_eq!(a.content, b.content.trim_start_matches("@page:\n"));
+    }
+    assert_eq!(imported.chapters.len(), 2);
+
+    // Title and author on the title page
+    assert_eq!(report.count(Dropped::FrontMatter), 2);
+    assert_eq!(report.dropped.len(), 1);
+    assert!(report.warnings.is_empty());
+}
+
+#[test]
+fn test_import_epub2_with_ncx() {
+    let opf = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="2.0" unique-identifier="isbn">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="uuid">urn:uuid:00000000-0000-0000-0000-000000000000</dc:identifier>
+    <dc:identifier id="isbn">978-3-16-148410-0</dc:identifier>
+    <dc:title>Old &amp; New</dc:title>
+    <dc:creator>Ana Lima</dc:creator>
+    <dc:creator>Ben Ode</dc:creator>
+    <dc:language>pt</dc:language>
+    <dc:date>2019-05-01</dc:date>
+  </metadata>
+  <manifest>
+    <item id="ncx" href="toc.ncx" media-type="application/x-dtbncx+xml"/>
+    <item id="cover" href="Text/cover.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c1" href="Text/Chapter%201.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c1b" href="Text/ch1b.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c2" href="Text/ch2.xhtml" media-type="application/xhtml+xml"/>
+    <item id="art" href="Images/art.jpg" media-type="image/jpeg"/>
+  </manifest>
+  <spine toc="ncx">
+    <itemref idref="cover"/>
+    <itemref idref="c1"/>
+    <itemref idref="c1b"/>
+    <itemref idref="gone"/>
+    <itemref idref="art"/>
+    <itemref idref="c2"/>
+  </spine>
+</package>"#;
+    let ncx = r#"<?xml version="1.0"?>
+<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/" version="2005-1">
+  <navMap>
+    <navPoint id="n1"><navLabel><text>One:  The Start</text></navLabel><content src="Text/Chapter%201.xhtml#top"/></navPoint>
+    <navPoint id="n2"><navLabel><text>Two</text></navLabel><content src="Text/ch2.xhtml"/></navPoint>
+  </navMap>
+</ncx>"#;
+    let cover = xhtml(r#"<div><img src="../Images/art.jpg" alt="Cover"/></div>"#);
+    let chapter_one = xhtml(
+        "<h1 id=\"top\">One: The Start</h1>\n<p>First\n   paragraph.</p><p>Second&nbsp;one&mdash;here.</p>",
+    );
+    let chapter_one_b = xhtml("<p>Continued after a file split.</p>");
+    let chapter_two = xhtml(
+        r##"<h2>Chapter Two</h2>
+<p>Before<br/>after<a epub:type="noteref" href="#n1">1</a>.</p>
+<hr/>
+<table><tr><td>Cell</td></tr></table>
+<blockquote><p>Quoted <em>text</em>.</p></blockquote>
+<aside epub:type="footnote" id="n1"><p>A note.</p></aside>"##,
+    );
+    let bytes = epub(&[
+        ("mimetype", "application/epub+zip"),
+        ("META-INF/container.xml", CONTAINER),
+        ("OEBPS/content.opf", opf),
+        ("OEBPS/toc.ncx", ncx),
+        ("OEBPS/Text/cover.xhtml", &cover),
+        ("OEBPS/Text/Chapter 1.xhtml", &chapter_one),
+        ("OEBPS/Text/ch1b.xhtml", &chapter_one_b),
+        ("OEBPS/Text/ch2.xhtml", &chapter_two),
+    ]);
+    let (book, report) = import_epub(&bytes).unwrap();
+
+    assert_eq!(book.title, "Old & New");
+    assert_eq!(book.author, "Ana Lima & Ben Ode");
+    assert_eq!(book.language.as_deref(), Some("pt"));
+    assert_eq!(
+        book.id,
+        Uuid::new_v5(&Uuid::NAMESPACE_URL, b"978-3-16-148410-0")
+    );
+    assert_eq!(book.id, import_epub(&bytes).unwrap().0.id);
+    assert_eq!(
+        book.created_at,
+        Utc.with_ymd_and_hms(2019, 5, 1, 0, 0, 0).unwrap()
+    );
+
+    let titles: Vec<&str> = book.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, ["One: The Start", "Two"]);
+    assert_eq!(
+        book.chapters[0].content,
+        "First paragraph.\nSecond\u{a0}one\u{2014}here.\nContinued after a file split."
+    );
+    assert_eq!(
+        book.chapters[1].content,
+        "Chapter Two\nBefore\nafter.\n* * *\nQuoted text."
+    );
+
+    assert_eq!(report.count(Dropped::Image), 1);
+    assert_eq!(report.count(Dropped::Table), 1);
+    assert_eq!(report.count(Dropped::Footnote), 2);
+    assert_eq!(report.warnings.len(), 2);
+}
+
+#[test]
+fn test_import_epub_without_toc() {
+    let opf = r#"<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>Loose</dc:title></metadata>
+  <manifest>
+    <item id="a" href="a.xhtml" media-type="application/xhtml+xml"/>
+    <item id="b" href="b.xhtml" media-type="application/xhtml+xml"/>
+  </manifest>
+  <spine><itemref idref="a"/><itemref idref="b"/></spine>
+</package>"#;
+    let a = xhtml("<p>Opening words.</p>");
+    let b = xhtml("<section><h1>Named</h1><p>Body.</p></section>");
+    let bytes = epub(&[
+        ("META-INF/container.xml", CONTAINER),
+        ("OEBPS/content.opf", opf),
+        ("OEBPS/a.xhtml", &a),
+        ("OEBPS/b.xhtml", &b),
+    ]);
+    let (book, report) = import_epub(&bytes).unwrap();
+
+    let titles: Vec<&str> = book.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, ["Untitled", "Named"]);
+    assert_eq!(book.chapters[0].content, "Opening words.");
+    assert_eq!(book.chapters[1].content, "Body.");
+    assert_eq!(book.author, "Unknown");
+    assert_eq!(report.warnings, ["The publication names no author"]);
+}
+
+#[test]
+fn test_import_epub_missing_parts() {
+    let bytes = epub(&[("mimetype", "application/epub+zip")]);
+    assert!(matches!(
+        import_epub(&bytes),
+        Err(ImportError::MissingPart(part)) if part == "META-INF/container.xml"
+    ));
+
+    let bytes = epub(&[("META-INF/container.xml", CONTAINER)]);
+    assert!(matches!(
+        import_epub(&bytes),
+        Err(ImportError::MissingPart(part)) if part == "OEBPS/content.opf"
+    ));
+}
@@ -0,0 +1,185 @@
+use crate::bk_format::{BkParseError, BkParser};
+use crate::export::markdown::export_markdown;
+use crate::import::markdown::{import_markdown, import_markdown_file};
+use crate::import::ImportError;
+use crate::models::{Book, BookFormat};
+
+use super::time;
+
+fn import(content: &str) -> Result<Book, ImportError> {
+    import_markdown(content, time(), time()).map(|(book, _)| book)
+}
+
+/// Book whose lines look like Markdown syntax
+fn tricky_book() -> Book {
+    let content = r#"
+@title: *Salt* & [Iron]
+@author: Tej <Chhabra>
+@id: 550e8400-e29b-41d4-a009-426655440000
+@dedication: For "the dreamers": yes
+@language: en-GB
+@target_words: 90000
+
+#chapter: Part #1 #
+@page:
+# not a heading
+1. not a list
+2) nor this
+- dash, + plus, > quote
+<b>tags</b> stay &amp; literal &#169;
+a * b _c_ `d` \e
+* * *
+#
+~~~
+---
+Plain prose & more.
+
+#chapter: 1999. Ending
+Last line.
+"#;
+    BkParser::parse_string(content, time(), time()).unwrap()
+}
+
+fn assert_same_book(a: &Book, b: &Book) {
+    assert_eq!(a.id, b.id);
+    assert_eq!(a.title, b.title);
+    assert_eq!(a.author, b.author);
+    assert_eq!(a.dedication, b.dedication);
+    assert_eq!(a.language, b.language);
+    assert_eq!(a.target_words, b.target_words);
+    assert_eq!(a.chapters.len(), b.chapters.len());
+    for (x, y) in a.chapters.iter().zip(&b.chapters) {
+        assert_eq!(x.id, y.id);
+        assert_eq!(x.title, y.title);
+        assert_eq!(x.content, y.content);
+        assert_eq!(x.order, y.order);
+    }
+}
+
+#[test]
+fn test_book_round_trips_through_markdown() {
+    let book = tricky_book();
+    let imported = import(&export_markdown(&book)).unwrap();
+    assert_same_book(&book, &imported);
+}
+
+#[test]
+fn test_markdown_round_trips_through_book() {
+    let markdown = export_markdown(&tricky_book());
+    let again = export_markdown(&import(&markdown).unwrap());
+    assert_eq!(markdown, again);
+}
+
+#[test]
+fn test_screenplay_round_trips_through_markdown() {
+    let content = "@title: Salt\n@author: Tej\n@format: screenplay\n\n\
+                   #chapter: Act One\n\
+                   EXT. SHORE - DAWN\n\n\
+                   MARCUS\n(quietly)\nStill here.\n\n\
+                   # Not a heading\n\
+                   ```\n\n\
+                   >THE END<";
+    let book = BkParser::parse_string(content, time(), time()).unwrap();
+    let markdown = export_markdown(&book);
+    assert!(markdown.contains("\n````fountain\nEXT. SHORE - DAWN\n\nMARCUS\n"));
+
+    let imported = import(&markdown).unwrap();
+    assert_eq!(imported.format, BookFormat::Screenplay);
+    assert_same_book(&book, &imported);
+    assert_eq!(export_markdown(&imported), markdown);
+}
+
+#[test]
+fn test_import_foreign_markdown() {
+    let content = r#"---
+title: Drafted Elsewhere
+author: Ana Lima
+tags: [draft, novel]
+---
+
+Preamble before any heading.
+
+# Part One
+
+Some *emphasis*, **strong** and `code`
+wrapped onto a second line.
+
+- first item
+- second item
+
+Roses are red,\
+violets are blue.
+
+---
+
+## Chapter Two
+
+### A subheading
+
+> Quoted text.
+
+<!-- a comment -->
+"#;
+    let book = import(content).unwrap();
+    assert_eq!(book.title, "Drafted Elsewhere");
+    assert_eq!(book.author, "Ana Lima");
+    assert_eq!(book.language, None);
+
+    let titles: Vec<&str> = book.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, ["Untitled", "Part One", "Chapter Two"]);
+    assert_eq!(book.chapters[0].content, "Preamble before any heading.");
+    assert_eq!(
+        book.chapters[1].content,
+        "Some emphasis, strong and code wrapped onto a second line.\n\
+         first item\n\
+         second item\n\
+         Roses are red,\n\
+         violets are blue.\n\
+         * * *"
+    );
+    assert_eq!(book.chapters[2].content, "A subheading\nQuoted text.");
+}
+
+#[test]
+fn test_import_without_frontmatter() {
+    let (book, report) =
+        import_markdown("Preamble.\n\n# Salt Road\n\nText.\n", time(), time()).unwrap();
+    assert_eq!(book.title, "Salt Road");
+    assert_eq!(book.author, "Unknown");
+    assert_eq!(book.chapters.len(), 2);
+    assert_eq!(
+        report.warnings,
+        [
+            "The frontmatter has no title; using the first heading",
+            "The document names no author"
+        ]
+    );
+
+    // Without a heading the file name is the title
+    let dir = std::env::temp_dir().join(format!("bk-markdown-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("Salt Road.md");
+    std::fs::write(&path, "---\nauthor: Ana Lima\n---\n\nJust text.\n").unwrap();
+    let imported = import_markdown_file(&path);
+    std::fs::remove_dir_all(&dir).unwrap();
+    let (book, report) = imported.unwrap();
+    assert_eq!(book.title, "Salt Road");
+    assert_eq!(book.author, "Ana Lima");
+    assert_eq!(book.chapters[0].title, "Untitled");
+    assert_eq!(
+        report.warnings,
+        ["The frontmatter has no title; using the file name"]
+    );
+
+    let (book, _) = import_markdown("Just text.\n", time(), time()).unwrap();
+    assert_eq!(book.title, "Untitled");
+}
+
+#[test]
+fn test_import_rejects_bad_frontmatter() {
+    let err = import("---\ntitle: [unclosed\n---\n\n# One\n").unwrap_err();
+    assert!(matches!(err, ImportError::Frontmatter(_)));
+
+    let err = import("---\ntitle: Empty\nauthor: Tej\n---\n").unwrap_err();
+    assert!(matches!(err, ImportError::Book(BkParseError::NoChapters)));
+}
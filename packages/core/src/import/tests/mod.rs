@@ -0,0 +1,13 @@
+mod docx;
+mod epub;
+mod markdown;
+mod pandoc;
+mod scrivener;
+mod text;
+
+use crate::export::tests::sample_book;
+use chrono::{DateTime, TimeZone, Utc};
+
+fn time() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 12, 1, 9, 30, 0).unwrap()
+}
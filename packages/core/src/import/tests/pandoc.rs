@@ -0,0 +1,89 @@
+use super::{sample_book, time};
+use crate::export::pandoc::export_pandoc_json;
+use crate::import::pandoc::import_pandoc_json;
+use crate::import::{Dropped, ImportError};
+use uuid::Uuid;
+
+/// `pandoc -t json` output for a short Markdown manuscript
+const MARKDOWN_AST: &str = include_str!("fixtures/pandoc-markdown.json");
+
+#[test]
+fn test_import_pandoc_json() {
+    let (book, report) = import_pandoc_json(MARKDOWN_AST, time(), time()).unwrap();
+
+    assert_eq!(book.title, "The Iron Road");
+    assert_eq!(book.author, "Tej Chhabra & Ana Silva");
+    assert_eq!(book.language.as_deref(), Some("en-GB"));
+    assert_eq!(book.dedication.as_deref(), Some("For my father"));
+    assert_eq!(
+        book.id,
+        Uuid::parse_str("6f1c2f9e-8f6b-4c47-9d3a-0e5b8f6f7a10").unwrap()
+    );
+
+    assert_eq!(book.chapters.len(), 2);
+    assert_eq!(book.chapters[0].title, "The Forge");
+    assert_eq!(
+        book.chapters[0].content,
+        "The morning sun cracked over the horizon.\n\
+         \u{201c}Again,\u{201d} said his father.\n\
+         He did not look up.\n\
+         * * *\n\
+         A quoted line with code and a link.\n\
+         A subheading\n\
+         Tongs\n\
+         Hammer,"
+    );
+    assert_eq!(book.chapters[1].title, "The Road");
+    assert_eq!(
+        book.chapters[1].content,
+        "\u{2018}Onward\u{2019}.\nThe road stretched on."
+    );
+
+    assert_eq!(report.count(Dropped::Footnote), 1);
+    assert_eq!(report.count(Dropped::Equation), 1);
+    assert_eq!(report.count(Dropped::Table), 1);
+    assert_eq!(report.count(Dropped::Image), 1);
+    assert_eq!(report.dropped.len(), 4);
+    assert!(report.warnings.is_empty());
+}
+
+#[test]
+fn test_import_exported_pandoc_json() {
+    let book = sample_book();
+    let (imported, report) =
+        import_pandoc_json(&export_pandoc_json(&book), time(), time()).unwrap();
+
+    assert_eq!(imported.id, book.id);
+    assert_eq!(imported.title, "Salt & Iron");
+    assert_eq!(imported.author, "Tej <Chhabra>");
+    assert_eq!(imported.language.as_deref(), Some("en-GB"));
+    assert_eq!(imported.dedication.as_deref(), Some("For \"the dreamers\""));
+    assert_eq!(imported.chapters.len(), book.chapters.len());
+    for (a, b) in imported.chapters.iter().zip(&book.chapters) {
+        assert_eq!(a.title, b.title);
+        // A chapter starts on a new page anyway, so its opening break is not kept
+        assert_eq!(a.content, b.content.trim_start_matches("@page:\n"));
+    }
+    assert!(report.is_clean());
+}
+
+#[test]
+fn test_import_pandoc_json_errors() {
+    assert!(matches!(
+        import_pandoc_json(r#"{"pandoc-api-version":[1,23,1],"meta":{}}"#, time(), time()),
+        Err(ImportError::MissingPart(part)) if part == "blocks"
+    ));
+    assert!(matches!(
+        import_pandoc_json("{\"blocks\": [", time(), time()),
+        Err(ImportError::Json(_))
+    ));
+
+    assert!(import_pandoc_json(r#"{"meta":{},"blocks":[]}"#, time(), time()).is_err());
+
+    // Text before any heading goes into an untitled chapter
+    let json = r#"{"meta":{},"blocks":[{"t":"Para","c":[{"t":"Str","c":"Hello."}]}]}"#;
+    let (book, report) = import_pandoc_json(json, time(), time()).unwrap();
+    assert_eq!(book.chapters.len(), 1);
+    assert_eq!(book.chapters[0].content, "Hello.");
+    assert_eq!(report.warnings.len(), 2);
+}
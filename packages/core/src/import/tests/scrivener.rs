@@ -0,0 +1,308 @@
+use super::time;
+use crate::bk_format::{BkParser, BkWriter};
+use crate::import::rtf::rtf_to_lines;
+use crate::import::scrivener::import_scrivener;
+use crate::import::{Dropped, ImportError, ImportReport};
+use chrono::{TimeZone, Utc};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!("bk-scrivener-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn write(path: PathBuf, contents: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+
+fn rtf(body: &str) -> String {
+    format!(
+        r"{{\rtf1\ansi\ansicpg1252\cocoartf2639
+{{\fonttbl\f0\froman\fcharset0 Palatino-Roman;}}
+{{\colortbl;\red255\green255\blue255;}}
+{{\*\expandedcolortbl;;}}
+\pard\tx560\pardirnatural\partightenfactor0
+\f0\fs24 \cf0 {}}}",
+        body
+    )
+}
+
+const BOOK_ID: &str = "6f1c2f9e-8f6b-4c47-9d3a-0e5b8f6f7a10";
+
+/// A Scrivener 3 project: a part holding two chapter folders, then a loose
+/// document, with research and trash that must not be imported
+fn scrivener3(dir: &Path) -> PathBuf {
+    let scriv = dir.join("Iron.scriv");
+    write(
+        scriv.join("Iron.scrivx"),
+        &format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<ScrivenerProject Template="NO" Version="2.0" Identifier="{}" Creator="SCRMAC-3.2.3">
+  <Binder>
+    <BinderItem UUID="DRAFT" Type="DraftFolder" Created="2024-01-01 10:00:00 +0000" Modified="2024-01-01 10:00:00 +0000">
+      <Title>Manuscript</Title>
+      <Children>
+        <BinderItem UUID="PART" Type="Folder" Created="2024-01-02 10:00:00 +0000" Modified="2024-01-02 10:00:00 +0000">
+          <Title>Part One</Title>
+          <Children>
+            <BinderItem UUID="CH1" Type="Folder" Created="2024-01-03 10:00:00 +0100" Modified="2024-02-01 12:00:00 +0100">
+              <Title>The Forge</Title>
+              <MetaData><LabelID>1</LabelID><IncludeInCompile>Yes</IncludeInCompile></MetaData>
+              <Children>
+                <BinderItem UUID="S1" Type="Text" Created="2024-01-03 11:00:00 +0000" Modified="2024-03-05 09:15:00 +0000">
+                  <Title>Dawn</Title>
+                  <MetaData><LabelID>2</LabelID></MetaData>
+                </BinderItem>
+                <BinderItem UUID="S2" Type="Text" Created="2024-01-04 11:00:00 +0000" Modified="2024-01-04 11:00:00 +0000">
+                  <Title>Cut scene</Title>
+                  <MetaData><IncludeInCompile>No</IncludeInCompile></MetaData>
+                </BinderItem>
+                <BinderItem UUID="S3" Type="Text" Created="2024-01-05 11:00:00 +0000" Modified="2024-01-05 11:00:00 +0000">
+                  <Title>Noon</Title>
+                </BinderItem>
+              </Children>
+            </BinderItem>
+            <BinderItem UUID="CH2" Type="Folder" Created="2024-01-06 10:00:00 +0000" Modified="2024-01-06 10:00:00 +0000">
+              <Title>The Road</Title>
+              <Children>
+                <BinderItem UUID="S4" Type="Text" Created="2024-01-06 11:00:00 +0000" Modified="2024-01-06 11:00:00 +0000">
+                  <Title>Departure</Title>
+                </BinderItem>
+              </Children>
+            </BinderItem>
+          </Children>
+        </BinderItem>
+        <BinderItem UUID="CH3" Type="Text" Created="2024-01-07 10:00:00 +0000" Modified="2024-01-08 10:00:00 +0000">
+          <Title>Epilogue</Title>
+        </BinderItem>
+      </Children>
+    </BinderItem>
+    <BinderItem UUID="RESEARCH" Type="ResearchFolder">
+      <Title>Research</Title>
+      <Children>
+        <BinderItem UUID="R1" Type="Text"><Title>Swords</Title></BinderItem>
+      </Children>
+    </BinderItem>
+    <BinderItem UUID="TRASH" Type="TrashFolder"><Title>Trash</Title></BinderItem>
+  </Binder>
+  <LabelSettings>
+    <Title>Label</Title>
+    <Labels>
+      <Label ID="-1">No Label</Label>
+      <Label ID="1" Color="0.5 0.5 1.0">Chapter</Label>
+      <Label ID="2" Color="1.0 0.5 0.5">Scene</Label>
+    </Labels>
+  </LabelSettings>
+  <ProjectProperties>
+    <ProjectTitle>The Way of Iron</ProjectTitle>
+    <FullName>Tej Chhabra</FullName>
+  </ProjectProperties>
+</ScrivenerProject>"#,
+            BOOK_ID
+        ),
+    );
+    let data = scriv.join("Files").join("Data");
+    write(
+        data.join("S1").join("content.rtf"),
+        &rtf(r"The morning sun cracked over the horizon\'97again.\
+\
+Marcus stood at the forge {\*\annotation a note} \ldblquote waiting\rdblquote .\
+{{{{\NeXTGraphic sword.png \width640 \height480}}}}"),
+    );
+    write(data.join("S1").join("synopsis.txt"), "Marcus wakes.");
+    write(
+        data.join("S1").join("notes.rtf"),
+        &rtf("Check the forge details."),
+    );
+    write(
+        data.join("S2").join("content.rtf"),
+        &rtf("Nobody reads this."),
+    );
+    write(
+        data.join("S3").join("content.rtf"),
+        &rtf(r"Caf\u233 ? Na\'efve.\
+"),
+    );
+    write(
+        data.join("S4").join("content.rtf"),
+        &rtf(r"The road stretched on."),
+    );
+    write(data.join("CH3").join("content.rtf"), &rtf(r"It ended."));
+    write(
+        data.join("R1").join("content.rtf"),
+        &rtf(r"Research notes."),
+    );
+    scriv
+}
+
+#[test]
+fn test_import_scrivener3_project() {
+    let dir = TempDir::new();
+    let scriv = scrivener3(dir.path());
+    let (book, report) = import_scrivener(&scriv).unwrap();
+
+    assert_eq!(book.title, "The Way of Iron");
+    assert_eq!(book.author, "Tej Chhabra");
+    assert_eq!(book.id, Uuid::parse_str(BOOK_ID).unwrap());
+
+    let titles: Vec<&str> = book.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, ["The Forge", "The Road", "Epilogue"]);
+    assert_eq!(
+        book.chapters[0].content,
+        "The morning sun cracked over the horizon\u{2014}again.\n\
+         Marcus stood at the forge \u{201c}waiting\u{201d}.\n\
+         * * *\n\
+         Café Naïve."
+    );
+    assert_eq!(book.chapters[1].content, "The road stretched on.");
+    assert_eq!(book.chapters[2].content, "It ended.");
+
+    // Dates come from the binder; a chapter is as new as its newest scene
+    let chapter = &book.chapters[0];
+    assert_eq!(
+        chapter.created_at,
+        Utc.with_ymd_and_hms(2024, 1, 3, 9, 0, 0).unwrap()
+    );
+    assert_eq!(
+        chapter.updated_at,
+        Utc.with_ymd_and_hms(2024, 3, 5, 9, 15, 0).unwrap()
+    );
+
+    assert_eq!(report.count(Dropped::Synopsis), 1);
+    assert_eq!(report.count(Dropped::Label), 2);
+    // The annotation and the document notes
+    assert_eq!(report.count(Dropped::Comment), 2);
+    assert_eq!(report.count(Dropped::Image), 1);
+    assert!(report.warnings.iter().any(|w| w.contains("Cut scene")));
+
+    // The scrivx file works as well as its folder
+    let (again, _) = import_scrivener(&scriv.join("Iron.scrivx")).unwrap();
+    assert_eq!(again.chapters.len(), 3);
+}
+
+#[test]
+fn test_imported_scrivener_saves_as_bk() {
+    let dir = TempDir::new();
+    let (book, _) = import_scrivener(&scrivener3(dir.path())).unwrap();
+
+    let reparsed = BkParser::parse_string(&BkWriter::write_string(&book), time(), time()).unwrap();
+    assert_eq!(reparsed.title, book.title);
+    assert_eq!(reparsed.author, book.author);
+    assert_eq!(reparsed.id, book.id);
+    assert_eq!(reparsed.chapters.len(), book.chapters.len());
+    for (a, b) in reparsed.chapters.iter().zip(&book.chapters) {
+        assert_eq!(a.title, b.title);
+        assert_eq!(a.content, b.content);
+    }
+}
+
+#[test]
+fn test_import_scrivener2_project() {
+    let dir = TempDir::new();
+    let scriv = dir.path().join("Old.scriv");
+    write(
+        scriv.join("Old.scrivx"),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ScrivenerProject Version="1.0">
+  <Binder>
+    <BinderItem ID="0" Type="DraftFolder">
+      <Title>Draft</Title>
+      <Children>
+        <BinderItem ID="3" Type="Text"><Title>Opening</Title></BinderItem>
+        <BinderItem ID="4" Type="Text">
+          <Title>Middle</Title>
+          <Children><BinderItem ID="5" Type="Text"><Title>Aside</Title></BinderItem></Children>
+        </BinderItem>
+      </Children>
+    </BinderItem>
+  </Binder>
+</ScrivenerProject>"#,
+    );
+    let docs = scriv.join("Files").join("Docs");
+    write(docs.join("3.rtf"), &rtf(r"First line.\par Second line."));
+    write(docs.join("3_synopsis.txt"), "   ");
+    write(docs.join("4.rtf"), &rtf(r"Middle text."));
+    write(docs.join("5.rtf"), &rtf(r"Aside text."));
+
+    let (book, report) = import_scrivener(&scriv).unwrap();
+    // No project title: the file name stands in
+    assert_eq!(book.title, "Old");
+    assert_eq!(book.author, "Unknown");
+    assert_eq!(book.chapters[0].content, "First line.\nSecond line.");
+    assert_eq!(book.chapters[1].content, "Middle text.\n* * *\nAside text.");
+    assert_eq!(report.count(Dropped::Synopsis), 0);
+    assert!(!report.warnings.is_empty());
+}
+
+#[test]
+fn test_import_scrivener_without_draft() {
+    let dir = TempDir::new();
+    write(
+        dir.path().join("Empty.scrivx"),
+        r#"<ScrivenerProject><Binder></Binder></ScrivenerProject>"#,
+    );
+    assert!(matches!(
+        import_scrivener(dir.path()),
+        Err(ImportError::MissingPart(_))
+    ));
+}
+
+#[test]
+fn test_import_scrivener_rejects_unsafe_item_ids() {
+    for id in ["../../secret", "/etc/passwd", "a/b", "a\\b", ".."] {
+        let dir = TempDir::new();
+        let scriv = dir.path().join("Bad.scriv");
+        write(
+            scriv.join("Bad.scrivx"),
+            &format!(
+                r#"<ScrivenerProject><Binder>
+  <BinderItem ID="0" Type="DraftFolder">
+    <Title>Draft</Title>
+    <Children><BinderItem ID="{}" Type="Text"><Title>Escape</Title></BinderItem></Children>
+  </BinderItem>
+</Binder></ScrivenerProject>"#,
+                id
+            ),
+        );
+        assert!(
+            matches!(
+                import_scrivener(&scriv),
+                Err(ImportError::InvalidItemId(bad)) if bad == id
+            ),
+            "{}",
+            id
+        );
+    }
+}
+
+#[test]
+fn test_rtf_to_lines() {
+    let mut report = ImportReport::default();
+    let lines = rtf_to_lines(
+        br"{\rtf1{\info{\title Hidden}}\uc1 Smile \u-10179?\u-8704? ok\line next\tab cell{\footnote gone}\par\par {\pict\pngblip 89504e47}End \{braces\}\~nbsp}",
+        &mut report,
+    );
+    assert_eq!(
+        lines,
+        ["Smile \u{1f600} ok", "next cell", "End {braces}\u{a0}nbsp"]
+    );
+    assert_eq!(report.count(Dropped::Footnote), 1);
+    assert_eq!(report.count(Dropped::Image), 1);
+}
@@ -0,0 +1,202 @@
+use super::time;
+use crate::import::text::{import_text, HeadingKind};
+use crate::import::Dropped;
+
+const GUTENBERG: &str = "\u{feff}The Project Gutenberg eBook of The Iron Road
+
+This ebook is for the use of anyone anywhere in the United States and
+most other parts of the world at no cost and with almost no restrictions
+whatsoever.
+
+Title: The Iron Road
+       A Tale of the Forge
+
+Author: Tej Chhabra
+
+Release date: March 1, 2024 [eBook #99999]
+
+Language: English
+
+*** START OF THE PROJECT GUTENBERG EBOOK THE IRON ROAD ***
+
+Produced by Volunteer Transcribers
+
+
+                             THE IRON ROAD
+
+                            By Tej Chhabra
+
+
+CONTENTS
+
+ CHAPTER I.     The Forge
+ CHAPTER II.    The Well-Known Road
+
+
+CHAPTER I.
+
+The Forge
+
+
+The morning sun cracked over the horizon and lit the yard, where Marcus
+had already been at work for an hour, his hammer ringing against the
+anvil in a rhythm that every smith in the valley knew as the well-
+known beat of the Chhabra forge.
+
+\"Again,\" said his father.
+
+                  *       *       *       *       *
+
+    The iron sings,
+    The iron weeps,
+    The forge remembers
+    What it keeps.
+
+
+CHAPTER II.
+
+The Well-Known Road
+
+
+The road stretched on before him, longer than any road he had walked
+before, and he did not look back.
+
+*** END OF THE PROJECT GUTENBERG EBOOK THE IRON ROAD ***
+
+Section 1. General Terms of Use and Redistributing Project Gutenberg-tm
+electronic works
+";
+
+#[test]
+fn test_import_gutenberg_text() {
+    let (book, report) = import_text(GUTENBERG, time(), time()).unwrap();
+
+    assert_eq!(book.title, "The Iron Road A Tale of the Forge");
+    assert_eq!(book.author, "Tej Chhabra");
+    assert_eq!(book.language.as_deref(), Some("en"));
+
+    let titles: Vec<&str> = book.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(
+        titles,
+        ["CHAPTER I: The Forge", "CHAPTER II: The Well-Known Road"]
+    );
+    assert_eq!(
+        book.chapters[0].content,
+        "The morning sun cracked over the horizon and lit the yard, where Marcus \
+         had already been at work for an hour, his hammer ringing against the \
+         anvil in a rhythm that every smith in the valley knew as the well-known \
+         beat of the Chhabra forge.\n\
+         \"Again,\" said his father.\n\
+         * * *\n\
+         The iron sings,\n\
+         The iron weeps,\n\
+         The forge remembers\n\
+         What it keeps."
+    );
+    assert_eq!(
+        book.chapters[1].content,
+        "The road stretched on before him, longer than any road he had walked \
+         before, and he did not look back."
+    );
+
+    // Header, footer and transcriber credit
+    assert_eq!(report.report.count(Dropped::Boilerplate), 3);
+    assert_eq!(report.report.count(Dropped::TableOfContents), 2);
+    // Title and byline
+    assert_eq!(report.report.count(Dropped::FrontMatter), 2);
+    assert_eq!(report.unwrapped_paragraphs, 2);
+
+    assert_eq!(report.headings.len(), 2);
+    assert_eq!(report.headings[0].kind, HeadingKind::Chapter);
+    assert_eq!(report.headings[0].number, Some(1));
+    assert_eq!(report.headings[0].line, 32);
+    assert!(report.confidence > 0.9);
+    assert!(report.report.warnings.is_empty());
+}
+
+#[test]
+fn test_import_text_with_roman_numerals() {
+    let text = "I.
+
+It began in the forge.
+
+II. The Road
+
+It went on.
+
+IV.
+
+It ended, though Chapter 3 was never written.
+";
+    let (book, report) = import_text(text, time(), time()).unwrap();
+
+    let titles: Vec<&str> = book.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, ["I", "II. The Road", "IV"]);
+    assert_eq!(
+        book.chapters[2].content,
+        "It ended, though Chapter 3 was never written."
+    );
+    assert_eq!(book.title, "Untitled");
+    assert_eq!(book.author, "Unknown");
+
+    // The skip from II to IV is flagged
+    let confidences: Vec<f32> = report.headings.iter().map(|h| h.confidence).collect();
+    assert_eq!(confidences, [0.8, 0.8, 0.4]);
+    assert!(report
+        .report
+        .warnings
+        .iter()
+        .any(|w| w.contains("from 2 to 4")));
+}
+
+#[test]
+fn test_import_text_with_centred_caps() {
+    let text = "                            THE FORGE
+
+The morning sun cracked over the horizon.
+
+                             THE ROAD
+
+The road stretched on.
+
+A LINE IN CAPITALS IS NOT A HEADING UNLESS IT IS CENTRED.
+";
+    let (book, report) = import_text(text, time(), time()).unwrap();
+
+    let titles: Vec<&str> = book.chapters.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, ["THE FORGE", "THE ROAD"]);
+    assert_eq!(
+        book.chapters[1].content,
+        "The road stretched on.\nA LINE IN CAPITALS IS NOT A HEADING UNLESS IT IS CENTRED."
+    );
+    assert!(report
+        .headings
+        .iter()
+        .all(|h| h.kind == HeadingKind::CenteredCaps));
+}
+
+#[test]
+fn test_import_text_heading_followed_by_prose() {
+    let text = "Chapter Twenty-One: The Forge
+The morning sun cracked over the horizon.
+Chapter Twenty-Two
+The Road
+";
+    let (book, report) = import_text(text, time(), time()).unwrap();
+    assert_eq!(book.chapters[0].title, "Chapter Twenty-One: The Forge");
+    assert_eq!(
+        book.chapters[0].content,
+        "The morning sun cracked over the horizon.\nChapter Twenty-Two\nThe Road"
+    );
+    assert_eq!(report.headings[0].number, Some(21));
+}
+
+#[test]
+fn test_import_text_without_headings() {
+    let (book, report) = import_text("Just a note.\n\nAnother one.\n", time(), time()).unwrap();
+    assert_eq!(book.chapters.len(), 1);
+    assert_eq!(book.chapters[0].title, "Untitled");
+    assert_eq!(book.chapters[0].content, "Just a note.\nAnother one.");
+    assert_eq!(report.confidence, 0.0);
+    assert!(!report.report.warnings.is_empty());
+}
@@ -0,0 +1,702 @@
+use crate::bk_format::{file_timestamps, BkChapter, BkMetadata};
+use crate::export::markdown::THEMATIC_BREAK;
+use crate::import::error::ImportError;
+use crate::import::heading::{chapter_heading, parse_roman};
+use crate::import::report::{Dropped, ImportReport};
+use crate::import::rtf::windows_1252;
+use crate::import::{UNKNOWN_AUTHOR, UNTITLED};
+use crate::models::Book;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+
+/// Longest line that can be a centred heading or a chapter subtitle
+const MAX_SHORT_LINE_CHARS: usize = 60;
+
+/// Narrower text is verse or lists rather than hard-wrapped prose
+const MIN_WRAP_WIDTH: usize = 50;
+
+/// Lines at least this share of the wrap width long were wrapped by hand
+const WRAPPED_LINE_SHARE: f32 = 0.6;
+
+/// Width assumed for centring when the text is not hard-wrapped
+const DEFAULT_LINE_WIDTH: usize = 72;
+
+/// Language names used in Project Gutenberg headers
+const LANGUAGES: &[(&str, &str)] = &[
+    ("english", "en"),
+    ("french", "fr"),
+    ("german", "de"),
+    ("spanish", "es"),
+    ("italian", "it"),
+    ("portuguese", "pt"),
+    ("dutch", "nl"),
+    ("latin", "la"),
+    ("finnish", "fi"),
+    ("swedish", "sv"),
+];
+
+/// Openings of the transcriber credits that follow a Gutenberg header
+const CREDIT_PREFIXES: &[&str] = &[
+    "produced by",
+    "e-text prepared by",
+    "etext prepared by",
+    "transcribed from",
+    "transcriber's note",
+];
+
+/// How a chapter heading was recognised, most reliable first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeadingKind {
+    /// "CHAPTER IV." or "Chapter 12: Title"
+    Chapter,
+    /// "IV." alone, or followed by a title
+    RomanNumeral,
+    /// "12" alone on its line
+    Number,
+    /// A short all-caps line centred in the text
+    CenteredCaps,
+}
+
+impl HeadingKind {
+    fn confidence(self) -> f32 {
+        match self {
+            HeadingKind::Chapter => 0.95,
+            HeadingKind::RomanNumeral => 0.8,
+            HeadingKind::Number => 0.6,
+            HeadingKind::CenteredCaps => 0.5,
+        }
+    }
+}
+
+/// A chapter heading found in the text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectedHeading {
+    /// Line of the source, counting from 1
+    pub line: usize,
+    /// Chapter title, with the subtitle line if there was one
+    pub title: String,
+    pub kind: HeadingKind,
+    /// Chapter number, when the heading has one
+    pub number: Option<u32>,
+    /// From 0 to 1; halved where the numbering breaks sequence
+    pub confidence: f32,
+}
+
+/// What a plain-text import found, for the user to review before saving
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TextImportReport {
+    /// One heading per chapter of the book, in order
+    pub headings: Vec<DetectedHeading>,
+    /// Confidence in the chapter split as a whole, 0 when nothing was found
+    pub confidence: f32,
+    /// Paragraphs rejoined from hard-wrapped lines
+    pub unwrapped_paragraphs: usize,
+    #[serde(flatten)]
+    pub report: ImportReport,
+}
+
+/// Read a plain-text file, taking timestamps from the filesystem
+///
+/// Files that are not UTF-8 are read as Windows-1252, a superset of Latin-1.
+/// The file name is the title if the text has none.
+pub fn import_text_file(path: &Path) -> Result<(Book, TextImportReport), ImportError> {
+    let bytes = std::fs::read(path)?;
+    let (created_at, updated_at) = file_timestamps(&std::fs::metadata(path)?);
+    let mut report = TextImportReport::default();
+    let content = match String::from_utf8(bytes) {
+        Ok(content) => content,
+        Err(e) => {
+            report
+                .report
+                .warn("The file is not UTF-8 and was read as Windows-1252");
+            e.into_bytes().into_iter().map(windows_1252).collect()
+        }
+    };
+    let stem = path.file_stem().and_then(|s| s.to_str());
+    read_text(&content, created_at, updated_at, stem, report)
+}
+
+/// Build a book from plain text, guessing where chapters start
+///
+/// Project Gutenberg headers and footers are stripped, taking the title,
+/// author and language from the header. Chapters start at headings such as
+/// "CHAPTER IV", "Chapter 12: Title", a Roman numeral or number on its own
+/// line, or a centred all-caps line; only the most reliable kind found is
+/// used. Hard-wrapped paragraphs are rejoined, while short lines such as
+/// verse keep their breaks. The report lists each heading with a confidence.
+pub fn import_text(
+    content: &str,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+) -> Result<(Book, TextImportReport), ImportError> {
+    read_text(
+        content,
+        created_at,
+        updated_at,
+        None,
+        TextImportReport::default(),
+    )
+}
+
+fn read_text(
+    content: &str,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    fallback_title: Option<&str>,
+    mut report: TextImportReport,
+) -> Result<(Book, TextImportReport), ImportError> {
+    let lines: Vec<&str> = content.trim_start_matches('\u{feff}').lines().collect();
+    let (header, body) = strip_boilerplate(&lines, &mut report.report);
+    let mut blocks = blocks(&lines[body.clone()], body.start + 1);
+    let credits = blocks
+        .iter()
+        .take_while(|block| is_credit(block.lines[0]))
+        .count();
+    for _ in blocks.drain(..credits) {
+        report.report.record(Dropped::Boilerplate);
+    }
+
+    let blocks = split_headings(blocks);
+    let width = wrap_width(&blocks);
+    let kinds: Vec<BlockKind> = blocks.iter().map(|b| classify(b, width)).collect();
+    let mut counts: BTreeMap<HeadingKind, usize> = BTreeMap::new();
+    for kind in &kinds {
+        if let BlockKind::Heading(candidate) = kind {
+            *counts.entry(candidate.kind).or_insert(0) += 1;
+        }
+    }
+    // The most reliable kind seen at least twice, else whatever was seen
+    let chosen = counts
+        .iter()
+        .find(|(_, &count)| count >= 2)
+        .or_else(|| counts.iter().next())
+        .map(|(&kind, _)| kind);
+
+    let mut chapters: Vec<(DetectedHeading, Vec<String>)> = Vec::new();
+    let mut front_matter: Vec<&Block> = Vec::new();
+    let mut i = 0;
+    while i < blocks.len() {
+        let block = &blocks[i];
+        match &kinds[i] {
+            BlockKind::Contents(entries) => {
+                for _ in 0..*entries {
+                    report.report.record(Dropped::TableOfContents);
+                }
+            }
+            BlockKind::Heading(candidate) if Some(candidate.kind) == chosen => {
+                // A heading with nothing under it was a contents entry
+                if chapters.last().is_some_and(|(_, lines)| lines.is_empty()) {
+                    chapters.pop();
+                    report.report.record(Dropped::TableOfContents);
+                }
+                let mut title = candidate.text.clone();
+                if candidate.kind != HeadingKind::CenteredCaps && !candidate.titled {
+                    let subtitle = blocks.get(i + 1).filter(|next| {
+                        i + 2 < blocks.len()
+                            && is_subtitle(next)
+                            && !matches!(&kinds[i + 1], BlockKind::Heading(c) if Some(c.kind) == chosen)
+                    });
+                    if let Some(next) = subtitle {
+                        title = format!("{}: {}", title, next.lines[0].trim());
+                        i += 1;
+                    }
+                }
+                let heading = DetectedHeading {
+                    line: block.line,
+                    title,
+                    kind: candidate.kind,
+                    number: candidate.number,
+                    confidence: candidate.kind.confidence(),
+                };
+                chapters.push((heading, Vec::new()));
+            }
+            BlockKind::Break => {
+                if let Some((_, lines)) = chapters.last_mut() {
+                    lines.push(THEMATIC_BREAK.to_string());
+                }
+            }
+            BlockKind::Text | BlockKind::Heading(_) => match chapters.last_mut() {
+                Some((_, lines)) => lines.extend(paragraphs(block, width, &mut report)),
+                None => front_matter.push(block),
+            },
+        }
+        i += 1;
+    }
+
+    let mut title = header.title;
+    let mut author = header.author;
+    if chapters.is_empty() {
+        report
+            .report
+            .warn("No chapter headings found; the whole text is one chapter");
+        let lines = front_matter
+            .iter()
+            .flat_map(|block| paragraphs(block, width, &mut report))
+            .collect();
+        let heading = DetectedHeading {
+            line: front_matter.first().map_or(body.start + 1, |b| b.line),
+            title: UNTITLED.to_string(),
+            kind: HeadingKind::Chapter,
+            number: None,
+            confidence: 0.0,
+        };
+        chapters.push((heading, lines));
+    } else {
+        for block in &front_matter {
+            let text = block.lines[0].trim();
+            let by = text
+                .get(..3)
+                .filter(|by| by.eq_ignore_ascii_case("by "))
+                .map(|_| text[3..].trim());
+            match by {
+                Some(name) if author.is_none() => author = Some(name.to_string()),
+                _ if title.is_none() && block.lines.len() == 1 && by.is_none() => {
+                    title = Some(text.to_string())
+                }
+                _ => {}
+            }
+            report.report.record(Dropped::FrontMatter);
+        }
+        check_numbering(&mut chapters, &mut report.report);
+    }
+
+    let title = title
+        .or_else(|| fallback_title.map(str::to_string))
+        .unwrap_or_else(|| {
+            report.report.warn("The text has no title");
+            UNTITLED.to_string()
+        });
+    let author = author.unwrap_or_else(|| {
+        report.report.warn("The text names no author");
+        UNKNOWN_AUTHOR.to_string()
+    });
+    let metadata = BkMetadata {
+        title: Some(title),
+        author: Some(author),
+        language: header.language,
+        ..BkMetadata::default()
+    };
+
+    let mut bk_chapters = Vec::new();
+    for (order, (heading, lines)) in chapters.into_iter().enumerate() {
+        let mut chapter = BkChapter::new(heading.title.clone(), order);
+        chapter.content = lines.join("\n");
+        bk_chapters.push(chapter);
+        report.headings.push(heading);
+    }
+    let found: Vec<f32> = report
+        .headings
+        .iter()
+        .map(|heading| heading.confidence)
+        .collect();
+    report.confidence = found.iter().sum::<f32>() / found.len() as f32;
+
+    let book = metadata.into_book(bk_chapters, created_at, updated_at)?;
+    Ok((book, report))
+}
+
+/// Halve the confidence of headings whose number breaks the sequence
+///
+/// Numbering may restart at 1, as it does in each part of some novels.
+fn check_numbering(chapters: &mut [(DetectedHeading, Vec<String>)], report: &mut ImportReport) {
+    let mut previous: Option<u32> = None;
+    for (heading, _) in chapters {
+        let Some(number) = heading.number else {
+            continue;
+        };
+        if let Some(previous) = previous {
+            if number != previous + 1 && number != 1 {
+                heading.confidence /= 2.0;
+                report.warn(format!(
+                    "Chapter numbers go from {} to {} at line {}",
+                    previous, number, heading.line
+                ));
+            }
+        }
+        previous = Some(number);
+    }
+}
+
+/// Metadata from a Project Gutenberg header
+#[derive(Debug, Default)]
+struct Header {
+    title: Option<String>,
+    author: Option<String>,
+    language: Option<String>,
+}
+
+impl Header {
+    /// Read `Title:`, `Author:` and `Language:` lines; indented lines continue
+    /// the field above them
+    fn parse(lines: &[&str]) -> Self {
+        const KEYS: [&str; 3] = ["title", "author", "language"];
+        let mut fields: [Option<String>; 3] = Default::default();
+        let mut current = None;
+        for line in lines {
+            if line.starts_with(char::is_whitespace) && !line.trim().is_empty() {
+                if let Some(value) = current.and_then(|i: usize| fields[i].as_mut()) {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+            current = line.split_once(':').and_then(|(key, value)| {
+                let key = key.trim().to_ascii_lowercase();
+                let i = KEYS.iter().position(|&k| k == key)?;
+                let value = value.trim();
+                (!value.is_empty()).then(|| {
+                    fields[i] = Some(value.to_string());
+                    i
+                })
+            });
+        }
+        let [title, author, language] = fields;
+        Header {
+            title,
+            author,
+            language: language.and_then(|name| language_code(&name)),
+        }
+    }
+}
+
+fn language_code(name: &str) -> Option<String> {
+    let name = name.trim().to_ascii_lowercase();
+    LANGUAGES
+        .iter()
+        .find(|(language, _)| *language == name)
+        .map(|(_, code)| code.to_string())
+        .or_else(|| {
+            let is_code = name.len() <= 3 && name.chars().all(|c| c.is_ascii_alphabetic());
+            is_code.then_some(name)
+        })
+}
+
+/// Find the Project Gutenberg header and footer, returning the lines between
+fn strip_boilerplate(lines: &[&str], report: &mut ImportReport) -> (Header, Range<usize>) {
+    let mut header = Header::default();
+    let start = match lines
+        .iter()
+        .position(|l| is_gutenberg_marker(l, "start of"))
+    {
+        Some(marker) => {
+            report.record(Dropped::Boilerplate);
+            header = Header::parse(&lines[..marker]);
+            marker + 1
+        }
+        None => 0,
+    };
+    let end = match lines[start..]
+        .iter()
+        .position(|l| is_gutenberg_marker(l, "end of"))
+    {
+        Some(marker) => {
+            report.record(Dropped::Boilerplate);
+            start + marker
+        }
+        None => lines.len(),
+    };
+    (header, start..end)
+}
+
+/// "*** START OF THE PROJECT GUTENBERG EBOOK ... ***" and its variants
+fn is_gutenberg_marker(line: &str, phrase: &str) -> bool {
+    let line = line
+        .trim()
+        .trim_start_matches(|c: char| c == '*' || c.is_whitespace())
+        .to_lowercase();
+    line.starts_with(phrase) && line.contains("project gutenberg")
+}
+
+fn is_credit(line: &str) -> bool {
+    let line = line.trim().to_lowercase();
+    CREDIT_PREFIXES
+        .iter()
+        .any(|prefix| line.starts_with(prefix))
+}
+
+/// Lines between blank lines
+#[derive(Debug)]
+struct Block<'a> {
+    /// Line number of the first line, counting from 1
+    line: usize,
+    lines: Vec<&'a str>,
+}
+
+fn blocks<'a>(lines: &[&'a str], first_line: usize) -> Vec<Block<'a>> {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut open = false;
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            open = false;
+        } else if open {
+            blocks.last_mut().expect("open block").lines.push(line);
+        } else {
+            blocks.push(Block {
+                line: first_line + i,
+                lines: vec![line],
+            });
+            open = true;
+        }
+    }
+    blocks
+}
+
+/// Give a "Chapter N" line its own block when prose follows it directly
+///
+/// A heading without a title followed by a single line keeps that line as
+/// its subtitle.
+fn split_headings(blocks: Vec<Block>) -> Vec<Block> {
+    let mut split = Vec::with_capacity(blocks.len());
+    for mut block in blocks {
+        let untitled = match chapter_heading(block.lines[0]) {
+            Some(found)
+                if found
+                    .title
+                    .is_none_or(|t| !t.starts_with(char::is_lowercase)) =>
+            {
+                found.title.is_none()
+            }
+            _ => {
+                split.push(block);
+                continue;
+            }
+        };
+        if block.lines.len() > 2 || (block.lines.len() == 2 && !untitled) {
+            let rest = block.lines.split_off(1);
+            let line = block.line;
+            split.push(block);
+            split.push(Block {
+                line: line + 1,
+                lines: rest,
+            });
+        } else {
+            split.push(block);
+        }
+    }
+    split
+}
+
+/// Width the text was wrapped at, or 0 when it was not hard-wrapped
+fn wrap_width(blocks: &[Block]) -> usize {
+    let mut lengths: Vec<usize> = blocks
+        .iter()
+        .filter(|block| block.lines.len() > 1)
+        .flat_map(|block| block.lines.iter().map(|l| l.trim_end().chars().count()))
+        .collect();
+    if lengths.is_empty() {
+        return 0;
+    }
+    lengths.sort_unstable();
+    let width = lengths[lengths.len() * 9 / 10];
+    if width < MIN_WRAP_WIDTH {
+        0
+    } else {
+        width
+    }
+}
+
+#[derive(Debug)]
+struct Candidate {
+    kind: HeadingKind,
+    number: Option<u32>,
+    /// The heading as written, without a trailing full stop
+    text: String,
+    /// The heading includes a title, so the next line is not one
+    titled: bool,
+}
+
+#[derive(Debug)]
+enum BlockKind {
+    Text,
+    Break,
+    Heading(Candidate),
+    /// A table of contents with this many entries
+    Contents(usize),
+}
+
+fn classify(block: &Block, width: usize) -> BlockKind {
+    let first = block.lines[0].trim();
+    if block.lines.len() == 1 && is_break(first) {
+        return BlockKind::Break;
+    }
+    if block.lines.len() == 1
+        && matches!(
+            first.to_lowercase().as_str(),
+            "contents" | "table of contents"
+        )
+    {
+        return BlockKind::Contents(0);
+    }
+    let entries = block
+        .lines
+        .iter()
+        .filter(|line| chapter_heading(line).is_some() || roman_heading(line.trim()).is_some())
+        .count();
+    if block.lines.len() > 2 && entries * 2 >= block.lines.len() {
+        return BlockKind::Contents(entries);
+    }
+    match heading(block, width) {
+        Some(candidate) => BlockKind::Heading(candidate),
+        None => BlockKind::Text,
+    }
+}
+
+fn heading(block: &Block, width: usize) -> Option<Candidate> {
+    let first = block.lines[0].trim();
+    if let Some(found) = chapter_heading(first) {
+        // "Chapter 3 was the longest" is prose
+        if found
+            .title
+            .is_some_and(|title| title.starts_with(char::is_lowercase))
+        {
+            return None;
+        }
+        let mut text = first.trim_end_matches('.').to_string();
+        match (block.lines.get(1), found.title) {
+            (None, _) => {}
+            (Some(second), None) if block.lines.len() == 2 => {
+                text = format!("{}: {}", text, second.trim());
+            }
+            _ => return None,
+        }
+        return Some(Candidate {
+            kind: HeadingKind::Chapter,
+            number: Some(found.number),
+            titled: found.title.is_some() || block.lines.len() == 2,
+            text,
+        });
+    }
+    if block.lines.len() != 1 {
+        return None;
+    }
+    if let Some((number, titled)) = roman_heading(first) {
+        return Some(Candidate {
+            kind: HeadingKind::RomanNumeral,
+            number: Some(number),
+            text: first.trim_end_matches('.').to_string(),
+            titled,
+        });
+    }
+    let digits = first.trim_end_matches('.');
+    if !digits.is_empty() && digits.len() <= 3 && digits.chars().all(|c| c.is_ascii_digit()) {
+        return Some(Candidate {
+            kind: HeadingKind::Number,
+            number: digits.parse().ok(),
+            text: digits.to_string(),
+            titled: false,
+        });
+    }
+    if is_caps(first) && is_centred(block.lines[0], width) {
+        return Some(Candidate {
+            kind: HeadingKind::CenteredCaps,
+            number: None,
+            text: first.to_string(),
+            titled: true,
+        });
+    }
+    None
+}
+
+/// "IV" or "IV." alone, or "IV. Title"; the number and whether it has a title
+fn roman_heading(line: &str) -> Option<(u32, bool)> {
+    let (numeral, title) = line.split_once('.').unwrap_or((line, ""));
+    let title = title.trim();
+    if !numeral.chars().all(|c| "IVXLC".contains(c)) {
+        return None;
+    }
+    if !title.is_empty() && !is_subtitle_text(title) {
+        return None;
+    }
+    parse_roman(&numeral.to_lowercase()).map(|number| (number, !title.is_empty()))
+}
+
+/// A line of only asterisks, dashes and the like
+fn is_break(line: &str) -> bool {
+    !line.is_empty()
+        && line
+            .chars()
+            .all(|c| c.is_whitespace() || "*#~-=_\u{b7}\u{2022}".contains(c))
+}
+
+fn is_caps(line: &str) -> bool {
+    let letters: Vec<char> = line.chars().filter(|c| c.is_alphabetic()).collect();
+    line.chars().count() <= MAX_SHORT_LINE_CHARS
+        && letters.len() >= 3
+        && letters.iter().all(|c| !c.is_lowercase())
+}
+
+/// Left and right margins roughly equal within the wrap width
+fn is_centred(line: &str, width: usize) -> bool {
+    let width = if width == 0 {
+        DEFAULT_LINE_WIDTH
+    } else {
+        width
+    } as isize;
+    let indent = (line.len() - line.trim_start().len()) as isize;
+    let length = line.trim().chars().count() as isize;
+    let right = width - indent - length;
+    indent >= 4 && (indent - right).abs() <= (width / 8).max(4)
+}
+
+/// A short line that can follow "CHAPTER I." as its title
+fn is_subtitle(block: &Block) -> bool {
+    block.lines.len() == 1 && is_subtitle_text(block.lines[0].trim())
+}
+
+fn is_subtitle_text(text: &str) -> bool {
+    text.chars().count() <= MAX_SHORT_LINE_CHARS
+        && text.starts_with(|c: char| c.is_uppercase())
+        && !text.ends_with(['.', ',', ';', '"', '\u{201d}', '\'', '\u{2019}'])
+        && !is_break(text)
+}
+
+/// Paragraphs of a block: hard-wrapped lines are rejoined, and an indented
+/// line starts a new paragraph
+fn paragraphs(block: &Block, width: usize, report: &mut TextImportReport) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut start = 0;
+    for end in 1..=block.lines.len() {
+        let next_indented = block
+            .lines
+            .get(end)
+            .is_some_and(|line| indent(line) > indent(block.lines[end - 1]));
+        if end == block.lines.len() || next_indented {
+            paragraphs.extend(unwrap(&block.lines[start..end], width, report));
+            start = end;
+        }
+    }
+    paragraphs
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn unwrap(lines: &[&str], width: usize, report: &mut TextImportReport) -> Vec<String> {
+    let collapse = |line: &&str| line.split_ascii_whitespace().collect::<Vec<_>>().join(" ");
+    let full = width as f32 * WRAPPED_LINE_SHARE;
+    let wrapped = width > 0
+        && lines.len() > 1
+        && lines[..lines.len() - 1]
+            .iter()
+            .all(|line| line.trim().chars().count() as f32 >= full);
+    if !wrapped {
+        return lines.iter().map(collapse).collect();
+    }
+
+    report.unwrapped_paragraphs += 1;
+    let mut text = String::new();
+    for line in lines.iter().map(collapse) {
+        // "well-" then "known" rejoins as "well-known"
+        let hyphenated = text.ends_with('-') && line.starts_with(char::is_lowercase);
+        if !text.is_empty() && !hyphenated {
+            text.push(' ');
+        }
+        text.push_str(&line);
+    }
+    vec![text]
+}
@@ -0,0 +1,404 @@
+use crate::layout::error::LayoutError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Page dimensions in points (72 pt = 1 inch)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PageSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl PageSize {
+    /// 8.5" × 11"
+    pub const US_LETTER: PageSize = PageSize {
+        width: 612.0,
+        height: 792.0,
+    };
+    /// 210mm × 297mm
+    pub const A4: PageSize = PageSize {
+        width: 595.0,
+        height: 842.0,
+    };
+}
+
+/// Page margins in points; `inner` is the binding side
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Margins {
+    pub top: f32,
+    pub bottom: f32,
+    pub inner: f32,
+    pub outer: f32,
+}
+
+impl Margins {
+    pub fn uniform(points: f32) -> Self {
+        Self {
+            top: points,
+            bottom: points,
+            inner: points,
+            outer: points,
+        }
+    }
+
+    pub fn symmetric(vertical: f32, horizontal: f32) -> Self {
+        Self {
+            top: vertical,
+            bottom: vertical,
+            inner: horizontal,
+            outer: horizontal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextStyle {
+    /// Font size in points
+    pub font_size: f32,
+    /// Line height as a multiple of the font size
+    pub line_height: f32,
+    pub alignment: Alignment,
+    /// OpenType features on top of the shaper's defaults, such as `onum` or `-liga`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<FontFeature>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 12.0,
+            line_height: 1.5,
+            alignment: Alignment::Justify,
+            features: Vec::new(),
+        }
+    }
+}
+
+/// OpenType feature setting, written `onum`, `+smcp`, `-liga` or `ss01=2`
+///
+/// Only used when text is shaped with a font; see `TextMetrics::shape`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct FontFeature {
+    pub tag: [u8; 4],
+    /// 0 turns the feature off, 1 on; higher values pick an alternate
+    pub value: u32,
+}
+
+impl FontFeature {
+    pub fn on(tag: &[u8; 4]) -> Self {
+        Self {
+            tag: *tag,
+            value: 1,
+        }
+    }
+
+    pub fn off(tag: &[u8; 4]) -> Self {
+        Self {
+            tag: *tag,
+            value: 0,
+        }
+    }
+}
+
+impl FromStr for FontFeature {
+    type Err = LayoutError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || LayoutError::InvalidConfig(format!("invalid OpenType feature '{}'", value));
+        let trimmed = value.trim();
+        let (name, setting) = match trimmed.split_once('=') {
+            Some((name, setting)) => (name, Some(setting.trim())),
+            None => (trimmed, None),
+        };
+        let name = name.trim();
+        let (name, default) = match name.strip_prefix('-') {
+            Some(name) => (name, 0),
+            None => (name.strip_prefix('+').unwrap_or(name), 1),
+        };
+        let tag: [u8; 4] = name
+            .as_bytes()
+            .try_into()
+            .ok()
+            .filter(|tag: &[u8; 4]| tag.iter().all(|b| b.is_ascii_graphic()))
+            .ok_or_else(invalid)?;
+        let value = match setting {
+            Some(setting) => setting.parse().map_err(|_| invalid())?,
+            None => default,
+        };
+        Ok(Self { tag, value })
+    }
+}
+
+impl fmt::Display for FontFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tag = String::from_utf8_lossy(&self.tag);
+        match self.value {
+            0 => write!(f, "-{}", tag),
+            1 => write!(f, "{}", tag),
+            value => write!(f, "{}={}", tag, value),
+        }
+    }
+}
+
+impl TryFrom<String> for FontFeature {
+    type Error = LayoutError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<FontFeature> for String {
+    fn from(feature: FontFeature) -> Self {
+        feature.to_string()
+    }
+}
+
+/// How paragraphs are broken into lines
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum LineBreaking {
+    /// Fill each line in turn; fast enough to rerun on every keystroke
+    #[default]
+    Greedy,
+    /// Knuth–Plass total-fit breaking, which evens out spacing across the paragraph
+    Optimal(OptimalBreaking),
+}
+
+/// Settings of the Knuth–Plass breaker, named and scaled as in TeX
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OptimalBreaking {
+    /// Worst badness a line may have, where 100 means spaces stretched by
+    /// their full stretchability; 10000 accepts any line
+    pub tolerance: f32,
+    /// Cost of breaking at a hyphenation point, which adds a hyphen
+    pub hyphen_penalty: f32,
+    /// Cost of breaking after a hyphen already in the text
+    pub explicit_hyphen_penalty: f32,
+    /// Extra demerits for two hyphenated lines in a row
+    pub consecutive_hyphen_demerits: f32,
+    /// Extra demerits when a tight line and a loose line are adjacent
+    pub fitness_demerits: f32,
+    /// Added to the badness of every line, so fewer lines are preferred
+    pub line_penalty: f32,
+    /// Lines to add (positive) or remove (negative) from the best breaking,
+    /// where the tolerance allows
+    pub looseness: i32,
+}
+
+impl Default for OptimalBreaking {
+    fn default() -> Self {
+        Self {
+            tolerance: 200.0,
+            hyphen_penalty: 50.0,
+            explicit_hyphen_penalty: 50.0,
+            consecutive_hyphen_demerits: 3000.0,
+            fitness_demerits: 100.0,
+            line_penalty: 10.0,
+            looseness: 0,
+        }
+    }
+}
+
+/// Whether and how words in body text are hyphenated
+///
+/// Patterns are picked by the chapter's language, or else the book's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hyphenation {
+    pub enabled: bool,
+    /// Fewest letters before a hyphen
+    pub left_min: usize,
+    /// Fewest letters after a hyphen
+    pub right_min: usize,
+}
+
+impl Default for Hyphenation {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            left_min: 2,
+            right_min: 3,
+        }
+    }
+}
+
+/// Where prose may break across pages, and how far a page's line spacing may
+/// give to respect that
+///
+/// Set `orphans`, `widows` or `last_page_lines` to 1 to turn that rule off.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Pagination {
+    /// Fewest lines of a paragraph left at the foot of a page
+    pub orphans: usize,
+    /// Fewest lines of a paragraph carried over to the top of a page
+    pub widows: usize,
+    /// Keep chapter titles and scene breaks on the page with the text after them
+    pub keep_with_next: bool,
+    /// Fewest lines on the last page of a chapter that runs over several pages
+    pub last_page_lines: usize,
+    /// Most the space between lines may grow or shrink to fill a page whose
+    /// break was moved, as a fraction of the line height
+    pub vertical_justification: f32,
+}
+
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            orphans: 2,
+            widows: 2,
+            keep_with_next: true,
+            last_page_lines: 2,
+            vertical_justification: 0.05,
+        }
+    }
+}
+
+/// Text repeated in the head or foot of a page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RunningText {
+    Author,
+    BookTitle,
+    ChapterTitle,
+    /// The page's number
+    Folio,
+}
+
+/// Running heads and feet, left out on front matter, blank pages and the
+/// opening page of each chapter
+///
+/// One-sided layouts use the recto settings on every page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunningHeads {
+    /// Head of left-hand pages
+    pub verso_head: Option<RunningText>,
+    /// Head of right-hand pages
+    pub recto_head: Option<RunningText>,
+    pub verso_foot: Option<RunningText>,
+    pub recto_foot: Option<RunningText>,
+    /// Print the folio at the foot of chapter openings after all
+    pub folio_on_openings: bool,
+    pub style: TextStyle,
+}
+
+impl Default for RunningHeads {
+    fn default() -> Self {
+        Self {
+            verso_head: Some(RunningText::Author),
+            recto_head: Some(RunningText::ChapterTitle),
+            verso_foot: Some(RunningText::Folio),
+            recto_foot: Some(RunningText::Folio),
+            folio_on_openings: false,
+            style: TextStyle {
+                font_size: 10.0,
+                line_height: 1.2,
+                alignment: Alignment::Center,
+                features: Vec::new(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    pub page_size: PageSize,
+    pub margins: Margins,
+    pub body_style: TextStyle,
+    pub chapter_title_style: TextStyle,
+    /// Start each chapter on a right-hand (odd) page, leaving a blank page if needed
+    pub first_chapter_on_odd_page: bool,
+    /// Swap the inner and outer margins on left-hand pages, as for a bound book
+    pub two_sided: bool,
+    pub line_breaking: LineBreaking,
+    pub hyphenation: Hyphenation,
+    pub pagination: Pagination,
+    pub running_heads: RunningHeads,
+    /// Open with a title page and the dedication, numbered in lowercase roman
+    /// numerals; the first chapter starts again at arabic 1
+    pub front_matter: bool,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            page_size: PageSize::US_LETTER,
+            margins: Margins::uniform(72.0),
+            body_style: TextStyle::default(),
+            chapter_title_style: TextStyle {
+                font_size: 24.0,
+                line_height: 1.2,
+                alignment: Alignment::Center,
+                features: Vec::new(),
+            },
+            first_chapter_on_odd_page: true,
+            two_sided: true,
+            line_breaking: LineBreaking::Greedy,
+            hyphenation: Hyphenation::default(),
+            pagination: Pagination::default(),
+            running_heads: RunningHeads::default(),
+            front_matter: false,
+        }
+    }
+}
+
+impl LayoutConfig {
+    /// Screenplay page: US Letter printed one-sided, a 1.5" left margin and 1"
+    /// elsewhere, 12 pt type at six lines per inch
+    ///
+    /// The columns assume a monospaced face at ten characters per inch, such as
+    /// the DejaVu Sans Mono that PDF export embeds in screenplays; 54 lines fit
+    /// a page. Pages after the first are numbered at the top right.
+    pub fn screenplay() -> Self {
+        let style = TextStyle {
+            font_size: 12.0,
+            line_height: 1.0,
+            alignment: Alignment::Left,
+            features: Vec::new(),
+        };
+        Self {
+            page_size: PageSize::US_LETTER,
+            margins: Margins {
+                top: 72.0,
+                bottom: 72.0,
+                inner: 108.0,
+                outer: 72.0,
+            },
+            body_style: style.clone(),
+            chapter_title_style: TextStyle {
+                alignment: Alignment::Center,
+                ..style.clone()
+            },
+            first_chapter_on_odd_page: false,
+            two_sided: false,
+            line_breaking: LineBreaking::Greedy,
+            hyphenation: Hyphenation::default(),
+            pagination: Pagination::default(),
+            running_heads: RunningHeads {
+                verso_head: None,
+                recto_head: Some(RunningText::Folio),
+                verso_foot: None,
+                recto_foot: None,
+                folio_on_openings: false,
+                style: TextStyle {
+                    alignment: Alignment::Right,
+                    ..style
+                },
+            },
+            front_matter: false,
+        }
+    }
+}
@@ -0,0 +1,14 @@
+use thiserror::Error;
+
+/// Errors that can occur while laying out a book
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum LayoutError {
+    #[error("Invalid layout configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("Unusable font: {0}")]
+    Font(String),
+
+    #[error("Unusable hyphenation patterns: {0}")]
+    Hyphenation(String),
+}
@@ -0,0 +1,272 @@
+use crate::layout::config::FontFeature;
+use crate::layout::error::LayoutError;
+use crate::layout::metrics::TextMetrics;
+use crate::layout::types::PositionedGlyph;
+use rustybuzz::{Direction, Feature, Script, ShapePlan, UnicodeBuffer};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use ttf_parser::gpos::{PairAdjustment, PositioningSubtable};
+use ttf_parser::{Face, GlyphId, Tag};
+
+/// Font files read from disk, kept so each file is loaded only once
+#[derive(Debug, Default)]
+pub struct FontCache {
+    files: HashMap<PathBuf, Arc<[u8]>>,
+}
+
+impl FontCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bytes of a TTF/OTF file, read on first use
+    ///
+    /// The file is checked to be a font the first time it is read.
+    pub fn load(&mut self, path: &Path) -> Result<Arc<[u8]>, LayoutError> {
+        if let Some(data) = self.files.get(path) {
+            return Ok(Arc::clone(data));
+        }
+        let data: Arc<[u8]> = std::fs::read(path)
+            .map_err(|e| LayoutError::Font(format!("{}: {}", path.display(), e)))?
+            .into();
+        Face::parse(&data, 0)
+            .map_err(|e| LayoutError::Font(format!("{}: {}", path.display(), e)))?;
+        self.files.insert(path.to_path_buf(), Arc::clone(&data));
+        Ok(data)
+    }
+}
+
+/// Metrics read from a TrueType/OpenType font
+///
+/// Widths are the glyphs' advances plus pair kerning, taken from the GPOS
+/// `kern` feature or, in older fonts, the `kern` table. Characters the font
+/// lacks are measured as its missing-glyph box, as the PDF exporter draws them.
+/// [`TextMetrics::shape`] runs text through the shaper for ligatures,
+/// contextual alternates and any requested features.
+pub struct FontMetrics<'a> {
+    data: &'a [u8],
+    face: rustybuzz::Face<'a>,
+    /// Pair adjustment subtables of the GPOS `kern` feature
+    pairs: Vec<PairAdjustment<'a>>,
+    /// Glyph and advance of each character measured so far
+    glyphs: RefCell<HashMap<char, (GlyphId, u16)>>,
+    /// Shaping plans, which are slow to build, by features and segment
+    plans: RefCell<HashMap<PlanKey, ShapePlan>>,
+    /// Text shaped so far, in font units
+    shaped: RefCell<HashMap<ShapeKey, Rc<[ShapedGlyph]>>>,
+}
+
+type PlanKey = (Vec<FontFeature>, Direction, Script);
+type ShapeKey = (String, Vec<FontFeature>);
+
+/// Shaper output for one glyph, in font units
+#[derive(Debug, Clone, Copy)]
+struct ShapedGlyph {
+    id: u16,
+    cluster: u32,
+    x_offset: i32,
+    y_offset: i32,
+    advance: i32,
+}
+
+impl<'a> FontMetrics<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self, LayoutError> {
+        let face = Face::parse(data, 0).map_err(|e| LayoutError::Font(e.to_string()))?;
+        let face = rustybuzz::Face::from_face(face);
+        let pairs = kern_pairs(&face);
+        Ok(Self {
+            data,
+            face,
+            pairs,
+            glyphs: RefCell::new(HashMap::new()),
+            plans: RefCell::new(HashMap::new()),
+            shaped: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// The font file the metrics were read from
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub(crate) fn face(&self) -> &Face<'a> {
+        &self.face
+    }
+
+    /// Shape text with the plan for its script and direction, caching the result
+    fn shape_units(&self, text: &str, features: &[FontFeature]) -> Rc<[ShapedGlyph]> {
+        let key = (text.to_string(), features.to_vec());
+        if let Some(glyphs) = self.shaped.borrow().get(&key) {
+            return Rc::clone(glyphs);
+        }
+
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        let mut plans = self.plans.borrow_mut();
+        let plan = plans
+            .entry((features.to_vec(), buffer.direction(), buffer.script()))
+            .or_insert_with(|| {
+                let features: Vec<Feature> = features
+                    .iter()
+                    .map(|feature| Feature::new(Tag::from_bytes(&feature.tag), feature.value, ..))
+                    .collect();
+                ShapePlan::new(
+                    &self.face,
+                    buffer.direction(),
+                    Some(buffer.script()),
+                    None,
+                    &features,
+                )
+            });
+        let shaped = rustybuzz::shape_with_plan(&self.face, plan, buffer);
+
+        let glyphs: Rc<[ShapedGlyph]> = shaped
+            .glyph_infos()
+            .iter()
+            .zip(shaped.glyph_positions())
+            .map(|(info, position)| ShapedGlyph {
+                id: info.glyph_id as u16,
+                cluster: info.cluster,
+                x_offset: position.x_offset,
+                y_offset: position.y_offset,
+                advance: position.x_advance,
+            })
+            .collect();
+        self.shaped.borrow_mut().insert(key, Rc::clone(&glyphs));
+        glyphs
+    }
+
+    /// Glyph for a character and its advance in font units
+    fn glyph(&self, c: char) -> (GlyphId, u16) {
+        *self.glyphs.borrow_mut().entry(c).or_insert_with(|| {
+            let glyph = self.face.glyph_index(c).unwrap_or(GlyphId(0));
+            (glyph, self.face.glyph_hor_advance(glyph).unwrap_or(0))
+        })
+    }
+
+    /// Kerning between two characters in font units; negative pulls them together
+    pub fn kerning(&self, left: char, right: char) -> i16 {
+        self.glyph_kerning(self.glyph(left).0, self.glyph(right).0)
+    }
+
+    fn glyph_kerning(&self, left: GlyphId, right: GlyphId) -> i16 {
+        if !self.pairs.is_empty() {
+            return self
+                .pairs
+                .iter()
+                .find_map(|pair| pair_adjustment(pair, left, right))
+                .unwrap_or(0);
+        }
+        self.face
+            .tables()
+            .kern
+            .and_then(|kern| {
+                kern.subtables
+                    .into_iter()
+                    .filter(|s| s.horizontal && !s.variable && !s.has_cross_stream)
+                    .find_map(|s| s.glyphs_kerning(left, right))
+            })
+            .unwrap_or(0)
+    }
+
+    /// Font size divided by units per em
+    fn scale(&self, font_size: f32) -> f32 {
+        font_size / self.face.units_per_em() as f32
+    }
+}
+
+impl TextMetrics for FontMetrics<'_> {
+    fn measure_text(&self, text: &str, font_size: f32) -> f32 {
+        let mut units = 0i32;
+        let mut previous = None;
+        for c in text.chars() {
+            let (glyph, advance) = self.glyph(c);
+            units += i32::from(advance);
+            if let Some(previous) = previous {
+                units += i32::from(self.glyph_kerning(previous, glyph));
+            }
+            previous = Some(glyph);
+        }
+        units as f32 * self.scale(font_size)
+    }
+
+    fn measure_char(&self, c: char, font_size: f32) -> f32 {
+        f32::from(self.glyph(c).1) * self.scale(font_size)
+    }
+
+    fn line_height(&self, font_size: f32, multiplier: f32) -> f32 {
+        font_size * multiplier
+    }
+
+    fn ascent(&self, font_size: f32) -> f32 {
+        f32::from(self.face.ascender()) * self.scale(font_size)
+    }
+
+    fn shape(
+        &self,
+        text: &str,
+        font_size: f32,
+        features: &[FontFeature],
+    ) -> Option<Vec<PositionedGlyph>> {
+        let scale = self.scale(font_size);
+        let mut pen = 0.0;
+        let glyphs = self
+            .shape_units(text, features)
+            .iter()
+            .map(|glyph| {
+                let positioned = PositionedGlyph {
+                    id: glyph.id,
+                    cluster: glyph.cluster as usize,
+                    x: pen + glyph.x_offset as f32 * scale,
+                    y: glyph.y_offset as f32 * scale,
+                    advance: glyph.advance as f32 * scale,
+                };
+                pen += positioned.advance;
+                positioned
+            })
+            .collect();
+        Some(glyphs)
+    }
+}
+
+/// Pair adjustment subtables of the lookups behind the GPOS `kern` feature
+fn kern_pairs<'a>(face: &Face<'a>) -> Vec<PairAdjustment<'a>> {
+    let Some(gpos) = face.tables().gpos else {
+        return Vec::new();
+    };
+    let kern = Tag::from_bytes(b"kern");
+    let mut indices: Vec<u16> = gpos
+        .features
+        .into_iter()
+        .filter(|feature| feature.tag == kern)
+        .flat_map(|feature| feature.lookup_indices)
+        .collect();
+    indices.sort_unstable();
+    indices.dedup();
+
+    indices
+        .into_iter()
+        .filter_map(|index| gpos.lookups.get(index))
+        .flat_map(|lookup| lookup.subtables.into_iter::<PositioningSubtable>())
+        .filter_map(|subtable| match subtable {
+            PositioningSubtable::Pair(pair) => Some(pair),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Advance adjustment of the first glyph of a pair, if the subtable covers it
+fn pair_adjustment(pair: &PairAdjustment, left: GlyphId, right: GlyphId) -> Option<i16> {
+    let index = pair.coverage().get(left)?;
+    let (first, _) = match pair {
+        PairAdjustment::Format1 { sets, .. } => sets.get(index)?.get(right)?,
+        PairAdjustment::Format2 {
+            classes, matrix, ..
+        } => matrix.get((classes.0.get(left), classes.1.get(right)))?,
+    };
+    Some(first.x_advance)
+}
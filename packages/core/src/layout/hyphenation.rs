@@ -0,0 +1,281 @@
+use crate::layout::error::LayoutError;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+/// Liang's patterns for US English, from hyph-utf8
+const EN_US_PATTERNS: &str = include_str!("../../assets/hyphenation/hyph-en-us.pat.txt");
+const EN_US_EXCEPTIONS: &str = include_str!("../../assets/hyphenation/hyph-en-us.hyp.txt");
+
+/// Patterns and exceptions read from one pattern file
+#[derive(Debug, Default)]
+struct Patterns {
+    /// Inter-letter values by letter string, `.` marking a word edge
+    values: HashMap<String, Vec<u8>>,
+    /// Letters in the longest pattern
+    longest: usize,
+    /// Break positions, counted in letters, by lowercase word
+    exceptions: HashMap<String, Vec<usize>>,
+}
+
+/// Finds where words may be hyphenated, using TeX hyphenation patterns
+///
+/// Patterns are Frank Liang's: digits between letters vote for (odd) or
+/// against (even) a break, and the highest vote wins. Exceptions list a
+/// word's breaks outright. Patterns are shared between clones, so a clone
+/// with its own dictionary is cheap.
+#[derive(Debug, Clone)]
+pub struct Hyphenator {
+    patterns: Arc<Patterns>,
+    /// Custom hyphenations, which take precedence over the file's exceptions
+    dictionary: HashMap<String, Vec<usize>>,
+    left_min: usize,
+    right_min: usize,
+}
+
+impl Hyphenator {
+    /// Read a TeX pattern file
+    ///
+    /// Takes the `\patterns{...}` and `\hyphenation{...}` groups of a `.tex`
+    /// file, or, without them, a bare list of patterns such as hyph-utf8's
+    /// `.pat.txt` files. `%` starts a comment.
+    pub fn parse(source: &str) -> Result<Self, LayoutError> {
+        let source: String = source
+            .lines()
+            .map(|line| line.split('%').next().unwrap_or(""))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (patterns, exceptions) = match tex_group(&source, "\\patterns") {
+            Some(patterns) => (patterns, tex_group(&source, "\\hyphenation").unwrap_or("")),
+            None => (source.as_str(), ""),
+        };
+
+        let mut parsed = Patterns::default();
+        for pattern in patterns.split_whitespace() {
+            let (letters, values) = parse_pattern(pattern).ok_or_else(|| {
+                LayoutError::Hyphenation(format!("invalid pattern '{}'", pattern))
+            })?;
+            parsed.longest = parsed.longest.max(values.len() - 1);
+            parsed.values.insert(letters, values);
+        }
+        if parsed.values.is_empty() {
+            return Err(LayoutError::Hyphenation(
+                "no hyphenation patterns found".to_string(),
+            ));
+        }
+        for word in exceptions.split_whitespace() {
+            let (word, breaks) = parse_exception(word);
+            parsed.exceptions.insert(word, breaks);
+        }
+
+        Ok(Self {
+            patterns: Arc::new(parsed),
+            dictionary: HashMap::new(),
+            left_min: 2,
+            right_min: 3,
+        })
+    }
+
+    /// Read a TeX pattern file from disk; see [`Hyphenator::parse`]
+    pub fn load(path: &Path) -> Result<Self, LayoutError> {
+        let source = std::fs::read_to_string(path)
+            .map_err(|e| LayoutError::Hyphenation(format!("{}: {}", path.display(), e)))?;
+        Self::parse(&source)
+    }
+
+    /// Built-in US English patterns with their exceptions
+    pub fn english() -> Self {
+        static ENGLISH: OnceLock<Hyphenator> = OnceLock::new();
+        ENGLISH
+            .get_or_init(|| {
+                let mut english = Self::parse(EN_US_PATTERNS).expect("built-in patterns are valid");
+                let patterns = Arc::get_mut(&mut english.patterns).expect("not yet shared");
+                for word in EN_US_EXCEPTIONS.split_whitespace() {
+                    let (word, breaks) = parse_exception(word);
+                    patterns.exceptions.insert(word, breaks);
+                }
+                english
+            })
+            .clone()
+    }
+
+    /// Fewest letters to leave before the first break and after the last
+    ///
+    /// TeX's `\lefthyphenmin` and `\righthyphenmin`; English uses 2 and 3.
+    pub fn with_min(mut self, left: usize, right: usize) -> Self {
+        self.left_min = left.max(1);
+        self.right_min = right.max(1);
+        self
+    }
+
+    /// Add a word to the custom dictionary, hyphens marking its breaks
+    ///
+    /// `man-u-script` breaks only where shown, and a word with no hyphens is
+    /// never broken.
+    pub fn add_exception(&mut self, word: &str) {
+        let (word, breaks) = parse_exception(word);
+        if !word.is_empty() {
+            self.dictionary.insert(word, breaks);
+        }
+    }
+
+    /// Byte offsets in `word` where it may be broken with a hyphen
+    ///
+    /// Punctuation around the word is skipped; a word with other characters
+    /// than letters inside it is not hyphenated.
+    pub fn hyphenate(&self, word: &str) -> Vec<usize> {
+        let Some(start) = word.find(char::is_alphabetic) else {
+            return Vec::new();
+        };
+        let end = word
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_alphabetic())
+            .map_or(word.len(), |(index, c)| index + c.len_utf8());
+        let core = &word[start..end];
+        if !core.chars().all(char::is_alphabetic) {
+            return Vec::new();
+        }
+
+        let letters: Vec<char> = core.chars().map(lowercase).collect();
+        let count = letters.len();
+        if count < self.left_min + self.right_min {
+            return Vec::new();
+        }
+        let lower: String = letters.iter().collect();
+        let breaks = match self
+            .dictionary
+            .get(&lower)
+            .or_else(|| self.patterns.exceptions.get(&lower))
+        {
+            Some(breaks) => breaks.clone(),
+            None => self.pattern_breaks(&letters),
+        };
+
+        let offsets: Vec<usize> = core
+            .char_indices()
+            .map(|(index, _)| start + index)
+            .collect();
+        breaks
+            .into_iter()
+            .filter(|&at| at >= self.left_min && count - at >= self.right_min)
+            .map(|at| offsets[at])
+            .collect()
+    }
+
+    /// Positions, counted in letters, where the patterns allow a break
+    fn pattern_breaks(&self, letters: &[char]) -> Vec<usize> {
+        let mut word = vec!['.'];
+        word.extend_from_slice(letters);
+        word.push('.');
+
+        // Value between word[i - 1] and word[i]
+        let mut votes = vec![0u8; word.len() + 1];
+        for start in 0..word.len() {
+            let mut key = String::new();
+            for &c in word[start..].iter().take(self.patterns.longest) {
+                key.push(c);
+                if let Some(values) = self.patterns.values.get(&key) {
+                    for (offset, &value) in values.iter().enumerate() {
+                        let vote = &mut votes[start + offset];
+                        *vote = (*vote).max(value);
+                    }
+                }
+            }
+        }
+
+        // A break after the n-th letter sits before word[n + 1]
+        (1..letters.len())
+            .filter(|&at| votes[at + 1] % 2 == 1)
+            .collect()
+    }
+}
+
+/// Hyphenators by language tag
+#[derive(Debug, Clone, Default)]
+pub struct HyphenationPatterns {
+    by_language: HashMap<String, Hyphenator>,
+}
+
+impl HyphenationPatterns {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in patterns: US English, also used for other English
+    pub fn builtin() -> Self {
+        let mut patterns = Self::new();
+        patterns.insert("en-US", Hyphenator::english());
+        patterns.insert("en", Hyphenator::english());
+        patterns
+    }
+
+    /// Use `hyphenator` for a language tag such as `de` or `pt-BR`
+    pub fn insert(&mut self, language: &str, hyphenator: Hyphenator) {
+        self.by_language
+            .insert(language.to_ascii_lowercase(), hyphenator);
+    }
+
+    /// Read a TeX pattern file for a language tag
+    pub fn load_file(&mut self, language: &str, path: &Path) -> Result<(), LayoutError> {
+        self.insert(language, Hyphenator::load(path)?);
+        Ok(())
+    }
+
+    /// Hyphenator for a BCP 47 tag, dropping subtags until one matches
+    ///
+    /// `de-CH-1996` tries `de-ch-1996`, then `de-ch`, then `de`.
+    pub fn get(&self, language: &str) -> Option<&Hyphenator> {
+        let mut tag = language.trim().to_ascii_lowercase();
+        loop {
+            if let Some(hyphenator) = self.by_language.get(&tag) {
+                return Some(hyphenator);
+            }
+            tag.truncate(tag.rfind('-')?);
+        }
+    }
+}
+
+/// Contents of the brace group after a TeX command, such as `\patterns{...}`
+fn tex_group<'s>(source: &'s str, command: &str) -> Option<&'s str> {
+    let rest = &source[source.find(command)? + command.len()..];
+    let rest = rest.trim_start().strip_prefix('{')?;
+    Some(&rest[..rest.find('}').unwrap_or(rest.len())])
+}
+
+/// Letters of a pattern such as `.ach4`, and the value before and after each
+fn parse_pattern(pattern: &str) -> Option<(String, Vec<u8>)> {
+    let mut letters = String::new();
+    let mut values = vec![0];
+    for c in pattern.chars() {
+        match c.to_digit(10) {
+            Some(digit) => *values.last_mut()? = digit as u8,
+            None => {
+                letters.push(lowercase(c));
+                values.push(0);
+            }
+        }
+    }
+    (!letters.is_empty()).then_some((letters, values))
+}
+
+/// Lowercase word and the positions of its hyphens, counted in letters
+fn parse_exception(word: &str) -> (String, Vec<usize>) {
+    let mut letters = String::new();
+    let mut breaks = Vec::new();
+    let mut count = 0;
+    for c in word.chars() {
+        if c == '-' {
+            breaks.push(count);
+        } else {
+            letters.push(lowercase(c));
+            count += 1;
+        }
+    }
+    (letters, breaks)
+}
+
+/// Lowercase a letter without changing the letter count
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
@@ -0,0 +1,244 @@
+use crate::layout::config::OptimalBreaking;
+
+/// Badness of a line that cannot stretch or shrink far enough
+const INFINITELY_BAD: f32 = 10000.0;
+
+/// Soft hyphen, an invisible hyphenation point written in the text
+pub(crate) const SOFT_HYPHEN: char = '\u{AD}';
+
+/// Box, glue and penalty items of a paragraph, as in Knuth and Plass
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Item<'t> {
+    /// Unbreakable text; `joins` is set when it continues the previous box's word
+    Box {
+        text: &'t str,
+        width: f32,
+        joins: bool,
+    },
+    /// A space that may stretch or shrink, and a place to break
+    Glue {
+        width: f32,
+        stretch: f32,
+        shrink: f32,
+    },
+    /// A place to break at a cost, adding `width` (a hyphen) when taken
+    ///
+    /// `flagged` marks hyphen breaks, so consecutive ones can be penalized.
+    Penalty {
+        width: f32,
+        cost: f32,
+        flagged: bool,
+    },
+}
+
+impl Item<'_> {
+    fn is_forced_break(&self) -> bool {
+        matches!(self, Item::Penalty { cost, .. } if *cost == f32::NEG_INFINITY)
+    }
+}
+
+/// A feasible break, linked to the best break before it
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    /// Index of the item broken at
+    position: usize,
+    /// Lines up to and including the one ending here
+    line: usize,
+    fitness: u8,
+    flagged: bool,
+    demerits: f32,
+    previous: Option<usize>,
+}
+
+/// Choose the breaks with the fewest total demerits
+///
+/// `items` must end with a forced break. Returns the indices of the items
+/// broken at, the last being the forced break, or `None` if no sequence of
+/// lines fits within the tolerance.
+pub(crate) fn find_breaks(
+    items: &[Item],
+    line_width: f32,
+    settings: &OptimalBreaking,
+    tolerance: f32,
+) -> Option<Vec<usize>> {
+    // Running totals of width, stretch and shrink before each item
+    let mut totals = Vec::with_capacity(items.len() + 1);
+    let mut sum = (0.0f32, 0.0f32, 0.0f32);
+    for item in items {
+        totals.push(sum);
+        match item {
+            Item::Box { width, .. } => sum.0 += width,
+            Item::Glue {
+                width,
+                stretch,
+                shrink,
+            } => {
+                sum.0 += width;
+                sum.1 += stretch;
+                sum.2 += shrink;
+            }
+            Item::Penalty { .. } => {}
+        }
+    }
+    totals.push(sum);
+
+    // A line after a break starts at the next box; glue and penalties before it vanish
+    let line_start = |position: usize| {
+        items[position + 1..]
+            .iter()
+            .position(|item| matches!(item, Item::Box { .. }))
+            .map_or(items.len(), |offset| position + 1 + offset)
+    };
+
+    let mut nodes = vec![Node {
+        position: 0,
+        line: 0,
+        fitness: 1,
+        flagged: false,
+        demerits: 0.0,
+        previous: None,
+    }];
+    // The first line starts at the first item, not after it
+    let mut starts = vec![0];
+    let mut active = vec![0];
+
+    for (position, item) in items.iter().enumerate() {
+        let (break_width, cost, flagged) = match *item {
+            Item::Glue { .. }
+                if position > 0 && matches!(items[position - 1], Item::Box { .. }) =>
+            {
+                (0.0, 0.0, false)
+            }
+            Item::Penalty {
+                width,
+                cost,
+                flagged,
+            } if cost < f32::INFINITY => (width, cost, flagged),
+            _ => continue,
+        };
+        let last = position + 1 == items.len();
+
+        // Best new node for each fitness class, and line count when looseness is set
+        let mut candidates: Vec<Node> = Vec::new();
+        active.retain(|&index| {
+            let node = nodes[index];
+            let start = starts[index];
+            let width = totals[position].0 - totals[start].0 + break_width;
+            let stretch = totals[position].1 - totals[start].1;
+            let shrink = totals[position].2 - totals[start].2;
+            let ratio = adjustment_ratio(width, stretch, shrink, line_width, last);
+
+            if ratio >= -1.0 {
+                let badness = badness(ratio);
+                if badness <= tolerance {
+                    let fitness = fitness_class(ratio);
+                    let mut demerits = (settings.line_penalty + badness).powi(2);
+                    if cost >= 0.0 {
+                        demerits += cost.powi(2);
+                    } else if cost > f32::NEG_INFINITY {
+                        demerits -= cost.powi(2);
+                    }
+                    if flagged && node.flagged {
+                        demerits += settings.consecutive_hyphen_demerits;
+                    }
+                    if fitness.abs_diff(node.fitness) > 1 {
+                        demerits += settings.fitness_demerits;
+                    }
+                    let candidate = Node {
+                        position,
+                        line: node.line + 1,
+                        fitness,
+                        flagged,
+                        demerits: node.demerits + demerits,
+                        previous: Some(index),
+                    };
+                    let rival = candidates.iter_mut().find(|other| {
+                        other.fitness == fitness
+                            && (settings.looseness == 0 || other.line == candidate.line)
+                    });
+                    match rival {
+                        Some(rival) if rival.demerits <= candidate.demerits => {}
+                        Some(rival) => *rival = candidate,
+                        None => candidates.push(candidate),
+                    }
+                }
+            }
+            // Lines from this node can only grow longer from here on
+            ratio >= -1.0 && !item.is_forced_break()
+        });
+
+        for candidate in candidates {
+            active.push(nodes.len());
+            starts.push(line_start(position));
+            nodes.push(candidate);
+        }
+        if active.is_empty() {
+            return None;
+        }
+    }
+
+    // The forced break at the end has deactivated every node before it
+    let finals = active;
+    let fewest = |a: &usize, b: &usize| nodes[*a].demerits.total_cmp(&nodes[*b].demerits);
+    let mut best = *finals.iter().min_by(|a, b| fewest(a, b))?;
+    if settings.looseness != 0 {
+        let target = nodes[best].line as i64 + i64::from(settings.looseness);
+        best = *finals
+            .iter()
+            .min_by(|a, b| {
+                let distance = |index: usize| (nodes[index].line as i64 - target).abs();
+                distance(**a).cmp(&distance(**b)).then_with(|| fewest(a, b))
+            })
+            .unwrap_or(&best);
+    }
+
+    let mut breaks = Vec::new();
+    let mut node = Some(best);
+    while let Some(index) = node.filter(|&index| index != 0) {
+        breaks.push(nodes[index].position);
+        node = nodes[index].previous;
+    }
+    breaks.reverse();
+    Some(breaks)
+}
+
+/// How far the spaces of a line must stretch (positive) or shrink (negative),
+/// as a fraction of how far they can
+///
+/// The last line of a paragraph is filled out with infinitely stretchable space.
+fn adjustment_ratio(width: f32, stretch: f32, shrink: f32, line_width: f32, last: bool) -> f32 {
+    if width < line_width {
+        if last {
+            0.0
+        } else if stretch > 0.0 {
+            (line_width - width) / stretch
+        } else {
+            f32::INFINITY
+        }
+    } else if width > line_width {
+        if shrink > 0.0 {
+            (line_width - width) / shrink
+        } else {
+            f32::NEG_INFINITY
+        }
+    } else {
+        0.0
+    }
+}
+
+fn badness(ratio: f32) -> f32 {
+    (100.0 * ratio.abs().powi(3)).min(INFINITELY_BAD)
+}
+
+/// Tight, decent, loose or very loose
+fn fitness_class(ratio: f32) -> u8 {
+    if ratio < -0.5 {
+        0
+    } else if ratio <= 0.5 {
+        1
+    } else if ratio <= 1.0 {
+        2
+    } else {
+        3
+    }
+}
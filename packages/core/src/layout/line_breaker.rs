@@ -0,0 +1,296 @@
+use crate::layout::config::{FontFeature, OptimalBreaking};
+use crate::layout::hyphenation::Hyphenator;
+use crate::layout::knuth_plass::{find_breaks, Item, SOFT_HYPHEN};
+use crate::layout::metrics::TextMetrics;
+
+/// One line produced by the line breaker
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrokenLine {
+    pub words: Vec<String>,
+    /// Natural width with single spaces between words
+    pub width: f32,
+}
+
+impl BrokenLine {
+    pub fn text(&self) -> String {
+        self.words.join(" ")
+    }
+}
+
+/// Breaks paragraphs into lines
+///
+/// By default each line is filled until the next word does not fit. With
+/// [`LineBreaker::optimal`], breaks are chosen for the whole paragraph at once.
+pub struct LineBreaker<'a> {
+    metrics: &'a dyn TextMetrics,
+    font_size: f32,
+    max_width: f32,
+    features: &'a [FontFeature],
+    optimal: Option<(&'a OptimalBreaking, bool)>,
+    hyphenator: Option<&'a Hyphenator>,
+}
+
+impl<'a> LineBreaker<'a> {
+    pub fn new(metrics: &'a dyn TextMetrics, font_size: f32, max_width: f32) -> Self {
+        Self {
+            metrics,
+            font_size,
+            max_width,
+            features: &[],
+            optimal: None,
+            hyphenator: None,
+        }
+    }
+
+    /// Measure words as shaped with these OpenType features
+    pub fn with_features(mut self, features: &'a [FontFeature]) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Use Knuth–Plass breaking, which may also break after hyphens and at
+    /// soft hyphens
+    ///
+    /// Spaces shrink as well as stretch when `justified`; ragged lines keep
+    /// their natural spacing and never run past the line width.
+    pub fn optimal(mut self, settings: &'a OptimalBreaking, justified: bool) -> Self {
+        self.optimal = Some((settings, justified));
+        self
+    }
+
+    /// Hyphenate words that would otherwise leave a line short
+    ///
+    /// Words with hyphens or soft hyphens of their own are only broken there.
+    /// The greedy breaker then also breaks at those.
+    pub fn with_hyphenator(mut self, hyphenator: &'a Hyphenator) -> Self {
+        self.hyphenator = Some(hyphenator);
+        self
+    }
+
+    /// Break text into lines; runs of whitespace collapse to one space
+    ///
+    /// A word wider than the line is placed on a line of its own.
+    pub fn break_text(&self, text: &str) -> Vec<BrokenLine> {
+        if let Some((settings, justified)) = self.optimal {
+            let items = self.items(text, settings, justified);
+            // A paragraph that fits nowhere within the tolerance gets a second
+            // pass accepting any spacing, then the greedy breaks
+            for tolerance in [settings.tolerance, f32::INFINITY] {
+                if let Some(breaks) = find_breaks(&items, self.max_width, settings, tolerance) {
+                    return self.lines_from_breaks(&items, &breaks);
+                }
+            }
+        }
+        self.break_greedy(text)
+    }
+
+    fn break_greedy(&self, text: &str) -> Vec<BrokenLine> {
+        let space = self.metrics.measure_char(' ', self.font_size);
+        let mut lines = Vec::new();
+        let mut current = BrokenLine {
+            words: Vec::new(),
+            width: 0.0,
+        };
+        let place = |current: &mut BrokenLine, word: String, width: f32| {
+            if !current.words.is_empty() {
+                current.width += space;
+            }
+            current.width += width;
+            current.words.push(word);
+        };
+
+        for word in text.split_whitespace() {
+            let pieces = match self.hyphenator {
+                Some(_) => self.word_pieces(word),
+                None => vec![(word, false)],
+            };
+            let mut rest = &pieces[..];
+            while !rest.is_empty() {
+                let word: String = rest.iter().map(|(piece, _)| *piece).collect();
+                let width = self.measure(&word);
+                let gap = if current.words.is_empty() { 0.0 } else { space };
+                if current.width + gap + width <= self.max_width {
+                    place(&mut current, word, width);
+                    break;
+                }
+                if let Some((head, head_width, taken)) =
+                    self.fitting_head(rest, self.max_width - current.width - gap)
+                {
+                    place(&mut current, head, head_width);
+                    rest = &rest[taken..];
+                } else if current.words.is_empty() {
+                    // Too wide for a line of its own
+                    place(&mut current, word, width);
+                    break;
+                }
+                lines.push(std::mem::replace(
+                    &mut current,
+                    BrokenLine {
+                        words: Vec::new(),
+                        width: 0.0,
+                    },
+                ));
+            }
+        }
+
+        if !current.words.is_empty() {
+            lines.push(current);
+        }
+        lines
+    }
+
+    /// Longest run of leading pieces that fits in `room`, with a hyphen added
+    /// after a soft break, its width and the number of pieces taken
+    fn fitting_head(&self, pieces: &[(&str, bool)], room: f32) -> Option<(String, f32, usize)> {
+        (1..pieces.len()).rev().find_map(|taken| {
+            let mut head: String = pieces[..taken].iter().map(|(piece, _)| *piece).collect();
+            if pieces[taken - 1].1 {
+                head.push('-');
+            }
+            let width = self.measure(&head);
+            (width <= room).then_some((head, width, taken))
+        })
+    }
+
+    fn measure(&self, text: &str) -> f32 {
+        self.metrics
+            .measure_shaped(text, self.font_size, self.features)
+    }
+
+    /// Non-empty pieces of a word between its break points, each with
+    /// whether the break after it is a soft hyphen
+    fn word_pieces<'w>(&self, word: &'w str) -> Vec<(&'w str, bool)> {
+        let mut pieces = marked_pieces(word);
+        if let Some(hyphenator) = self.hyphenator {
+            if pieces.len() == 1 && !word.contains(SOFT_HYPHEN) {
+                pieces.clear();
+                let mut start = 0;
+                for at in hyphenator.hyphenate(word) {
+                    pieces.push((&word[start..at], true));
+                    start = at;
+                }
+                pieces.push((&word[start..], false));
+            }
+        }
+        pieces.retain(|(piece, _)| !piece.is_empty());
+        pieces
+    }
+
+    /// Boxes for the pieces of each word, glue for the spaces between them and
+    /// penalties at hyphens
+    fn items<'t>(
+        &self,
+        text: &'t str,
+        settings: &OptimalBreaking,
+        justified: bool,
+    ) -> Vec<Item<'t>> {
+        let space = self.metrics.measure_char(' ', self.font_size);
+        let hyphen = self.measure("-");
+        let mut items = Vec::new();
+
+        for word in text.split_whitespace() {
+            let pieces = self.word_pieces(word);
+            if pieces.is_empty() {
+                continue;
+            }
+            if !items.is_empty() {
+                items.push(Item::Glue {
+                    width: space,
+                    stretch: space / 2.0,
+                    shrink: if justified { space / 3.0 } else { 0.0 },
+                });
+            }
+            // Whether the break after the previous piece is a soft hyphen
+            let mut after: Option<bool> = None;
+            for (piece, soft) in pieces {
+                if let Some(soft) = after {
+                    items.push(Item::Penalty {
+                        width: if soft { hyphen } else { 0.0 },
+                        cost: if soft {
+                            settings.hyphen_penalty
+                        } else {
+                            settings.explicit_hyphen_penalty
+                        },
+                        flagged: true,
+                    });
+                }
+                items.push(Item::Box {
+                    text: piece,
+                    width: self.measure(piece),
+                    joins: after.is_some(),
+                });
+                after = Some(soft);
+            }
+        }
+
+        if items.is_empty() {
+            return items;
+        }
+        items.push(Item::Penalty {
+            width: 0.0,
+            cost: f32::NEG_INFINITY,
+            flagged: false,
+        });
+        items
+    }
+
+    /// Lines between the chosen breaks, with a hyphen added where a word was
+    /// broken at a soft hyphen
+    fn lines_from_breaks(&self, items: &[Item], breaks: &[usize]) -> Vec<BrokenLine> {
+        let space = self.metrics.measure_char(' ', self.font_size);
+        let mut lines = Vec::with_capacity(breaks.len());
+        let mut start = 0;
+        for &end in breaks {
+            let mut words: Vec<String> = Vec::new();
+            for item in &items[start..end] {
+                if let Item::Box { text, joins, .. } = item {
+                    match words.last_mut() {
+                        Some(word) if *joins => word.push_str(text),
+                        _ => words.push(text.to_string()),
+                    }
+                }
+            }
+            if let (Some(word), Item::Penalty { width, .. }) = (words.last_mut(), &items[end]) {
+                if *width > 0.0 {
+                    word.push('-');
+                }
+            }
+            start = end + 1;
+
+            if words.is_empty() {
+                continue;
+            }
+            let width = words.iter().map(|word| self.measure(word)).sum::<f32>()
+                + space * (words.len() - 1) as f32;
+            lines.push(BrokenLine { words, width });
+        }
+        lines
+    }
+}
+
+/// Pieces of a word split at the hyphens and soft hyphens written in it
+///
+/// Explicit hyphens between letters stay at the end of their piece, and soft
+/// hyphens are dropped.
+fn marked_pieces(word: &str) -> Vec<(&str, bool)> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+    let mut chars = word.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let next = chars.peek().map(|(_, next)| *next);
+        if c == SOFT_HYPHEN {
+            pieces.push((&word[start..index], true));
+            start = index + c.len_utf8();
+        } else if c == '-'
+            && previous.is_some_and(char::is_alphanumeric)
+            && next.is_some_and(char::is_alphanumeric)
+        {
+            pieces.push((&word[start..index + 1], false));
+            start = index + 1;
+        }
+        previous = Some(c);
+    }
+    pieces.push((&word[start..], false));
+    pieces
+}
@@ -0,0 +1,70 @@
+use crate::layout::config::FontFeature;
+use crate::layout::types::PositionedGlyph;
+
+/// Pluggable text measurement used for line breaking and positioning
+pub trait TextMetrics {
+    /// Width of `text` in points
+    fn measure_text(&self, text: &str, font_size: f32) -> f32;
+    /// Width of a single character in points
+    fn measure_char(&self, c: char, font_size: f32) -> f32;
+    /// Height of one line in points
+    fn line_height(&self, font_size: f32, multiplier: f32) -> f32;
+    /// Distance from the top of the glyphs to the baseline
+    fn ascent(&self, font_size: f32) -> f32 {
+        font_size * 0.8
+    }
+
+    /// Glyphs for `text` after OpenType shaping, with `features` applied
+    ///
+    /// Metrics without a font return `None`, and text is measured and drawn
+    /// as characters.
+    fn shape(
+        &self,
+        _text: &str,
+        _font_size: f32,
+        _features: &[FontFeature],
+    ) -> Option<Vec<PositionedGlyph>> {
+        None
+    }
+
+    /// Width of `text` as shaped, or as measured when it cannot be shaped
+    fn measure_shaped(&self, text: &str, font_size: f32, features: &[FontFeature]) -> f32 {
+        match self.shape(text, font_size, features) {
+            Some(glyphs) => glyphs.iter().map(|glyph| glyph.advance).sum(),
+            None => self.measure_text(text, font_size),
+        }
+    }
+}
+
+/// Character-count approximation that needs no font data
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimpleTextMetrics {
+    /// Width of every character as a fraction of the font size
+    pub char_width_ratio: f32,
+}
+
+impl SimpleTextMetrics {
+    pub fn new(char_width_ratio: f32) -> Self {
+        Self { char_width_ratio }
+    }
+}
+
+impl Default for SimpleTextMetrics {
+    fn default() -> Self {
+        Self::new(0.6)
+    }
+}
+
+impl TextMetrics for SimpleTextMetrics {
+    fn measure_text(&self, text: &str, font_size: f32) -> f32 {
+        text.chars().map(|c| self.measure_char(c, font_size)).sum()
+    }
+
+    fn measure_char(&self, _c: char, font_size: f32) -> f32 {
+        font_size * self.char_width_ratio
+    }
+
+    fn line_height(&self, font_size: f32, multiplier: f32) -> f32 {
+        font_size * multiplier
+    }
+}
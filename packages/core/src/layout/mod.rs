@@ -0,0 +1,101 @@
+mod config;
+mod error;
+mod font;
+mod hyphenation;
+mod knuth_plass;
+mod line_breaker;
+mod metrics;
+mod paginator;
+mod types;
+
+#[cfg(test)]
+mod tests;
+
+pub use config::{
+    Alignment, FontFeature, Hyphenation, LayoutConfig, LineBreaking, Margins, OptimalBreaking,
+    PageSize, Pagination, RunningHeads, RunningText, TextStyle,
+};
+pub use error::LayoutError;
+pub use font::{FontCache, FontMetrics};
+pub use hyphenation::{HyphenationPatterns, Hyphenator};
+pub use line_breaker::{BrokenLine, LineBreaker};
+pub use metrics::{SimpleTextMetrics, TextMetrics};
+pub use types::{
+    ChapterStart, FrameType, NumberStyle, PageLabel, PageRender, PageSide, PaginationIssue,
+    PaginationRule, PositionedGlyph, Rect, RenderMetadata, RenderTree, TextFragment, TextFrame,
+    TextLine,
+};
+
+use crate::models::Book;
+use paginator::Paginator;
+
+/// Lay out a book with the default character-based metrics
+pub fn layout_book(book: &Book, config: &LayoutConfig) -> Result<RenderTree, LayoutError> {
+    layout_book_with_metrics(book, config, &SimpleTextMetrics::default())
+}
+
+/// Lay out a book, measuring text with `metrics`
+///
+/// Hyphenation, when enabled, uses the built-in patterns.
+pub fn layout_book_with_metrics(
+    book: &Book,
+    config: &LayoutConfig,
+    metrics: &dyn TextMetrics,
+) -> Result<RenderTree, LayoutError> {
+    layout_book_with_patterns(book, config, metrics, &HyphenationPatterns::builtin())
+}
+
+/// Lay out a book, hyphenating with `patterns` when hyphenation is enabled
+pub fn layout_book_with_patterns(
+    book: &Book,
+    config: &LayoutConfig,
+    metrics: &dyn TextMetrics,
+    patterns: &HyphenationPatterns,
+) -> Result<RenderTree, LayoutError> {
+    validate(config, metrics)?;
+    Ok(Paginator::new(config, metrics, patterns).layout(book))
+}
+
+fn validate(config: &LayoutConfig, metrics: &dyn TextMetrics) -> Result<(), LayoutError> {
+    let margins = config.margins;
+    let width = config.page_size.width - margins.inner - margins.outer;
+    let height = config.page_size.height - margins.top - margins.bottom;
+    if width <= 0.0 || height <= 0.0 {
+        return Err(LayoutError::InvalidConfig(
+            "margins leave no room for text on the page".to_string(),
+        ));
+    }
+
+    for (name, style) in [
+        ("body_style", &config.body_style),
+        ("chapter_title_style", &config.chapter_title_style),
+        ("running_heads.style", &config.running_heads.style),
+    ] {
+        if style.font_size <= 0.0 || style.line_height <= 0.0 {
+            return Err(LayoutError::InvalidConfig(format!(
+                "{} needs a positive font size and line height",
+                name
+            )));
+        }
+        if metrics.line_height(style.font_size, style.line_height) > height {
+            return Err(LayoutError::InvalidConfig(format!(
+                "a line of {} is taller than the text area",
+                name
+            )));
+        }
+    }
+    if let LineBreaking::Optimal(settings) = &config.line_breaking {
+        if settings.tolerance.is_nan() || settings.tolerance < 0.0 {
+            return Err(LayoutError::InvalidConfig(
+                "line breaking tolerance must not be negative".to_string(),
+            ));
+        }
+    }
+    let justification = config.pagination.vertical_justification;
+    if !(0.0..1.0).contains(&justification) {
+        return Err(LayoutError::InvalidConfig(
+            "vertical justification must be at least 0 and less than 1".to_string(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,763 @@
+use crate::bk_format::{parse_screenplay, ScreenplayElement};
+use crate::document::{Block, DocChapter, Document};
+use crate::layout::config::{Alignment, LayoutConfig, LineBreaking, RunningText, TextStyle};
+use crate::layout::hyphenation::{HyphenationPatterns, Hyphenator};
+use crate::layout::line_breaker::{BrokenLine, LineBreaker};
+use crate::layout::metrics::TextMetrics;
+use crate::layout::types::{
+    ChapterStart, FrameType, NumberStyle, PageLabel, PageRender, PageSide, PaginationIssue,
+    PaginationRule, Rect, RenderMetadata, RenderTree, TextFragment, TextFrame, TextLine,
+};
+use crate::models::{generate_block_id, Book, BookFormat, Chapter};
+use uuid::Uuid;
+
+/// Text drawn for a scene break
+const SCENE_BREAK: &str = "* * *";
+
+/// What a page holds, which decides its running heads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PageKind {
+    FrontMatter,
+    Blank,
+    /// The first page of a chapter
+    Opening,
+    Body,
+}
+
+/// A body line waiting for its page
+struct PendingLine {
+    line: BrokenLine,
+    style: TextStyle,
+    block_id: Uuid,
+    /// Position of the line in its block, and the block's number of lines
+    index: usize,
+    count: usize,
+    /// A scene break, which belongs with the text after it
+    keep_with_next: bool,
+}
+
+/// Horizontal extent of a line within the text area
+#[derive(Debug, Clone, Copy)]
+struct Column {
+    indent: f32,
+    width: f32,
+}
+
+/// Where a screenplay element sits, measured from the left of a 6" text area
+///
+/// These are the usual positions: cues at 3.7" from the page edge, dialogue
+/// at 2.5" and parentheticals at 3.1" when the left margin is 1.5".
+fn screenplay_column(element: &ScreenplayElement, full: Column) -> Column {
+    let (indent, width) = match element {
+        ScreenplayElement::Character(_) => (158.4, full.width - 158.4),
+        ScreenplayElement::Dialogue(_) => (72.0, 252.0),
+        ScreenplayElement::Parenthetical(_) => (115.2, 165.6),
+        _ => return full,
+    };
+    Column {
+        indent,
+        width: width.min(full.width - indent).max(1.0),
+    }
+}
+
+/// Lays a book out page by page
+pub(crate) struct Paginator<'a> {
+    config: &'a LayoutConfig,
+    metrics: &'a dyn TextMetrics,
+    patterns: &'a HyphenationPatterns,
+    pages: Vec<PageRender>,
+    chapters: Vec<ChapterStart>,
+    issues: Vec<PaginationIssue>,
+    /// Kind of the last page
+    page_kind: PageKind,
+    /// Label for the next page
+    label: PageLabel,
+    /// Texts for running heads
+    book_title: String,
+    author: String,
+    chapter_title: String,
+    /// Body lines of the last page, not yet wrapped in a frame
+    body_lines: Vec<TextLine>,
+    /// Top of the body frame, relative to the top of the content area
+    body_top: f32,
+    /// Where the next body line starts, relative to the top of the content area
+    cursor: f32,
+}
+
+impl<'a> Paginator<'a> {
+    pub(crate) fn new(
+        config: &'a LayoutConfig,
+        metrics: &'a dyn TextMetrics,
+        patterns: &'a HyphenationPatterns,
+    ) -> Self {
+        Self {
+            config,
+            metrics,
+            patterns,
+            pages: Vec::new(),
+            chapters: Vec::new(),
+            issues: Vec::new(),
+            page_kind: PageKind::Blank,
+            label: PageLabel {
+                style: if config.front_matter {
+                    NumberStyle::LowerRoman
+                } else {
+                    NumberStyle::Arabic
+                },
+                number: 1,
+            },
+            book_title: String::new(),
+            author: String::new(),
+            chapter_title: String::new(),
+            body_lines: Vec::new(),
+            body_top: 0.0,
+            cursor: 0.0,
+        }
+    }
+
+    pub(crate) fn layout(mut self, book: &Book) -> RenderTree {
+        let doc = Document::from_book(book);
+        self.book_title = doc.title.clone();
+        self.author = doc.author.clone();
+        if self.config.front_matter {
+            self.layout_front_matter(&doc);
+        }
+        let mut sources: Vec<&Chapter> = book.chapters.iter().collect();
+        sources.sort_by_key(|c| c.order);
+        for (chapter, source) in doc.chapters.iter().zip(sources) {
+            self.layout_chapter_title(chapter);
+            match book.format {
+                BookFormat::Prose => {
+                    let language = source.language.as_deref().unwrap_or(&doc.language);
+                    let hyphenator = self.hyphenator(language, book);
+                    self.layout_blocks(chapter, hyphenator.as_ref())
+                }
+                BookFormat::Screenplay => {
+                    self.layout_screenplay(chapter.id, &parse_screenplay(&source.content))
+                }
+            }
+        }
+        self.finish_page();
+
+        RenderTree {
+            book_id: book.id,
+            metadata: RenderMetadata {
+                total_pages: self.pages.len(),
+                total_chapters: doc.chapters.len(),
+                page_size: self.config.page_size,
+                chapters: self.chapters,
+                pagination_issues: self.issues,
+            },
+            pages: self.pages,
+        }
+    }
+
+    /// Hyphenator for body text in `language`, with the book's custom hyphenations
+    fn hyphenator(&self, language: &str, book: &Book) -> Option<Hyphenator> {
+        let settings = self.config.hyphenation;
+        if !settings.enabled {
+            return None;
+        }
+        let mut hyphenator = self
+            .patterns
+            .get(language)?
+            .clone()
+            .with_min(settings.left_min, settings.right_min);
+        for word in &book.hyphenation {
+            hyphenator.add_exception(word);
+        }
+        Some(hyphenator)
+    }
+
+    /// Title page, then the dedication on a page of its own
+    fn layout_front_matter(&mut self, doc: &Document) {
+        let title = self.config.chapter_title_style.clone();
+        let body = TextStyle {
+            alignment: Alignment::Center,
+            ..self.config.body_style.clone()
+        };
+        self.start_page(None, PageKind::FrontMatter);
+        self.push_display_text(&[(&doc.title, &title), ("", &body), (&doc.author, &body)]);
+
+        if let Some(dedication) = &doc.dedication {
+            if self.config.first_chapter_on_odd_page && self.pages.len() % 2 == 1 {
+                self.start_page(None, PageKind::Blank);
+            }
+            self.start_page(None, PageKind::FrontMatter);
+            let lines: Vec<(&str, &TextStyle)> = dedication
+                .lines()
+                .map(|line| (line.trim(), &body))
+                .collect();
+            self.push_display_text(&lines);
+        }
+    }
+
+    /// Set text on the last page a third of the way down; empty text leaves a blank line
+    fn push_display_text(&mut self, texts: &[(&str, &TextStyle)]) {
+        let content = self.content_rect();
+        let mut lines = Vec::new();
+        let mut height = 0.0;
+        for (text, style) in texts {
+            let line_height = self.metrics.line_height(style.font_size, style.line_height);
+            if text.is_empty() {
+                height += line_height;
+                continue;
+            }
+            for line in self.break_lines(text, style, content.width, None) {
+                let y_offset = height + self.baseline(style, line_height);
+                lines.push(self.place_line(&line, style, content.width, true, y_offset, None));
+                height += line_height;
+            }
+        }
+        let top = (content.height / 3.0).min(content.height - height).max(0.0);
+        if let Some(page) = self.pages.last_mut() {
+            page.frames.push(TextFrame {
+                bounds: Rect {
+                    y: content.y + top,
+                    height,
+                    ..content
+                },
+                lines,
+                frame_type: FrameType::FrontMatter,
+            });
+        }
+    }
+
+    fn layout_chapter_title(&mut self, chapter: &DocChapter) {
+        if self.config.first_chapter_on_odd_page && self.pages.len() % 2 == 1 {
+            self.start_page(None, PageKind::Blank);
+        }
+        // The first chapter ends the front matter and starts again at 1
+        if self.label.style == NumberStyle::LowerRoman {
+            self.label = PageLabel {
+                style: NumberStyle::Arabic,
+                number: 1,
+            };
+        }
+        self.start_page(Some(chapter.id), PageKind::Opening);
+        self.chapter_title = chapter.title.clone();
+        self.chapters.push(ChapterStart {
+            chapter_id: chapter.id,
+            title: chapter.title.clone(),
+            page_number: self.pages.len(),
+        });
+
+        let style = &self.config.chapter_title_style;
+        let line_height = self.metrics.line_height(style.font_size, style.line_height);
+        let content = self.content_rect();
+        let lines = self.break_lines(&chapter.title, style, content.width, None);
+        let title_lines: Vec<TextLine> = lines
+            .iter()
+            .enumerate()
+            .map(|(index, line)| {
+                let y_offset = index as f32 * line_height + self.baseline(style, line_height);
+                self.place_line(line, style, content.width, true, y_offset, Some(chapter.id))
+            })
+            .collect();
+        let title_height = title_lines.len() as f32 * line_height;
+        if let Some(page) = self.pages.last_mut() {
+            page.frames.push(TextFrame {
+                bounds: Rect {
+                    height: title_height,
+                    ..content
+                },
+                lines: title_lines,
+                frame_type: FrameType::ChapterTitle,
+            });
+        }
+        // Leave one title line of space below the title
+        self.body_top = title_height + line_height;
+        self.cursor = self.body_top;
+    }
+
+    fn layout_blocks(&mut self, chapter: &DocChapter, hyphenator: Option<&Hyphenator>) {
+        let column = self.full_column();
+        let mut pending = Vec::new();
+        for (order, block) in chapter.blocks.iter().enumerate() {
+            let block_id = generate_block_id(&chapter.id, order);
+            match block {
+                Block::Paragraph(text) => {
+                    let style = self.config.body_style.clone();
+                    let lines = self.break_lines(text, &style, column.width, hyphenator);
+                    let count = lines.len();
+                    pending.extend(lines.into_iter().enumerate().map(|(index, line)| {
+                        PendingLine {
+                            line,
+                            style: style.clone(),
+                            block_id,
+                            index,
+                            count,
+                            keep_with_next: false,
+                        }
+                    }));
+                }
+                // Screenplay books are laid out by `layout_screenplay`
+                Block::Screenplay(_) => {}
+                Block::SceneBreak => {
+                    let style = TextStyle {
+                        alignment: Alignment::Center,
+                        ..self.config.body_style.clone()
+                    };
+                    let line = BrokenLine {
+                        words: vec![SCENE_BREAK.to_string()],
+                        width: self.metrics.measure_shaped(
+                            SCENE_BREAK,
+                            style.font_size,
+                            &style.features,
+                        ),
+                    };
+                    pending.push(PendingLine {
+                        line,
+                        style,
+                        block_id,
+                        index: 0,
+                        count: 1,
+                        keep_with_next: true,
+                    });
+                }
+            }
+        }
+        self.fill_pages(&pending, column, chapter.id);
+    }
+
+    /// Place a chapter's body lines, breaking pages where the pagination rules allow
+    fn fill_pages(&mut self, lines: &[PendingLine], column: Column, chapter_id: Uuid) {
+        let body = &self.config.body_style;
+        let line_height = self.metrics.line_height(body.font_size, body.line_height);
+        let mut start = 0;
+        while start < lines.len() {
+            let (count, spacing) = self.page_break(lines, start, line_height);
+            for pending in &lines[start..start + count] {
+                let last = pending.index + 1 == pending.count;
+                self.place_body_line(
+                    &pending.line,
+                    &pending.style,
+                    column,
+                    last,
+                    pending.block_id,
+                );
+                self.cursor += spacing;
+            }
+            start += count;
+            if start < lines.len() {
+                self.start_page(Some(chapter_id), PageKind::Body);
+            }
+        }
+    }
+
+    /// How many of the lines from `start` go on the current page, and the
+    /// space to add between them (negative to squeeze them together)
+    ///
+    /// A page breaks after all it has room for when the rules allow it.
+    /// Otherwise the break that keeps the rules with the least change to the
+    /// line spacing is chosen; a page whose lines would spread further than
+    /// the limit is left short instead. Failing that, the page is filled and
+    /// the broken rule reported.
+    fn page_break(
+        &mut self,
+        lines: &[PendingLine],
+        start: usize,
+        line_height: f32,
+    ) -> (usize, f32) {
+        let available = self.content_height() - self.cursor;
+        let remaining = lines.len() - start;
+        let room = ((available + 0.01) / line_height).floor() as usize;
+        if remaining <= room {
+            return (remaining, 0.0);
+        }
+        let rules = self.config.pagination;
+        if room > 0 && self.break_rule(lines, start + room).is_none() {
+            return (room, 0.0);
+        }
+
+        let limit = rules.vertical_justification * line_height;
+        // A single line has no spacing to adjust
+        let spacing = |count: usize| {
+            if count > 1 {
+                (available - count as f32 * line_height) / (count - 1) as f32
+            } else {
+                f32::INFINITY
+            }
+        };
+        let squeezable = (room.max(1) + 1..=remaining)
+            .take_while(|&count| spacing(count) >= -limit)
+            .last()
+            .unwrap_or(room);
+        let best = (1..=squeezable)
+            .filter(|&count| count != room && self.break_rule(lines, start + count).is_none())
+            .min_by(|a, b| spacing(*a).abs().total_cmp(&spacing(*b).abs()));
+        if let Some(count) = best {
+            let spacing = spacing(count);
+            return (count, if spacing > limit { 0.0 } else { spacing });
+        }
+
+        // A chapter title may be left alone on its page if it need not be kept
+        let title_page = self.body_lines.is_empty() && self.body_top > 0.0;
+        if room == 0 || (title_page && !rules.keep_with_next) {
+            if title_page && rules.keep_with_next {
+                self.report(PaginationRule::KeepWithNext);
+            }
+            return (0, 0.0);
+        }
+        if let Some(rule) = self.break_rule(lines, start + room) {
+            self.report(rule);
+        }
+        (room, 0.0)
+    }
+
+    /// The rule broken by a page break before `lines[at]`, if any
+    fn break_rule(&self, lines: &[PendingLine], at: usize) -> Option<PaginationRule> {
+        let rules = &self.config.pagination;
+        let (Some(before), Some(after)) = (at.checked_sub(1).map(|i| &lines[i]), lines.get(at))
+        else {
+            return None;
+        };
+        if rules.keep_with_next && before.keep_with_next {
+            Some(PaginationRule::KeepWithNext)
+        } else if before.block_id == after.block_id && after.index < rules.orphans {
+            Some(PaginationRule::Orphans)
+        } else if before.block_id == after.block_id && after.count - after.index < rules.widows {
+            Some(PaginationRule::Widows)
+        } else if lines.len() - at < rules.last_page_lines {
+            Some(PaginationRule::LastPageLines)
+        } else {
+            None
+        }
+    }
+
+    /// Note that the last page ends where a pagination rule could not be kept
+    fn report(&mut self, rule: PaginationRule) {
+        self.issues.push(PaginationIssue {
+            page_number: self.pages.len(),
+            rule,
+        });
+    }
+
+    /// Lay out screenplay elements in their columns, a blank line apart
+    ///
+    /// Parentheticals and dialogue follow their cue without a gap.
+    fn layout_screenplay(&mut self, chapter_id: Uuid, elements: &[ScreenplayElement]) {
+        let body = self.config.body_style.clone();
+        let line_height = self.metrics.line_height(body.font_size, body.line_height);
+        for (order, element) in elements.iter().enumerate() {
+            let block_id = generate_block_id(&chapter_id, order);
+            let (text, alignment) = match element {
+                ScreenplayElement::PageBreak => {
+                    if !self.body_lines.is_empty() {
+                        self.start_page(Some(chapter_id), PageKind::Body);
+                    }
+                    continue;
+                }
+                ScreenplayElement::Transition(text) => (text, Alignment::Right),
+                ScreenplayElement::Centered(text) => (text, Alignment::Center),
+                ScreenplayElement::SceneHeading(text)
+                | ScreenplayElement::Action(text)
+                | ScreenplayElement::Character(text)
+                | ScreenplayElement::Parenthetical(text)
+                | ScreenplayElement::Dialogue(text) => (text, Alignment::Left),
+            };
+            let follows_cue = matches!(
+                element,
+                ScreenplayElement::Parenthetical(_) | ScreenplayElement::Dialogue(_)
+            );
+            if !follows_cue && !self.body_lines.is_empty() {
+                self.cursor += line_height;
+            }
+
+            let style = TextStyle {
+                alignment,
+                ..body.clone()
+            };
+            let column = screenplay_column(element, self.full_column());
+            for line in text.lines() {
+                self.push_text(line, &style, column, block_id, chapter_id);
+            }
+        }
+    }
+
+    /// Break text to the column width and add its lines to the body
+    fn push_text(
+        &mut self,
+        text: &str,
+        style: &TextStyle,
+        column: Column,
+        block_id: Uuid,
+        chapter_id: Uuid,
+    ) {
+        let lines = self.break_lines(text, style, column.width, None);
+        self.push_lines(&lines, style, column, block_id, chapter_id);
+    }
+
+    /// Add the lines of one block to the body
+    fn push_lines(
+        &mut self,
+        lines: &[BrokenLine],
+        style: &TextStyle,
+        column: Column,
+        block_id: Uuid,
+        chapter_id: Uuid,
+    ) {
+        let count = lines.len();
+        for (index, line) in lines.iter().enumerate() {
+            self.push_body_line(
+                line,
+                style,
+                column,
+                index + 1 == count,
+                block_id,
+                chapter_id,
+            );
+        }
+    }
+
+    fn full_column(&self) -> Column {
+        Column {
+            indent: 0.0,
+            width: self.content_rect().width,
+        }
+    }
+
+    fn break_lines(
+        &self,
+        text: &str,
+        style: &TextStyle,
+        width: f32,
+        hyphenator: Option<&Hyphenator>,
+    ) -> Vec<BrokenLine> {
+        let mut breaker =
+            LineBreaker::new(self.metrics, style.font_size, width).with_features(&style.features);
+        if let Some(hyphenator) = hyphenator {
+            breaker = breaker.with_hyphenator(hyphenator);
+        }
+        match &self.config.line_breaking {
+            LineBreaking::Greedy => breaker,
+            LineBreaking::Optimal(settings) => {
+                breaker.optimal(settings, style.alignment == Alignment::Justify)
+            }
+        }
+        .break_text(text)
+    }
+
+    /// Add a line to the body frame, starting a new page when it does not fit
+    fn push_body_line(
+        &mut self,
+        line: &BrokenLine,
+        style: &TextStyle,
+        column: Column,
+        last: bool,
+        block_id: Uuid,
+        chapter_id: Uuid,
+    ) {
+        let line_height = self.metrics.line_height(style.font_size, style.line_height);
+        if self.cursor + line_height > self.content_height() && !self.body_lines.is_empty() {
+            self.start_page(Some(chapter_id), PageKind::Body);
+        }
+        self.place_body_line(line, style, column, last, block_id);
+    }
+
+    /// Add a line to the body frame at the cursor
+    fn place_body_line(
+        &mut self,
+        line: &BrokenLine,
+        style: &TextStyle,
+        column: Column,
+        last: bool,
+        block_id: Uuid,
+    ) {
+        let line_height = self.metrics.line_height(style.font_size, style.line_height);
+        let y_offset = self.cursor - self.body_top + self.baseline(style, line_height);
+        let mut line = self.place_line(line, style, column.width, last, y_offset, Some(block_id));
+        for fragment in &mut line.fragments {
+            fragment.x_offset += column.indent;
+        }
+        self.body_lines.push(line);
+        self.cursor += line_height;
+    }
+
+    /// Distance from the top of a line box to its baseline
+    fn baseline(&self, style: &TextStyle, line_height: f32) -> f32 {
+        (line_height - style.font_size) / 2.0 + self.metrics.ascent(style.font_size)
+    }
+
+    /// Position a broken line horizontally according to its alignment
+    fn place_line(
+        &self,
+        line: &BrokenLine,
+        style: &TextStyle,
+        width: f32,
+        last: bool,
+        y_offset: f32,
+        source: Option<Uuid>,
+    ) -> TextLine {
+        let fragment = |text: String, x_offset: f32, width: f32| TextFragment {
+            glyphs: self
+                .metrics
+                .shape(&text, style.font_size, &style.features)
+                .unwrap_or_default(),
+            text,
+            x_offset,
+            width,
+            style: style.clone(),
+            source_block_id: source,
+        };
+
+        let slack = width - line.width;
+        let fragments = match style.alignment {
+            // Optimal breaking may shrink spaces, so slack can be negative here
+            Alignment::Justify if !last && line.words.len() > 1 => {
+                let space = self.metrics.measure_char(' ', style.font_size)
+                    + slack / (line.words.len() - 1) as f32;
+                let mut x = 0.0;
+                line.words
+                    .iter()
+                    .map(|word| {
+                        let word_width =
+                            self.metrics
+                                .measure_shaped(word, style.font_size, &style.features);
+                        let placed = fragment(word.clone(), x, word_width);
+                        x += word_width + space;
+                        placed
+                    })
+                    .collect()
+            }
+            alignment => {
+                let slack = slack.max(0.0);
+                let x_offset = match alignment {
+                    Alignment::Center => slack / 2.0,
+                    Alignment::Right => slack,
+                    _ => 0.0,
+                };
+                vec![fragment(line.text(), x_offset, line.width)]
+            }
+        };
+
+        TextLine {
+            y_offset,
+            fragments,
+        }
+    }
+
+    fn start_page(&mut self, chapter_id: Option<Uuid>, kind: PageKind) {
+        self.finish_page();
+        let page_number = self.pages.len() + 1;
+        self.pages.push(PageRender {
+            page_number,
+            label: self.label,
+            side: PageSide::for_page(page_number),
+            chapter_id,
+            frames: Vec::new(),
+        });
+        self.label.number += 1;
+        self.page_kind = kind;
+        self.body_top = 0.0;
+        self.cursor = 0.0;
+    }
+
+    /// Wrap the pending body lines in a frame and add the running heads
+    fn finish_page(&mut self) {
+        let content = self.content_rect();
+        let margins = self.config.margins;
+        let page_height = self.config.page_size.height;
+        let body_top = self.body_top;
+        let body_lines = std::mem::take(&mut self.body_lines);
+        let running = self.running_frames();
+
+        let Some(page) = self.pages.last_mut() else {
+            return;
+        };
+        if !body_lines.is_empty() {
+            page.frames.push(TextFrame {
+                bounds: Rect {
+                    y: content.y + body_top,
+                    height: page_height - margins.bottom - content.y - body_top,
+                    ..content
+                },
+                lines: body_lines,
+                frame_type: FrameType::BodyText,
+            });
+        }
+        page.frames.extend(running);
+    }
+
+    /// Running head and foot of the last page
+    fn running_frames(&self) -> Vec<TextFrame> {
+        let Some(page) = self.pages.last() else {
+            return Vec::new();
+        };
+        let heads = &self.config.running_heads;
+        let (head, foot) = if self.config.two_sided && page.side == PageSide::Left {
+            (heads.verso_head, heads.verso_foot)
+        } else {
+            (heads.recto_head, heads.recto_foot)
+        };
+        let (head, foot) = match self.page_kind {
+            PageKind::Body => (head, foot),
+            PageKind::Opening if heads.folio_on_openings => (None, Some(RunningText::Folio)),
+            _ => return Vec::new(),
+        };
+
+        let margins = self.config.margins;
+        let foot_y = self.config.page_size.height - margins.bottom;
+        [(head, 0.0, margins.top), (foot, foot_y, margins.bottom)]
+            .into_iter()
+            .filter_map(|(text, y, height)| Some(self.running_frame(text?, y, height)))
+            .collect()
+    }
+
+    /// One line of running text, centred vertically in a margin
+    fn running_frame(&self, text: RunningText, y: f32, height: f32) -> TextFrame {
+        let (text, frame_type) = match text {
+            RunningText::Author => (self.author.clone(), FrameType::RunningHead),
+            RunningText::BookTitle => (self.book_title.clone(), FrameType::RunningHead),
+            RunningText::ChapterTitle => (self.chapter_title.clone(), FrameType::RunningHead),
+            RunningText::Folio => {
+                let label = self.pages.last().map(|page| page.label.to_string());
+                (label.unwrap_or_default(), FrameType::PageNumber)
+            }
+        };
+        let content = self.content_rect();
+        let style = &self.config.running_heads.style;
+        let line = BrokenLine {
+            width: self
+                .metrics
+                .measure_shaped(&text, style.font_size, &style.features),
+            words: vec![text],
+        };
+        let y_offset = (height + style.font_size) / 2.0;
+        TextFrame {
+            bounds: Rect {
+                y,
+                height,
+                ..content
+            },
+            lines: vec![self.place_line(&line, style, content.width, true, y_offset, None)],
+            frame_type,
+        }
+    }
+
+    fn content_height(&self) -> f32 {
+        self.config.page_size.height - self.config.margins.top - self.config.margins.bottom
+    }
+
+    /// Text area of the last page; the inner margin is on the binding side
+    ///
+    /// One-sided layouts keep the inner margin on the left of every page.
+    fn content_rect(&self) -> Rect {
+        let margins = self.config.margins;
+        let side = if self.config.two_sided {
+            self.pages.last().map_or(PageSide::Right, |page| page.side)
+        } else {
+            PageSide::Right
+        };
+        let x = match side {
+            PageSide::Right => margins.inner,
+            PageSide::Left => margins.outer,
+        };
+        Rect {
+            x,
+            y: margins.top,
+            width: self.config.page_size.width - margins.inner - margins.outer,
+            height: self.content_height(),
+        }
+    }
+}
@@ -0,0 +1,965 @@
+use crate::layout::{
+    layout_book, layout_book_with_metrics, Alignment, FontCache, FontFeature, FontMetrics,
+    FrameType, Hyphenation, HyphenationPatterns, Hyphenator, LayoutConfig, LayoutError,
+    LineBreaker, LineBreaking, Margins, NumberStyle, OptimalBreaking, PageLabel, PageSide,
+    PageSize, Pagination, PaginationIssue, PaginationRule, RenderTree, RunningHeads, RunningText,
+    SimpleTextMetrics, TextMetrics, TextStyle,
+};
+use crate::models::{generate_block_id, Book, BookFormat};
+
+fn book_with(chapters: &[(&str, &str)]) -> Book {
+    let mut book = Book::new("Book".to_string(), "Author".to_string());
+    for (title, content) in chapters {
+        book.add_chapter(title.to_string(), content.to_string());
+    }
+    book
+}
+
+#[test]
+fn test_simple_metrics() {
+    let metrics = SimpleTextMetrics::default();
+    assert_eq!(metrics.measure_char('W', 10.0), 6.0);
+    assert_eq!(metrics.measure_text("abc", 10.0), 18.0);
+    assert_eq!(metrics.line_height(12.0, 1.5), 18.0);
+}
+
+#[test]
+fn test_line_breaking() {
+    let metrics = SimpleTextMetrics::default();
+    // 10pt at 0.6 is 6pt per character, so 60pt holds 10 characters
+    let breaker = LineBreaker::new(&metrics, 10.0, 60.0);
+
+    assert!(breaker.break_text("").is_empty());
+    assert!(breaker.break_text("   \n ").is_empty());
+
+    let single = breaker.break_text("word");
+    assert_eq!(single.len(), 1);
+    assert_eq!(single[0].width, 24.0);
+
+    let lines = breaker.break_text("one two   three four");
+    let texts: Vec<String> = lines.iter().map(|l| l.text()).collect();
+    assert_eq!(texts, ["one two", "three four"]);
+    assert_eq!(lines[1].width, 60.0);
+
+    let long = breaker.break_text("a extraordinarily b");
+    let texts: Vec<String> = long.iter().map(|l| l.text()).collect();
+    assert_eq!(texts, ["a", "extraordinarily", "b"]);
+}
+
+/// Sum of squared gaps at the ends of all but the last line
+fn raggedness(lines: &[crate::layout::BrokenLine], width: f32) -> f32 {
+    let mut lines = lines.to_vec();
+    lines.pop();
+    lines.iter().map(|line| (width - line.width).powi(2)).sum()
+}
+
+#[test]
+fn test_optimal_line_breaking() {
+    let metrics = SimpleTextMetrics::default();
+    let settings = OptimalBreaking::default();
+    let text = "aaa bb cc ddddd ee ff gggg hh iii jjjjjjj k llll mmm nnn oooooo pp qqq rr";
+    let greedy = LineBreaker::new(&metrics, 10.0, 120.0).break_text(text);
+    let optimal = LineBreaker::new(&metrics, 10.0, 120.0)
+        .optimal(&settings, false)
+        .break_text(text);
+
+    let words = |lines: &[crate::layout::BrokenLine]| -> Vec<String> {
+        lines.iter().flat_map(|l| l.words.clone()).collect()
+    };
+    assert_eq!(words(&optimal), words(&greedy));
+    assert!(optimal.iter().all(|line| line.width <= 120.0));
+    assert!(raggedness(&optimal, 120.0) < raggedness(&greedy, 120.0));
+
+    let looser = OptimalBreaking {
+        looseness: 1,
+        tolerance: 10000.0,
+        ..settings
+    };
+    let loose = LineBreaker::new(&metrics, 10.0, 120.0)
+        .optimal(&looser, false)
+        .break_text(text);
+    assert_eq!(loose.len(), optimal.len() + 1);
+
+    assert!(LineBreaker::new(&metrics, 10.0, 120.0)
+        .optimal(&settings, true)
+        .break_text(" \n ")
+        .is_empty());
+
+    // A word wider than the line falls back to greedy breaking
+    let long = LineBreaker::new(&metrics, 10.0, 60.0)
+        .optimal(&settings, true)
+        .break_text("a extraordinarily b");
+    let texts: Vec<String> = long.iter().map(|l| l.text()).collect();
+    assert_eq!(texts, ["a", "extraordinarily", "b"]);
+}
+
+#[test]
+fn test_optimal_breaking_at_hyphens() {
+    let metrics = SimpleTextMetrics::default();
+    let settings = OptimalBreaking::default();
+    let breaker = LineBreaker::new(&metrics, 10.0, 60.0).optimal(&settings, false);
+
+    let texts: Vec<String> = breaker
+        .break_text("abcd well-known")
+        .iter()
+        .map(|l| l.text())
+        .collect();
+    assert_eq!(texts, ["abcd well-", "known"]);
+
+    let texts: Vec<String> = breaker
+        .break_text("abcdef co\u{AD}operate")
+        .iter()
+        .map(|l| l.text())
+        .collect();
+    assert_eq!(texts, ["abcdef co-", "operate"]);
+
+    // Soft hyphens not broken at are dropped
+    let texts: Vec<String> = breaker
+        .break_text("co\u{AD}op")
+        .iter()
+        .map(|l| l.text())
+        .collect();
+    assert_eq!(texts, ["coop"]);
+
+    let never = OptimalBreaking {
+        hyphen_penalty: f32::INFINITY,
+        explicit_hyphen_penalty: f32::INFINITY,
+        ..settings
+    };
+    let texts: Vec<String> = LineBreaker::new(&metrics, 10.0, 60.0)
+        .optimal(&never, false)
+        .break_text("abcd well-known")
+        .iter()
+        .map(|l| l.text())
+        .collect();
+    assert_eq!(texts, ["abcd", "well-known"]);
+}
+
+#[test]
+fn test_layout_with_optimal_breaking() {
+    let text = "The quick brown fox jumps over the lazy dog and keeps on running \
+                through fields of barley until the sun goes down. "
+        .repeat(6);
+    let book = book_with(&[("Chapter", &text)]);
+    let config = LayoutConfig {
+        line_breaking: LineBreaking::Optimal(OptimalBreaking::default()),
+        ..LayoutConfig::default()
+    };
+    let tree = layout_book(&book, &config).unwrap();
+    let width = config.page_size.width - config.margins.inner - config.margins.outer;
+
+    let body = tree.pages[0]
+        .frames
+        .iter()
+        .find(|f| f.frame_type == FrameType::BodyText)
+        .unwrap();
+    let (last, lines) = body.lines.split_last().unwrap();
+    for line in lines {
+        let end = line.fragments.last().unwrap();
+        assert!((end.x_offset + end.width - width).abs() < 0.01);
+    }
+    assert!(last.fragments.len() == 1);
+
+    let json = serde_json::to_string(&config).unwrap();
+    let parsed: LayoutConfig = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, config);
+    let parsed: LayoutConfig =
+        serde_json::from_str(r#"{"line_breaking": {"Optimal": {"looseness": -1}}}"#).unwrap();
+    assert_eq!(
+        parsed.line_breaking,
+        LineBreaking::Optimal(OptimalBreaking {
+            looseness: -1,
+            ..OptimalBreaking::default()
+        })
+    );
+
+    let invalid = LayoutConfig {
+        line_breaking: LineBreaking::Optimal(OptimalBreaking {
+            tolerance: -1.0,
+            ..OptimalBreaking::default()
+        }),
+        ..LayoutConfig::default()
+    };
+    assert!(matches!(
+        layout_book(&book, &invalid),
+        Err(LayoutError::InvalidConfig(_))
+    ));
+}
+
+/// Split a word at its hyphenation points
+fn hyphenated(hyphenator: &Hyphenator, word: &str) -> String {
+    let mut out = String::new();
+    let mut start = 0;
+    for at in hyphenator.hyphenate(word) {
+        out.push_str(&word[start..at]);
+        out.push('-');
+        start = at;
+    }
+    out.push_str(&word[start..]);
+    out
+}
+
+#[test]
+fn test_hyphenator() {
+    let english = Hyphenator::english();
+    assert_eq!(
+        hyphenated(&english, "\"Typesetting,\""),
+        "\"Type-set-ting,\""
+    );
+    assert_eq!(hyphenated(&english, "algorithm"), "al-go-rithm");
+    assert_eq!(hyphenated(&english, "cat"), "cat");
+    assert_eq!(hyphenated(&english, "x2y"), "x2y");
+    // Three letters must follow the last hyphen: not "com-put-er"
+    assert_eq!(hyphenated(&english, "computer"), "com-puter");
+    // From the exception list rather than the patterns
+    assert_eq!(hyphenated(&english, "hyphenation"), "hy-phen-a-tion");
+
+    let strict = english.clone().with_min(3, 4);
+    assert_eq!(hyphenated(&strict, "hyphenation"), "hyphen-a-tion");
+
+    let mut custom = english.clone();
+    custom.add_exception("hy-phenation");
+    custom.add_exception("Typesetting");
+    assert_eq!(hyphenated(&custom, "Hyphenation"), "Hy-phenation");
+    assert_eq!(hyphenated(&custom, "typesetting"), "typesetting");
+    assert_eq!(hyphenated(&english, "typesetting"), "type-set-ting");
+
+    // Liang's example patterns, in a TeX file
+    let tex = r"
+        % Patterns from Liang's thesis
+        \patterns{ hy3ph he2n hena4 hen5at 1na n2at 1tio 2io o2n }
+        \hyphenation{ ta-ble }
+    ";
+    let liang = Hyphenator::parse(tex).unwrap().with_min(1, 1);
+    assert_eq!(hyphenated(&liang, "hyphenation"), "hy-phen-ation");
+    assert_eq!(hyphenated(&liang, "table"), "ta-ble");
+    assert!(matches!(
+        Hyphenator::parse("% nothing"),
+        Err(LayoutError::Hyphenation(_))
+    ));
+    assert!(matches!(
+        Hyphenator::parse("\\patterns{ a1b 12 }"),
+        Err(LayoutError::Hyphenation(_))
+    ));
+
+    let mut patterns = HyphenationPatterns::builtin();
+    patterns.insert("de", liang);
+    assert!(patterns.get("de-CH-1996").is_some());
+    assert!(patterns.get("en-GB").is_some());
+    assert!(patterns.get("fr").is_none());
+}
+
+#[test]
+fn test_line_breaking_with_hyphenation() {
+    let metrics = SimpleTextMetrics::default();
+    let english = Hyphenator::english();
+    let texts = |lines: Vec<crate::layout::BrokenLine>| -> Vec<String> {
+        lines.iter().map(|l| l.text()).collect()
+    };
+
+    // 16 characters to a line
+    let greedy = LineBreaker::new(&metrics, 10.0, 96.0).with_hyphenator(&english);
+    assert_eq!(
+        texts(greedy.break_text("the hyphenation of typesetting")),
+        ["the hyphenation", "of typesetting"]
+    );
+    assert_eq!(
+        texts(greedy.break_text("still hyphenation")),
+        ["still hyphena-", "tion"]
+    );
+    assert_eq!(
+        texts(LineBreaker::new(&metrics, 10.0, 96.0).break_text("still hyphenation")),
+        ["still", "hyphenation"]
+    );
+    // A word too long for any line is broken as often as needed
+    assert_eq!(
+        texts(
+            LineBreaker::new(&metrics, 10.0, 36.0)
+                .with_hyphenator(&english)
+                .break_text("hyphenation")
+        ),
+        ["hy-", "phena-", "tion"]
+    );
+
+    let settings = OptimalBreaking::default();
+    let optimal = LineBreaker::new(&metrics, 10.0, 96.0)
+        .with_hyphenator(&english)
+        .optimal(&settings, true);
+    assert_eq!(
+        texts(optimal.break_text("abcd typesetting hyphenation")),
+        ["abcd typesetting", "hyphenation"]
+    );
+    assert_eq!(
+        texts(optimal.break_text("abcdefghij typesetting")),
+        ["abcdefghij type-", "setting"]
+    );
+}
+
+#[test]
+fn test_layout_hyphenates_by_language() {
+    let text = "Hyphenation keeps justified typesetting even. ".repeat(20);
+    let hyphen_ends = |book: &Book, config: &LayoutConfig| {
+        let tree = layout_book(book, config).unwrap();
+        tree.pages
+            .iter()
+            .flat_map(|p| &p.frames)
+            .filter(|f| f.frame_type == FrameType::BodyText)
+            .flat_map(|f| &f.lines)
+            .filter(|l| l.fragments.last().unwrap().text.ends_with('-'))
+            .count()
+    };
+    let config = LayoutConfig {
+        margins: Margins::uniform(200.0),
+        hyphenation: Hyphenation {
+            enabled: true,
+            ..Hyphenation::default()
+        },
+        ..LayoutConfig::default()
+    };
+
+    let mut book = book_with(&[("Chapter", &text)]);
+    assert!(hyphen_ends(&book, &config) > 0);
+    assert_eq!(hyphen_ends(&book, &LayoutConfig::default()), 0);
+
+    // No French patterns are built in, so a French chapter is not hyphenated
+    book.chapters[0].language = Some("fr".to_string());
+    assert_eq!(hyphen_ends(&book, &config), 0);
+    book.chapters[0].language = None;
+    book.language = Some("fr".to_string());
+    assert_eq!(hyphen_ends(&book, &config), 0);
+    book.chapters[0].language = Some("en-GB".to_string());
+    assert!(hyphen_ends(&book, &config) > 0);
+
+    book.hyphenation = ["Hyphenation", "keeps", "justified", "typesetting"]
+        .map(String::from)
+        .to_vec();
+    assert_eq!(hyphen_ends(&book, &config), 0);
+}
+
+#[test]
+fn test_empty_book() {
+    let tree = layout_book(&book_with(&[]), &LayoutConfig::default()).unwrap();
+    assert!(tree.pages.is_empty());
+    assert_eq!(tree.metadata.total_pages, 0);
+}
+
+#[test]
+fn test_simple_book() {
+    let book = book_with(&[("Opening", "First paragraph.\nSecond paragraph.")]);
+    let tree = layout_book(&book, &LayoutConfig::default()).unwrap();
+
+    assert_eq!(tree.book_id, book.id);
+    assert_eq!(tree.pages.len(), 1);
+    let page = &tree.pages[0];
+    assert_eq!(page.side, PageSide::Right);
+    assert_eq!(page.chapter_id, Some(book.chapters[0].id));
+
+    // A chapter's opening page has no running head or folio
+    let types: Vec<FrameType> = page.frames.iter().map(|f| f.frame_type).collect();
+    assert_eq!(types, [FrameType::ChapterTitle, FrameType::BodyText]);
+    let body = &page.frames[1];
+    assert_eq!(body.lines.len(), 2);
+    assert_eq!(
+        body.lines[1].fragments[0].source_block_id,
+        Some(generate_block_id(&book.chapters[0].id, 1))
+    );
+    assert!(body.lines[1].y_offset > body.lines[0].y_offset);
+    assert_eq!(page.label.to_string(), "1");
+}
+
+#[test]
+fn test_multi_page_chapter() {
+    let paragraph = "word ".repeat(400);
+    let content = [paragraph.as_str(); 5].join("\n");
+    let book = book_with(&[("Long", &content)]);
+    let config = LayoutConfig::default();
+    let tree = layout_book(&book, &config).unwrap();
+
+    assert!(tree.pages.len() > 1);
+    assert_eq!(tree.metadata.total_pages, tree.pages.len());
+    for page in &tree.pages {
+        assert_eq!(page.page_number % 2 == 1, page.side == PageSide::Right);
+        for frame in &page.frames {
+            let bottom = frame.bounds.y + frame.bounds.height;
+            assert!(bottom <= config.page_size.height + 0.01);
+            for line in &frame.lines {
+                assert!(line.y_offset <= frame.bounds.height);
+            }
+        }
+    }
+    // Only the first page has a chapter title
+    let titles = tree
+        .pages
+        .iter()
+        .flat_map(|p| &p.frames)
+        .filter(|f| f.frame_type == FrameType::ChapterTitle)
+        .count();
+    assert_eq!(titles, 1);
+}
+
+#[test]
+fn test_chapters_start_on_odd_pages() {
+    let book = book_with(&[("One", "Short."), ("Two", "Short."), ("Three", "Short.")]);
+    let tree = layout_book(&book, &LayoutConfig::default()).unwrap();
+
+    let starts: Vec<usize> = tree
+        .metadata
+        .chapters
+        .iter()
+        .map(|c| c.page_number)
+        .collect();
+    assert_eq!(starts, [1, 3, 5]);
+    // The blank verso has no frames at all
+    assert_eq!(tree.pages[1].chapter_id, None);
+    assert!(tree.pages[1].frames.is_empty());
+
+    let config = LayoutConfig {
+        first_chapter_on_odd_page: false,
+        ..LayoutConfig::default()
+    };
+    assert_eq!(layout_book(&book, &config).unwrap().pages.len(), 3);
+}
+
+#[test]
+fn test_inner_margin_on_binding_side() {
+    let paragraph = "word ".repeat(2000);
+    let book = book_with(&[("Long", &paragraph)]);
+    let config = LayoutConfig {
+        margins: Margins {
+            top: 72.0,
+            bottom: 72.0,
+            inner: 90.0,
+            outer: 54.0,
+        },
+        ..LayoutConfig::default()
+    };
+    let tree = layout_book(&book, &config).unwrap();
+
+    let body_x = |index: usize| {
+        tree.pages[index]
+            .frames
+            .iter()
+            .find(|f| f.frame_type == FrameType::BodyText)
+            .unwrap()
+            .bounds
+            .x
+    };
+    assert_eq!(body_x(0), 90.0);
+    assert_eq!(body_x(1), 54.0);
+}
+
+#[test]
+fn test_justified_lines_fill_width() {
+    let book = book_with(&[("Title", &"justify these words ".repeat(30))]);
+    let config = LayoutConfig::default();
+    let tree = layout_book(&book, &config).unwrap();
+    let body = &tree.pages[0].frames[1];
+    let width = body.bounds.width;
+
+    let first = &body.lines[0];
+    assert!(first.fragments.len() > 1);
+    let last_fragment = first.fragments.last().unwrap();
+    assert!((last_fragment.x_offset + last_fragment.width - width).abs() < 0.01);
+
+    // The last line of a paragraph stays ragged
+    let last = body.lines.last().unwrap();
+    assert_eq!(last.fragments.len(), 1);
+    assert_eq!(last.fragments[0].x_offset, 0.0);
+
+    let centered = LayoutConfig {
+        body_style: TextStyle {
+            alignment: Alignment::Center,
+            ..config.body_style.clone()
+        },
+        ..config
+    };
+    let tree = layout_book(&book_with(&[("Title", "Hi")]), &centered).unwrap();
+    let fragment = &tree.pages[0].frames[1].lines[0].fragments[0];
+    assert!((fragment.x_offset * 2.0 + fragment.width - width).abs() < 0.01);
+}
+
+#[test]
+fn test_scene_break_line() {
+    let book = book_with(&[("Title", "Before.\n* * *\nAfter.")]);
+    let tree = layout_book(&book, &LayoutConfig::default()).unwrap();
+    let body = &tree.pages[0].frames[1];
+    assert_eq!(body.lines.len(), 3);
+    assert_eq!(body.lines[1].fragments[0].text, "* * *");
+    assert!(body.lines[1].fragments[0].x_offset > 0.0);
+}
+
+/// Ten body lines to a page, six below a chapter title
+fn short_pages(pagination: Pagination) -> LayoutConfig {
+    LayoutConfig {
+        page_size: PageSize {
+            width: 612.0,
+            height: 324.0,
+        },
+        body_style: TextStyle {
+            alignment: Alignment::Left,
+            ..TextStyle::default()
+        },
+        pagination,
+        ..LayoutConfig::default()
+    }
+}
+
+/// A paragraph of `lines` lines, one word to a line
+fn paragraph(lines: usize) -> String {
+    vec!["line".repeat(10); lines].join(" ")
+}
+
+fn body_lines_per_page(tree: &RenderTree) -> Vec<usize> {
+    tree.pages
+        .iter()
+        .map(|page| {
+            page.frames
+                .iter()
+                .filter(|f| f.frame_type == FrameType::BodyText)
+                .map(|f| f.lines.len())
+                .sum()
+        })
+        .collect()
+}
+
+#[test]
+fn test_orphans_and_widows() {
+    // Without any give in the line spacing, lines can only move
+    let config = short_pages(Pagination {
+        vertical_justification: 0.0,
+        ..Pagination::default()
+    });
+    let lax = short_pages(Pagination {
+        orphans: 1,
+        widows: 1,
+        last_page_lines: 1,
+        ..Pagination::default()
+    });
+
+    // One line of the second paragraph would be left at the foot of the page
+    let book = book_with(&[("Title", &[paragraph(5), paragraph(5)].join("\n"))]);
+    assert_eq!(
+        body_lines_per_page(&layout_book(&book, &lax).unwrap()),
+        [6, 4]
+    );
+    let tree = layout_book(&book, &config).unwrap();
+    assert_eq!(body_lines_per_page(&tree), [5, 5]);
+    assert!(tree.metadata.pagination_issues.is_empty());
+
+    // One line of the second paragraph would be carried over
+    let book = book_with(&[("Title", &[paragraph(3), paragraph(4)].join("\n"))]);
+    assert_eq!(
+        body_lines_per_page(&layout_book(&book, &lax).unwrap()),
+        [6, 1]
+    );
+    assert_eq!(
+        body_lines_per_page(&layout_book(&book, &config).unwrap()),
+        [5, 2]
+    );
+}
+
+#[test]
+fn test_vertical_justification() {
+    let book = book_with(&[("Title", &[paragraph(3), paragraph(4)].join("\n"))]);
+    let tree = layout_book(&book, &short_pages(Pagination::default())).unwrap();
+
+    // Squeezing the lines together keeps the paragraph on one page
+    assert_eq!(body_lines_per_page(&tree), [7]);
+    let body = &tree.pages[0].frames[1];
+    let gaps: Vec<f32> = body
+        .lines
+        .windows(2)
+        .map(|pair| pair[1].y_offset - pair[0].y_offset)
+        .collect();
+    assert!(gaps.iter().all(|gap| *gap < 18.0 && *gap > 16.2));
+    assert!(body.lines.last().unwrap().y_offset < body.bounds.height);
+
+    // A page cut one line short is spread to the foot of the text area
+    let content = [paragraph(6), paragraph(9), paragraph(3)].join("\n");
+    let book = book_with(&[("Title", &content)]);
+    let config = short_pages(Pagination {
+        vertical_justification: 0.15,
+        ..Pagination::default()
+    });
+    let tree = layout_book(&book, &config).unwrap();
+    assert_eq!(body_lines_per_page(&tree), [6, 9, 3]);
+    let body = &tree.pages[1].frames[0];
+    assert!(body.lines[1].y_offset - body.lines[0].y_offset > 18.0);
+    let bottom = body.lines[8].y_offset - body.lines[0].y_offset + 18.0;
+    assert!((bottom - body.bounds.height).abs() < 0.01);
+
+    // Without enough give, the page is left short
+    let tree = layout_book(&book, &short_pages(Pagination::default())).unwrap();
+    let body = &tree.pages[1].frames[0];
+    assert_eq!(body.lines[1].y_offset - body.lines[0].y_offset, 18.0);
+}
+
+#[test]
+fn test_keep_with_next_and_last_page() {
+    let config = short_pages(Pagination {
+        vertical_justification: 0.0,
+        ..Pagination::default()
+    });
+
+    // A scene break at the foot of a page moves over with the text after it
+    let content = [paragraph(5), "* * *".to_string(), paragraph(3)].join("\n");
+    let tree = layout_book(&book_with(&[("Title", &content)]), &config).unwrap();
+    assert_eq!(body_lines_per_page(&tree), [5, 4]);
+    assert_eq!(tree.pages[1].frames[0].lines[0].fragments[0].text, "* * *");
+
+    // The chapter's last page does not hold a single line
+    let content = ["Short."; 7].join("\n");
+    let tree = layout_book(&book_with(&[("Title", &content)]), &config).unwrap();
+    assert_eq!(body_lines_per_page(&tree), [5, 2]);
+}
+
+#[test]
+fn test_unmet_pagination_rules_are_reported() {
+    let book = book_with(&[("Title", &paragraph(8))]);
+    let config = short_pages(Pagination {
+        orphans: 5,
+        widows: 5,
+        ..Pagination::default()
+    });
+    let tree = layout_book(&book, &config).unwrap();
+
+    // The title keeps its page, so the break falls where it must
+    assert_eq!(body_lines_per_page(&tree), [6, 2]);
+    assert_eq!(
+        tree.metadata.pagination_issues,
+        [PaginationIssue {
+            page_number: 1,
+            rule: PaginationRule::Widows,
+        }]
+    );
+
+    let invalid = short_pages(Pagination {
+        vertical_justification: -0.1,
+        ..Pagination::default()
+    });
+    assert!(matches!(
+        layout_book(&book, &invalid),
+        Err(LayoutError::InvalidConfig(_))
+    ));
+}
+
+/// Text of each frame of a kind on a page, one string per frame
+fn frame_texts(tree: &RenderTree, page: usize, frame_type: FrameType) -> Vec<String> {
+    tree.pages[page]
+        .frames
+        .iter()
+        .filter(|f| f.frame_type == frame_type)
+        .map(|f| f.lines[0].fragments[0].text.clone())
+        .collect()
+}
+
+#[test]
+fn test_running_heads_and_folios() {
+    let long = "word ".repeat(2000);
+    let book = book_with(&[("Opening", &long), ("Second", "Short.")]);
+    let tree = layout_book(&book, &LayoutConfig::default()).unwrap();
+
+    // Author on the verso, chapter title on the recto, folios at the foot
+    assert_eq!(frame_texts(&tree, 1, FrameType::RunningHead), ["Author"]);
+    assert_eq!(frame_texts(&tree, 2, FrameType::RunningHead), ["Opening"]);
+    assert_eq!(frame_texts(&tree, 1, FrameType::PageNumber), ["2"]);
+    let folio = tree.pages[1]
+        .frames
+        .iter()
+        .find(|f| f.frame_type == FrameType::PageNumber)
+        .unwrap();
+    assert_eq!(folio.bounds.y, 792.0 - 72.0);
+
+    // Nothing on chapter openings or blank pages
+    let second = tree.metadata.chapters[1].page_number - 1;
+    assert!(frame_texts(&tree, second, FrameType::PageNumber).is_empty());
+    assert!(frame_texts(&tree, second, FrameType::RunningHead).is_empty());
+    if tree.pages[second - 1].chapter_id.is_none() {
+        assert!(tree.pages[second - 1].frames.is_empty());
+    }
+
+    let config = LayoutConfig {
+        running_heads: RunningHeads {
+            verso_head: Some(RunningText::BookTitle),
+            recto_head: None,
+            folio_on_openings: true,
+            ..RunningHeads::default()
+        },
+        ..LayoutConfig::default()
+    };
+    let tree = layout_book(&book, &config).unwrap();
+    assert_eq!(frame_texts(&tree, 1, FrameType::RunningHead), ["Book"]);
+    assert!(frame_texts(&tree, 2, FrameType::RunningHead).is_empty());
+    assert_eq!(frame_texts(&tree, 0, FrameType::PageNumber), ["1"]);
+}
+
+#[test]
+fn test_front_matter_numbering() {
+    let mut book = book_with(&[("One", &"word ".repeat(2000))]);
+    book.dedication = Some("For the curious".to_string());
+    let config = LayoutConfig {
+        front_matter: true,
+        ..LayoutConfig::default()
+    };
+    let tree = layout_book(&book, &config).unwrap();
+
+    let labels: Vec<String> = tree.pages.iter().map(|p| p.label.to_string()).collect();
+    assert_eq!(labels[..6], ["i", "ii", "iii", "iv", "1", "2"]);
+    assert_eq!(
+        tree.pages[4].label,
+        PageLabel {
+            style: NumberStyle::Arabic,
+            number: 1,
+        }
+    );
+    assert_eq!(tree.metadata.chapters[0].page_number, 5);
+
+    assert_eq!(
+        frame_texts(&tree, 0, FrameType::FrontMatter),
+        ["Book".to_string()]
+    );
+    assert_eq!(tree.pages[0].frames[0].lines[1].fragments[0].text, "Author");
+    assert_eq!(
+        frame_texts(&tree, 2, FrameType::FrontMatter),
+        ["For the curious"]
+    );
+    // Front matter carries no folios; the body does, in arabic
+    assert!(frame_texts(&tree, 0, FrameType::PageNumber).is_empty());
+    assert_eq!(frame_texts(&tree, 5, FrameType::PageNumber), ["2"]);
+
+    let numerals: Vec<String> = [4, 9, 14, 40, 90, 400, 1994]
+        .into_iter()
+        .map(|number| {
+            PageLabel {
+                style: NumberStyle::LowerRoman,
+                number,
+            }
+            .to_string()
+        })
+        .collect();
+    assert_eq!(numerals, ["iv", "ix", "xiv", "xl", "xc", "cd", "mcmxciv"]);
+}
+
+#[test]
+fn test_invalid_config() {
+    let book = book_with(&[("Title", "Text")]);
+    let config = LayoutConfig {
+        margins: Margins::uniform(400.0),
+        ..LayoutConfig::default()
+    };
+    assert!(matches!(
+        layout_book(&book, &config),
+        Err(LayoutError::InvalidConfig(_))
+    ));
+}
+
+#[test]
+fn test_render_tree_serializes() {
+    let book = book_with(&[("Title", "Text")]);
+    let tree = layout_book(&book, &LayoutConfig::default()).unwrap();
+    let json = serde_json::to_string(&tree).unwrap();
+    let back: RenderTree = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, tree);
+}
+
+#[test]
+fn test_screenplay_layout() {
+    let mut book = book_with(&[(
+        "Act One",
+        "INT. FORGE - NIGHT\n\nMARCUS\n(quietly)\nIt never cools.\n\nCUT TO:\n\n===\n\nSparks.",
+    )]);
+    book.format = BookFormat::Screenplay;
+    let tree = layout_book(&book, &LayoutConfig::screenplay()).unwrap();
+    assert_eq!(tree.pages.len(), 2);
+
+    let body = &tree.pages[0].frames[1];
+    assert_eq!(body.bounds.x, 108.0);
+    let placed: Vec<(&str, f32)> = body
+        .lines
+        .iter()
+        .map(|line| (line.fragments[0].text.as_str(), line.fragments[0].x_offset))
+        .collect();
+    assert_eq!(
+        placed[..4],
+        [
+            ("INT. FORGE - NIGHT", 0.0),
+            ("MARCUS", 158.4),
+            ("(quietly)", 115.2),
+            ("It never cools.", 72.0),
+        ]
+    );
+    // The transition is set flush right
+    let transition = &body.lines[4].fragments[0];
+    assert!((transition.x_offset + transition.width - 432.0).abs() < 0.01);
+
+    // A blank line separates the heading from the cue, but not the cue from its dialogue
+    let y: Vec<f32> = body.lines.iter().map(|line| line.y_offset).collect();
+    assert!((y[1] - y[0] - 24.0).abs() < 0.01);
+    assert!((y[2] - y[1] - 12.0).abs() < 0.01);
+
+    // One-sided: the wide margin stays on the left after the page break
+    assert_eq!(tree.pages[1].side, PageSide::Left);
+    assert_eq!(tree.pages[1].frames[0].bounds.x, 108.0);
+
+    // Pages after the first are numbered at the top right
+    assert!(frame_texts(&tree, 0, FrameType::PageNumber).is_empty());
+    let folio = tree.pages[1]
+        .frames
+        .iter()
+        .find(|f| f.frame_type == FrameType::PageNumber)
+        .unwrap();
+    assert_eq!(folio.bounds.y, 0.0);
+    let number = &folio.lines[0].fragments[0];
+    assert_eq!(number.text, "2");
+    assert!((number.x_offset + number.width - 432.0).abs() < 0.01);
+}
+
+const FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSerif.ttf");
+
+#[test]
+fn test_font_metrics() {
+    let metrics = FontMetrics::new(FONT).unwrap();
+    let simple = SimpleTextMetrics::default();
+
+    // Proportional widths, unlike the character-count approximation
+    assert!(metrics.measure_char('i', 12.0) < metrics.measure_char('m', 12.0));
+    assert_ne!(
+        metrics.measure_text("illicit", 12.0),
+        simple.measure_text("illicit", 12.0)
+    );
+    // Kerning pulls the pair together
+    assert!(metrics.kerning('A', 'V') < 0);
+    let apart = metrics.measure_char('A', 12.0) + metrics.measure_char('V', 12.0);
+    assert!(metrics.measure_text("AV", 12.0) < apart);
+    assert!(
+        (metrics.measure_text("AV", 24.0) - 2.0 * metrics.measure_text("AV", 12.0)).abs() < 0.01
+    );
+    assert!(metrics.ascent(12.0) > 0.0 && metrics.ascent(12.0) < 12.0);
+
+    assert!(matches!(
+        FontMetrics::new(b"not a font"),
+        Err(LayoutError::Font(_))
+    ));
+}
+
+#[test]
+fn test_font_cache_reads_each_file_once() {
+    let path =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/fonts/DejaVuSerif.ttf");
+    let mut cache = FontCache::new();
+    let first = cache.load(&path).unwrap();
+    let second = cache.load(&path).unwrap();
+    assert!(std::sync::Arc::ptr_eq(&first, &second));
+    assert_eq!(&first[..], FONT);
+
+    assert!(matches!(
+        cache.load(&path.with_file_name("Missing.ttf")),
+        Err(LayoutError::Font(_))
+    ));
+}
+
+#[test]
+fn test_layout_with_font_metrics() {
+    let paragraph = "Wide words wrap sooner than narrow ones in a proportional face. ".repeat(200);
+    let book = book_with(&[("Title", &paragraph)]);
+    let metrics = FontMetrics::new(FONT).unwrap();
+    let tree = layout_book_with_metrics(&book, &LayoutConfig::default(), &metrics).unwrap();
+
+    let width = LayoutConfig::default().page_size.width - 144.0;
+    for line in &tree.pages[0].frames[1].lines {
+        let last = line.fragments.last().unwrap();
+        assert!(last.x_offset + last.width <= width + 0.01);
+    }
+    // Lines break where the real glyphs run out, not at a fixed character count
+    let simple = layout_book(&book, &LayoutConfig::default()).unwrap();
+    let first_line = |tree: &crate::layout::RenderTree| {
+        tree.pages[0].frames[1].lines[0]
+            .fragments
+            .iter()
+            .map(|f| f.text.clone())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    assert_ne!(first_line(&tree), first_line(&simple));
+}
+
+#[test]
+fn test_font_feature_syntax() {
+    let features: Vec<FontFeature> = ["onum", "+smcp", "-liga", "ss01=2"]
+        .iter()
+        .map(|f| f.parse().unwrap())
+        .collect();
+    assert_eq!(
+        features,
+        [
+            FontFeature::on(b"onum"),
+            FontFeature::on(b"smcp"),
+            FontFeature::off(b"liga"),
+            FontFeature {
+                tag: *b"ss01",
+                value: 2
+            },
+        ]
+    );
+    let json = serde_json::to_string(&features).unwrap();
+    assert_eq!(json, r#"["onum","smcp","-liga","ss01=2"]"#);
+    assert_eq!(
+        serde_json::from_str::<Vec<FontFeature>>(&json).unwrap(),
+        features
+    );
+
+    for invalid in ["", "ligatures", "-li", "ss01=x"] {
+        assert!(invalid.parse::<FontFeature>().is_err(), "{}", invalid);
+    }
+    assert!(serde_json::from_str::<TextStyle>(
+        r#"{"font_size":12,"line_height":1.5,"alignment":"Left","features":["kerning"]}"#
+    )
+    .is_err());
+}
+
+#[test]
+fn test_shaping_ligatures_and_features() {
+    let metrics = FontMetrics::new(FONT).unwrap();
+    let glyphs = metrics.shape("office", 12.0, &[]).unwrap();
+    // "ff" becomes one ligature glyph covering both letters
+    assert_eq!(glyphs.len(), 5);
+    assert_eq!(glyphs[1].cluster, 1);
+    assert_eq!(glyphs[2].cluster, 3);
+    let width: f32 = glyphs.iter().map(|g| g.advance).sum();
+    assert!((metrics.measure_shaped("office", 12.0, &[]) - width).abs() < 0.001);
+
+    let plain = metrics
+        .shape("office", 12.0, &[FontFeature::off(b"liga")])
+        .unwrap();
+    assert_eq!(plain.len(), 6);
+    assert!((plain[1].x - plain[0].advance).abs() < 0.001);
+
+    // Character metrics cannot shape
+    assert!(SimpleTextMetrics::default()
+        .shape("office", 12.0, &[])
+        .is_none());
+}
+
+#[test]
+fn test_fragments_carry_glyphs() {
+    let book = book_with(&[("Offices", "The office staff filed in.")]);
+    let metrics = FontMetrics::new(FONT).unwrap();
+    let tree = layout_book_with_metrics(&book, &LayoutConfig::default(), &metrics).unwrap();
+    let fragment = &tree.pages[0].frames[1].lines[0].fragments[0];
+    assert!(!fragment.glyphs.is_empty());
+    let width: f32 = fragment.glyphs.iter().map(|g| g.advance).sum();
+    assert!((width - fragment.width).abs() < 0.01);
+
+    // Features set on a style reach the shaper
+    let mut config = LayoutConfig::default();
+    config.body_style.features = vec![FontFeature::off(b"liga")];
+    let tree = layout_book_with_metrics(&book, &config, &metrics).unwrap();
+    let plain = &tree.pages[0].frames[1].lines[0].fragments[0];
+    assert_eq!(plain.glyphs.len(), plain.text.chars().count());
+    assert!(fragment.glyphs.len() < plain.glyphs.len());
+
+    let tree = layout_book(&book, &LayoutConfig::default()).unwrap();
+    assert!(tree.pages[0].frames[1].lines[0].fragments[0]
+        .glyphs
+        .is_empty());
+}
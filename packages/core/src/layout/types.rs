@@ -0,0 +1,190 @@
+use crate::layout::config::{PageSize, TextStyle};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// Complete render output for a book
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderTree {
+    pub book_id: Uuid,
+    pub pages: Vec<PageRender>,
+    pub metadata: RenderMetadata,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderMetadata {
+    pub total_pages: usize,
+    pub total_chapters: usize,
+    pub page_size: PageSize,
+    /// Opening page of each chapter, in reading order
+    pub chapters: Vec<ChapterStart>,
+    /// Pages that end where a pagination rule could not be kept
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pagination_issues: Vec<PaginationIssue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChapterStart {
+    pub chapter_id: Uuid,
+    pub title: String,
+    pub page_number: usize,
+}
+
+/// A page break that breaks one of the `Pagination` rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaginationIssue {
+    /// Page before the break
+    pub page_number: usize,
+    pub rule: PaginationRule,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PaginationRule {
+    Orphans,
+    Widows,
+    KeepWithNext,
+    LastPageLines,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PageSide {
+    Left,
+    Right,
+}
+
+impl PageSide {
+    /// Odd pages are on the right, even pages on the left
+    pub fn for_page(page_number: usize) -> Self {
+        if page_number % 2 == 1 {
+            PageSide::Right
+        } else {
+            PageSide::Left
+        }
+    }
+}
+
+/// How a run of pages is numbered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NumberStyle {
+    Arabic,
+    LowerRoman,
+}
+
+/// The number a page goes by, whether or not its folio is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageLabel {
+    pub style: NumberStyle,
+    pub number: usize,
+}
+
+impl fmt::Display for PageLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.style {
+            NumberStyle::Arabic => write!(f, "{}", self.number),
+            NumberStyle::LowerRoman => f.write_str(&lower_roman(self.number)),
+        }
+    }
+}
+
+fn lower_roman(mut number: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            out.push_str(numeral);
+            number -= value;
+        }
+    }
+    out
+}
+
+/// A single rendered page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageRender {
+    /// 1-indexed position of the page in the book
+    pub page_number: usize,
+    /// Number printed in the page's folio, which exporters and viewers show too
+    pub label: PageLabel,
+    pub side: PageSide,
+    /// Chapter the page belongs to (`None` for front matter and blank pages)
+    pub chapter_id: Option<Uuid>,
+    pub frames: Vec<TextFrame>,
+}
+
+/// Rectangle in points, measured from the top-left corner of the page
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameType {
+    ChapterTitle,
+    BodyText,
+    PageNumber,
+    /// Author, book title or chapter title in the head or foot of a page
+    RunningHead,
+    /// Title page and dedication
+    FrontMatter,
+}
+
+/// A positioned text box
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextFrame {
+    pub bounds: Rect,
+    pub lines: Vec<TextLine>,
+    pub frame_type: FrameType,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextLine {
+    /// Baseline position relative to the top of the frame
+    pub y_offset: f32,
+    pub fragments: Vec<TextFragment>,
+}
+
+/// Styled text piece
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextFragment {
+    pub text: String,
+    /// Horizontal position relative to the left edge of the frame
+    pub x_offset: f32,
+    pub width: f32,
+    pub style: TextStyle,
+    /// Block (or chapter, for titles) the text came from
+    pub source_block_id: Option<Uuid>,
+    /// Shaped glyphs, when the text was laid out with a font
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub glyphs: Vec<PositionedGlyph>,
+}
+
+/// A glyph from the shaper, in points relative to the fragment's origin
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PositionedGlyph {
+    /// Glyph id in the font
+    pub id: u16,
+    /// Byte offset in the fragment's text of the characters the glyph shows
+    pub cluster: usize,
+    /// Where the glyph is drawn; `y` is up from the baseline
+    pub x: f32,
+    pub y: f32,
+    /// How far the pen moves after the glyph
+    pub advance: f32,
+}
@@ -1,5 +1,14 @@
 pub mod bk_format;
+pub mod calibre;
+pub mod collab;
+pub mod document;
+pub mod export;
+pub mod history;
+pub mod import;
+pub mod layout;
 pub mod models;
+pub mod session;
+pub mod stats;
 
 #[cfg(feature = "wasm")]
 pub mod wasm;
@@ -1,24 +1,85 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Book {
     pub id: Uuid,
     pub title: String,
     pub author: String,
     pub dedication: Option<String>,
+    /// Word-count goal from the `@target_words:` field
+    #[serde(default)]
+    pub target_words: Option<u32>,
+    /// BCP 47 language tag from the `@language:` field (e.g. "en", "de-CH")
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Author name for sorting, such as "Chhabra, Tej", from `@author_sort:`
+    #[serde(default)]
+    pub author_sort: Option<String>,
+    #[serde(default)]
+    pub series: Option<String>,
+    /// Position in the series; fractional for novellas between books
+    #[serde(default)]
+    pub series_index: Option<f64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Identifiers by lowercase scheme, such as `isbn` or `goodreads`
+    #[serde(default)]
+    pub identifiers: BTreeMap<String, String>,
+    /// Prose or screenplay, from the `@format:` field
+    #[serde(default)]
+    pub format: BookFormat,
+    /// Custom hyphenations from `@hyphenation:`, such as `man-u-script`
+    ///
+    /// Hyphens mark where a word may break; a word without any is never broken.
+    #[serde(default)]
+    pub hyphenation: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub chapters: Vec<Chapter>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// How chapter content is written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookFormat {
+    /// One paragraph per line
+    #[default]
+    Prose,
+    /// Fountain screenplay markup, where blank lines separate elements
+    Screenplay,
+}
+
+impl BookFormat {
+    /// Value of the `@format:` field
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BookFormat::Prose => "prose",
+            BookFormat::Screenplay => "screenplay",
+        }
+    }
+
+    /// Read an `@format:` value, ignoring case
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "prose" => Some(BookFormat::Prose),
+            "screenplay" => Some(BookFormat::Screenplay),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Chapter {
     pub id: Uuid,
     pub title: String,
     pub content: String,
     pub order: usize,
+    /// Language of this chapter when it differs from the book's
+    #[serde(default)]
+    pub language: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -31,6 +92,15 @@ impl Book {
             title,
             author,
             dedication: None,
+            target_words: None,
+            language: None,
+            author_sort: None,
+            series: None,
+            series_index: None,
+            tags: Vec::new(),
+            identifiers: BTreeMap::new(),
+            format: BookFormat::Prose,
+            hyphenation: Vec::new(),
             created_at: now,
             updated_at: now,
             chapters: Vec::new(),
@@ -45,6 +115,7 @@ impl Book {
             title,
             content,
             order,
+            language: None,
             created_at: now,
             updated_at: now,
         };
@@ -58,3 +129,8 @@ pub fn generate_chapter_id(book_id: &Uuid, order: usize, title: &str) -> Uuid {
     let name = format!("{}-{}", order, title);
     Uuid::new_v5(book_id, name.as_bytes())
 }
+
+/// Generate deterministic block ID from chapter ID and order
+pub fn generate_block_id(chapter_id: &Uuid, order: usize) -> Uuid {
+    Uuid::new_v5(chapter_id, order.to_string().as_bytes())
+}
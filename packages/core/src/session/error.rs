@@ -0,0 +1,11 @@
+use thiserror::Error;
+
+/// Errors that can occur while loading or saving a session log
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error("IO error in session log: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Session log is not valid JSON: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
@@ -0,0 +1,219 @@
+use crate::models::Book;
+use crate::session::error::SessionError;
+use crate::session::models::{ChapterDelta, DailyTotal, Dashboard, SaveRecord};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Number of days included in `Dashboard::recent_days`
+const RECENT_DAYS: i64 = 30;
+
+/// Log of word counts at each save, used for daily totals, streaks and goals
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionLog {
+    records: Vec<SaveRecord>,
+}
+
+impl SessionLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load a log from `path`, or start an empty one if the file does not exist
+    pub fn load(path: &Path) -> Result<Self, SessionError> {
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// Write the log to `path`
+    pub fn save(&self, path: &Path) -> Result<(), SessionError> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Log file that sits next to a .bk file (`my-book.bk` -> `my-book.bk.sessions.json`)
+    pub fn sidecar_path(book_path: &Path) -> PathBuf {
+        let mut name = book_path
+            .file_name()
+            .map(|n| n.to_os_string())
+            .unwrap_or_default();
+        name.push(".sessions.json");
+        book_path.with_file_name(name)
+    }
+
+    /// All recorded saves, oldest first
+    pub fn records(&self) -> &[SaveRecord] {
+        &self.records
+    }
+
+    /// Record a save of `book`, timestamped with its `updated_at`
+    ///
+    /// Chapters whose `updated_at` is not newer than the previous save are
+    /// assumed unchanged and are not recounted. Returns `None` if the book has
+    /// not been updated since the last recorded save. The first save is the
+    /// baseline and has a delta of 0, so an existing manuscript does not count
+    /// as a single day's writing.
+    pub fn record_save(&mut self, book: &Book) -> Option<&SaveRecord> {
+        let last = self.records.last();
+        if last.is_some_and(|r| book.updated_at <= r.timestamp) {
+            return None;
+        }
+
+        let mut previous = self.chapter_counts();
+        let mut chapters = Vec::new();
+        let mut total_words = 0;
+
+        for chapter in &book.chapters {
+            let known = previous.remove(&chapter.id);
+            let unchanged = last.is_some_and(|r| chapter.updated_at <= r.timestamp);
+            let words = match known {
+                Some(words) if unchanged => words,
+                _ => chapter.stats().words,
+            };
+            total_words += words;
+
+            if known != Some(words) {
+                let before = match last {
+                    Some(_) => known.unwrap_or(0),
+                    None => words,
+                };
+                chapters.push(ChapterDelta {
+                    chapter_id: chapter.id,
+                    words,
+                    delta: words as i64 - before as i64,
+                    updated_at: chapter.updated_at,
+                });
+            }
+        }
+
+        // Chapters that disappeared since the last save
+        for (chapter_id, words) in previous {
+            if words > 0 {
+                chapters.push(ChapterDelta {
+                    chapter_id,
+                    words: 0,
+                    delta: -(words as i64),
+                    updated_at: book.updated_at,
+                });
+            }
+        }
+
+        let delta = match last {
+            Some(last) => total_words as i64 - last.total_words as i64,
+            None => 0,
+        };
+        self.records.push(SaveRecord {
+            timestamp: book.updated_at,
+            total_words,
+            delta,
+            chapters,
+        });
+        self.records.last()
+    }
+
+    /// Net words written per day, for days with at least one save
+    ///
+    /// Days are calendar days in the writer's time zone `offset`.
+    pub fn daily_totals(&self, offset: FixedOffset) -> Vec<DailyTotal> {
+        let mut days: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+        for record in &self.records {
+            *days
+                .entry(local_date(record.timestamp, offset))
+                .or_insert(0) += record.delta;
+        }
+        days.into_iter()
+            .map(|(date, words)| DailyTotal { date, words })
+            .collect()
+    }
+
+    /// Current and longest streaks of days with net words written
+    ///
+    /// The current streak still counts if today has no words yet but
+    /// yesterday did, so it does not reset first thing in the morning.
+    pub fn streaks(&self, today: NaiveDate, offset: FixedOffset) -> (usize, usize) {
+        let productive: Vec<NaiveDate> = self
+            .daily_totals(offset)
+            .into_iter()
+            .filter(|d| d.words > 0)
+            .map(|d| d.date)
+            .collect();
+
+        let mut longest = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for date in &productive {
+            run = match previous {
+                Some(prev) if *date - prev == Duration::days(1) => run + 1,
+                _ => 1,
+            };
+            longest = longest.max(run);
+            previous = Some(*date);
+        }
+
+        let mut current = 0;
+        let mut day = today;
+        if !productive.contains(&day) {
+            day -= Duration::days(1);
+        }
+        while productive.contains(&day) {
+            current += 1;
+            day -= Duration::days(1);
+        }
+
+        (current, longest)
+    }
+
+    /// Build the goals dashboard for `book` as of `now`
+    pub fn dashboard(&self, book: &Book, now: DateTime<Utc>, offset: FixedOffset) -> Dashboard {
+        let total_words = book.stats().total.words;
+        let today = local_date(now, offset);
+        let totals: HashMap<NaiveDate, i64> = self
+            .daily_totals(offset)
+            .into_iter()
+            .map(|d| (d.date, d.words))
+            .collect();
+        let (current_streak, longest_streak) = self.streaks(today, offset);
+
+        let target = book.target_words.filter(|t| *t > 0);
+        let recent_days = (0..RECENT_DAYS)
+            .rev()
+            .map(|back| {
+                let date = today - Duration::days(back);
+                DailyTotal {
+                    date,
+                    words: totals.get(&date).copied().unwrap_or(0),
+                }
+            })
+            .collect();
+
+        Dashboard {
+            total_words,
+            target_words: book.target_words,
+            progress: target.map(|t| (total_words as f64 / t as f64).min(1.0)),
+            words_remaining: target.map(|t| (t as usize).saturating_sub(total_words)),
+            today_words: totals.get(&today).copied().unwrap_or(0),
+            current_streak,
+            longest_streak,
+            recent_days,
+        }
+    }
+
+    /// Latest known word count of every chapter seen so far
+    fn chapter_counts(&self) -> HashMap<Uuid, usize> {
+        let mut counts = HashMap::new();
+        for record in &self.records {
+            for chapter in &record.chapters {
+                counts.insert(chapter.chapter_id, chapter.words);
+            }
+        }
+        counts
+    }
+}
+
+fn local_date(timestamp: DateTime<Utc>, offset: FixedOffset) -> NaiveDate {
+    timestamp.with_timezone(&offset).date_naive()
+}
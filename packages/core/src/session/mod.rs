@@ -0,0 +1,10 @@
+mod error;
+mod log;
+mod models;
+
+#[cfg(test)]
+mod tests;
+
+pub use error::SessionError;
+pub use log::SessionLog;
+pub use models::{ChapterDelta, DailyTotal, Dashboard, SaveRecord};
@@ -0,0 +1,48 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Word count change of one chapter in a save
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChapterDelta {
+    pub chapter_id: Uuid,
+    pub words: usize,
+    pub delta: i64,
+    /// `updated_at` of the chapter when it was saved
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One recorded save of the manuscript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveRecord {
+    /// `updated_at` of the book when it was saved
+    pub timestamp: DateTime<Utc>,
+    pub total_words: usize,
+    /// Net words added since the previous save (0 for the first save)
+    pub delta: i64,
+    /// Chapters whose word count changed
+    pub chapters: Vec<ChapterDelta>,
+}
+
+/// Net words written on one calendar day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyTotal {
+    pub date: NaiveDate,
+    pub words: i64,
+}
+
+/// Everything the writing-goals dashboard shows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dashboard {
+    pub total_words: usize,
+    pub target_words: Option<u32>,
+    /// Share of the target reached, from 0.0 to 1.0 (`None` without a target)
+    pub progress: Option<f64>,
+    pub words_remaining: Option<usize>,
+    pub today_words: i64,
+    /// Consecutive days with net words written, ending today (or yesterday)
+    pub current_streak: usize,
+    pub longest_streak: usize,
+    /// Daily totals for the most recent days, oldest first
+    pub recent_days: Vec<DailyTotal>,
+}
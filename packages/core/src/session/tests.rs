@@ -0,0 +1,156 @@
+use crate::models::Book;
+use crate::session::log::SessionLog;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, TimeZone, Utc};
+
+fn at(day: u32, hour: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 12, day, hour, 0, 0).unwrap()
+}
+
+fn utc() -> FixedOffset {
+    FixedOffset::east_opt(0).unwrap()
+}
+
+/// Save `book` as if it was edited at `time`, touching only `chapter`
+fn edit(book: &mut Book, chapter: usize, text: &str, time: DateTime<Utc>) {
+    book.chapters[chapter].content.push_str(text);
+    book.chapters[chapter].updated_at = time;
+    book.updated_at = time;
+}
+
+fn sample_book(time: DateTime<Utc>) -> Book {
+    let mut book = Book::new("Book".to_string(), "Author".to_string());
+    book.add_chapter("One".to_string(), "one two three".to_string());
+    book.add_chapter("Two".to_string(), "four five".to_string());
+    book.created_at = time;
+    book.updated_at = time;
+    for chapter in &mut book.chapters {
+        chapter.created_at = time;
+        chapter.updated_at = time;
+    }
+    book
+}
+
+#[test]
+fn test_first_save_is_baseline() {
+    let mut log = SessionLog::new();
+    let book = sample_book(at(1, 9));
+
+    let record = log.record_save(&book).unwrap();
+    assert_eq!(record.total_words, 5);
+    assert_eq!(record.delta, 0);
+    assert_eq!(record.chapters.len(), 2);
+    assert!(record.chapters.iter().all(|c| c.delta == 0));
+
+    // Saving without a newer updated_at records nothing
+    assert!(log.record_save(&book).is_none());
+}
+
+#[test]
+fn test_word_deltas_per_chapter() {
+    let mut log = SessionLog::new();
+    let mut book = sample_book(at(1, 9));
+    log.record_save(&book);
+
+    edit(&mut book, 1, " six seven eight", at(1, 10));
+    let record = log.record_save(&book).unwrap();
+    assert_eq!(record.delta, 3);
+    assert_eq!(record.total_words, 8);
+    assert_eq!(record.chapters.len(), 1);
+    assert_eq!(record.chapters[0].chapter_id, book.chapters[1].id);
+    assert_eq!(record.chapters[0].delta, 3);
+
+    // Removing a chapter is a negative delta
+    book.chapters.remove(0);
+    book.updated_at = at(1, 11);
+    let record = log.record_save(&book).unwrap();
+    assert_eq!(record.delta, -3);
+    assert_eq!(record.chapters[0].words, 0);
+}
+
+#[test]
+fn test_daily_totals_and_time_zones() {
+    let mut log = SessionLog::new();
+    let mut book = sample_book(at(1, 9));
+    log.record_save(&book);
+    edit(&mut book, 0, " a b", at(1, 20));
+    log.record_save(&book);
+    edit(&mut book, 0, " c", at(2, 1));
+    log.record_save(&book);
+
+    let totals = log.daily_totals(utc());
+    assert_eq!(totals.len(), 2);
+    assert_eq!(totals[0].words, 2);
+    assert_eq!(totals[1].words, 1);
+
+    // Five hours behind UTC, the 01:00 save still belongs to the 1st
+    let totals = log.daily_totals(FixedOffset::west_opt(5 * 3600).unwrap());
+    assert_eq!(totals.len(), 1);
+    assert_eq!(totals[0].words, 3);
+}
+
+#[test]
+fn test_streaks() {
+    let mut log = SessionLog::new();
+    let mut book = sample_book(at(1, 9));
+    log.record_save(&book);
+    for day in [1, 2, 3, 5, 6] {
+        edit(&mut book, 0, " more", at(day, 12));
+        log.record_save(&book);
+    }
+
+    let (current, longest) = log.streaks(NaiveDate::from_ymd_opt(2025, 12, 6).unwrap(), utc());
+    assert_eq!((current, longest), (2, 3));
+
+    // Nothing written yet today, but yesterday keeps the streak alive
+    let (current, _) = log.streaks(NaiveDate::from_ymd_opt(2025, 12, 7).unwrap(), utc());
+    assert_eq!(current, 2);
+
+    let (current, _) = log.streaks(NaiveDate::from_ymd_opt(2025, 12, 8).unwrap(), utc());
+    assert_eq!(current, 0);
+}
+
+#[test]
+fn test_dashboard_progress() {
+    let mut log = SessionLog::new();
+    let mut book = sample_book(at(1, 9));
+    book.target_words = Some(20);
+    log.record_save(&book);
+    edit(&mut book, 0, " six seven eight nine ten", at(1, 12));
+    log.record_save(&book);
+
+    let dashboard = log.dashboard(&book, at(1, 18), utc());
+    assert_eq!(dashboard.total_words, 10);
+    assert_eq!(dashboard.target_words, Some(20));
+    assert_eq!(dashboard.progress, Some(0.5));
+    assert_eq!(dashboard.words_remaining, Some(10));
+    assert_eq!(dashboard.today_words, 5);
+    assert_eq!(dashboard.current_streak, 1);
+    assert_eq!(dashboard.recent_days.len(), 30);
+    assert_eq!(dashboard.recent_days.last().unwrap().words, 5);
+    assert_eq!(
+        dashboard.recent_days[0].date,
+        at(1, 18).date_naive() - Duration::days(29)
+    );
+
+    book.target_words = None;
+    let dashboard = log.dashboard(&book, at(1, 18), utc());
+    assert_eq!(dashboard.progress, None);
+}
+
+#[test]
+fn test_persistence_round_trip() {
+    let path = std::env::temp_dir().join(format!("bk-sessions-{}.json", uuid::Uuid::new_v4()));
+    let mut log = SessionLog::load(&path).unwrap();
+    assert!(log.records().is_empty());
+
+    log.record_save(&sample_book(at(1, 9)));
+    log.save(&path).unwrap();
+    let loaded = SessionLog::load(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(loaded, log);
+    assert_eq!(
+        SessionLog::sidecar_path(std::path::Path::new("/books/novel.bk")),
+        std::path::Path::new("/books/novel.bk.sessions.json")
+    );
+}
@@ -0,0 +1,151 @@
+use crate::models::{Book, Chapter};
+use crate::stats::models::{BookStats, ChapterStats, TextStats};
+use unicode_segmentation::UnicodeSegmentation;
+
+impl TextStats {
+    /// Analyze a piece of text; each non-empty line is one paragraph
+    pub fn from_text(text: &str) -> Self {
+        let mut stats = TextStats {
+            characters: text.chars().count(),
+            characters_excluding_spaces: text.chars().filter(|c| !c.is_whitespace()).count(),
+            ..TextStats::default()
+        };
+
+        for paragraph in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let dialogue = dialogue_spans(paragraph);
+            let mut words = 0;
+            for (offset, _) in paragraph.unicode_word_indices() {
+                words += 1;
+                if dialogue
+                    .iter()
+                    .any(|(start, end)| offset >= *start && offset < *end)
+                {
+                    stats.dialogue_words += 1;
+                }
+            }
+
+            stats.paragraphs += 1;
+            stats.words += words;
+            stats.sentences += count_sentences(paragraph, words);
+        }
+
+        stats.with_derived()
+    }
+}
+
+impl Chapter {
+    /// Word, sentence and reading-time statistics for this chapter
+    pub fn stats(&self) -> TextStats {
+        TextStats::from_text(&self.content)
+    }
+}
+
+impl Book {
+    /// Statistics for the whole book and each of its chapters
+    pub fn stats(&self) -> BookStats {
+        let chapters: Vec<ChapterStats> = self
+            .chapters
+            .iter()
+            .map(|chapter| ChapterStats {
+                chapter_id: chapter.id,
+                title: chapter.title.clone(),
+                stats: chapter.stats(),
+            })
+            .collect();
+
+        let mut total = TextStats::default();
+        for chapter in &chapters {
+            total.accumulate(&chapter.stats);
+        }
+
+        BookStats {
+            total: total.with_derived(),
+            chapters,
+        }
+    }
+}
+
+fn is_sentence_terminator(c: char) -> bool {
+    matches!(
+        c,
+        '.' | '!' | '?' | '…' | '。' | '！' | '？' | '‼' | '⁇' | '⁈' | '⁉'
+    )
+}
+
+/// Characters that may trail a terminator without ending the sentence run
+fn is_closing(c: char) -> bool {
+    matches!(c, '"' | '\'' | '”' | '’' | '»' | '」' | '』' | ')' | ']')
+}
+
+/// Count sentences in a paragraph
+///
+/// A run of terminators ("?!", "...") ends one sentence when followed by
+/// whitespace, a closing quote or the end of the paragraph, so abbreviations
+/// like "3.5" do not split. CJK full stops end a sentence on their own.
+/// Trailing text without a terminator still counts as a sentence.
+fn count_sentences(paragraph: &str, words: usize) -> usize {
+    if words == 0 {
+        return 0;
+    }
+
+    let chars: Vec<char> = paragraph.chars().collect();
+    let mut sentences = 0;
+    let mut open = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if is_sentence_terminator(c) {
+            let mut j = i;
+            while j < chars.len() && is_sentence_terminator(chars[j]) {
+                j += 1;
+            }
+            let mut k = j;
+            while k < chars.len() && is_closing(chars[k]) {
+                k += 1;
+            }
+            let cjk = matches!(chars[j - 1], '。' | '！' | '？');
+            if open && (cjk || k == chars.len() || chars[k].is_whitespace()) {
+                sentences += 1;
+                open = false;
+            }
+            i = k;
+            continue;
+        }
+        if c.is_alphanumeric() {
+            open = true;
+        }
+        i += 1;
+    }
+
+    if open {
+        sentences += 1;
+    }
+    sentences
+}
+
+/// Byte ranges of a paragraph that are inside double quotation marks
+///
+/// Straight quotes toggle; curly, guillemet and corner brackets open and close.
+/// Single quotes are ignored because they double as apostrophes. An unclosed
+/// quote runs to the end of the paragraph, as is usual for multi-paragraph
+/// speeches.
+fn dialogue_spans(paragraph: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start: Option<usize> = None;
+
+    for (offset, c) in paragraph.char_indices() {
+        match (c, start) {
+            ('"' | '“' | '«' | '「' | '『' | '„', None) => start = Some(offset),
+            ('"' | '”' | '»' | '」' | '』', Some(open)) => {
+                spans.push((open, offset));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(open) = start {
+        spans.push((open, paragraph.len()));
+    }
+    spans
+}
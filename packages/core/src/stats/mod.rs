@@ -0,0 +1,7 @@
+mod counter;
+mod models;
+
+#[cfg(test)]
+mod tests;
+
+pub use models::{BookStats, ChapterStats, TextStats, WORDS_PER_MINUTE};
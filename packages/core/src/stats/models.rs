@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Average silent reading speed used for reading-time estimates
+pub const WORDS_PER_MINUTE: f64 = 238.0;
+
+/// Counts for a piece of manuscript text
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TextStats {
+    /// Words per Unicode word boundaries; each CJK ideograph counts as one word
+    pub words: usize,
+    /// Unicode scalar values, including whitespace
+    pub characters: usize,
+    pub characters_excluding_spaces: usize,
+    pub sentences: usize,
+    pub paragraphs: usize,
+    /// Words that appear inside quotation marks
+    pub dialogue_words: usize,
+    /// Share of words that are dialogue, from 0.0 to 1.0
+    pub dialogue_ratio: f64,
+    /// Words per sentence
+    pub average_sentence_length: f64,
+    pub reading_time_minutes: f64,
+}
+
+impl TextStats {
+    /// Fill in the ratios derived from the raw counts
+    pub(crate) fn with_derived(mut self) -> Self {
+        self.dialogue_ratio = ratio(self.dialogue_words, self.words);
+        self.average_sentence_length = ratio(self.words, self.sentences);
+        self.reading_time_minutes = self.words as f64 / WORDS_PER_MINUTE;
+        self
+    }
+
+    /// Add the raw counts of `other` to these
+    pub(crate) fn accumulate(&mut self, other: &TextStats) {
+        self.words += other.words;
+        self.characters += other.characters;
+        self.characters_excluding_spaces += other.characters_excluding_spaces;
+        self.sentences += other.sentences;
+        self.paragraphs += other.paragraphs;
+        self.dialogue_words += other.dialogue_words;
+    }
+}
+
+/// Statistics for one chapter of a book
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChapterStats {
+    pub chapter_id: Uuid,
+    pub title: String,
+    pub stats: TextStats,
+}
+
+/// Statistics for a whole book, with a per-chapter breakdown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookStats {
+    pub total: TextStats,
+    pub chapters: Vec<ChapterStats>,
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
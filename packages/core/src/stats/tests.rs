@@ -0,0 +1,80 @@
+use crate::models::Book;
+use crate::stats::models::TextStats;
+
+#[test]
+fn test_basic_counts() {
+    let stats = TextStats::from_text("The morning sun rose. Marcus woke!\nHe walked to the forge.");
+    assert_eq!(stats.words, 11);
+    assert_eq!(stats.sentences, 3);
+    assert_eq!(stats.paragraphs, 2);
+    assert_eq!(stats.characters, 58);
+    assert_eq!(stats.characters_excluding_spaces, 48);
+    assert!((stats.average_sentence_length - 11.0 / 3.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_empty_text() {
+    let stats = TextStats::from_text("  \n\n ");
+    assert_eq!(stats.words, 0);
+    assert_eq!(stats.sentences, 0);
+    assert_eq!(stats.paragraphs, 0);
+    assert_eq!(stats.dialogue_ratio, 0.0);
+    assert_eq!(stats.average_sentence_length, 0.0);
+    assert_eq!(stats.reading_time_minutes, 0.0);
+}
+
+#[test]
+fn test_sentence_edge_cases() {
+    // Ellipses and "?!" end one sentence, decimals do not split
+    let stats = TextStats::from_text("Wait... What?! It cost 3.5 crowns");
+    assert_eq!(stats.sentences, 3);
+
+    // A terminator inside a closing quote still ends the sentence
+    let stats = TextStats::from_text("\"Run.\" She ran.");
+    assert_eq!(stats.sentences, 2);
+}
+
+#[test]
+fn test_dialogue_ratio() {
+    let stats = TextStats::from_text("\u{201c}Come here,\u{201d} she said.\n\"Now,\" he replied.");
+    assert_eq!(stats.words, 7);
+    assert_eq!(stats.dialogue_words, 3);
+    assert!((stats.dialogue_ratio - 3.0 / 7.0).abs() < 1e-9);
+
+    // Apostrophes are not quotes
+    let stats = TextStats::from_text("Don't stop, it's fine.");
+    assert_eq!(stats.dialogue_words, 0);
+}
+
+#[test]
+fn test_unicode_and_cjk_words() {
+    let stats = TextStats::from_text("Привет мир! Café naïve.");
+    assert_eq!(stats.words, 4);
+    assert_eq!(stats.sentences, 2);
+
+    // Each ideograph counts as a word, and CJK full stops end sentences
+    let stats = TextStats::from_text("我爱读书。她说：「你好」。");
+    assert_eq!(stats.words, 8);
+    assert_eq!(stats.sentences, 2);
+    assert_eq!(stats.dialogue_words, 2);
+}
+
+#[test]
+fn test_book_and_chapter_stats() {
+    let mut book = Book::new("Book".to_string(), "Author".to_string());
+    book.add_chapter("One".to_string(), "One two three.".to_string());
+    book.add_chapter("Two".to_string(), "\"Four five,\" six.\nSeven.".to_string());
+
+    assert_eq!(book.chapters[0].stats().words, 3);
+
+    let stats = book.stats();
+    assert_eq!(stats.chapters.len(), 2);
+    assert_eq!(stats.chapters[1].title, "Two");
+    assert_eq!(stats.chapters[1].chapter_id, book.chapters[1].id);
+    assert_eq!(stats.total.words, 7);
+    assert_eq!(stats.total.sentences, 3);
+    assert_eq!(stats.total.paragraphs, 3);
+    assert_eq!(stats.total.dialogue_words, 2);
+    assert!((stats.total.dialogue_ratio - 2.0 / 7.0).abs() < 1e-9);
+    assert!((stats.total.reading_time_minutes - 7.0 / 238.0).abs() < 1e-9);
+}
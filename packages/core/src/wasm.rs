@@ -1,6 +1,9 @@
 use wasm_bindgen::prelude::*;
 
 use crate::bk_format::BkParser;
+use crate::export::{self, ExportOptions};
+use crate::stats::TextStats;
+use crate::Book;
 use chrono::{DateTime, Utc};
 
 /// Parse a .bk file from string and return as JavaScript object
@@ -71,3 +74,87 @@ pub fn parse_bk(
     serde_wasm_bindgen::to_value(&book)
         .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
 }
+
+/// Compute manuscript statistics for a book
+///
+/// # Arguments
+/// * `book` - A Book object as returned by `parse_bk`
+///
+/// # Returns
+/// A JavaScript object with `total` and `chapters` (each with `chapter_id`,
+/// `title` and `stats`). Stats include words, characters, sentences,
+/// paragraphs, dialogue ratio, average sentence length and reading time.
+///
+/// # Examples
+/// ```javascript
+/// const stats = book_stats(book);
+/// console.log(`${stats.total.words} words`);
+/// ```
+#[wasm_bindgen]
+pub fn book_stats(book: JsValue) -> Result<JsValue, JsValue> {
+    let book: Book = serde_wasm_bindgen::from_value(book)
+        .map_err(|e| JsValue::from_str(&format!("Invalid book: {}", e)))?;
+
+    serde_wasm_bindgen::to_value(&book.stats())
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+/// Compute statistics for a piece of text, e.g. the chapter being edited
+///
+/// # Examples
+/// ```javascript
+/// const { words, reading_time_minutes } = text_stats(editor.value);
+/// ```
+#[wasm_bindgen]
+pub fn text_stats(text: &str) -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&TextStats::from_text(text))
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+/// List the formats `export_book` can write
+///
+/// # Returns
+/// An array of `{ id, name, extension, media_type }` objects, e.g. for an
+/// export menu.
+///
+/// # Examples
+/// ```javascript
+/// for (const format of export_formats()) {
+///     menu.add(format.name, () => save(book, format));
+/// }
+/// ```
+#[wasm_bindgen]
+pub fn export_formats() -> Result<JsValue, JsValue> {
+    serde_wasm_bindgen::to_value(&export::formats())
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+/// Export a book in one of the formats listed by `export_formats`
+///
+/// # Arguments
+/// * `book` - A Book object as returned by `parse_bk`
+/// * `format` - A format id, such as `"epub"` or `"docx"`
+/// * `options` - Optional settings: `{ pdf, manuscript, latex }`, each
+///   read only by its own format; anything left out uses the defaults
+///
+/// # Returns
+/// The file contents as a `Uint8Array`
+///
+/// # Examples
+/// ```javascript
+/// const bytes = export_book(book, "docx", { manuscript: { font: "Times" } });
+/// const blob = new Blob([bytes], { type: format.media_type });
+/// ```
+#[wasm_bindgen]
+pub fn export_book(book: JsValue, format: &str, options: JsValue) -> Result<Vec<u8>, JsValue> {
+    let book: Book = serde_wasm_bindgen::from_value(book)
+        .map_err(|e| JsValue::from_str(&format!("Invalid book: {}", e)))?;
+    let options: ExportOptions = if options.is_undefined() || options.is_null() {
+        ExportOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options)
+            .map_err(|e| JsValue::from_str(&format!("Invalid export options: {}", e)))?
+    };
+
+    export::export_book(&book, format, &options).map_err(|e| JsValue::from_str(&e.to_string()))
+}
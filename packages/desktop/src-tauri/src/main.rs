@@ -1,7 +1,18 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use bookwriter_core::{bk_format::BkParser, Book};
+use bookwriter_core::{
+    bk_format::BkParser,
+    calibre::{self, CalibreMetadata},
+    export::{self, ExportOptions, FormatInfo},
+    history::RevisionStore,
+    layout::FontCache,
+    session::{Dashboard, SessionLog},
+    stats::BookStats,
+    Book,
+};
+use chrono::Offset;
 use std::path::Path;
+use std::sync::{Mutex, PoisonError};
 
 #[tauri::command]
 async fn open_file_dialog() -> Result<Option<String>, String> {
@@ -29,24 +40,141 @@ async fn save_file_dialog(default_name: String) -> Result<Option<String>, String
 
 #[tauri::command]
 async fn load_bk_file(path: String) -> Result<Book, String> {
-    let book = BkParser::parse_file(Path::new(&path))
+    let mut book = BkParser::parse_file(Path::new(&path))
         .map_err(|e| format!("Parse error: {}\n\nHelp: {}", e, e.help_message()))?;
+
+    // Metadata edited in Calibre since the last save wins
+    if calibre_is_newer(Path::new(&path)) {
+        calibre::sync_from_calibre(&mut book, Path::new(&path))
+            .map_err(|e| format!("Failed to read Calibre metadata: {}", e))?;
+    }
     Ok(book)
 }
 
+/// Whether the book sits in a Calibre folder whose metadata changed after it
+fn calibre_is_newer(book_path: &Path) -> bool {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    match (
+        modified(&CalibreMetadata::sidecar_path(book_path)),
+        modified(book_path),
+    ) {
+        (Some(opf), Some(book)) => opf > book,
+        _ => false,
+    }
+}
+
+/// Save a book, then snapshot its history and sync its Calibre metadata
+///
+/// Returns a warning for each bookkeeping step that failed after the save.
 #[tauri::command]
-async fn save_bk_file(path: String, content: String) -> Result<(), String> {
-    std::fs::write(&path, content).map_err(|e| format!("Failed to save file: {}", e))?;
+async fn save_bk_file(path: String, content: String) -> Result<Vec<String>, String> {
+    std::fs::write(&path, &content).map_err(|e| format!("Failed to save file: {}", e))?;
+
+    // The file is already saved, so failed bookkeeping must not fail the save
+    let mut warnings = Vec::new();
+    if let Err(e) = record_save_history(Path::new(&path), &content) {
+        warnings.push(format!("Failed to record history: {}", e));
+    }
+    if let Err(e) = update_calibre_metadata(Path::new(&path), &content) {
+        warnings.push(format!("Failed to update Calibre metadata: {}", e));
+    }
+    Ok(warnings)
+}
+
+/// Keep the `metadata.opf` of a Calibre book folder in step with the book
+fn update_calibre_metadata(path: &Path, content: &str) -> Result<(), String> {
+    if !CalibreMetadata::sidecar_path(path).is_file() {
+        return Ok(());
+    }
+    calibre::sync_to_calibre(content, path).map_err(|e| e.to_string())
+}
+
+/// Take an automatic revision snapshot and log the save's word counts
+fn record_save_history(path: &Path, content: &str) -> Result<(), String> {
+    let now = chrono::Utc::now();
+    let book = BkParser::parse_string(content, now, now).map_err(|e| e.to_string())?;
+
+    let mut store = RevisionStore::open_for_book_file(path).map_err(|e| e.to_string())?;
+    store
+        .snapshot_at(&book, "Auto-save", now)
+        .map_err(|e| e.to_string())?;
+
+    let log_path = SessionLog::sidecar_path(path);
+    let mut log = SessionLog::load(&log_path).map_err(|e| e.to_string())?;
+    log.record_save(&book);
+    log.save(&log_path).map_err(|e| e.to_string())?;
     Ok(())
 }
 
+#[tauri::command]
+async fn export_formats() -> Result<Vec<FormatInfo>, String> {
+    Ok(export::formats())
+}
+
+/// Ask where to save, then export the book there in the chosen format
+///
+/// Returns the path written, or `None` if the dialog was cancelled. Font
+/// files are read once and kept for later exports.
+#[tauri::command]
+async fn export_book(
+    book: Book,
+    format: String,
+    options: Option<ExportOptions>,
+    fonts: tauri::State<'_, Mutex<FontCache>>,
+) -> Result<Option<String>, String> {
+    use tauri::api::dialog::blocking::FileDialogBuilder;
+
+    let exporter =
+        export::exporter(&format).ok_or_else(|| format!("Unknown export format: {}", format))?;
+    let path = FileDialogBuilder::new()
+        .add_filter(exporter.name(), &[exporter.extension()])
+        .set_file_name(&format!(
+            "{}.{}",
+            book.title.replace(['/', '\\', ':'], "-"),
+            exporter.extension()
+        ))
+        .save_file();
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    let mut fonts = fonts.lock().unwrap_or_else(PoisonError::into_inner);
+    let bytes = exporter
+        .export_with_fonts(&book, &options.unwrap_or_default(), &mut fonts)
+        .map_err(|e| format!("Export failed: {}", e))?;
+    std::fs::write(&path, bytes).map_err(|e| format!("Failed to save file: {}", e))?;
+    Ok(Some(path.to_string_lossy().to_string()))
+}
+
+#[tauri::command]
+async fn book_stats(book: Book) -> Result<BookStats, String> {
+    Ok(book.stats())
+}
+
+#[tauri::command]
+async fn writing_dashboard(path: String) -> Result<Dashboard, String> {
+    let path = Path::new(&path);
+    let book = BkParser::parse_file(path)
+        .map_err(|e| format!("Parse error: {}\n\nHelp: {}", e, e.help_message()))?;
+    let log = SessionLog::load(&SessionLog::sidecar_path(path))
+        .map_err(|e| format!("Failed to load session log: {}", e))?;
+
+    let offset = chrono::Local::now().offset().fix();
+    Ok(log.dashboard(&book, chrono::Utc::now(), offset))
+}
+
 fn main() {
     tauri::Builder::default()
+        .manage(Mutex::new(FontCache::new()))
         .invoke_handler(tauri::generate_handler![
             open_file_dialog,
             save_file_dialog,
             load_bk_file,
-            save_bk_file
+            save_bk_file,
+            export_formats,
+            export_book,
+            book_stats,
+            writing_dashboard
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -3,6 +3,7 @@ export interface Chapter {
   title: string
   content: string
   order: number
+  language?: string | null
   created_at: string
   updated_at: string
 }
@@ -12,7 +13,38 @@ export interface Book {
   title: string
   author: string
   dedication: string | null
+  target_words?: number | null
+  language?: string | null
+  author_sort?: string | null
+  series?: string | null
+  series_index?: number | null
+  tags?: string[]
+  identifiers?: Record<string, string>
+  format?: 'prose' | 'screenplay'
+  hyphenation?: string[]
   created_at: string
   updated_at: string
   chapters: Chapter[]
 }
+
+export interface ExportFormat {
+  id: string
+  name: string
+  extension: string
+  media_type: string
+}
+
+/** Per-format export settings; anything left out uses the defaults */
+export interface ExportOptions {
+  pdf?: Record<string, unknown>
+  manuscript?: {
+    font?: 'Courier' | 'Times'
+    short_title?: string | null
+    contact?: string[]
+  }
+  latex?: {
+    document_class?: string
+    class_options?: string[]
+    preamble?: string | null
+  }
+}
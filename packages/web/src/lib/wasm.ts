@@ -1,5 +1,9 @@
-import init, { parse_bk as wasmParseBk } from '../../../core/pkg/bookwriter_core.js';
-import type { Book } from './types';
+import init, {
+  parse_bk as wasmParseBk,
+  export_book as wasmExportBook,
+  export_formats as wasmExportFormats,
+} from '../../../core/pkg/bookwriter_core.js';
+import type { Book, ExportFormat, ExportOptions } from './types';
 
 let wasmInitialized = false;
 let wasmInitPromise: Promise<void> | null = null;
@@ -52,6 +56,40 @@ export async function parseBk(
   }
 }
 
+/**
+ * List the formats a book can be exported to
+ */
+export async function exportFormats(): Promise<ExportFormat[]> {
+  await initWasm();
+  return wasmExportFormats() as ExportFormat[];
+}
+
+/**
+ * Export a book and return it as a Blob ready to download
+ *
+ * @param book - The book to export
+ * @param format - A format from exportFormats()
+ * @param options - Optional per-format settings
+ * @throws Error if the export fails
+ */
+export async function exportBook(
+  book: Book,
+  format: ExportFormat,
+  options?: ExportOptions
+): Promise<Blob> {
+  await initWasm();
+
+  try {
+    const bytes = wasmExportBook(book, format.id, options);
+    return new Blob([bytes], { type: format.media_type });
+  } catch (error) {
+    if (error instanceof Error) {
+      throw new Error(`Failed to export book: ${error.message}`);
+    }
+    throw error;
+  }
+}
+
 /**
  * Check if WASM is initialized
  */